@@ -0,0 +1,374 @@
+use crate::elf::{self, Segment, ET_CORE, PT_LOAD, PT_NOTE};
+use crate::inferior::MemoryMap;
+use libc::user_regs_struct;
+use nix::errno::Errno;
+use nix::unistd::Pid;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::File;
+use std::mem::size_of;
+use std::os::unix::fs::FileExt;
+
+//Types of the notes in a core file that the debugger reads
+const NT_PRSTATUS: u32 = 1;
+const NT_FPREGSET: u32 = 2;
+const NT_PRPSINFO: u32 = 3;
+const NT_X86_XSTATE: u32 = 0x202;
+const NT_FILE: u32 = 0x4649_4c45;
+
+//Offsets into the notes. NT_PRSTATUS is a struct elf_prstatus, which has the signal the thread
+//stopped with, its id and its registers, laid out as PTRACE_GETREGS lays them out. NT_PRPSINFO
+//is a struct elf_prpsinfo, which has the process's command line.
+const PRSTATUS_CURSIG: usize = 12;
+const PRSTATUS_PID: usize = 32;
+const PRSTATUS_REGS: usize = 112;
+const PRPSINFO_ARGS: usize = 56;
+const PRPSINFO_ARGS_LEN: usize = 80;
+
+//A thread of the process the core file was dumped from
+pub struct CoreThread {
+    pub tid: Pid,
+    //the signal the thread stopped with, which is the one that killed the process for the thread
+    //that got it
+    pub signal: i32,
+    pub registers: user_regs_struct,
+    //the x87, SSE and AVX registers, as an XSAVE area, or as PTRACE_GETFPREGS lays them out
+    //without one
+    pub float_area: Vec<u8>,
+}
+
+//The memory and registers of a process when it died, as the kernel dumped them, for looking at
+//them after the fact with `core-file`
+pub struct CoreFile {
+    data: Vec<u8>,
+    //the memory saved in the file
+    segments: Vec<Segment>,
+    //the memory mapped in the process. Memory that wasn't saved, such as code, is read from the
+    //files mapped there.
+    pub maps: Vec<MemoryMap>,
+    //the process's threads, the first being the one that got the signal that killed it
+    pub threads: Vec<CoreThread>,
+    //the command line the process was started with, which may be cut short
+    pub command: String,
+    //memory written since the core file was loaded, such as breakpoints
+    written: RefCell<HashMap<usize, u8>>,
+}
+
+impl CoreFile {
+    pub fn open(path: &str) -> Result<CoreFile, String> {
+        let data = std::fs::read(path)
+            .map_err(|e| format!("{}: {}.", path, Errno::from_i32(e.raw_os_error().unwrap_or(0)).desc()))?;
+        let elf = match elf::parse(&data) {
+            Some(elf) if elf.core => elf,
+            _ => return Err(format!("\"{}\" is not a core dump: file format not recognized", path)),
+        };
+        let mut threads: Vec<CoreThread> = Vec::new();
+        let mut maps = Vec::new();
+        let mut command = String::new();
+        for segment in elf.segments.iter().filter(|s| s.kind == PT_NOTE) {
+            for note in elf::notes(&data, segment) {
+                match note.kind {
+                    NT_PRSTATUS => {
+                        if let Some(thread) = thread(note.desc) {
+                            threads.push(thread);
+                        }
+                    }
+                    //the float registers of a thread follow its NT_PRSTATUS
+                    NT_FPREGSET | NT_X86_XSTATE => {
+                        if let Some(thread) = threads.last_mut() {
+                            if note.kind == NT_X86_XSTATE || thread.float_area.is_empty() {
+                                thread.float_area = note.desc.to_vec();
+                            }
+                        }
+                    }
+                    NT_PRPSINFO => {
+                        let args = note.desc.get(PRPSINFO_ARGS..PRPSINFO_ARGS + PRPSINFO_ARGS_LEN).unwrap_or(&[]);
+                        command = elf::read_string(args, 0).unwrap_or_default().trim_end().to_string();
+                    }
+                    NT_FILE => maps = file_maps(note.desc, &elf.segments).unwrap_or_default(),
+                    _ => (),
+                }
+            }
+        }
+        if threads.is_empty() {
+            return Err(format!("\"{}\": core file has no threads.", path));
+        }
+        let segments = elf.segments.into_iter().filter(|s| s.kind == PT_LOAD).collect();
+        Ok(CoreFile {
+            data,
+            segments,
+            maps,
+            threads,
+            command,
+            written: RefCell::new(HashMap::new()),
+        })
+    }
+
+    pub fn thread(&self, tid: Pid) -> Option<&CoreThread> {
+        self.threads.iter().find(|thread| thread.tid == tid)
+    }
+
+    //Read `len` bytes of the process's memory at `addr`, from the core file where it was saved and
+    //from the file mapped there otherwise. It is an error if any of it was in neither.
+    pub fn read_mem(&self, addr: usize, len: usize) -> Result<Vec<u8>, nix::Error> {
+        let mut bytes = Vec::with_capacity(len);
+        while bytes.len() < len {
+            let at = addr.checked_add(bytes.len()).ok_or(nix::Error::Sys(Errno::EIO))?;
+            let wanted = len - bytes.len();
+            //the segments are read from the file, so their offsets and sizes may be anything
+            let saved = self
+                .segments
+                .iter()
+                .find(|s| s.addr <= at && at - s.addr < s.file_size)
+                .and_then(|s| {
+                    let offset = s.offset.checked_add(at - s.addr)?;
+                    self.data.get(offset..offset.checked_add(wanted.min(s.file_size - (at - s.addr)))?)
+                });
+            match saved {
+                Some(chunk) => bytes.extend_from_slice(chunk),
+                None => bytes.extend(self.read_mapped_file(at, wanted)?),
+            }
+        }
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            if let Some(written) = self.written.borrow().get(&(addr + i)) {
+                *byte = *written;
+            }
+        }
+        Ok(bytes)
+    }
+
+    //Read up to `len` bytes at `addr` from the file mapped there
+    fn read_mapped_file(&self, addr: usize, len: usize) -> Result<Vec<u8>, nix::Error> {
+        let map = self
+            .maps
+            .iter()
+            .find(|map| map.start <= addr && addr < map.end)
+            .ok_or(nix::Error::Sys(Errno::EIO))?;
+        let file = File::open(&map.path).map_err(|_| nix::Error::Sys(Errno::EIO))?;
+        let mut chunk = vec![0; len.min(map.end - addr)];
+        let offset = map.offset.checked_add(addr - map.start).ok_or(nix::Error::Sys(Errno::EIO))?;
+        match file.read_at(&mut chunk, offset as u64) {
+            Ok(n) if n > 0 => {
+                chunk.truncate(n);
+                Ok(chunk)
+            }
+            _ => Err(nix::Error::Sys(Errno::EIO)),
+        }
+    }
+
+    //Change the process's memory, which is only kept by the debugger, as the process is gone
+    pub fn write_mem(&self, addr: usize, bytes: &[u8]) -> Result<(), nix::Error> {
+        self.read_mem(addr, bytes.len())?;
+        let mut written = self.written.borrow_mut();
+        for (i, byte) in bytes.iter().enumerate() {
+            written.insert(addr + i, *byte);
+        }
+        Ok(())
+    }
+}
+
+//A thread from its NT_PRSTATUS note
+fn thread(desc: &[u8]) -> Option<CoreThread> {
+    let regs = desc.get(PRSTATUS_REGS..PRSTATUS_REGS + size_of::<user_regs_struct>())?;
+    let registers = unsafe { std::ptr::read_unaligned(regs.as_ptr() as *const user_regs_struct) };
+    Some(CoreThread {
+        tid: Pid::from_raw(elf::read_u32(desc, PRSTATUS_PID)? as i32),
+        signal: elf::read_u16(desc, PRSTATUS_CURSIG)? as i32,
+        registers,
+        float_area: Vec::new(),
+    })
+}
+
+//The files mapped into the process, from the NT_FILE note. It has the number of mappings and the
+//page size, then the start, end and page offset in the file of each mapping, and then their
+//paths. Their permissions are those of the segments of the core file for them.
+fn file_maps(desc: &[u8], segments: &[Segment]) -> Option<Vec<MemoryMap>> {
+    let count = elf::read_u64(desc, 0)? as usize;
+    let page_size = elf::read_u64(desc, 8)? as usize;
+    let mut path_at = count.checked_mul(24)?.checked_add(16)?;
+    let mut maps = Vec::new();
+    for at in (0..count).map(|i| 16 + i * 24) {
+        let start = elf::read_u64(desc, at)? as usize;
+        let path = elf::read_string(desc, path_at)?;
+        path_at += path.len() + 1;
+        let permissions = segments
+            .iter()
+            .find(|s| s.kind == PT_LOAD && s.addr == start)
+            .map_or("---".to_string(), |s| s.permissions());
+        maps.push(MemoryMap {
+            start,
+            end: elf::read_u64(desc, at + 8)? as usize,
+            permissions: format!("{}p", permissions),
+            offset: (elf::read_u64(desc, at + 16)? as usize).checked_mul(page_size)?,
+            path,
+        });
+    }
+    Some(maps)
+}
+
+//The state of a live process, to write to a core file with `gcore`
+pub struct Snapshot {
+    pub pid: Pid,
+    //the threads, the one being debugged first
+    pub threads: Vec<CoreThread>,
+    pub maps: Vec<MemoryMap>,
+    //the contents of each of `maps`, or None where it can't be read
+    pub memory: Vec<Option<Vec<u8>>>,
+    //the command line, with the arguments separated by spaces
+    pub command: String,
+    //the auxiliary vector the kernel gave the process, which says e.g. where a position
+    //independent executable was loaded
+    pub auxv: Vec<u8>,
+}
+
+const NT_AUXV: u32 = 6;
+const PRSTATUS_SIZE: usize = 336;
+const PRSTATUS_FPVALID: usize = 328;
+const PRPSINFO_SIZE: usize = 136;
+const PRPSINFO_PID: usize = 24;
+const PRPSINFO_NAME: usize = 40;
+const PRPSINFO_NAME_LEN: usize = 16;
+const FXSAVE_SIZE: usize = 512;
+const ELF_HEADER_SIZE: usize = 64;
+const PROGRAM_HEADER_SIZE: usize = 56;
+const EM_X86_64: u16 = 62;
+const PAGE_SIZE: usize = 4096;
+
+//Write a process's state to a core file, laid out as the kernel lays out the core files it dumps:
+//a PT_NOTE segment with the registers of each thread and what files are mapped where, followed
+//by a PT_LOAD segment for each range of memory, with its contents if they could be read.
+pub fn write(path: &str, snapshot: &Snapshot) -> std::io::Result<()> {
+    let mut notes = Vec::new();
+    for thread in &snapshot.threads {
+        note(&mut notes, "CORE", NT_PRSTATUS, &prstatus(thread));
+        if thread.float_area.len() >= FXSAVE_SIZE {
+            note(&mut notes, "CORE", NT_FPREGSET, &thread.float_area[..FXSAVE_SIZE]);
+        }
+        if thread.float_area.len() > FXSAVE_SIZE {
+            note(&mut notes, "LINUX", NT_X86_XSTATE, &thread.float_area);
+        }
+    }
+    note(&mut notes, "CORE", NT_PRPSINFO, &prpsinfo(snapshot));
+    if !snapshot.auxv.is_empty() {
+        note(&mut notes, "CORE", NT_AUXV, &snapshot.auxv);
+    }
+    note(&mut notes, "CORE", NT_FILE, &file_note(&snapshot.maps));
+
+    let count = snapshot.maps.len() + 1;
+    let notes_offset = ELF_HEADER_SIZE + count * PROGRAM_HEADER_SIZE;
+    let mut data = elf_header(count as u16);
+    data.extend(program_header(PT_NOTE, 0, notes_offset, 0, notes.len(), 0, 0));
+    let mut offset = align_up(notes_offset + notes.len(), PAGE_SIZE);
+    for (map, memory) in snapshot.maps.iter().zip(&snapshot.memory) {
+        let saved = memory.as_ref().map_or(0, Vec::len);
+        let flags = map.permissions.chars().zip(&[4u32, 2, 1]).filter(|(c, _)| *c != '-').map(|(_, f)| *f).sum();
+        data.extend(program_header(PT_LOAD, flags, offset, map.start, saved, map.end - map.start, PAGE_SIZE));
+        offset += align_up(saved, PAGE_SIZE);
+    }
+    data.extend(notes);
+    for memory in snapshot.memory.iter().flatten() {
+        data.resize(align_up(data.len(), PAGE_SIZE), 0);
+        data.extend(memory);
+    }
+    std::fs::write(path, data)
+}
+
+fn align_up(value: usize, align: usize) -> usize {
+    (value + align - 1) & !(align - 1)
+}
+
+//A note: its name and the sizes of its name and description, its type, and then the name and the
+//description, each padded to 4 bytes
+fn note(notes: &mut Vec<u8>, name: &str, kind: u32, desc: &[u8]) {
+    notes.extend(&(name.len() as u32 + 1).to_le_bytes());
+    notes.extend(&(desc.len() as u32).to_le_bytes());
+    notes.extend(&kind.to_le_bytes());
+    notes.extend(name.as_bytes());
+    notes.push(0);
+    notes.resize(align_up(notes.len(), 4), 0);
+    notes.extend(desc);
+    notes.resize(align_up(notes.len(), 4), 0);
+}
+
+fn prstatus(thread: &CoreThread) -> Vec<u8> {
+    let mut desc = vec![0; PRSTATUS_SIZE];
+    //the signal is in the siginfo at the start as well as in pr_cursig
+    desc[..4].copy_from_slice(&thread.signal.to_le_bytes());
+    desc[PRSTATUS_CURSIG..PRSTATUS_CURSIG + 2].copy_from_slice(&(thread.signal as u16).to_le_bytes());
+    desc[PRSTATUS_PID..PRSTATUS_PID + 4].copy_from_slice(&thread.tid.as_raw().to_le_bytes());
+    let registers = unsafe {
+        std::slice::from_raw_parts(
+            &thread.registers as *const user_regs_struct as *const u8,
+            size_of::<user_regs_struct>(),
+        )
+    };
+    desc[PRSTATUS_REGS..PRSTATUS_REGS + registers.len()].copy_from_slice(registers);
+    let fpvalid = !thread.float_area.is_empty() as u32;
+    desc[PRSTATUS_FPVALID..PRSTATUS_FPVALID + 4].copy_from_slice(&fpvalid.to_le_bytes());
+    desc
+}
+
+//The process's id, the name of its program and its command line, each cut short to fit
+fn prpsinfo(snapshot: &Snapshot) -> Vec<u8> {
+    let mut desc = vec![0; PRPSINFO_SIZE];
+    desc[PRPSINFO_PID..PRPSINFO_PID + 4].copy_from_slice(&snapshot.pid.as_raw().to_le_bytes());
+    let program = snapshot.command.split(' ').next().unwrap_or("");
+    let name = program.rsplit('/').next().unwrap_or("").as_bytes();
+    let name = &name[..name.len().min(PRPSINFO_NAME_LEN - 1)];
+    desc[PRPSINFO_NAME..PRPSINFO_NAME + name.len()].copy_from_slice(name);
+    let args = snapshot.command.as_bytes();
+    let args = &args[..args.len().min(PRPSINFO_ARGS_LEN - 1)];
+    desc[PRPSINFO_ARGS..PRPSINFO_ARGS + args.len()].copy_from_slice(args);
+    desc
+}
+
+//The NT_FILE note for the ranges of memory that have a file mapped there
+fn file_note(maps: &[MemoryMap]) -> Vec<u8> {
+    let files: Vec<&MemoryMap> = maps.iter().filter(|map| map.path.starts_with('/')).collect();
+    let mut desc = Vec::new();
+    desc.extend(&(files.len() as u64).to_le_bytes());
+    desc.extend(&(PAGE_SIZE as u64).to_le_bytes());
+    for map in &files {
+        for value in &[map.start, map.end, map.offset / PAGE_SIZE] {
+            desc.extend(&(*value as u64).to_le_bytes());
+        }
+    }
+    for map in &files {
+        desc.extend(map.path.as_bytes());
+        desc.push(0);
+    }
+    desc
+}
+
+fn elf_header(program_headers: u16) -> Vec<u8> {
+    let mut header = vec![0; ELF_HEADER_SIZE];
+    //64-bit, little endian, version 1
+    header[..7].copy_from_slice(b"\x7fELF\x02\x01\x01");
+    header[0x10..0x12].copy_from_slice(&ET_CORE.to_le_bytes());
+    header[0x12..0x14].copy_from_slice(&EM_X86_64.to_le_bytes());
+    header[0x14..0x18].copy_from_slice(&1u32.to_le_bytes());
+    header[0x20..0x28].copy_from_slice(&(ELF_HEADER_SIZE as u64).to_le_bytes());
+    header[0x34..0x36].copy_from_slice(&(ELF_HEADER_SIZE as u16).to_le_bytes());
+    header[0x36..0x38].copy_from_slice(&(PROGRAM_HEADER_SIZE as u16).to_le_bytes());
+    header[0x38..0x3a].copy_from_slice(&program_headers.to_le_bytes());
+    header
+}
+
+fn program_header(
+    kind: u32,
+    flags: u32,
+    offset: usize,
+    addr: usize,
+    file_size: usize,
+    mem_size: usize,
+    align: usize,
+) -> Vec<u8> {
+    let mut header = Vec::with_capacity(PROGRAM_HEADER_SIZE);
+    header.extend(&kind.to_le_bytes());
+    header.extend(&flags.to_le_bytes());
+    //the physical address is the same as the virtual one
+    for value in &[offset, addr, addr, file_size, mem_size, align] {
+        header.extend(&(*value as u64).to_le_bytes());
+    }
+    header
+}
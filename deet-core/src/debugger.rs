@@ -0,0 +1,5302 @@
+use crate::core_file::{self, CoreFile};
+use crate::debugger_command::{DebuggerCommand, Redirections, COMMANDS};
+use crate::disassemble::{self, MAX_INSTRUCTION_LEN};
+use crate::dwarf_data::{DwarfData, Encoding, Error as DwarfError, Line, TypeKind};
+use crate::elf::{self, PT_LOAD};
+use crate::exception::{self, CATCH_FUNCTION, THROW_FUNCTION};
+use crate::expression::{self, Evaluator, Expr, Value};
+#[cfg(feature = "cli")]
+use crate::interrupt;
+use crate::inferior::{frame_description, register_field, BacktraceFrame, Frame, Inferior, MemoryMap, Status, HARDWARE_SLOTS};
+use nix::sys::signal::Signal;
+#[cfg(feature = "cli")]
+use crate::line_editor::{self, Completions};
+#[cfg(feature = "scripting")]
+use crate::script::Scripts;
+use crate::listing::Listing;
+use crate::remote::RemoteTarget;
+use crate::logging;
+use crate::session::Event;
+use crate::settings::Settings;
+use crate::signals::{self, SignalTable};
+use crate::source_cache::SourceCache;
+use crate::value_format::{read_uint, symbol_label, Format, ValueFormatter};
+use regex::Regex;
+#[cfg(feature = "cli")]
+use rustyline::error::ReadlineError;
+#[cfg(feature = "cli")]
+use rustyline::Editor;
+#[cfg(feature = "cli")]
+use nix::poll::{poll, PollFd, PollFlags};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::convert::TryFrom;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+//struct to represent the breakpoints set in the program
+#[derive(Clone)]
+pub struct Breakpoint {
+    //the number shown by `info break`, or None for a breakpoint the debugger set for itself, e.g.
+    //for a catchpoint
+    pub number: Option<usize>,
+    //the address of the breakpoint
+    pub addr: usize,
+    //the original byte replaced by the breakpoint
+    pub orig_byte: u8,
+    //disabled breakpoints stay in the list but aren't written into the child process
+    pub enabled: bool,
+    //the group the breakpoint was tagged with, so that related breakpoints can be enabled,
+    //disabled or deleted together
+    pub group: Option<String>,
+    //an expression that has to be true (non-zero) for the breakpoint to stop the program
+    pub condition: Option<String>,
+    //how many times the breakpoint has been reached with its condition true, since the program
+    //was last run
+    pub hit_count: usize,
+    //how many more times the breakpoint is passed before it stops the program again
+    pub ignore_count: usize,
+    //a temporary breakpoint, set by `tbreak`, is deleted the first time it stops the program
+    pub temporary: bool,
+    //commands run every time the breakpoint stops the program, set with `commands`
+    pub commands: Vec<String>,
+    //the arguments of `printf` for a breakpoint set with `dprintf`, which prints them every time
+    //it is hit instead of stopping the program
+    pub dprintf: Option<String>,
+    //the debug register used by a hardware breakpoint, set with `hbreak`, which stops the program
+    //without changing its code
+    pub hardware_slot: Option<usize>,
+    //where the user asked for the breakpoint, e.g. `parser.c:42` or `main`, to find it again in a
+    //new program the child execs. None for the debugger's own breakpoints.
+    pub location: Option<String>,
+}
+
+//struct to represent a watchpoint, which stops the program when some memory changes
+pub struct Watchpoint {
+    pub number: usize,
+    //what the user asked to watch, shown when the watchpoint triggers
+    pub expression: String,
+    //the memory being watched
+    pub addr: usize,
+    pub len: usize,
+    //the type the watched memory is shown as
+    pub type_offset: usize,
+    //contents of the watched memory when it was last checked
+    pub old_value: Vec<u8>,
+    //only changes to these bits of the watched value trigger the watchpoint
+    pub mask: Option<u64>,
+    //debug registers watching the memory. Empty for a software watchpoint, which is checked
+    //after every instruction because the memory doesn't fit in the free debug registers.
+    pub hardware_slots: Vec<usize>,
+    //a disabled watchpoint isn't checked and gives up its debug registers
+    pub enabled: bool,
+    //for a watchpoint on a variable in a frame, the frame's return address and base, so that the
+    //watchpoint can be deleted when the frame returns and its memory is reused
+    pub scope: Option<(usize, usize)>,
+}
+
+//struct to represent a catchpoint, which stops the program when a C++ exception is thrown or caught
+pub struct Catchpoint {
+    pub number: usize,
+    //the C++ runtime function called when the event happens
+    pub function: &'static str,
+    //the PLT stub of that function, where the breakpoint for the catchpoint is
+    pub addr: usize,
+    pub enabled: bool,
+}
+
+//an expression shown every time the program stops, set with `display`
+pub struct Display {
+    pub number: usize,
+    pub expression: String,
+    //the letters after the `/`: a format as for `print`, such as `x`, or one only `x` has, such
+    //as `i`, which shows the memory at the address the expression gives
+    pub letters: String,
+    //the value it was last shown with, if it could be evaluated, which is shown alongside the new
+    //one when it has changed
+    pub last: Option<String>,
+}
+
+//size of a page of memory, which the segments of the program are mapped in
+const PAGE_SIZE: usize = 4096;
+
+//the function in the dynamic linker that it calls whenever it has loaded or is about to unload
+//libraries, for debuggers to break on
+const SHLIB_EVENT_FUNCTION: &str = "_dl_debug_state";
+
+//how deeply user-defined commands can run inside each other, which stops one that runs itself
+const MAX_USER_CALL_DEPTH: usize = 64;
+
+//the user-defined command run whenever the program stops
+const STOP_HOOK: &str = "hook-stop";
+
+//the file of commands run at startup, in the home directory and then in the working directory
+#[cfg(feature = "cli")]
+const INIT_FILE: &str = ".deetrc";
+
+//Debugger struct
+pub struct Debugger {
+    //path to the C executable file
+    target: String,
+    //the source files of the program
+    sources: SourceCache,
+    //where `list` is in the source, once something has been listed or the program has stopped
+    listing: Option<Listing>,
+    //history file for this target, if there is somewhere to keep it
+    #[cfg(feature = "cli")]
+    history_path: Option<String>,
+    //shortcuts defined with `alias`, by name, and the file they are saved in, shared by all targets
+    aliases: BTreeMap<String, String>,
+    aliases_path: Option<String>,
+    //commands defined with `define`, by name
+    user_commands: BTreeMap<String, Vec<String>>,
+    //how many user-defined commands are running inside each other
+    user_call_depth: usize,
+    //whether the child has stopped since the user-defined command `hook-stop` last ran
+    stop_hook_pending: bool,
+    //the scripts loaded with `script`. None while one of them runs.
+    #[cfg(feature = "scripting")]
+    scripts: Option<Scripts>,
+    //utility to read line entered to the debugger
+    #[cfg(feature = "cli")]
+    readline: Editor<Completions>,
+    //utility to change the status of the child process being examined by the debugger
+    inferior: Option<Inferior>,
+    //the number of the inferior in `inferior`, which `inferior <n>` switches between
+    inferior_number: usize,
+    //the other inferiors by number, such as the other side of a fork kept with
+    //`set detach-on-fork off`. None for one whose process has exited or been killed.
+    other_inferiors: BTreeMap<usize, Option<Inferior>>,
+    //the number given to the next inferior
+    next_inferior_number: usize,
+    //copies of the program kept stopped by `checkpoint`, by number, to go back to with `restart`.
+    //They have none of the breakpoints in them.
+    checkpoints: BTreeMap<usize, Inferior>,
+    //the number given to the next checkpoint
+    next_checkpoint: usize,
+    //a copy of the program as it was when `record` started recording it, kept stopped, which the
+    //reverse commands run forward again to go back to an earlier point
+    record: Option<Inferior>,
+    //meta data about the child process
+    debug_data: DwarfData,
+    //breakpoints in the child process
+    breakpoints: HashMap<usize, Breakpoint>,
+    //settings changed with the `set` command
+    settings: Settings,
+    //what is done with each signal the program gets, changed with `handle`
+    signals: SignalTable,
+    //watchpoints set with the `watch` command
+    watchpoints: Vec<Watchpoint>,
+    //catchpoints set with the `catch` command
+    catchpoints: Vec<Catchpoint>,
+    //expressions set with `display`, shown whenever the program stops
+    displays: Vec<Display>,
+    //the number given to the next display
+    next_display: usize,
+    //PLT stubs with a breakpoint for `set trace-plt on`, which logs the shared library function
+    //each stub calls the first time it is called, and is then removed
+    traced_stubs: HashSet<usize>,
+    //breakpoints on functions in shared libraries that haven't been loaded yet, set once they are
+    pending_breakpoints: Vec<Breakpoint>,
+    //the function in the dynamic linker it calls whenever it loads or unloads a library, which has
+    //a breakpoint while there are pending breakpoints
+    shlib_event: Option<usize>,
+    //the number given to the next breakpoint, watchpoint or catchpoint
+    next_number: usize,
+    //what happened to the child process during the last command, for `Session`
+    events: Vec<Event>,
+    //the values shown by `print`
+    value_history: Vec<Value>,
+    //the commands of the breakpoint the child last stopped at, to be run once the command that
+    //resumed the child is done
+    pending_commands: Option<Vec<String>>,
+    //the format and unit size of the last `x`, and the address after what it showed, which `x`
+    //without an address carries on from
+    examine_format: Format,
+    examine_size: usize,
+    examine_next: Option<usize>,
+    //the frame selected with `frame`, `up` or `down`, 0 being the innermost. It goes back to 0
+    //whenever the child stops.
+    selected_frame: usize,
+    //Some while the child runs in the background, after `run &` or `continue &`, with whether it
+    //is being single stepped for a software watchpoint
+    running: Option<bool>,
+}
+
+impl Debugger {
+    /// Create the debugger, with the user's history, inputrc and aliases, and run the commands in
+    /// their init files unless `init_files` is false.
+    #[cfg(feature = "cli")]
+    pub fn new(target: &str, init_files: bool) -> Debugger {
+        let mut debugger = match Debugger::load(target) {
+            Ok(debugger) => debugger,
+            Err(e) => {
+                println!("{}", e);
+                std::process::exit(1);
+            }
+        };
+        debugger.debug_data.print();
+        debugger.history_path = line_editor::history_path(target);
+        if let Some(path) = &debugger.history_path {
+            line_editor::load_history(&mut debugger.readline, path);
+        }
+        line_editor::configure(&mut debugger.readline, &mut debugger.settings);
+        debugger.aliases_path = std::env::var("HOME").ok().map(|home| format!("{}/.deet_aliases", home));
+        debugger.load_aliases();
+        if init_files && !debugger.run_init_files() {
+            std::process::exit(0);
+        }
+        debugger
+    }
+
+    /// Create the debugger without reading the user's history or inputrc, for running commands
+    /// that don't come from the terminal (see `Session`).
+    pub fn load(target: &str) -> Result<Debugger, String> {
+        //read the metadata from the file to be examined
+        let debug_data = match DwarfData::from_file(target) {
+            Ok(val) => val,
+            Err(DwarfError::ErrorOpeningFile) => {
+                return Err(format!("Could not open file {}", target));
+            }
+            Err(DwarfError::DwarfFormatError(err)) => {
+                return Err(format!("Could not debugging symbols from {}: {:?}", target, err));
+            }
+        };
+        let sources = SourceCache::new(&debug_data, target);
+        let breakpoints = HashMap::new();
+        #[cfg(feature = "cli")]
+        let functions = debug_data.get_functions().iter().map(|func| func.name.clone()).collect();
+        Ok(Debugger {
+            target: target.to_string(),
+            #[cfg(feature = "cli")]
+            history_path: None,
+            aliases: BTreeMap::new(),
+            aliases_path: None,
+            user_commands: BTreeMap::new(),
+            user_call_depth: 0,
+            stop_hook_pending: false,
+            #[cfg(feature = "scripting")]
+            scripts: Some(Scripts::new()),
+            #[cfg(feature = "cli")]
+            readline: line_editor::new_editor(functions),
+            inferior: None,
+            inferior_number: 1,
+            other_inferiors: BTreeMap::new(),
+            next_inferior_number: 2,
+            checkpoints: BTreeMap::new(),
+            next_checkpoint: 1,
+            record: None,
+            debug_data,
+            breakpoints,
+            sources,
+            listing: None,
+            settings: Settings::new(),
+            signals: SignalTable::new(),
+            watchpoints: Vec::new(),
+            catchpoints: Vec::new(),
+            displays: Vec::new(),
+            next_display: 1,
+            traced_stubs: HashSet::new(),
+            pending_breakpoints: Vec::new(),
+            shlib_event: None,
+            next_number: 1,
+            events: Vec::new(),
+            value_history: Vec::new(),
+            pending_commands: None,
+            examine_format: Format::Hex,
+            examine_size: 4,
+            examine_next: None,
+            selected_frame: 0,
+            running: None,
+        })
+    }
+
+    /// Look at the process a core file was dumped from, as `core-file` does.
+    pub fn load_core_file(&mut self, path: &str) {
+        if let Err(e) = self.load_core(path) {
+            println!("{}", e);
+        }
+    }
+
+    //Create the debugger and run it 
+    #[cfg(feature = "cli")]
+    pub fn run(&mut self) {
+        //Ctrl+C stops the program rather than the debugger
+        interrupt::catch_interrupts().expect("Error catching SIGINT");
+        //Inside the infinety loop, the debugger will repeatedly take user command and perform the tasks requested
+        loop {
+            let cmd = match self.running {
+                Some(_) => match self.next_command_while_running() {
+                    Some(cmd) => cmd,
+                    //the child stopped, and the commands of a breakpoint it stopped at are run
+                    None => {
+                        if !self.run_pending_commands() {
+                            return;
+                        }
+                        continue;
+                    }
+                },
+                None => self.get_next_command(),
+            };
+            if !self.execute(cmd) {
+                return;
+            }
+        }
+    }
+
+    /// Run a line as if it was typed at the prompt, e.g. a command given on the command line.
+    /// Returns false if it was to quit the debugger. An empty line does nothing.
+    pub fn run_line(&mut self, line: &str) -> bool {
+        if line.trim().is_empty() {
+            return true;
+        }
+        self.log_command(line);
+        let line = self.expand_alias(line);
+        match self.parse_line(&line) {
+            Some(cmd) => self.execute(cmd),
+            None => {
+                println!("Undefined command: \"{}\".", line.split_whitespace().next().unwrap_or(""));
+                true
+            }
+        }
+    }
+
+    //Run a line for a script, like `run_line`, leaving the commands of any breakpoint it stops
+    //at to the command that ran the script. Returns false if it was to quit the debugger.
+    #[cfg(feature = "scripting")]
+    pub(crate) fn run_script_command(&mut self, line: &str) -> bool {
+        let line = self.expand_alias(line);
+        match self.parse_line(&line) {
+            Some(cmd) => self.execute_command(cmd),
+            None => {
+                println!("Undefined command: \"{}\".", line.split_whitespace().next().unwrap_or(""));
+                true
+            }
+        }
+    }
+
+    /// Carry out a command, and then the commands of any breakpoint it stopped the child at.
+    /// Returns false if the command was to quit the debugger.
+    pub fn execute(&mut self, cmd: DebuggerCommand) -> bool {
+        self.events.clear();
+        self.pending_commands = None;
+        if self.running.is_some() {
+            return self.execute_while_running(cmd);
+        }
+        if !self.execute_command(cmd) {
+            return false;
+        }
+        self.run_pending_commands()
+    }
+
+    //Run `hook-stop` if the child has stopped, and the commands of the breakpoint it last stopped
+    //at. Returns false if one of them was to quit the debugger.
+    fn run_pending_commands(&mut self) -> bool {
+        if !self.run_stop_hook() {
+            return false;
+        }
+        //a command that resumes the child ends the list it is in, like in gdb, and the child may
+        //stop at another breakpoint with commands of its own
+        while let Some(commands) = self.pending_commands.take() {
+            for line in commands {
+                let stops = self.events.len();
+                match self.parse_line(&line) {
+                    Some(cmd) => {
+                        if !self.execute_command(cmd) {
+                            return false;
+                        }
+                    }
+                    None => {
+                        println!("Unrecognized command \"{}\" in breakpoint commands", line);
+                        break;
+                    }
+                }
+                if self.events.len() != stops {
+                    break;
+                }
+            }
+            if !self.run_stop_hook() {
+                return false;
+            }
+        }
+        true
+    }
+
+    //Run the user-defined command `hook-stop`, if there is one, once the child has stopped, before
+    //the commands of the breakpoint it stopped at. Returns false if it was to quit the debugger.
+    fn run_stop_hook(&mut self) -> bool {
+        if !std::mem::take(&mut self.stop_hook_pending) || !self.user_commands.contains_key(STOP_HOOK) {
+            return true;
+        }
+        self.run_user_command(STOP_HOOK, &[])
+    }
+
+    fn execute_command(&mut self, cmd: DebuggerCommand) -> bool {
+        //the process of a core file can be looked at but not resumed. `run` starts the program
+        //instead.
+        let starts = match &cmd {
+            DebuggerCommand::Run(_) => true,
+            DebuggerCommand::Background(cmd) => matches!(**cmd, DebuggerCommand::Run(_)),
+            _ => false,
+        };
+        if self.examining_core() && cmd.resumes() && !starts {
+            println!("The program is not being run.");
+            return true;
+        }
+        match cmd {
+            //Input the args into the child process and run it
+            DebuggerCommand::Run(args) => {
+                if self.start_program(args) {
+                    //Wait for child process to stop or exit and print its status
+                    match self.resume() {
+                        Ok(s) => self.print_child_status(s),
+                        Err(e) => println!("Cannot run child process. Error: {}", e),
+                    }
+                }
+            }
+            //Run the program or carry on with it in the background
+            DebuggerCommand::Background(cmd) => {
+                if let DebuggerCommand::Run(args) = *cmd {
+                    if !self.start_program(args) {
+                        return true;
+                    }
+                } else if self.inferior.is_none() {
+                    println!("The program is not being run.");
+                    return true;
+                }
+                self.resume_in_background();
+            }
+            DebuggerCommand::Interrupt => {
+                //the program isn't running in the background, so it is stopped already
+                if self.inferior.is_none() {
+                    println!("The program is not being run.");
+                }
+            }
+            //Quit the debugger. Againg it needs to check if there is any child process that has not been reaped and reap it
+            DebuggerCommand::Quit => {
+                if self.examining_core() {
+                    self.forget_process();
+                }
+                if let Some(inf) = self.inferior.as_mut() {
+                    if inf.kill_child().is_ok() { println!("Child {} killed", inf.process_id()) }
+                }
+                for inf in self.other_inferiors.values_mut().filter_map(Option::as_mut) {
+                    if inf.kill_child().is_ok() {
+                        println!("Child {} killed", inf.process_id());
+                    }
+                }
+                for inf in self.checkpoints.values_mut() {
+                    inf.kill_child().ok();
+                }
+                self.stop_recording();
+                return false;
+            }
+            DebuggerCommand::Kill => {
+                if let Err(e) = self.kill() {
+                    println!("{}", e);
+                }
+            }
+            DebuggerCommand::Detach => {
+                if let Err(e) = self.detach() {
+                    println!("{}", e);
+                }
+            }
+            //Continue from the breakpoints
+            DebuggerCommand::Continue => {
+                if self.inferior.is_none() {
+                    println!("No process is currently being run");
+                    return true;
+                }
+                //resume the child process until it is paused or exists and print its status
+                match self.resume() {
+                    Ok(s) => self.print_child_status(s),
+                    Err(e) => println!("Cannot run child process. Error: {}", e),
+                }
+            }
+            //Step to the next source line, into any function called on the way for `step` and
+            //over it for `next`
+            DebuggerCommand::Step(count)
+            | DebuggerCommand::Next(count)
+            | DebuggerCommand::StepInstruction(count)
+            | DebuggerCommand::NextInstruction(count) => {
+                if self.inferior.is_none() {
+                    println!("The program is not being run.");
+                    return true;
+                }
+                let into_calls =
+                    matches!(cmd, DebuggerCommand::Step(_) | DebuggerCommand::StepInstruction(_));
+                let by_instruction = matches!(
+                    cmd,
+                    DebuggerCommand::StepInstruction(_) | DebuggerCommand::NextInstruction(_)
+                );
+                for i in 0..count {
+                    let result = if by_instruction {
+                        self.step_instruction(into_calls)
+                    } else {
+                        self.step(into_calls)
+                    };
+                    let status = match result {
+                        Ok(s) => s,
+                        Err(e) => {
+                            println!("Cannot step child process. Error: {}", e);
+                            break;
+                        }
+                    };
+                    //a breakpoint or signal on the way ends the stepping early
+                    let stepped = match status {
+                        Status::Stopped(Signal::SIGTRAP, rip) => {
+                            !self.breakpoints.get(&rip).is_some_and(|b| b.enabled)
+                        }
+                        _ => false,
+                    };
+                    if stepped && by_instruction && i + 1 == count {
+                        self.print_instruction_location(status);
+                    } else if i + 1 == count || !stepped {
+                        self.print_child_status(status);
+                        break;
+                    }
+                }
+            }
+            //Run until the current function returns to its caller
+            DebuggerCommand::Finish => {
+                if let Err(e) = self.finish() {
+                    println!("{}", e);
+                }
+            }
+            DebuggerCommand::Until(location) => match self.until(location.trim()) {
+                Ok(status) => self.print_child_status(status),
+                Err(e) => println!("{}", e),
+            },
+            DebuggerCommand::Advance(location) => match self.advance(location.trim(), false) {
+                Ok(status) => self.print_child_status(status),
+                Err(e) => println!("{}", e),
+            },
+            DebuggerCommand::Jump(location) => match self.jump(location.trim()) {
+                Ok(status) => self.print_child_status(status),
+                Err(e) => println!("{}", e),
+            },
+            DebuggerCommand::Return(value) => {
+                if let Err(e) = self.force_return(value.trim()) {
+                    println!("{}", e);
+                }
+            }
+            //Print the call stack backtrace
+            DebuggerCommand::Backtrace(args) => {
+                if self.inferior.is_none() {
+                    println!("No process is currently being run");
+                    return true;
+                }
+                let mut full = false;
+                let mut limit = None;
+                for arg in args.split_whitespace() {
+                    match arg {
+                        "full" | "-full" => full = true,
+                        _ => match arg.parse() {
+                            Ok(n) => limit = Some(n),
+                            Err(_) => {
+                                println!("No symbol \"{}\" in current context.", arg);
+                                return true;
+                            }
+                        },
+                    }
+                }
+                let inf = self.inferior.as_ref().unwrap();
+                if let Err(e) = inf.print_backtrace(&self.debug_data, &self.settings, full, limit) {
+                    println!("Cannot print backtrace. Error: {}", e);
+                }
+            }
+            DebuggerCommand::List(args) => match self.list(&args) {
+                Ok(lines) => lines.iter().for_each(|line| println!("{}", line)),
+                Err(e) => println!("{}", e),
+            },
+            DebuggerCommand::Search(pattern) => match self.search(&pattern, true) {
+                Ok(line) => println!("{}", line),
+                Err(e) => println!("{}", e),
+            },
+            DebuggerCommand::ReverseSearch(pattern) => match self.search(&pattern, false) {
+                Ok(line) => println!("{}", line),
+                Err(e) => println!("{}", e),
+            },
+            DebuggerCommand::Frame(level) => {
+                let level = level.unwrap_or(self.selected_frame);
+                if let Err(e) = self.select_frame(level) {
+                    println!("{}", e);
+                }
+            }
+            DebuggerCommand::Up(count) => {
+                if let Err(e) = self.move_frame(count as isize) {
+                    println!("{}", e);
+                }
+            }
+            DebuggerCommand::Down(count) => {
+                if let Err(e) = self.move_frame(-(count as isize)) {
+                    println!("{}", e);
+                }
+            }
+            //Set the breakpoint in the child process
+            DebuggerCommand::Break(args) => self.set_breakpoint(&args, false, false),
+            DebuggerCommand::TemporaryBreak(args) => self.set_breakpoint(&args, true, false),
+            DebuggerCommand::HardwareBreak(args) => self.set_breakpoint(&args, false, true),
+            DebuggerCommand::RegexBreak(pattern) => self.break_on_regex(&pattern),
+            DebuggerCommand::Dprintf(args) => {
+                if let Err(e) = self.set_dprintf(&args) {
+                    println!("{}", e);
+                }
+            }
+            DebuggerCommand::Ignore(args) => {
+                if let Err(e) = self.ignore(&args) {
+                    println!("{}", e);
+                }
+            }
+            DebuggerCommand::Condition(args) => {
+                if let Err(e) = self.set_condition(&args) {
+                    println!("{}", e);
+                }
+            }
+            DebuggerCommand::Script(path) => {
+                if let Err(e) = self.load_script(path.trim()) {
+                    println!("{}", e);
+                }
+            }
+            DebuggerCommand::Source(path) => return self.source(path.trim()),
+            DebuggerCommand::Define(name, body) => {
+                if let Err(e) = self.define(name.trim(), body) {
+                    println!("{}", e);
+                }
+            }
+            DebuggerCommand::User(name, args) => return self.run_user_command(&name, &args),
+            DebuggerCommand::Commands(number, commands) => {
+                if let Err(e) = self.set_commands(&number, commands) {
+                    println!("{}", e);
+                }
+            }
+            DebuggerCommand::Catch(event) => {
+                if let Err(e) = self.catch(&event) {
+                    println!("{}", e);
+                }
+            }
+            DebuggerCommand::Enable(args) => self.change_breakpoints("enable", &args),
+            DebuggerCommand::Disable(args) => self.change_breakpoints("disable", &args),
+            DebuggerCommand::Delete(args) => match args.strip_prefix("checkpoint") {
+                Some(number) => {
+                    if let Err(e) = self.delete_checkpoint(number.trim()) {
+                        println!("{}", e);
+                    }
+                }
+                None => match args.strip_prefix("display") {
+                    Some(numbers) => {
+                        if let Err(e) = self.undisplay(numbers.trim()) {
+                            println!("{}", e);
+                        }
+                    }
+                    None => self.change_breakpoints("delete", &args),
+                },
+            },
+            DebuggerCommand::Info(what) => match what.as_str() {
+                "all-registers" => self.print_registers("all"),
+                _ if what.split(' ').next().is_some_and(|w| w == "r" || w == "registers") => {
+                    let args = what.split_once(' ').map(|x| x.1).unwrap_or("");
+                    self.print_registers(args)
+                }
+                "files" | "target" => self.print_files(),
+                "locals" => match self.locals() {
+                    Ok(locals) if locals.is_empty() => println!("No locals."),
+                    Ok(locals) => {
+                        for (name, value) in locals {
+                            println!("{} = {}", name, value);
+                        }
+                    }
+                    Err(e) => println!("{}", e),
+                },
+                "b" | "break" | "breakpoints" | "watchpoints" => self.print_breakpoints(),
+                "inferiors" => self.print_inferiors(),
+                "checkpoints" => self.print_checkpoints(),
+                "display" => self.print_displays(),
+                "record" | "rec" => self.print_record(),
+                "sharedlibrary" | "shared" | "dll" => self.print_shared_libraries(),
+                _ if what.split(' ').next() == Some("proc") => {
+                    let args = what.split_once(' ').map(|x| x.1).unwrap_or("");
+                    if let Err(e) = self.print_proc(args.trim()) {
+                        println!("{}", e);
+                    }
+                }
+                "threads" => self.print_threads(),
+                _ if what.split(' ').next().is_some_and(|w| w == "signals" || w == "handle") => {
+                    let args = what.split_once(' ').map(|x| x.1).unwrap_or("");
+                    self.print_signals(args)
+                }
+                "" => println!("\"info\" must be followed by the name of an info command."),
+                _ => println!("Undefined info command: \"{}\".", what),
+            },
+            DebuggerCommand::Set(name, value) => {
+                if let Err(e) = self.settings.set(&name, &value) {
+                    println!("{}", e);
+                } else if name == "editing-mode" {
+                    #[cfg(feature = "cli")]
+                    line_editor::set_editing_mode(&mut self.readline, self.settings.editing_mode);
+                } else if name == "follow-fork-mode" {
+                    if let Some(inferior) = self.inferior.as_mut() {
+                        inferior.set_follow_fork_mode(self.settings.follow_fork_mode);
+                    }
+                } else if name == "detach-on-fork" {
+                    if let Some(inferior) = self.inferior.as_mut() {
+                        inferior.set_detach_on_fork(self.settings.detach_on_fork);
+                    }
+                } else if name == "scheduler-locking" {
+                    if let Some(inferior) = self.inferior.as_mut() {
+                        inferior.set_scheduler_locking(self.settings.scheduler_locking);
+                    }
+                } else if name == "logging" {
+                    self.update_logging(&value);
+                } else if name == "trace-plt" {
+                    if self.settings.trace_plt {
+                        self.start_plt_trace();
+                    } else {
+                        self.stop_plt_trace();
+                    }
+                }
+            }
+            DebuggerCommand::Assign(text) => {
+                if let Err(e) = self.evaluate_text(&text) {
+                    println!("{}", e);
+                }
+            }
+            DebuggerCommand::Apropos(keyword) => apropos(keyword.trim()),
+            DebuggerCommand::Alias(args) => {
+                if let Err(e) = self.alias(args.trim()) {
+                    println!("{}", e);
+                }
+            }
+            DebuggerCommand::Unalias(name) => {
+                if self.aliases.remove(name.trim()).is_none() {
+                    println!("No alias named \"{}\".", name.trim());
+                } else {
+                    self.save_aliases();
+                }
+            }
+            DebuggerCommand::Show(name) if name == "user" || name.starts_with("user ") => {
+                self.show_user_commands(name["user".len()..].trim())
+            }
+            DebuggerCommand::Show(name) => match self.settings.show(&name) {
+                Ok(description) => println!("{}", description),
+                Err(e) => println!("{}", e),
+            },
+            DebuggerCommand::Signal(name) => {
+                if let Err(e) = self.continue_with_signal(&name) {
+                    println!("{}", e);
+                }
+            }
+            DebuggerCommand::CoreFile(path) => {
+                if let Err(e) = self.load_core(path.trim()) {
+                    println!("{}", e);
+                }
+            }
+            DebuggerCommand::Target(args) => {
+                if let Err(e) = self.target(args.trim()) {
+                    println!("{}", e);
+                }
+            }
+            DebuggerCommand::GenerateCore(path) => {
+                if let Err(e) = self.generate_core(path.trim()) {
+                    println!("{}", e);
+                }
+            }
+            DebuggerCommand::Checkpoint => {
+                if let Err(e) = self.checkpoint() {
+                    println!("{}", e);
+                }
+            }
+            DebuggerCommand::Restart(number) => {
+                if let Err(e) = self.restart(number.trim()) {
+                    println!("{}", e);
+                }
+            }
+            DebuggerCommand::Record(args) => {
+                if let Err(e) = self.record(args.trim()) {
+                    println!("{}", e);
+                }
+            }
+            DebuggerCommand::ReverseStep
+            | DebuggerCommand::ReverseStepInstruction
+            | DebuggerCommand::ReverseContinue => {
+                if let Err(e) = self.reverse(&cmd) {
+                    println!("{}", e);
+                }
+            }
+            DebuggerCommand::Inferior(args) => {
+                if let Err(e) = self.switch_inferior(args.trim()) {
+                    println!("{}", e);
+                }
+            }
+            DebuggerCommand::Thread(args) => {
+                if let Err(e) = self.switch_thread(args.trim()) {
+                    println!("{}", e);
+                }
+            }
+            DebuggerCommand::Handle(args) => match self.signals.handle(&args) {
+                Ok(signals) => {
+                    if let Some(inferior) = self.inferior.as_mut() {
+                        inferior.set_signals(self.signals.clone());
+                    }
+                    self.signals.lines(&signals).iter().for_each(|line| println!("{}", line));
+                }
+                Err(e) => println!("{}", e),
+            },
+            DebuggerCommand::Watch(args) => {
+                if let Err(e) = self.watch(&args) {
+                    println!("{}", e);
+                }
+            }
+            DebuggerCommand::Printf(args) => {
+                if let Err(e) = self.printf(&args) {
+                    println!("{}", e);
+                }
+            }
+            DebuggerCommand::Echo(text) => match expression::unescape(&text) {
+                Ok(bytes) => {
+                    print!("{}", String::from_utf8_lossy(&bytes));
+                    let _ = std::io::stdout().flush();
+                }
+                Err(e) => println!("{}", e),
+            },
+            DebuggerCommand::Print(letters, expr) => {
+                let result = parse_print_format(&letters, "print").and_then(|format| self.print(&expr, format));
+                if let Err(e) = result {
+                    println!("{}", e);
+                }
+            }
+            DebuggerCommand::Display(letters, expr) => {
+                if let Err(e) = self.display(&letters, expr.trim()) {
+                    println!("{}", e);
+                }
+            }
+            DebuggerCommand::Undisplay(numbers) => {
+                if let Err(e) = self.undisplay(numbers.trim()) {
+                    println!("{}", e);
+                }
+            }
+            DebuggerCommand::Examine(letters, expr) => {
+                if let Err(e) = self.examine(&letters, &expr) {
+                    println!("{}", e);
+                }
+            }
+            DebuggerCommand::Disassemble(letters, args) => match self.disassemble(&letters, &args) {
+                Ok(lines) => lines.iter().for_each(|line| println!("{}", line)),
+                Err(e) => println!("{}", e),
+            },
+            DebuggerCommand::Output(letters, expr) => match parse_print_format(&letters, "output")
+                .and_then(|format| self.format_expression(&expr, format))
+            {
+                Ok(text) => {
+                    print!("{}", text);
+                    let _ = std::io::stdout().flush();
+                }
+                Err(e) => println!("{}", e),
+            },
+        }
+        true
+    }
+
+   
+    #[cfg(feature = "cli")]
+    fn get_next_command(&mut self) -> DebuggerCommand {
+        loop {
+            let numbers = self.breakpoint_numbers();
+            let names = self.aliases.keys().chain(self.user_commands.keys()).cloned().collect();
+            if let Some(completions) = self.readline.helper_mut() {
+                completions.numbers = numbers;
+                completions.commands = names;
+            }
+            // Print prompt and get next line of user input
+            let line = match self.read_line("(deet) ") {
+                Err(ReadlineError::Interrupted) => {
+                    // User pressed ctrl+c. We're going to ignore it
+                    println!("Type \"quit\" to exit");
+                    continue;
+                }
+                Err(ReadlineError::Eof) => {
+                    // User pressed ctrl+d, which is the equivalent of "quit"
+                    return DebuggerCommand::Quit;
+                }
+                Err(err) => {
+                    //the terminal has gone, so no more commands can be read
+                    println!("Cannot read a command: {}", err);
+                    return DebuggerCommand::Quit;
+                }
+                Ok(line) => line,
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let line = self.take_line(&line);
+            //Some commands are followed by a block of lines ending with `end`
+            let cmd = if DebuggerCommand::starts_block(&line) {
+                match self.read_block() {
+                    Some(body) => DebuggerCommand::from_block(&line, body),
+                    None => continue,
+                }
+            } else {
+                self.parse_line(&line)
+            };
+            if let Some(cmd) = cmd {
+                return cmd;
+            } else {
+                println!("Unrecognized command.");
+            }
+        }
+    }
+
+    //Log a line typed at the prompt and save it to the history, and expand the alias it starts
+    //with, if any
+    #[cfg(feature = "cli")]
+    fn take_line(&mut self, line: &str) -> String {
+        self.log_command(line);
+        //Only lines that were added, and not dropped as a repeat of the last one, are saved
+        if self.readline.add_history_entry(line) {
+            if let Some(path) = &self.history_path {
+                if let Err(err) = line_editor::append_history(path, line) {
+                    println!("Warning: failed to save history file at {}: {}", path, err);
+                }
+            }
+        }
+        self.expand_alias(line)
+    }
+
+    /// Parse a line as a command, which may be one defined with `define`
+    pub fn parse_line(&self, line: &str) -> Option<DebuggerCommand> {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some(name) if self.user_commands.contains_key(name) => Some(DebuggerCommand::User(
+                name.to_string(),
+                words.map(str::to_string).collect(),
+            )),
+            _ => DebuggerCommand::from_line(line),
+        }
+    }
+
+    //Handle `define <name>`, followed by the commands the new command runs and `end`. Empty lines
+    //in them are left out.
+    fn define(&mut self, name: &str, mut body: Vec<String>) -> Result<(), String> {
+        if name.is_empty() {
+            return Err("Argument required (name of command to define).".to_string());
+        }
+        if !name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+            return Err(format!("Invalid command name \"{}\".", name));
+        }
+        if COMMANDS.iter().any(|(command, _)| *command == name) || DebuggerCommand::from_line(name).is_some() {
+            return Err(format!("Cannot redefine built-in command \"{}\".", name));
+        }
+        if self.user_commands.contains_key(name) && !self.query(&format!("Redefine command \"{}\"?", name)) {
+            return Err(format!("Command \"{}\" not redefined.", name));
+        }
+        body.retain(|line| !line.trim().is_empty());
+        self.user_commands.insert(name.to_string(), body);
+        Ok(())
+    }
+
+    //Run the commands of a user-defined command, with its arguments in place of `$arg0`, `$arg1`,
+    //... and their number in place of `$argc`. Returns false if one of them was to quit the
+    //debugger.
+    fn run_user_command(&mut self, name: &str, args: &[String]) -> bool {
+        if self.user_call_depth >= MAX_USER_CALL_DEPTH {
+            println!("Max user call depth exceeded -- command aborted.");
+            return true;
+        }
+        let body = self.user_commands.get(name).cloned().unwrap_or_default();
+        self.user_call_depth += 1;
+        let mut lines = &body[..];
+        let mut carry_on = true;
+        while let Some((line, rest)) = lines.split_first() {
+            lines = rest;
+            let line = match substitute_args(line, args) {
+                Ok(line) => line,
+                Err(e) => {
+                    println!("{}", e);
+                    break;
+                }
+            };
+            //a block inside the definition, such as `commands`, runs as a whole
+            let cmd = if DebuggerCommand::starts_block(&line) {
+                let (block, rest) = split_block(lines);
+                lines = rest;
+                DebuggerCommand::from_block(&line, block)
+            } else {
+                self.parse_line(&line)
+            };
+            match cmd {
+                Some(cmd) => {
+                    if !self.execute_command(cmd) {
+                        carry_on = false;
+                        break;
+                    }
+                }
+                None => {
+                    println!("Undefined command: \"{}\".", line.split_whitespace().next().unwrap_or(""));
+                    break;
+                }
+            }
+        }
+        self.user_call_depth -= 1;
+        carry_on
+    }
+
+    //Handle `script <file>`, running a Rhai script
+    #[cfg(feature = "scripting")]
+    fn load_script(&mut self, path: &str) -> Result<(), String> {
+        if path.is_empty() {
+            return Err("Argument required (file name of script).".to_string());
+        }
+        let mut scripts = self.scripts.take().ok_or("Scripts cannot load other scripts.".to_string())?;
+        let result = scripts.load(self, path);
+        self.scripts = Some(scripts);
+        result
+    }
+
+    #[cfg(not(feature = "scripting"))]
+    fn load_script(&mut self, _path: &str) -> Result<(), String> {
+        Err("This build of deet cannot run scripts. Build it with `--features scripting`.".to_string())
+    }
+
+    //Ask the scripts whether a breakpoint the child has reached should stop it
+    #[cfg(feature = "scripting")]
+    fn script_should_stop(&mut self, number: usize, addr: usize) -> bool {
+        let mut scripts = match self.scripts.take() {
+            Some(scripts) => scripts,
+            None => return true,
+        };
+        let function = self.debug_data.get_function_from_addr(addr);
+        let line = self.debug_data.get_line_from_addr(addr).map(|line| (line.file, line.number));
+        let stop = scripts.should_stop(self, number, addr, function, line);
+        self.scripts = Some(scripts);
+        stop
+    }
+
+    //What a printer a script added for the type of a value makes of it, given the expression it
+    //came from
+    #[cfg(feature = "scripting")]
+    fn script_print(&mut self, type_offset: usize, text: &str) -> Option<Result<String, String>> {
+        let type_name = self.debug_data.get_type(Some(type_offset))?.name.clone();
+        let mut scripts = self.scripts.take()?;
+        let printed = scripts.print(self, &type_name, text);
+        self.scripts = Some(scripts);
+        printed
+    }
+
+    //Run the commands in ~/.deetrc and then those in .deetrc in the working directory, such as
+    //settings, aliases and breakpoints wanted in every session, or every session in a project.
+    //Returns false if one of them was to quit the debugger.
+    #[cfg(feature = "cli")]
+    fn run_init_files(&mut self) -> bool {
+        let home = std::env::var("HOME").ok().map(|home| format!("{}/{}", home, INIT_FILE));
+        let mut done = None;
+        for path in home.into_iter().chain(Some(INIT_FILE.to_string())) {
+            //the working directory may be the home directory
+            let canonical = match std::fs::canonicalize(&path) {
+                Ok(canonical) => canonical,
+                Err(_) => continue,
+            };
+            if done.as_ref() == Some(&canonical) {
+                continue;
+            }
+            done = Some(canonical);
+            match self.run_script(&path) {
+                Ok(true) => (),
+                Ok(false) => return false,
+                Err(e) => println!("{}", e),
+            }
+        }
+        true
+    }
+
+    //Run the commands in a file one by one, as if they were typed at the prompt, stopping at one
+    //that isn't understood. Empty lines and lines starting with `#` are skipped. Returns Ok(false)
+    //if a command was to quit the debugger.
+    fn run_script(&mut self, path: &str) -> Result<bool, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}.", path, e))?;
+        let all: Vec<String> = text.lines().map(|line| line.trim().to_string()).collect();
+        let mut lines = &all[..];
+        while let Some((line, rest)) = lines.split_first() {
+            let number = all.len() - lines.len() + 1;
+            lines = rest;
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let line = self.expand_alias(line);
+            let cmd = if DebuggerCommand::starts_block(&line) {
+                let (block, rest) = split_block(lines);
+                lines = rest;
+                DebuggerCommand::from_block(&line, block)
+            } else {
+                self.parse_line(&line)
+            };
+            match cmd {
+                Some(cmd) => {
+                    if !self.execute(cmd) {
+                        return Ok(false);
+                    }
+                }
+                None => {
+                    let name = line.split_whitespace().next().unwrap_or("");
+                    return Err(format!("{}:{}: Undefined command: \"{}\".", path, number, name));
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    //Handle `source`, running the commands in a file. A file that sources itself stops at the
+    //same depth as a user-defined command calling itself. Returns false if a command in it was to
+    //quit the debugger.
+    fn source(&mut self, path: &str) -> bool {
+        if path.is_empty() {
+            println!("source command requires file name of file to source.");
+            return true;
+        }
+        if self.user_call_depth >= MAX_USER_CALL_DEPTH {
+            println!("Max user call depth exceeded -- command aborted.");
+            return true;
+        }
+        self.user_call_depth += 1;
+        let result = self.run_script(path);
+        self.user_call_depth -= 1;
+        match result {
+            Ok(carry_on) => carry_on,
+            Err(e) => {
+                println!("{}", e);
+                true
+            }
+        }
+    }
+
+    //Handle `show user`, printing the definitions of the user-defined commands, or of one
+    fn show_user_commands(&self, name: &str) {
+        if !name.is_empty() && !self.user_commands.contains_key(name) {
+            println!("Not a user command.");
+            return;
+        }
+        for (command, body) in self.user_commands.iter().filter(|(command, _)| name.is_empty() || *command == name) {
+            println!("User command \"{}\":", command);
+            for line in body {
+                println!("  {}", line);
+            }
+        }
+    }
+
+    //Replace an alias at the start of a line with the command it stands for, keeping the rest of
+    //the line as arguments
+    fn expand_alias(&self, line: &str) -> String {
+        let line = line.trim_start();
+        let end = line.find(char::is_whitespace).unwrap_or(line.len());
+        match self.aliases.get(&line[..end]) {
+            Some(command) => format!("{}{}", command, &line[end..]),
+            None => line.to_string(),
+        }
+    }
+
+    //Handle `alias <name> = <command>`, which makes the name a shortcut for the command and any
+    //arguments after it. `alias` on its own lists the aliases, and `alias <name>` shows one.
+    fn alias(&mut self, args: &str) -> Result<(), String> {
+        let (name, command) = match args.find('=') {
+            Some(i) => (args[..i].trim(), Some(args[i + 1..].trim())),
+            None => (args, None),
+        };
+        let command = match command {
+            Some(command) => command,
+            None => {
+                for (alias, command) in self.aliases.iter().filter(|(alias, _)| name.is_empty() || *alias == name) {
+                    println!("{} = {}", alias, command);
+                }
+                if !name.is_empty() && !self.aliases.contains_key(name) {
+                    return Err(format!("No alias named \"{}\".", name));
+                }
+                return Ok(());
+            }
+        };
+        let valid = |c: char| c.is_alphanumeric() || c == '-' || c == '_';
+        if name.is_empty() || command.is_empty() || !name.chars().all(valid) {
+            return Err("Usage: alias <name> = <command>".to_string());
+        }
+        if COMMANDS.iter().any(|(command, _)| *command == name) || DebuggerCommand::from_line(name).is_some() {
+            return Err(format!("Alias already exists: {}", name));
+        }
+        self.aliases.insert(name.to_string(), command.to_string());
+        self.save_aliases();
+        Ok(())
+    }
+
+    //Read the aliases saved by earlier sessions, one `<name> = <command>` per line
+    #[cfg(feature = "cli")]
+    fn load_aliases(&mut self) {
+        let text = match self.aliases_path.as_ref().and_then(|path| std::fs::read_to_string(path).ok()) {
+            Some(text) => text,
+            None => return,
+        };
+        for line in text.lines() {
+            if let Some(i) = line.find('=') {
+                self.aliases.insert(line[..i].trim().to_string(), line[i + 1..].trim().to_string());
+            }
+        }
+    }
+
+    fn save_aliases(&self) {
+        if let Some(path) = &self.aliases_path {
+            let text: String = self.aliases.iter().map(|(name, command)| format!("{} = {}\n", name, command)).collect();
+            if let Err(err) = std::fs::write(path, text) {
+                println!("Warning: failed to save aliases at {}: {}", path, err);
+            }
+        }
+    }
+
+    //The numbers of the breakpoints, watchpoints and catchpoints, in order
+    #[cfg(feature = "cli")]
+    fn breakpoint_numbers(&self) -> Vec<usize> {
+        let mut numbers: Vec<usize> = self.breakpoints.values().filter_map(|b| b.number).collect();
+        numbers.extend(self.watchpoints.iter().map(|w| w.number));
+        numbers.extend(self.catchpoints.iter().map(|c| c.number));
+        numbers.sort();
+        numbers
+    }
+
+    //Ask a yes or no question before doing something that can't be undone or may crash the
+    //program. The answer is yes without asking with `set confirm off`, or when the commands don't
+    //come from a terminal, e.g. in a script.
+    #[cfg(feature = "cli")]
+    fn query(&mut self, question: &str) -> bool {
+        if !self.settings.confirm || !nix::unistd::isatty(libc::STDIN_FILENO).unwrap_or(false) {
+            return true;
+        }
+        loop {
+            match self.readline.readline(&format!("{} (y or n) ", question)) {
+                Ok(answer) => match answer.trim() {
+                    "y" | "Y" | "yes" => return true,
+                    "n" | "N" | "no" => return false,
+                    _ => println!("Please answer y or n."),
+                },
+                Err(_) => return false,
+            }
+        }
+    }
+
+    //Without the prompt there is no one to ask, as when the debugger is embedded
+    #[cfg(not(feature = "cli"))]
+    fn query(&mut self, _question: &str) -> bool {
+        true
+    }
+
+    //Read a line of input. A line ending with a backslash is continued on the next line.
+    #[cfg(feature = "cli")]
+    fn read_line(&mut self, prompt: &str) -> Result<String, ReadlineError> {
+        let mut line = self.readline.readline(prompt)?;
+        while line.ends_with('\\') {
+            line.pop();
+            line.push_str(&self.readline.readline("> ")?);
+        }
+        Ok(line)
+    }
+
+    //Read the lines of a block up to the `end` that closes it, leaving out empty lines. Blocks can
+    //be nested, and ctrl+d ends the block like `end` does, as does an error reading a line, after
+    //it is reported. ctrl+c abandons the block, and None is returned.
+    #[cfg(feature = "cli")]
+    fn read_block(&mut self) -> Option<Vec<String>> {
+        let mut body = Vec::new();
+        let mut depth = 0;
+        loop {
+            let line = match self.read_line(">") {
+                Ok(line) => line,
+                Err(ReadlineError::Eof) => return Some(body),
+                Err(ReadlineError::Interrupted) => return None,
+                Err(err) => {
+                    println!("Cannot read the rest of the block: {}", err);
+                    return Some(body);
+                }
+            };
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            } else if line == "end" {
+                if depth == 0 {
+                    return Some(body);
+                }
+                depth -= 1;
+            } else if DebuggerCommand::starts_block(line) {
+                depth += 1;
+            }
+            body.push(line.to_string());
+        }
+    }
+
+    //the breakpoints can be set on address, line number and function name
+    fn parse_address(&self, addr: &str) -> Option<usize> {
+        //Case1: The breakpoint is an address in the child's address space
+        if addr.to_lowercase().starts_with("*0x") {
+            return usize::from_str_radix(&addr[3..], 16).ok();
+        }
+        //A line or function can be preceded by the source file it is in, e.g. `parser.c:42`, for
+        //programs made of several files. Without one, a line is in the first file. A C++ name
+        //such as `Shape::area` is not a file.
+        let (file, location) = match addr.find(':') {
+            Some(i) if !addr[i + 1..].starts_with(':') => (Some(&addr[..i]), &addr[i + 1..]),
+            _ => (None, addr),
+        };
+        //the file can also be named by its full path, or the end of it
+        let file = file.map(|file| self.sources.find(file).unwrap_or(file));
+        //Case2: The breakpoint is a line number
+        if let Ok(line_number) = location.parse::<usize>() {
+            self.debug_data.get_addr_for_line(file, line_number)
+        //Case3: The breakpoint is a function name
+        } else {
+            match self.debug_data.get_addr_for_function(file, location.trim()) {
+                Some(entry) => Some(entry),
+                //a function in a shared library the program has loaded, which has no debug info
+                None if file.is_none() => self.library_symbol(location.trim(), |_| true),
+                None => None,
+            }
+        }
+    }
+
+    //For a location naming a function that doesn't exist, optionally in a file as in
+    //`calls.c:twcie`, the message saying so along with the functions with the closest names. None
+    //if no function has a name close enough.
+    fn suggest_functions(&self, location: &str) -> Option<String> {
+        let (file, name) = match location.find(':') {
+            Some(i) if !location[i + 1..].starts_with(':') => (Some(&location[..i]), &location[i + 1..]),
+            _ => (None, location),
+        };
+        if name.is_empty() || name.parse::<usize>().is_ok() || name.starts_with('*') {
+            return None;
+        }
+        let functions = match file {
+            Some(file) => self.debug_data.get_functions_in_file(self.sources.find(file).unwrap_or(file)),
+            None => self.debug_data.get_functions(),
+        };
+        let matches = close_matches(name, functions.iter().map(|func| func.name.as_str()));
+        let (last, rest) = matches.split_last()?;
+        let mut names: Vec<String> = rest.iter().map(|m| format!("\"{}\"", m)).collect();
+        if !names.is_empty() {
+            names = vec![names.join(", "), "or".to_string()];
+        }
+        names.push(format!("\"{}\"", last));
+        Some(format!("Function \"{}\" not defined. Did you mean {}?", name, names.join(" ")))
+    }
+
+    //Print lines `first` to `last` of a source file, each after its number
+    fn print_source_lines(&self, file: &str, first: usize, last: usize) {
+        for number in first..=last.min(self.sources.line_count(file)) {
+            println!("{}\t{}", number, self.sources.line(file, number));
+        }
+    }
+
+    //The source file and line a function starts at, optionally looking only in one file
+    fn function_location(&self, file: Option<&str>, name: &str) -> Option<(String, usize)> {
+        let addr = self.debug_data.get_addr_for_function(file, name)?;
+        let line = self.debug_data.get_line_from_addr(addr)?;
+        Some((line.file, line.number))
+    }
+
+    //Make `list` carry on in another source file
+    fn switch_listing(&mut self, file: &str) {
+        if self.listing.as_ref().is_none_or(|listing| listing.file() != file) {
+            self.listing = Some(Listing::new(file, self.sources.line_count(file)));
+        }
+    }
+
+    //Make the next `list` on its own show the lines around a line
+    fn center_listing(&mut self, file: &str, line: usize) {
+        self.switch_listing(file);
+        self.listing.as_mut().unwrap().center_on(line);
+    }
+
+    /// The lines `list <args>` shows, each after its number: the lines around a line number or the
+    /// start of a function, optionally in a given file such as `helper.c:12`, the lines after the
+    /// last listing on their own, or the lines before it with `-`
+    pub fn list(&mut self, args: &str) -> Result<Vec<String>, String> {
+        let size = self.settings.listsize.unwrap_or(0);
+        let (file, location) = match args.rfind(':') {
+            Some(colon) => (Some(&args[..colon]), &args[colon + 1..]),
+            None => (None, args),
+        };
+        //the first listing is around main, unless the program has stopped somewhere
+        let main = self.function_location(None, "main");
+        let (first, last) = match location {
+            "" | "-" if file.is_none() => {
+                let mut default = 1;
+                if self.listing.is_none() {
+                    let (path, line) = main.ok_or("No symbol table is loaded.".to_string())?;
+                    self.switch_listing(&path);
+                    default = line;
+                }
+                let listing = self.listing.as_mut().unwrap();
+                if location == "-" {
+                    listing.backward(size)?
+                } else {
+                    listing.forward(size, default)?
+                }
+            }
+            _ => {
+                let (path, line) = match location.parse::<usize>() {
+                    Ok(line) => {
+                        let path = match file {
+                            Some(file) => match self.sources.find(file) {
+                                Some(path) => path.to_string(),
+                                None => return Err(format!("No source file named {}.", file)),
+                            },
+                            None => match (&self.listing, main) {
+                                (Some(listing), _) => listing.file().to_string(),
+                                (None, Some((path, _))) => path,
+                                (None, None) => return Err("No symbol table is loaded.".to_string()),
+                            },
+                        };
+                        (path, line)
+                    }
+                    Err(_) => self
+                        .function_location(file, location)
+                        .ok_or(format!("Function \"{}\" not defined.", location))?,
+                };
+                self.switch_listing(&path);
+                self.listing.as_mut().unwrap().around(line, size)?
+            }
+        };
+        let file = self.listing.as_ref().unwrap().file();
+        Ok((first..=last)
+            .map(|number| format!("{}\t{}", number, self.sources.line(file, number)))
+            .collect())
+    }
+
+    /// The line `search <regex>` finds, after its number: the first line after the last one
+    /// listed that matches the regex, or with `forward` false the last one before it. `list`
+    /// then shows the lines around it.
+    pub fn search(&mut self, pattern: &str, forward: bool) -> Result<String, String> {
+        if pattern.is_empty() {
+            return Err("Empty regular expression".to_string());
+        }
+        let regex = Regex::new(pattern).map_err(|e| format!("Invalid regexp: {}", e))?;
+        if self.listing.is_none() {
+            let (path, line) = self
+                .function_location(None, "main")
+                .ok_or("No symbol table is loaded.".to_string())?;
+            self.center_listing(&path, line);
+        }
+        let listing = self.listing.as_ref().unwrap();
+        let file = listing.file().to_string();
+        let position = listing.position();
+        let lines = self.sources.lines(&file).unwrap_or_default();
+        let found = if forward {
+            (position + 1..=lines.len()).find(|number| regex.is_match(&lines[number - 1]))
+        } else {
+            (1..position).rev().find(|number| regex.is_match(&lines[number - 1]))
+        };
+        match found {
+            Some(number) => {
+                self.center_listing(&file, number);
+                Ok(format!("{}\t{}", number, lines[number - 1]))
+            }
+            None => Err("Expression not found".to_string()),
+        }
+    }
+
+    //Get the selected frame of the stopped child process, for evaluating expressions
+    fn current_frame(&self) -> Result<Frame, String> {
+        let mut frames = self.frames()?;
+        if self.selected_frame >= frames.len() {
+            return Err(format!("No frame at level {}.", self.selected_frame));
+        }
+        Ok(frames.swap_remove(self.selected_frame))
+    }
+
+    //The frames of the call stack of the stopped child process, innermost first
+    fn frames(&self) -> Result<Vec<Frame>, String> {
+        match &self.inferior {
+            Some(inf) => inf
+                .frames(&self.debug_data)
+                .map_err(|e| format!("Cannot read registers. Error: {}", e)),
+            None => Err("No stack.".to_string()),
+        }
+    }
+
+    //Select frame number `level` of the call stack and print it
+    fn select_frame(&mut self, level: usize) -> Result<(), String> {
+        let frames = self.frames()?;
+        match frames.get(level) {
+            Some(frame) => {
+                self.selected_frame = level;
+                self.print_frame(level, frame);
+                Ok(())
+            }
+            None => Err(format!("No frame at level {}.", level)),
+        }
+    }
+
+    //Select the frame `count` frames further out than the selected one, or further in if `count`
+    //is negative. Like gdb, going past either end of the stack stops at that end, unless the frame
+    //there is already selected.
+    fn move_frame(&mut self, count: isize) -> Result<(), String> {
+        let outermost = self.frames()?.len() - 1;
+        if count > 0 && self.selected_frame == outermost {
+            return Err("Initial frame selected; you cannot go up.".to_string());
+        }
+        if count < 0 && self.selected_frame == 0 {
+            return Err("Bottom (innermost) frame selected; you cannot go down.".to_string());
+        }
+        let level = (self.selected_frame as isize + count).clamp(0, outermost as isize);
+        self.select_frame(level as usize)
+    }
+
+    //Print a frame the way `bt` does, followed by the line being executed
+    fn print_frame(&mut self, level: usize, frame: &Frame) {
+        println!("{}", frame_description(&self.debug_data, level, frame.pc));
+        if let Some(line) = self.debug_data.get_line_from_addr(frame.lookup_pc(level)) {
+            println!("{}\t{}", line.number, self.sources.line(&line.file, line.number));
+            self.center_listing(&line.file, line.number);
+        }
+    }
+
+    //Handle `break`, `tbreak` and `hbreak`. The breakpoint is set in the child process if it is
+    //running, and written into it when it starts otherwise. A temporary breakpoint is deleted the
+    //first time it stops the program, and a hardware one uses a debug register.
+    fn set_breakpoint(&mut self, args: &str, temporary: bool, hardware: bool) {
+        //the location can be followed by `group=<name>` and then `if <condition>`
+        let (args, condition) = match args.find(" if ") {
+            Some(i) => (&args[..i], Some(args[i + 4..].trim().to_string())),
+            None => (args, None),
+        };
+        if let Some(condition) = &condition {
+            if let Err(e) = expression::parse(condition, &self.debug_data) {
+                println!("{}", e);
+                return;
+            }
+        }
+        let mut addr = String::new();
+        let mut group = None;
+        for arg in args.split_whitespace() {
+            match arg.strip_prefix("group=") {
+                Some(name) if !name.is_empty() => group = Some(name.to_string()),
+                _ => addr = arg.to_string(),
+            }
+        }
+        //`<file>:*` breaks on every function defined in the file
+        if let Some(file) = addr.strip_suffix(":*") {
+            if condition.is_some() || hardware {
+                println!("Only a breakpoint can be set on every function in a file");
+                return;
+            }
+            self.break_on_file(file, group, temporary);
+            return;
+        }
+        //parse the address string to usize 
+        let parsed_addr = self.parse_address(&addr);
+        if parsed_addr.is_none() {
+            //a name close to some in the program is more likely misspelt than in a library
+            if let Some(suggestion) = self.suggest_functions(&addr) {
+                println!("{}", suggestion);
+            //a function may be in a shared library that hasn't been loaded yet
+            } else if is_function_name(&addr) && !hardware {
+                self.set_pending_breakpoint(addr, group, condition, temporary);
+            } else {
+                println!("Invalid breakpoint address");
+            }
+            return;
+        }
+
+        let parsed_addr = parsed_addr.unwrap();
+        let hardware_slot = match self.free_hardware_slot() {
+            Some(slot) if hardware => Some(slot),
+            None if hardware => {
+                println!("Hardware breakpoints used exceeds limit.");
+                return;
+            }
+            _ => None,
+        };
+        let kind = match (temporary, hardware) {
+            (true, _) => "Temporary breakpoint",
+            (false, true) => "Hardware assisted breakpoint",
+            (false, false) => "Breakpoint",
+        };
+        let result = self.add_breakpoint(parsed_addr, group, temporary);
+        if let Ok((_, false)) = result {
+            self.breakpoints.get_mut(&parsed_addr).unwrap().location = Some(addr.clone());
+        }
+        if let (Ok((_, false)), Some(slot)) = (&result, hardware_slot) {
+            if let Err(e) = self.use_hardware_slot(parsed_addr, slot) {
+                println!("Cannot set hardware breakpoint. Error: {}", e);
+            }
+        }
+        match result {
+            Ok((number, already_set)) if condition.is_some() => {
+                self.breakpoints.get_mut(&parsed_addr).unwrap().condition = condition;
+                if already_set {
+                    println!("Breakpoint {} now has a condition", number);
+                } else {
+                    println!("{} {} at {}", kind, number, self.describe_address(parsed_addr));
+                }
+            }
+            Ok((number, false)) => {
+                println!("{} {} at {}", kind, number, self.describe_address(parsed_addr))
+            }
+            //There is already a breakpoint there, so it was just retagged
+            Ok((number, true)) => println!("Breakpoint {} already set at {}", number, addr),
+            Err(_) => println!("Cannot set breakpoint at {}", addr),
+        }
+    }
+
+    //Make a breakpoint on a function that isn't in the program or in any library it has loaded,
+    //which is set once a library with the function is loaded
+    fn set_pending_breakpoint(&mut self, name: String, group: Option<String>, condition: Option<String>, temporary: bool) {
+        println!("Function \"{}\" not defined.", name);
+        let number = self.next_number;
+        self.next_number += 1;
+        self.pending_breakpoints.push(Breakpoint {
+            number: Some(number),
+            addr: 0,
+            orig_byte: 0,
+            enabled: true,
+            group,
+            condition,
+            hit_count: 0,
+            ignore_count: 0,
+            temporary,
+            commands: Vec::new(),
+            dprintf: None,
+            hardware_slot: None,
+            location: Some(name.clone()),
+        });
+        println!("Breakpoint {} ({}) pending.", number, name);
+        self.watch_library_loads();
+    }
+
+    //Find a function in the shared libraries the program has loaded, from their symbol tables as
+    //they usually have no debug info. `only` picks the libraries to look in.
+    fn library_symbol(&self, name: &str, only: fn(&str) -> bool) -> Option<usize> {
+        let maps = self.inferior.as_ref()?.memory_maps();
+        let mut libraries: Vec<&str> =
+            maps.iter().map(|map| map.path.as_str()).filter(|path| path.contains(".so") && only(path)).collect();
+        libraries.dedup();
+        for path in libraries {
+            let data = match std::fs::read(path) {
+                Ok(data) => data,
+                Err(_) => continue,
+            };
+            let (value, elf) = match (elf::symbol_value(&data, name), elf::parse(&data)) {
+                (Some(value), Some(elf)) => (value, elf),
+                _ => continue,
+            };
+            return Some(library_bias(&maps, path, &elf)? + value);
+        }
+        None
+    }
+
+    //Handle `info sharedlibrary`: list the shared libraries the program has loaded, with where
+    //their code is and whether their symbols could be read
+    fn print_shared_libraries(&self) {
+        let maps = match &self.inferior {
+            Some(inf) => inf.memory_maps(),
+            None => Vec::new(),
+        };
+        let mut libraries: Vec<&str> = maps.iter().map(|map| map.path.as_str()).filter(|path| path.contains(".so")).collect();
+        libraries.dedup();
+        if libraries.is_empty() {
+            println!("No shared libraries loaded at this time.");
+            return;
+        }
+        println!("{:<19} {:<19} {:<11} Shared Object Library", "From", "To", "Syms Read");
+        let mut missing_debug_info = false;
+        for path in libraries {
+            let data = std::fs::read(path).unwrap_or_default();
+            let elf = elf::parse(&data);
+            let text = elf.as_ref().and_then(|elf| {
+                let section = elf.sections.iter().find(|section| section.name == ".text")?;
+                let bias = library_bias(&maps, path, elf)?;
+                Some((section.addr + bias, section.addr + bias + section.size))
+            });
+            let (from, to) = match text {
+                Some((from, to)) => (format!("{:#018x}", from), format!("{:#018x}", to)),
+                None => (String::new(), String::new()),
+            };
+            let symbols = match &elf {
+                Some(elf) if elf.sections.iter().any(|section| section.name == ".debug_info") => "Yes",
+                Some(_) => {
+                    missing_debug_info = true;
+                    "Yes (*)"
+                }
+                None => "No",
+            };
+            println!("{:<19} {:<19} {:<11} {}", from, to, symbols, path);
+        }
+        if missing_debug_info {
+            println!("(*): Shared library is missing debugging information.");
+        }
+    }
+
+    //Handle `info proc`, which shows the process being debugged, and `info proc mappings`, which
+    //lists the ranges of memory it has mapped and what is mapped there
+    fn print_proc(&self, what: &str) -> Result<(), String> {
+        let inf = match &self.inferior {
+            Some(inf) if inf.core().is_none() => inf,
+            _ => return Err("No current process: you must name one.".to_string()),
+        };
+        let pid = inf.pid();
+        match what {
+            "" => {
+                println!("process {}", pid);
+                let proc = |name: &str| format!("/proc/{}/{}", pid, name);
+                let cmdline = std::fs::read(proc("cmdline")).unwrap_or_default();
+                let args: Vec<String> = cmdline
+                    .split(|byte| *byte == 0)
+                    .filter(|arg| !arg.is_empty())
+                    .map(|arg| String::from_utf8_lossy(arg).to_string())
+                    .collect();
+                println!("cmdline = '{}'", args.join(" "));
+                let link = |name: &str| std::fs::read_link(proc(name)).map(|path| path.display().to_string());
+                println!("cwd = '{}'", link("cwd").unwrap_or_default());
+                println!("exe = '{}'", link("exe").unwrap_or_default());
+            }
+            "mappings" | "maps" => {
+                println!("process {}", pid);
+                println!("Mapped address spaces:");
+                println!();
+                println!(
+                    "{:>18} {:>18} {:>10} {:>10}  {:<5}  {}",
+                    "Start Addr", "End Addr", "Size", "Offset", "Perms", "objfile"
+                );
+                for map in inf.memory_maps() {
+                    let line = format!(
+                        "{:>18} {:>18} {:>10} {:>10}  {:<5}  {}",
+                        format!("{:#x}", map.start),
+                        format!("{:#x}", map.end),
+                        format!("{:#x}", map.end - map.start),
+                        format!("{:#x}", map.offset),
+                        map.permissions,
+                        map.path
+                    );
+                    println!("{}", line.trim_end());
+                }
+            }
+            _ => return Err(format!("Undefined info proc command: \"{}\".  Try \"info proc mappings\".", what)),
+        }
+        Ok(())
+    }
+
+    //Have the program stop whenever the dynamic linker loads a library while there are pending
+    //breakpoints, by breaking on the function it calls to let debuggers know
+    fn watch_library_loads(&mut self) {
+        if self.inferior.is_none() || self.shlib_event.is_some() || self.pending_breakpoints.is_empty() {
+            return;
+        }
+        self.shlib_event = self.library_symbol(SHLIB_EVENT_FUNCTION, is_dynamic_linker);
+        if let Some(addr) = self.shlib_event {
+            if let Err(e) = self.update_stub_breakpoint(addr) {
+                println!("Cannot insert breakpoint at {:#x}. Error: {}", addr, e);
+            }
+        }
+    }
+
+    //Set the pending breakpoints on functions in libraries that have been loaded. Once there are
+    //none left, the dynamic linker isn't watched any more.
+    fn set_pending_breakpoints(&mut self) {
+        for mut breakpoint in std::mem::take(&mut self.pending_breakpoints) {
+            let name = breakpoint.location.clone().unwrap_or_default();
+            let addr = match self.library_symbol(&name, |_| true) {
+                Some(addr) if !self.breakpoints.contains_key(&addr) => addr,
+                _ => {
+                    self.pending_breakpoints.push(breakpoint);
+                    continue;
+                }
+            };
+            breakpoint.addr = addr;
+            if breakpoint.enabled {
+                let inf = self.inferior.as_mut().unwrap();
+                let result = match breakpoint.hardware_slot {
+                    Some(slot) => inf.set_hardware_breakpoint(slot, addr),
+                    None => inf.write_byte(addr, 0xcc).map(|orig_byte| breakpoint.orig_byte = orig_byte),
+                };
+                if let Err(e) = result {
+                    println!("Cannot insert breakpoint {}. Error: {}", breakpoint.number.unwrap(), e);
+                    self.pending_breakpoints.push(breakpoint);
+                    continue;
+                }
+            }
+            self.breakpoints.insert(addr, breakpoint);
+        }
+        if self.pending_breakpoints.is_empty() {
+            if let Some(addr) = self.shlib_event.take() {
+                if let Err(e) = self.update_stub_breakpoint(addr) {
+                    println!("Cannot remove breakpoint at {:#x}. Error: {}", addr, e);
+                }
+            }
+        }
+    }
+
+    //Handle `dprintf <location>,"<format>",<expressions>...`, setting a breakpoint that prints
+    //formatted output like `printf` every time it is hit, and lets the program carry on
+    fn set_dprintf(&mut self, args: &str) -> Result<(), String> {
+        let usage = "Usage: dprintf <location>,\"<format>\",<expressions>...".to_string();
+        let quote = args.find('"').ok_or(usage.clone())?;
+        let location = args[..quote].trim().trim_end_matches(',').trim();
+        let printf = args[quote..].to_string();
+        if location.is_empty() {
+            return Err(usage);
+        }
+        expression::parse_list(&printf, &self.debug_data)?;
+        let addr = match self.parse_address(location) {
+            Some(addr) => addr,
+            None => return Err(self.suggest_functions(location).unwrap_or("Invalid breakpoint address".to_string())),
+        };
+        if let Some(number) = self.breakpoints.get(&addr).and_then(|b| b.number) {
+            return Err(format!("Breakpoint {} already set at {}", number, location));
+        }
+        let (number, _) = self
+            .add_breakpoint(addr, None, false)
+            .map_err(|_| format!("Cannot set breakpoint at {}", location))?;
+        let breakpoint = self.breakpoints.get_mut(&addr).unwrap();
+        breakpoint.dprintf = Some(printf);
+        breakpoint.location = Some(location.to_string());
+        println!("Dprintf {} at {}", number, self.describe_address(addr));
+        Ok(())
+    }
+
+    //Handle `enable`, `disable` and `delete`. The argument is a list of breakpoint, watchpoint
+    //and catchpoint numbers or ranges of them such as `2-4`, `group <name>` for the breakpoints in
+    //a group, or nothing for all of them.
+    fn change_breakpoints(&mut self, action: &str, args: &str) {
+        let tokens: Vec<&str> = args.split_whitespace().collect();
+        let numbers: Vec<usize> = match tokens.as_slice() {
+            ["group", name] => {
+                let mut numbers: Vec<usize> = self
+                    .breakpoints
+                    .values()
+                    .chain(self.pending_breakpoints.iter())
+                    .filter(|b| b.group.as_deref() == Some(*name))
+                    .filter_map(|b| b.number)
+                    .collect();
+                if numbers.is_empty() {
+                    println!("No breakpoints in group {}", name);
+                    return;
+                }
+                numbers.sort();
+                numbers
+            }
+            ["group", ..] => {
+                println!("Usage: {} group <name>", action);
+                return;
+            }
+            [] => {
+                let mut numbers: Vec<usize> = self
+                    .breakpoints
+                    .values()
+                    .chain(self.pending_breakpoints.iter())
+                    .filter_map(|b| b.number)
+                    .chain(self.watchpoints.iter().map(|w| w.number))
+                    .chain(self.catchpoints.iter().map(|c| c.number))
+                    .collect();
+                numbers.sort();
+                numbers
+            }
+            _ => match parse_numbers(&tokens) {
+                Some(numbers) => numbers,
+                None => {
+                    println!("Usage: {} [<number>...] or {} group <name>", action, action);
+                    return;
+                }
+            },
+        };
+        for number in numbers {
+            if let Err(e) = self.change_breakpoint(action, number) {
+                println!("{}", e);
+            }
+        }
+    }
+
+    //Enable, disable or delete the breakpoint, watchpoint or catchpoint with a number
+    fn change_breakpoint(&mut self, action: &str, number: usize) -> Result<(), String> {
+        let enable = action == "enable";
+        let error = |e: nix::Error| format!("Cannot {} {}. Error: {}", action, number, e);
+        if let Some(addr) = self.breakpoints.values().find(|b| b.number == Some(number)).map(|b| b.addr) {
+            if action == "delete" {
+                //the debug register is given up, and the debugger's own breakpoint is an int3
+                if self.breakpoints[&addr].hardware_slot.is_some() {
+                    self.set_breakpoint_enabled(addr, false).map_err(error)?;
+                    self.breakpoints.get_mut(&addr).unwrap().hardware_slot = None;
+                }
+                let breakpoint = self.breakpoints.get_mut(&addr).unwrap();
+                breakpoint.number = None;
+                breakpoint.group = None;
+                breakpoint.condition = None;
+                breakpoint.ignore_count = 0;
+                breakpoint.temporary = false;
+                breakpoint.commands.clear();
+                breakpoint.dprintf = None;
+                //the debugger may still need a breakpoint there for itself
+                return self.update_stub_breakpoint(addr).map_err(error);
+            }
+            return self.set_breakpoint_enabled(addr, enable).map_err(error);
+        }
+        if let Some(i) = self.pending_breakpoints.iter().position(|b| b.number == Some(number)) {
+            if action == "delete" {
+                self.pending_breakpoints.remove(i);
+            } else {
+                self.pending_breakpoints[i].enabled = enable;
+            }
+            return Ok(());
+        }
+        if let Some(i) = self.watchpoints.iter().position(|w| w.number == number) {
+            if action == "enable" {
+                if !self.watchpoints[i].enabled && self.inferior.is_some() {
+                    let mut watchpoint = self.watchpoints.remove(i);
+                    let armed = self.arm_watchpoint(&mut watchpoint);
+                    watchpoint.enabled = armed.is_ok();
+                    self.watchpoints.insert(i, watchpoint);
+                    return armed;
+                }
+                self.watchpoints[i].enabled = true;
+                return Ok(());
+            }
+            //free the debug registers
+            if let Some(inf) = self.inferior.as_ref() {
+                for slot in &self.watchpoints[i].hardware_slots {
+                    inf.clear_hardware_slot(*slot).map_err(error)?;
+                }
+            }
+            self.watchpoints[i].hardware_slots.clear();
+            self.watchpoints[i].enabled = false;
+            if action == "delete" {
+                if let Some((ret, _)) = self.watchpoints.remove(i).scope {
+                    return self.update_stub_breakpoint(ret).map_err(error);
+                }
+            }
+            return Ok(());
+        }
+        if let Some(i) = self.catchpoints.iter().position(|c| c.number == number) {
+            let addr = self.catchpoints[i].addr;
+            if action == "delete" {
+                self.catchpoints.remove(i);
+            } else {
+                self.catchpoints[i].enabled = enable;
+            }
+            return self.update_stub_breakpoint(addr).map_err(error);
+        }
+        Err(format!("No breakpoint number {}.", number))
+    }
+
+    //Handle `break <file>:*`, setting a breakpoint on the entry of every function defined in
+    //`file`, and list the functions
+    fn break_on_file(&mut self, file: &str, group: Option<String>, temporary: bool) {
+        let functions: Vec<(String, usize, usize)> = self
+            .debug_data
+            .get_functions_in_file(file)
+            .iter()
+            .map(|func| (func.name.clone(), func.address, func.line_number))
+            .collect();
+        if functions.is_empty() {
+            println!("No functions defined in {}", file);
+            return;
+        }
+        println!("Setting breakpoints on {} functions in {}:", functions.len(), file);
+        self.break_on_functions(functions, group, temporary);
+    }
+
+    //Handle `rbreak <regex>`, setting a breakpoint on the entry of every function whose name
+    //matches the regex anywhere, and list the functions
+    fn break_on_regex(&mut self, pattern: &str) {
+        let regex = match Regex::new(pattern) {
+            Ok(regex) => regex,
+            Err(e) => {
+                println!("Invalid regex {}: {}", pattern, e);
+                return;
+            }
+        };
+        let functions: Vec<(String, usize, usize)> = self
+            .debug_data
+            .get_functions()
+            .iter()
+            .filter(|func| regex.is_match(&func.name))
+            .map(|func| (func.name.clone(), func.address, func.line_number))
+            .collect();
+        if functions.is_empty() {
+            println!("No functions match {}", pattern);
+            return;
+        }
+        println!("Setting breakpoints on {} functions matching {}:", functions.len(), pattern);
+        let created = self.break_on_functions(functions, None, false);
+        match created {
+            1 => println!("1 breakpoint set."),
+            _ => println!("{} breakpoints set.", created),
+        }
+    }
+
+    //Set a breakpoint on each of a list of functions, given as their names, entry addresses and
+    //first lines, and list them. Returns how many breakpoints were new.
+    fn break_on_functions(
+        &mut self,
+        functions: Vec<(String, usize, usize)>,
+        group: Option<String>,
+        temporary: bool,
+    ) -> usize {
+        let mut created = 0;
+        for (name, addr, line) in functions {
+            match self.add_breakpoint(addr, group.clone(), temporary) {
+                Ok((number, false)) => {
+                    created += 1;
+                    println!("    {} at {:#x} (line {}), breakpoint {}", name, addr, line, number);
+                    self.breakpoints.get_mut(&addr).unwrap().location = Some(name);
+                }
+                Ok((number, true)) => println!(
+                    "    {} at {:#x} (line {}), already set as breakpoint {}",
+                    name, addr, line, number
+                ),
+                Err(e) => println!("    Cannot set breakpoint on {}. Error: {}", name, e),
+            }
+        }
+        created
+    }
+
+    //Add a breakpoint for the user at `addr`, or tag the one already there with `group`. Returns
+    //the breakpoint's number and whether it was already set.
+    fn add_breakpoint(
+        &mut self,
+        addr: usize,
+        group: Option<String>,
+        temporary: bool,
+    ) -> Result<(usize, bool), nix::Error> {
+        if let Some(breakpoint) = self.breakpoints.get_mut(&addr) {
+            breakpoint.group = group;
+            if let Some(number) = breakpoint.number {
+                return Ok((number, true));
+            }
+            //the debugger's own breakpoint, e.g. for a catchpoint, becomes the user's too
+            let number = self.next_number;
+            self.next_number += 1;
+            breakpoint.number = Some(number);
+            breakpoint.temporary = temporary;
+            self.set_breakpoint_enabled(addr, true)?;
+            return Ok((number, false));
+        }
+        let number = self.next_number;
+        self.insert_breakpoint(addr, group, Some(number))?;
+        self.breakpoints.get_mut(&addr).unwrap().temporary = temporary;
+        self.next_number += 1;
+        Ok((number, false))
+    }
+
+    //Add a breakpoint at `addr`. There are two cases:
+    //1. The child process has been started and is currently paused. The breakpoint instruction
+    //   needs to be written directly into the child process's address space.
+    //2. The child process has not been started yet. The breakpoint is only added to the
+    //   breakpoints hashtable, and written into the child process once the debugger starts running.
+    fn insert_breakpoint(
+        &mut self,
+        addr: usize,
+        group: Option<String>,
+        number: Option<usize>,
+    ) -> Result<(), nix::Error> {
+        let orig_byte = match self.inferior.as_mut() {
+            Some(inf) => inf.write_byte(addr, 0xcc)?,
+            None => 0,
+        };
+        self.breakpoints.insert(
+            addr,
+            Breakpoint {
+                number,
+                addr,
+                orig_byte,
+                enabled: true,
+                group,
+                condition: None,
+                hit_count: 0,
+                ignore_count: 0,
+                temporary: false,
+                commands: Vec::new(),
+                dprintf: None,
+                hardware_slot: None,
+                location: None,
+            },
+        );
+        Ok(())
+    }
+
+    //Handle `condition <number> <expression>`, which makes a breakpoint stop the program only when
+    //the expression is true, and `condition <number>`, which makes it unconditional again
+    fn set_condition(&mut self, args: &str) -> Result<(), String> {
+        let (number, condition) = match args.find(char::is_whitespace) {
+            Some(i) => (&args[..i], Some(args[i..].trim().to_string())),
+            None => (args, None),
+        };
+        let number: usize = number
+            .parse()
+            .map_err(|_| "Usage: condition <breakpoint number> [<expression>]".to_string())?;
+        if let Some(condition) = &condition {
+            expression::parse(condition, &self.debug_data)?;
+        }
+        let breakpoint = self
+            .breakpoints
+            .values_mut()
+            .find(|b| b.number == Some(number))
+            .ok_or(format!("No breakpoint number {}.", number))?;
+        match &condition {
+            Some(_) => println!("Breakpoint {} now has a condition", number),
+            None => println!("Breakpoint {} now unconditional.", number),
+        }
+        breakpoint.condition = condition;
+        Ok(())
+    }
+
+    //Handle `commands [<number>]` followed by a block of commands, which are run every time the
+    //breakpoint stops the program. Without a number, the commands are for the last breakpoint
+    //set, and an empty block removes them.
+    fn set_commands(&mut self, number: &str, mut commands: Vec<String>) -> Result<(), String> {
+        let number = match number {
+            "" => self
+                .breakpoints
+                .values()
+                .filter_map(|b| b.number)
+                .max()
+                .ok_or("No breakpoints specified.".to_string())?,
+            number => number
+                .parse::<usize>()
+                .map_err(|_| "Usage: commands [<breakpoint number>]".to_string())?,
+        };
+        //empty lines in a block from a file or a `Session` do nothing
+        commands.retain(|line| !line.trim().is_empty());
+        for line in &commands {
+            if DebuggerCommand::starts_block(line) || DebuggerCommand::from_line(line).is_none() {
+                return Err(format!("Unrecognized command \"{}\" in breakpoint commands", line));
+            }
+        }
+        let breakpoint = self
+            .breakpoints
+            .values_mut()
+            .find(|b| b.number == Some(number))
+            .ok_or(format!("No breakpoint number {}.", number))?;
+        breakpoint.commands = commands;
+        Ok(())
+    }
+
+    //Handle `ignore <number> <count>`, which makes a breakpoint let the program pass it `count`
+    //more times before stopping it
+    fn ignore(&mut self, args: &str) -> Result<(), String> {
+        let usage = "Usage: ignore <breakpoint number> <count>".to_string();
+        let (number, count) = match args.split_whitespace().collect::<Vec<&str>>().as_slice() {
+            [number, count] => (
+                number.parse::<usize>().map_err(|_| usage.clone())?,
+                count.parse::<usize>().map_err(|_| usage.clone())?,
+            ),
+            _ => return Err(usage),
+        };
+        let breakpoint = self
+            .breakpoints
+            .values_mut()
+            .find(|b| b.number == Some(number))
+            .ok_or(format!("No breakpoint number {}.", number))?;
+        breakpoint.ignore_count = count;
+        match count {
+            0 => println!("Will stop next time breakpoint {} is reached.", number),
+            1 => println!("Will ignore next crossing of breakpoint {}.", number),
+            _ => println!("Will ignore next {} crossings of breakpoint {}.", count, number),
+        }
+        Ok(())
+    }
+
+    //Whether the child, stopped at `addr`, should stay stopped for the breakpoint there. A
+    //breakpoint with a condition only stops the child when the condition is true; if the
+    //condition can't be evaluated, the child stops so that the user can fix it. Every time the
+    //condition is true counts as a hit, but the breakpoint is passed while it is being ignored.
+    //A `dprintf` breakpoint prints its output and is passed too.
+    fn breakpoint_should_stop(&mut self, addr: usize) -> bool {
+        let (number, condition) = match self.breakpoints.get(&addr) {
+            Some(breakpoint) if breakpoint.enabled => match breakpoint.number {
+                Some(number) => (number, breakpoint.condition.clone()),
+                None => return true,
+            },
+            _ => return true,
+        };
+        if let Some(condition) = condition {
+            let value = expression::parse(&condition, &self.debug_data)
+                .and_then(|expr| self.evaluate(&[expr]))
+                .and_then(|mut values| values.remove(0).scalar(&self.debug_data));
+            match value {
+                Ok(value) if value.as_f64() == 0.0 => return false,
+                Ok(_) => (),
+                Err(e) => {
+                    println!("Error in testing condition for breakpoint {}:", number);
+                    println!("{}", e);
+                    return true;
+                }
+            }
+        }
+        #[cfg(feature = "scripting")]
+        {
+            if !self.script_should_stop(number, addr) {
+                return false;
+            }
+        }
+        let breakpoint = self.breakpoints.get_mut(&addr).unwrap();
+        breakpoint.hit_count += 1;
+        if breakpoint.ignore_count > 0 {
+            breakpoint.ignore_count -= 1;
+            return false;
+        }
+        if let Some(printf) = breakpoint.dprintf.clone() {
+            if let Err(e) = self.printf(&printf) {
+                println!("{}", e);
+            }
+            return false;
+        }
+        true
+    }
+
+    //Delete the temporary breakpoint at `addr`, if any, now that it has stopped the child
+    fn delete_temporary_breakpoint(&mut self, addr: usize) {
+        let number = match self.breakpoints.get(&addr) {
+            Some(breakpoint) if breakpoint.temporary && breakpoint.enabled => breakpoint.number,
+            _ => None,
+        };
+        if let Some(number) = number {
+            println!("Temporary breakpoint {} deleted", number);
+            if let Err(e) = self.change_breakpoint("delete", number) {
+                println!("{}", e);
+            }
+        }
+    }
+
+    //An address along with the source line there, as shown when a breakpoint is set
+    fn describe_address(&self, addr: usize) -> String {
+        match self.debug_data.get_line_from_addr(addr) {
+            Some(line) => format!("{:#x}: file {}, line {}.", addr, line.file, line.number),
+            None => format!("{:#x}", addr),
+        }
+    }
+
+    //Handle `info break`, listing the breakpoints, watchpoints and catchpoints in the order they
+    //were set
+    fn print_breakpoints(&self) {
+        let row = |number: usize, kind: &str, temporary: bool, enabled: bool, addr: &str, what: &str| {
+            format!(
+                "{:<7} {:<14} {:<4} {:<3} {:<18} {}",
+                number,
+                kind,
+                if temporary { "del" } else { "keep" },
+                if enabled { "y" } else { "n" },
+                addr,
+                what
+            )
+        };
+        //each row is preceded by its number, to sort them by
+        let mut rows: Vec<(usize, String)> = Vec::new();
+        for breakpoint in self.breakpoints.values() {
+            let number = match breakpoint.number {
+                Some(number) => number,
+                None => continue,
+            };
+            let what = match (
+                self.debug_data.get_function_from_addr(breakpoint.addr),
+                self.debug_data.get_line_from_addr(breakpoint.addr),
+            ) {
+                (Some(func), Some(line)) => format!("in {} at {}", func, line),
+                (None, Some(line)) => format!("at {}", line),
+                _ => match (self.debug_data.get_plt_name(breakpoint.addr), &breakpoint.location) {
+                    (Some(name), _) => format!("<{}@plt>", name),
+                    //a function in a shared library
+                    (None, Some(location)) if is_function_name(location) => format!("<{}>", location),
+                    _ => String::new(),
+                },
+            };
+            let mut text = row(
+                number,
+                match (&breakpoint.dprintf, breakpoint.hardware_slot) {
+                    (Some(_), _) => "dprintf",
+                    (None, Some(_)) => "hw breakpoint",
+                    (None, None) => "breakpoint",
+                },
+                breakpoint.temporary,
+                breakpoint.enabled, &format!("{:#018x}", breakpoint.addr), &what);
+            if let Some(condition) = &breakpoint.condition {
+                text.push_str(&format!("\n\tstop only if {}", condition));
+            }
+            match breakpoint.hit_count {
+                0 => (),
+                1 => text.push_str("\n\tbreakpoint already hit 1 time"),
+                n => text.push_str(&format!("\n\tbreakpoint already hit {} times", n)),
+            }
+            if breakpoint.ignore_count > 0 {
+                text.push_str(&format!(
+                    "\n\tWill ignore next {} crossings of breakpoint.",
+                    breakpoint.ignore_count
+                ));
+            }
+            if let Some(group) = &breakpoint.group {
+                text.push_str(&format!("\n\tgroup {}", group));
+            }
+            if let Some(printf) = &breakpoint.dprintf {
+                text.push_str(&format!("\n        printf {}", printf));
+            }
+            for command in &breakpoint.commands {
+                text.push_str(&format!("\n        {}", command));
+            }
+            rows.push((number, text));
+        }
+        for breakpoint in &self.pending_breakpoints {
+            let number = breakpoint.number.unwrap();
+            let location = breakpoint.location.as_deref().unwrap_or("");
+            let mut text = row(number, "breakpoint", breakpoint.temporary, breakpoint.enabled, "<PENDING>", location);
+            if let Some(condition) = &breakpoint.condition {
+                text.push_str(&format!("\n\tstop only if {}", condition));
+            }
+            if let Some(group) = &breakpoint.group {
+                text.push_str(&format!("\n\tgroup {}", group));
+            }
+            rows.push((number, text));
+        }
+        for watchpoint in &self.watchpoints {
+            //a disabled watchpoint has given up its debug registers, but would get them back if
+            //enough are free
+            let hardware = if watchpoint.enabled {
+                !watchpoint.hardware_slots.is_empty()
+            } else {
+                hardware_watch_ranges(watchpoint.addr, watchpoint.len).len() <= HARDWARE_SLOTS
+            };
+            let kind = if hardware { "hw watchpoint" } else { "watchpoint" };
+            let text = row(watchpoint.number, kind, false, watchpoint.enabled, "", &watchpoint.expression);
+            rows.push((watchpoint.number, text));
+        }
+        for catchpoint in &self.catchpoints {
+            let event = if catchpoint.function == THROW_FUNCTION { "throw" } else { "catch" };
+            let what = format!("exception {}", event);
+            let text = row(catchpoint.number, "catchpoint", false, catchpoint.enabled, "", &what);
+            rows.push((catchpoint.number, text));
+        }
+        if rows.is_empty() {
+            println!("No breakpoints or watchpoints.");
+            return;
+        }
+        rows.sort();
+        println!("Num     Type           Disp Enb Address            What");
+        for (_, text) in rows {
+            println!("{}", text);
+        }
+    }
+
+    //Make sure the debugger's own breakpoint on a PLT stub is there if and only if something needs
+    //it: an enabled catchpoint or `set trace-plt on`. A breakpoint the user set there is left as
+    //it is.
+    fn update_stub_breakpoint(&mut self, addr: usize) -> Result<(), nix::Error> {
+        let needed = self.traced_stubs.contains(&addr)
+            || self.shlib_event == Some(addr)
+            || self.catchpoint_at(addr).is_some()
+            || self.watchpoints.iter().any(|w| w.scope.is_some_and(|(ret, _)| ret == addr));
+        match self.breakpoints.get(&addr) {
+            Some(breakpoint) if breakpoint.number.is_some() => Ok(()),
+            Some(_) if needed => self.set_breakpoint_enabled(addr, true),
+            Some(_) => {
+                self.set_breakpoint_enabled(addr, false)?;
+                self.breakpoints.remove(&addr);
+                Ok(())
+            }
+            None if needed => self.insert_breakpoint(addr, None, None),
+            None => Ok(()),
+        }
+    }
+
+    //The enabled catchpoint whose breakpoint is at `addr`, if any
+    fn catchpoint_at(&self, addr: usize) -> Option<&Catchpoint> {
+        self.catchpoints.iter().find(|c| c.addr == addr && c.enabled)
+    }
+
+    //Handle `detach`: put back the instructions the breakpoints replaced, and let the child run
+    //on its own. The breakpoints are kept for the next `run`.
+    fn detach(&mut self) -> Result<(), String> {
+        if self.examining_core() {
+            return self.load_core("");
+        }
+        let inf = self.inferior.as_mut().ok_or("The program is not being run.".to_string())?;
+        let pid = inf.process_id();
+        println!("Detaching from program: {}, process {}", self.target, pid);
+        for breakpoint in self.breakpoints.values() {
+            if breakpoint.enabled && breakpoint.hardware_slot.is_none() {
+                inf.write_byte(breakpoint.addr, breakpoint.orig_byte)
+                    .map_err(|e| format!("Cannot remove breakpoint at {:#x}. Error: {}", breakpoint.addr, e))?;
+            }
+        }
+        inf.detach().map_err(|e| format!("Cannot detach from process {}. Error: {}", pid, e))?;
+        println!("[Inferior {} (process {}) detached]", self.inferior_number, pid);
+        self.events.push(Event::Detached(pid));
+        self.forget_process();
+        Ok(())
+    }
+
+    //Handle `kill`: end the child process, keeping the breakpoints for the next `run`
+    fn kill(&mut self) -> Result<(), String> {
+        if self.examining_core() {
+            return Err("The program is not being run.".to_string());
+        }
+        let inf = self.inferior.as_mut().ok_or("The program is not being run.".to_string())?;
+        let pid = inf.process_id();
+        inf.kill_child().map_err(|e| format!("Cannot kill process {}. Error: {}", pid, e))?;
+        println!("[Inferior {} (process {}) killed]", self.inferior_number, pid);
+        self.record_event(&Status::Signaled(Signal::SIGKILL));
+        self.forget_process();
+        Ok(())
+    }
+
+    //Handle `core-file <file>`, which looks at the process a core file was dumped from as if it
+    //were stopped where it died, and `core-file` on its own, which puts the core file away
+    fn load_core(&mut self, path: &str) -> Result<(), String> {
+        if path.is_empty() {
+            if self.examining_core() {
+                self.forget_process();
+            }
+            println!("No core file now.");
+            return Ok(());
+        }
+        if self.inferior.is_some() && !self.examining_core() {
+            return Err("A program is being debugged already.  Kill it with \"kill\" first.".to_string());
+        }
+        let core = CoreFile::open(path)?;
+        if self.examining_core() {
+            self.forget_process();
+        }
+        for thread in &core.threads[1..] {
+            println!("[New LWP {}]", thread.tid);
+        }
+        println!("Core was generated by `{}'.", core.command);
+        match Signal::try_from(core.threads[0].signal) {
+            Ok(signal) => println!("Program terminated with signal {}, {}.", signal, signals::description(signal)),
+            Err(_) => println!("Program terminated."),
+        }
+        let mut inferior = Inferior::from_core(core, &self.target, &self.settings);
+        inferior.set_signals(self.signals.clone());
+        self.inferior = Some(inferior);
+        self.relocate();
+        self.select_frame(0)
+    }
+
+    //Handle `target remote <host>:<port>`, which debugs a program running under gdbserver or
+    //another stub speaking the gdb remote protocol, stopped where the stub has it. It is run
+    //with `continue` rather than `run`.
+    fn target(&mut self, args: &str) -> Result<(), String> {
+        let mut words = args.split_whitespace();
+        let address = match (words.next(), words.next()) {
+            (None, _) => return Err("Argument required (target name).  Try `help target'.".to_string()),
+            (Some("remote"), Some(address)) => address,
+            (Some("remote"), None) => {
+                return Err("To open a remote debug connection, you need to specify the host and port of the \
+                            remote stub (e.g. localhost:1234)."
+                    .to_string())
+            }
+            (Some(name), _) => return Err(format!("Undefined target command: \"{}\".", name)),
+        };
+        if self.inferior.is_some() && !self.examining_core() {
+            return Err("A program is being debugged already.  Kill it with \"kill\" first.".to_string());
+        }
+        //like in gdb, `:1234` is a port on this machine
+        let address = match address.strip_prefix(':') {
+            Some(port) => format!("localhost:{}", port),
+            None => address.to_string(),
+        };
+        let remote = RemoteTarget::connect(&address)?;
+        if self.examining_core() {
+            self.forget_process();
+        }
+        println!("Remote debugging using {}", address);
+        let mut inferior = Inferior::from_remote(remote, &self.target, &self.settings);
+        inferior.set_signals(self.signals.clone());
+        self.inferior = Some(inferior);
+        self.relocate();
+        self.inferior.as_mut().unwrap().write_breakpoints(&mut self.breakpoints);
+        self.select_frame(0)
+    }
+
+    //Handle `gcore [file]`, which writes the memory and registers of the program to a core file,
+    //`core.<pid>` by default, that `core-file` (or gdb) can look at later
+    fn generate_core(&self, path: &str) -> Result<(), String> {
+        let inf = match &self.inferior {
+            Some(inf) if inf.remote().is_some() => {
+                return Err("Can't create a corefile of a program debugged remotely.".to_string())
+            }
+            Some(inf) if inf.core().is_none() => inf,
+            _ => return Err("The program is not being run.".to_string()),
+        };
+        let path = match path {
+            "" => format!("core.{}", inf.process_id()),
+            path => path.to_string(),
+        };
+        let snapshot = inf
+            .snapshot(&self.breakpoints)
+            .map_err(|e| format!("Cannot read the state of the program. Error: {}", e))?;
+        core_file::write(&path, &snapshot).map_err(|e| format!("Failed to write core file {}: {}", path, e))?;
+        println!("Saved corefile {}", path);
+        Ok(())
+    }
+
+    //Handle `checkpoint`: fork the stopped program, and keep the new process stopped where it is
+    //to go back to with `restart`
+    fn checkpoint(&mut self) -> Result<(), String> {
+        let inf = match self.inferior.as_mut() {
+            Some(inf) if inf.remote().is_some() => {
+                return Err("checkpoint: a program debugged remotely can't be forked.".to_string())
+            }
+            Some(inf) if inf.core().is_none() => inf,
+            _ => return Err("The program is not being run.".to_string()),
+        };
+        let pid = inf.fork_process(&self.breakpoints).map_err(|e| format!("checkpoint: fork failed. Error: {}", e))?;
+        println!("checkpoint {}: fork returned pid {}.", self.next_checkpoint, pid);
+        self.checkpoints.insert(self.next_checkpoint, inf.fork_of(pid));
+        self.next_checkpoint += 1;
+        Ok(())
+    }
+
+    //Handle `restart <n>`: replace the program with another fork of checkpoint n, which is kept
+    //to go back to again. The breakpoints and watchpoints are set in the new process.
+    fn restart(&mut self, number: &str) -> Result<(), String> {
+        let number: usize = number.parse().map_err(|_| "Usage: restart <checkpoint number>".to_string())?;
+        let checkpoint = self.checkpoints.get_mut(&number).ok_or(format!("Not found: checkpoint {}", number))?;
+        let pid = checkpoint
+            .fork_process(&HashMap::new())
+            .map_err(|e| format!("restart: fork failed. Error: {}", e))?;
+        let inferior = checkpoint.fork_of(pid);
+        if self.examining_core() {
+            self.forget_process();
+        }
+        if let Some(inf) = self.inferior.as_mut() {
+            inf.kill_child().ok();
+            self.forget_process();
+        }
+        self.adopt_process(inferior);
+        println!("Switching to process {}", pid);
+        self.select_frame(0)
+    }
+
+    //Make a copy of the program forked from the one kept for a checkpoint or a recording the
+    //program being debugged, with the breakpoints and watchpoints set in it
+    fn adopt_process(&mut self, mut inferior: Inferior) {
+        inferior.set_signals(self.signals.clone());
+        self.inferior = Some(inferior);
+        self.relocate();
+        self.inferior.as_mut().unwrap().write_breakpoints(&mut self.breakpoints);
+        self.watch_library_loads();
+        //the copy may be inside the frames of watched variables, so they are all kept
+        let mut watchpoints = std::mem::take(&mut self.watchpoints);
+        for watchpoint in watchpoints.iter_mut().filter(|w| w.enabled) {
+            if let Err(e) = self.arm_watchpoint(watchpoint) {
+                println!("Cannot set watchpoint {}: {}", watchpoint.number, e);
+            }
+        }
+        self.watchpoints = watchpoints;
+    }
+
+    //Handle `record` and `record stop`. Recording keeps a copy of the program as it is now, and
+    //logs every instruction the program runs from then on, which the reverse commands go back
+    //through, running another copy forward to the point they go back to.
+    fn record(&mut self, args: &str) -> Result<(), String> {
+        let inf = match self.inferior.as_mut() {
+            Some(inf) if inf.core().is_none() => inf,
+            _ => return Err("The program is not being run.".to_string()),
+        };
+        match args {
+            "" | "full" => {
+                if self.record.is_some() {
+                    return Err("The process is already being recorded.  Use \"record stop\" to stop recording first."
+                        .to_string());
+                }
+                //only the thread being debugged is stepped and logged
+                if inf.threads().len() > 1 {
+                    return Err("Process record does not support programs with more than one thread.".to_string());
+                }
+                let pid = inf
+                    .fork_process(&self.breakpoints)
+                    .map_err(|e| format!("Cannot record the program. Error: {}", e))?;
+                self.record = Some(inf.fork_of(pid));
+                inf.start_recording(Vec::new());
+                Ok(())
+            }
+            "stop" if self.stop_recording() => {
+                println!("Process record is stopped and all execution logs are deleted.");
+                Ok(())
+            }
+            "stop" => Err("No recording is currently active.".to_string()),
+            _ => Err(format!("Undefined record command: \"{}\".", args)),
+        }
+    }
+
+    //Stop recording the program, and let go of the copy kept for going back. Returns whether it
+    //was being recorded.
+    fn stop_recording(&mut self) -> bool {
+        let mut start = match self.record.take() {
+            Some(start) => start,
+            None => return false,
+        };
+        start.kill_child().ok();
+        if let Some(inf) = &self.inferior {
+            inf.stop_recording();
+        }
+        true
+    }
+
+    //Handle `info record`
+    fn print_record(&self) {
+        match self.inferior.as_ref().and_then(Inferior::history) {
+            Some(history) if self.record.is_some() => {
+                println!("Active record target: record-full");
+                println!("Log contains {} instructions.", history.len());
+            }
+            _ => println!("No recording is currently active."),
+        }
+    }
+
+    //Handle `reverse-step`, `reverse-stepi` and `reverse-continue`, which take the recorded
+    //program back to the start of the line it ran before the current one, the instruction before,
+    //or the last breakpoint it passed. It goes back to where the recording started if there is
+    //no such point.
+    fn reverse(&mut self, cmd: &DebuggerCommand) -> Result<(), String> {
+        let history = match (&self.record, self.inferior.as_ref().and_then(Inferior::history)) {
+            (Some(_), Some(history)) => history,
+            _ => return Err("Target native does not support this command.".to_string()),
+        };
+        let inf = self.inferior.as_ref().unwrap();
+        let rip = inf.get_registers().map_err(|e| format!("Cannot read registers. Error: {}", e))?.rip as usize;
+        let target = match cmd {
+            DebuggerCommand::ReverseStepInstruction => history.len().checked_sub(1),
+            DebuggerCommand::ReverseStep => self.previous_line(&history, rip),
+            _ => history
+                .iter()
+                .rposition(|addr| self.breakpoints.get(addr).is_some_and(|b| b.enabled && b.number.is_some())),
+        };
+        self.go_back(&history[..target.unwrap_or(0)])?;
+        if target.is_none() {
+            println!("\nNo more reverse-execution history.");
+        }
+        let rip = self.inferior.as_ref().unwrap().get_registers().map_err(|e| e.to_string())?.rip as usize;
+        let status = Status::Stopped(Signal::SIGTRAP, rip);
+        if matches!(cmd, DebuggerCommand::ReverseStepInstruction) && !self.breakpoints.contains_key(&rip) {
+            self.print_instruction_location(status);
+        } else {
+            self.print_child_status(status);
+        }
+        Ok(())
+    }
+
+    //Where the source line run before the one the program is at started, for `reverse-step`. The
+    //recorded instructions are gone back through past those of the current line, and then those
+    //of the line before it, skipping any without line info, such as in libc.
+    fn previous_line(&self, history: &[usize], rip: usize) -> Option<usize> {
+        let line_of = |addr: usize| self.debug_data.get_line_from_addr(addr).map(|line| (line.file, line.number));
+        let current = line_of(rip);
+        let mut previous = None;
+        let mut start = None;
+        for (position, addr) in history.iter().enumerate().rev() {
+            let line = match line_of(*addr) {
+                Some(line) => line,
+                None => continue,
+            };
+            match &previous {
+                None if Some(&line) == current.as_ref() => continue,
+                None => previous = Some(line),
+                Some(previous) if *previous != line => break,
+                Some(_) => (),
+            }
+            start = Some(position);
+        }
+        start
+    }
+
+    //Take the recorded program back to where it was after running the instructions in `history`,
+    //which the recording starts with, by running a new copy of the program as it was when the
+    //recording started forward through them. The copy replaces the program, and is recorded from
+    //there on.
+    fn go_back(&mut self, history: &[usize]) -> Result<(), String> {
+        let start = self.record.as_mut().unwrap();
+        let pid = start
+            .fork_process(&HashMap::new())
+            .map_err(|e| format!("Cannot go back. Error: {}", e))?;
+        let mut copy = start.fork_of(pid);
+        let count = match copy.replay(history) {
+            Ok(count) => count,
+            Err(e) => {
+                copy.kill_child().ok();
+                return Err(format!("Cannot go back: the program did not run the same way again. Error: {}", e));
+            }
+        };
+        if count < history.len() {
+            println!(
+                "The program ran differently after {} of the {} recorded instructions, and is stopped there.",
+                count,
+                history.len()
+            );
+        }
+        copy.start_recording(history[..count].to_vec());
+        if let Some(inf) = self.inferior.as_mut() {
+            inf.kill_child().ok();
+        }
+        self.adopt_process(copy);
+        Ok(())
+    }
+
+    //Handle `delete checkpoint <n>`, killing the process kept for it
+    fn delete_checkpoint(&mut self, number: &str) -> Result<(), String> {
+        let number: usize = number.parse().map_err(|_| "Usage: delete checkpoint <number>".to_string())?;
+        let mut checkpoint = self.checkpoints.remove(&number).ok_or(format!("Not found: checkpoint {}", number))?;
+        let pid = checkpoint.process_id();
+        checkpoint.kill_child().map_err(|e| format!("Cannot kill process {}. Error: {}", pid, e))?;
+        println!("Killed process {}", pid);
+        Ok(())
+    }
+
+    //Handle `info checkpoints`: list the checkpoints with where each one is stopped
+    fn print_checkpoints(&self) {
+        if self.checkpoints.is_empty() {
+            println!("No checkpoints.");
+            return;
+        }
+        for (number, checkpoint) in &self.checkpoints {
+            let frame = match checkpoint.get_registers() {
+                Ok(registers) => frame_description(&self.debug_data, 0, registers.rip as usize)[4..].to_string(),
+                Err(_) => "??".to_string(),
+            };
+            println!("  {:<4} process {}: {}", number, checkpoint.process_id(), frame);
+        }
+    }
+
+    //Whether the inferior is the process of a core file rather than a live one
+    fn examining_core(&self) -> bool {
+        self.inferior.as_ref().is_some_and(|inf| inf.core().is_some())
+    }
+
+    //Forget the child process once it has exited, been killed or been detached from. The
+    //breakpoints stay, and are written into the next process `run` starts.
+    fn forget_process(&mut self) {
+        self.stop_recording();
+        //values from this process aren't compared with those of the next
+        for display in &mut self.displays {
+            display.last = None;
+        }
+        self.inferior = None;
+        self.selected_frame = 0;
+        //the dynamic linker is somewhere else in the next process
+        if let Some(addr) = self.shlib_event.take() {
+            if self.breakpoints.get(&addr).is_some_and(|b| b.number.is_none()) {
+                self.breakpoints.remove(&addr);
+            }
+        }
+        //the processes of other inferiors still have the breakpoints written into them
+        if self.other_inferiors.values().any(|inferior| inferior.is_some()) {
+            return;
+        }
+        for breakpoint in self.breakpoints.values_mut() {
+            //nothing has been replaced by the breakpoint until it is written into a process
+            breakpoint.orig_byte = 0;
+        }
+    }
+
+    //Make inferiors of the processes the child forked that are kept with `set detach-on-fork off`.
+    //They stay stopped until switched to with `inferior <n>` and resumed.
+    fn add_kept_inferiors(&mut self) {
+        let inf = match self.inferior.as_ref() {
+            Some(inf) => inf,
+            None => return,
+        };
+        for pid in inf.take_kept() {
+            println!("[New inferior {} (process {})]", self.next_inferior_number, pid);
+            self.other_inferiors.insert(self.next_inferior_number, Some(inf.fork_of(pid)));
+            self.next_inferior_number += 1;
+        }
+    }
+
+    //Handle `inferior <n>`, which makes another inferior the one commands apply to and shows
+    //where it is stopped, and `inferior` on its own, which says which one that is
+    fn switch_inferior(&mut self, args: &str) -> Result<(), String> {
+        if args.is_empty() {
+            println!(
+                "[Current inferior is {} [{}] ({})]",
+                self.inferior_number,
+                describe_inferior(&self.inferior),
+                self.target
+            );
+            return Ok(());
+        }
+        let number: usize = args.parse().map_err(|_| format!("Invalid inferior number \"{}\".", args))?;
+        if number != self.inferior_number {
+            let inferior = self
+                .other_inferiors
+                .remove(&number)
+                .ok_or(format!("Inferior ID {} not known.", number))?;
+            let previous = std::mem::replace(&mut self.inferior, inferior);
+            self.other_inferiors.insert(self.inferior_number, previous);
+            self.inferior_number = number;
+        }
+        println!(
+            "[Switching to inferior {} [{}] ({})]",
+            number,
+            describe_inferior(&self.inferior),
+            self.target
+        );
+        if self.inferior.is_some() {
+            self.select_frame(0)?;
+        }
+        Ok(())
+    }
+
+    //Handle `info inferiors`, listing the inferiors with the current one marked
+    fn print_inferiors(&self) {
+        let mut inferiors: Vec<(usize, &Option<Inferior>)> =
+            self.other_inferiors.iter().map(|(number, inferior)| (*number, inferior)).collect();
+        inferiors.push((self.inferior_number, &self.inferior));
+        inferiors.sort_by_key(|(number, _)| *number);
+        println!("  Num  Description       Executable");
+        for (number, inferior) in inferiors {
+            let program = match inferior {
+                Some(inferior) => inferior.program().display().to_string(),
+                None => self.target.clone(),
+            };
+            println!(
+                "{} {:<4} {:<17} {}",
+                if number == self.inferior_number { "*" } else { " " },
+                number,
+                describe_inferior(inferior),
+                program
+            );
+        }
+    }
+
+    //Handle `thread <n>`, which makes another thread the one commands such as `bt`, `info
+    //registers` and `step` apply to, and `thread` on its own, which says which one that is
+    fn switch_thread(&mut self, args: &str) -> Result<(), String> {
+        let inf = self.inferior.as_ref().ok_or("No thread selected".to_string())?;
+        if args.is_empty() {
+            println!("[Current thread is {} (LWP {})]", inf.thread_number(), inf.pid());
+            return Ok(());
+        }
+        let number: usize = args.parse().map_err(|_| format!("Invalid thread ID: {}", args))?;
+        let tid = inf.select_thread(number)?;
+        println!("[Switching to thread {} (LWP {})]", number, tid);
+        self.select_frame(0)
+    }
+
+    //Say which thread stopped when it is not the one stopped or selected last
+    fn report_thread_switch(&self, s: &Status) {
+        if let (Some(inf), true) = (self.inferior.as_ref(), s.is_stop()) {
+            if inf.thread_switched() && inf.threads().len() > 1 {
+                println!("[Switching to thread {} (LWP {})]", inf.thread_number(), inf.pid());
+            }
+        }
+    }
+
+    //Handle `info threads`, listing the threads of the program and where each is stopped, with
+    //the one being debugged marked
+    fn print_threads(&self) {
+        let inf = match &self.inferior {
+            Some(inf) => inf,
+            None => {
+                println!("No threads.");
+                return;
+            }
+        };
+        println!("  Id   Target Id         Frame");
+        for (number, tid) in inf.threads() {
+            //the frame without its level
+            let frame = match inf.thread_rip(tid) {
+                Ok(rip) => frame_description(&self.debug_data, 0, rip)[4..].to_string(),
+                Err(e) => format!("(cannot read registers: {})", e),
+            };
+            println!(
+                "{} {:<4} {:<17} {}",
+                if tid == inf.pid() { "*" } else { " " },
+                number,
+                format!("LWP {}", tid),
+                frame
+            );
+        }
+    }
+
+    //If the child has exec'd a different program, load its symbols and debug it from then on, and
+    //set the user's breakpoints again wherever their locations are in it. The child is stopped at
+    //the new program's entry point, with nothing written into it yet. Watchpoints are deleted,
+    //as what they watched belonged to the old program. Returns whether the child exec'd.
+    fn follow_exec(&mut self) -> bool {
+        let path = match self.inferior.as_mut().and_then(|inf| inf.take_exec()) {
+            Some(path) => path.to_string_lossy().into_owned(),
+            None => return false,
+        };
+        let old: Vec<Breakpoint> = std::mem::take(&mut self.breakpoints).into_values().collect();
+        self.traced_stubs.clear();
+        self.shlib_event = None;
+        for watchpoint in self.watchpoints.drain(..) {
+            println!("Deleting watchpoint {}: the program it watched is gone", watchpoint.number);
+        }
+        self.debug_data = match DwarfData::from_file(&path) {
+            Ok(debug_data) => debug_data,
+            Err(_) => {
+                println!("Could not read debugging symbols from {}, so its breakpoints are deleted", path);
+                self.catchpoints.clear();
+                return true;
+            }
+        };
+        self.sources = SourceCache::new(&self.debug_data, &path);
+        self.target = path;
+        if let Some(bias) = self.load_bias() {
+            self.debug_data.relocate(bias);
+        }
+        self.listing = None;
+        self.examine_next = None;
+        //the types of the values printed so far are those of the old program
+        self.value_history.clear();
+        let mut old: Vec<Breakpoint> = old.into_iter().filter(|b| b.number.is_some()).collect();
+        old.sort_by_key(|b| b.number);
+        for mut breakpoint in old {
+            let location = breakpoint.location.clone().unwrap_or_default();
+            let addr = match self.parse_address(&location) {
+                Some(addr) if !self.breakpoints.contains_key(&addr) => addr,
+                //the function may be in a library the new program hasn't loaded yet
+                None if is_function_name(&location) => {
+                    breakpoint.orig_byte = 0;
+                    self.pending_breakpoints.push(breakpoint);
+                    continue;
+                }
+                _ => {
+                    println!(
+                        "Error in re-setting breakpoint {}: \"{}\" is not in the new program.",
+                        breakpoint.number.unwrap(),
+                        location
+                    );
+                    continue;
+                }
+            };
+            breakpoint.addr = addr;
+            breakpoint.orig_byte = 0;
+            if breakpoint.enabled {
+                let inf = self.inferior.as_mut().unwrap();
+                let result = match breakpoint.hardware_slot {
+                    Some(slot) => inf.set_hardware_breakpoint(slot, addr),
+                    None => inf.write_byte(addr, 0xcc).map(|orig_byte| breakpoint.orig_byte = orig_byte),
+                };
+                if let Err(e) = result {
+                    println!("Cannot insert breakpoint {}. Error: {}", breakpoint.number.unwrap(), e);
+                    continue;
+                }
+            }
+            self.breakpoints.insert(addr, breakpoint);
+        }
+        for mut catchpoint in std::mem::take(&mut self.catchpoints) {
+            match self.debug_data.get_plt_entry(catchpoint.function) {
+                Some(addr) => {
+                    catchpoint.addr = addr;
+                    self.catchpoints.push(catchpoint);
+                }
+                None => println!(
+                    "Deleting catchpoint {}: the new program never calls {}",
+                    catchpoint.number, catchpoint.function
+                ),
+            }
+        }
+        let addrs: Vec<usize> = self.catchpoints.iter().map(|c| c.addr).collect();
+        for addr in addrs {
+            if let Err(e) = self.update_stub_breakpoint(addr) {
+                println!("Cannot insert breakpoint at {:#x}. Error: {}", addr, e);
+            }
+        }
+        if self.settings.trace_plt {
+            self.start_plt_trace();
+        }
+        self.pending_breakpoints.sort_by_key(|b| b.number);
+        self.watch_library_loads();
+        true
+    }
+
+    //Enable or disable a breakpoint, writing or removing the int3 instruction, or setting or
+    //clearing the debug register of a hardware breakpoint, if the child process is running
+    fn set_breakpoint_enabled(&mut self, addr: usize, enabled: bool) -> Result<(), nix::Error> {
+        let breakpoint = self.breakpoints.get_mut(&addr).unwrap();
+        if breakpoint.enabled == enabled {
+            return Ok(());
+        }
+        if let Some(inf) = self.inferior.as_mut() {
+            if let Some(slot) = breakpoint.hardware_slot {
+                if enabled {
+                    inf.set_hardware_breakpoint(slot, addr)?;
+                } else {
+                    inf.clear_hardware_slot(slot)?;
+                }
+            } else if enabled {
+                breakpoint.orig_byte = inf.write_byte(addr, 0xcc)?;
+            } else {
+                inf.write_byte(addr, breakpoint.orig_byte)?;
+            }
+        }
+        breakpoint.enabled = enabled;
+        Ok(())
+    }
+
+    //Make the breakpoint at `addr` a hardware breakpoint using debug register `slot`, putting
+    //back the instruction its int3 replaced
+    fn use_hardware_slot(&mut self, addr: usize, slot: usize) -> Result<(), nix::Error> {
+        let enabled = self.breakpoints[&addr].enabled;
+        self.set_breakpoint_enabled(addr, false)?;
+        self.breakpoints.get_mut(&addr).unwrap().hardware_slot = Some(slot);
+        self.set_breakpoint_enabled(addr, enabled)
+    }
+
+    //The debug registers used by hardware watchpoints and breakpoints
+    fn used_hardware_slots(&self) -> Vec<usize> {
+        self.watchpoints
+            .iter()
+            .flat_map(|w| w.hardware_slots.iter().cloned())
+            .chain(self.breakpoints.values().filter_map(|b| b.hardware_slot))
+            .collect()
+    }
+
+    fn free_hardware_slot(&self) -> Option<usize> {
+        let used = self.used_hardware_slots();
+        (0..HARDWARE_SLOTS).find(|slot| !used.contains(slot))
+    }
+
+    //Handle `watch -l <expression>` and `watch <address> len <bytes>`, either of which can be
+    //followed by `mask <bits>`
+    fn watch(&mut self, args: &str) -> Result<(), String> {
+        if self.inferior.is_none() {
+            return Err("No process is currently being run".to_string());
+        }
+        let (watched, mask) = match args.rfind(" mask ") {
+            Some(i) => {
+                let mask_expr = expression::parse(&args[i + 6..], &self.debug_data)?;
+                let mask = self.evaluate(&[mask_expr])?.remove(0);
+                (&args[..i], Some(mask.scalar(&self.debug_data)?.as_u64()))
+            }
+            None => (args, None),
+        };
+        let location = watched
+            .strip_prefix("-location ")
+            .or_else(|| watched.strip_prefix("-l "))
+            .map(|expr| expr.trim());
+        let mut scope = None;
+        let (addr, len, type_offset) = if let Some(expr) = location {
+            let value = self.evaluate(&[expression::parse(expr, &self.debug_data)?])?.remove(0);
+            match value.address {
+                Some(addr) => (addr, value.bytes.len(), value.type_offset),
+                None => return Err(format!("Cannot watch constant value `{}'.", expr)),
+            }
+        } else if let Some(i) = watched.rfind(" len ") {
+            let exprs = [
+                expression::parse(&watched[..i], &self.debug_data)?,
+                expression::parse(&watched[i + 5..], &self.debug_data)?,
+            ];
+            let values = self.evaluate(&exprs)?;
+            let addr = values[0].scalar(&self.debug_data)?.as_u64() as usize;
+            let len = values[1].scalar(&self.debug_data)?.as_i64();
+            if len <= 0 {
+                return Err("Watched length must be positive.".to_string());
+            }
+            let byte = self.debug_data.base_type("unsigned char", 1, Encoding::UnsignedChar);
+            (addr, len as usize, self.debug_data.array_type(byte, len as usize))
+        } else if !watched.trim().is_empty() {
+            //a variable, or anything else in memory that an expression refers to. One in the
+            //current frame is only watched while the frame exists.
+            let expr = watched.trim();
+            let value = self.evaluate(&[expression::parse(expr, &self.debug_data)?])?.remove(0);
+            let addr = match value.address {
+                Some(addr) => addr,
+                None => return Err(format!("Cannot watch constant value `{}'.", expr)),
+            };
+            //a function that calls nothing may keep its locals in the 128 bytes below the stack
+            //pointer
+            let frame = self.current_frame()?;
+            if addr >= frame.regs.rsp as usize - 128 && addr < frame.cfa {
+                let inf = self.inferior.as_ref().unwrap();
+                let ret = inf
+                    .read_mem(frame.cfa - 8, 8)
+                    .map_err(|_| "Cannot find the return address of the current frame".to_string())?;
+                scope = Some((read_uint(&ret) as usize, frame.cfa));
+            }
+            (addr, value.bytes.len(), value.type_offset)
+        } else {
+            return Err(
+                "Usage: watch <expression>, watch -l <expression> or watch <address> len <bytes>, optionally followed by mask <bits>"
+                    .to_string(),
+            );
+        };
+        if len == 0 {
+            return Err("Cannot watch a value of size 0.".to_string());
+        }
+        if mask.is_some() && len > 8 {
+            return Err("A masked watchpoint can only watch up to 8 bytes.".to_string());
+        }
+        let mut watchpoint = Watchpoint {
+            number: self.next_number,
+            expression: args.to_string(),
+            addr,
+            len,
+            type_offset,
+            old_value: Vec::new(),
+            mask,
+            hardware_slots: Vec::new(),
+            enabled: true,
+            scope,
+        };
+        self.arm_watchpoint(&mut watchpoint)?;
+        println!("{}", watchpoint_title(&watchpoint));
+        self.next_number += 1;
+        self.watchpoints.push(watchpoint);
+        //the frame's return address gets a breakpoint of the debugger's own
+        if let Some((ret, _)) = scope {
+            self.update_stub_breakpoint(ret)
+                .map_err(|e| format!("Cannot set breakpoint at {:#x}. Error: {}", ret, e))?;
+        }
+        Ok(())
+    }
+
+    //Delete the watchpoints on variables in frames that have returned, now that the child is
+    //stopped at `addr`. A frame has returned once the child is at its return address with the
+    //stack pointer back at its base, rather than in a deeper, recursive call. Returns whether any
+    //were deleted.
+    fn leave_watchpoint_scopes(&mut self, addr: usize) -> Result<bool, nix::Error> {
+        let inf = self.inferior.as_ref().unwrap();
+        let sp = inf.get_registers()?.rsp as usize;
+        let left = |w: &Watchpoint| w.scope.is_some_and(|(ret, base)| ret == addr && sp >= base);
+        let mut deleted = false;
+        for watchpoint in self.watchpoints.iter().filter(|w| left(w)) {
+            for slot in &watchpoint.hardware_slots {
+                inf.clear_hardware_slot(*slot)?;
+            }
+            println!();
+            println!(
+                "Watchpoint {} deleted because the program has left the block in which its expression is valid.",
+                watchpoint.number
+            );
+            deleted = true;
+        }
+        self.watchpoints.retain(|w| !left(w));
+        self.update_stub_breakpoint(addr)?;
+        Ok(deleted)
+    }
+
+    //Whether the breakpoint at `addr` is only there to see when a watched variable's frame returns
+    fn is_scope_breakpoint(&self, addr: usize) -> bool {
+        self.breakpoints.get(&addr).is_some_and(|b| b.enabled && b.number.is_none())
+            && !self.traced_stubs.contains(&addr)
+            && self.catchpoint_at(addr).is_none()
+    }
+
+    //Record the current contents of a watchpoint's memory and give it debug registers, if enough
+    //are free to cover it
+    fn arm_watchpoint(&self, watchpoint: &mut Watchpoint) -> Result<(), String> {
+        let inf = self.inferior.as_ref().unwrap();
+        watchpoint.old_value = inf
+            .read_mem(watchpoint.addr, watchpoint.len)
+            .map_err(|_| format!("Cannot access memory at address {:#x}", watchpoint.addr))?;
+        let used = self.used_hardware_slots();
+        let free: Vec<usize> = (0..HARDWARE_SLOTS)
+            .filter(|slot| !used.contains(slot))
+            .collect();
+        let ranges = hardware_watch_ranges(watchpoint.addr, watchpoint.len);
+        watchpoint.hardware_slots = Vec::new();
+        if ranges.len() <= free.len() {
+            for (slot, (addr, len)) in free.iter().zip(ranges) {
+                inf.set_hardware_watchpoint(*slot, addr, len)
+                    .map_err(|e| format!("Cannot set hardware watchpoint. Error: {}", e))?;
+                watchpoint.hardware_slots.push(*slot);
+            }
+        }
+        Ok(())
+    }
+
+    //Set the watchpoints up again in a newly started child process. The frames of watched
+    //variables are gone, so those watchpoints are deleted.
+    fn rearm_watchpoints(&mut self) {
+        let mut watchpoints = std::mem::take(&mut self.watchpoints);
+        for mut watchpoint in watchpoints.drain(..) {
+            if let Some((ret, _)) = watchpoint.scope {
+                println!("Deleting watchpoint {}: its frame is gone", watchpoint.number);
+                if let Err(e) = self.update_stub_breakpoint(ret) {
+                    println!("Cannot remove breakpoint at {:#x}. Error: {}", ret, e);
+                }
+                continue;
+            }
+            if !watchpoint.enabled {
+                self.watchpoints.push(watchpoint);
+                continue;
+            }
+            match self.arm_watchpoint(&mut watchpoint) {
+                Ok(()) => self.watchpoints.push(watchpoint),
+                Err(e) => println!("Deleting watchpoint {}: {}", watchpoint.number, e),
+            }
+        }
+    }
+
+    //Handle `catch throw` and `catch catch`, which stop the program when a C++ exception is thrown
+    //or caught, by breaking on the C++ runtime function called when that happens
+    fn catch(&mut self, event: &str) -> Result<(), String> {
+        let function = match event {
+            "throw" => THROW_FUNCTION,
+            "catch" => CATCH_FUNCTION,
+            "" => return Err("Catch requires an event name.".to_string()),
+            _ => return Err(format!("Undefined catch command: \"{}\".", event)),
+        };
+        let addr = self
+            .debug_data
+            .get_plt_entry(function)
+            .ok_or(format!("Cannot catch {}: the program never calls {}.", event, function))?;
+        let catchpoint = Catchpoint {
+            number: self.next_number,
+            function,
+            addr,
+            enabled: true,
+        };
+        self.catchpoints.push(catchpoint);
+        if let Err(e) = self.update_stub_breakpoint(addr) {
+            self.catchpoints.pop();
+            return Err(format!("Cannot catch {}. Error: {}", event, e));
+        }
+        println!("Catchpoint {} ({})", self.next_number, event);
+        self.next_number += 1;
+        Ok(())
+    }
+
+    //Report that the child stopped at a catchpoint, along with the type of the exception and the
+    //call stack where it was thrown or caught
+    fn print_catchpoint(&self, catchpoint: &Catchpoint) {
+        let inf = self.inferior.as_ref().unwrap();
+        let event = if catchpoint.function == THROW_FUNCTION { "thrown" } else { "caught" };
+        println!();
+        match exception::exception_type(inf, catchpoint.function) {
+            Some(ty) => println!("Catchpoint {} (exception {}), type {}", catchpoint.number, event, ty),
+            None => println!("Catchpoint {} (exception {})", catchpoint.number, event),
+        }
+        if let Err(e) = inf.print_backtrace(&self.debug_data, &self.settings, false, None) {
+            println!("Cannot print backtrace. Error: {}", e);
+        }
+    }
+
+    //Open or close the log file after `set logging on` or `set logging off`. Turning logging on
+    //again starts a new log, with any new file name or overwrite setting.
+    fn update_logging(&mut self, value: &str) {
+        let file = &self.settings.logging_file;
+        match value.split(' ').next() {
+            Some("on") | Some("enabled") => match logging::start(file, self.settings.logging_overwrite) {
+                Ok(()) => println!("Copying output to {}.", file),
+                Err(e) => {
+                    println!("{}", e);
+                    self.settings.logging = false;
+                }
+            },
+            Some("off") | Some("disabled") => {
+                logging::stop();
+                println!("Done logging to {}.", file);
+            }
+            _ => (),
+        }
+    }
+
+    //Copy a command to the log file if `set logging commands` is on
+    fn log_command(&self, line: &str) {
+        if self.settings.logging && self.settings.logging_commands {
+            logging::log_command(line.trim());
+        }
+    }
+
+    //Put a breakpoint on every PLT stub that doesn't have one, so that `log_plt_binding` can log
+    //which library each function comes from the first time it is called
+    fn start_plt_trace(&mut self) {
+        for addr in self.debug_data.get_plt_stubs() {
+            if self.breakpoints.contains_key(&addr) {
+                continue;
+            }
+            match self.insert_breakpoint(addr, None, None) {
+                Ok(()) => {
+                    self.traced_stubs.insert(addr);
+                }
+                Err(e) => println!("Cannot trace PLT stub at {:#x}. Error: {}", addr, e),
+            }
+        }
+    }
+
+    //Remove the breakpoints of the stubs that haven't been called yet
+    fn stop_plt_trace(&mut self) {
+        for addr in std::mem::take(&mut self.traced_stubs) {
+            if let Err(e) = self.update_stub_breakpoint(addr) {
+                println!("Cannot remove breakpoint at {:#x}. Error: {}", addr, e);
+            }
+        }
+    }
+
+    //The child is stopped on the first call through a PLT stub. Single step through the stub, and
+    //through the dynamic linker if the function hasn't been bound yet, until the child reaches
+    //the function itself, and log the library it is in. The stub's breakpoint is then removed.
+    //Returns the status of the child if it stopped for some other reason on the way.
+    fn log_plt_binding(&mut self, stub: usize) -> Result<Option<Status>, nix::Error> {
+        let name = self.debug_data.get_plt_name(stub).unwrap_or("??").to_string();
+        let bias = self.debug_data.load_bias();
+        let plt_sections: Vec<(usize, usize)> = match self.debug_data.get_elf() {
+            Some(elf) => elf
+                .sections
+                .iter()
+                .filter(|s| s.name.starts_with(".plt"))
+                .map(|s| (s.addr + bias, s.addr + s.size + bias))
+                .collect(),
+            None => Vec::new(),
+        };
+        let inf = self.inferior.as_mut().unwrap();
+        let maps = inf.memory_maps();
+        let linker: Vec<&MemoryMap> = maps.iter().filter(|m| is_dynamic_linker(&m.path)).collect();
+        let mut lazy = false;
+        let function = loop {
+            let rip = match inf.step_instruction(&self.breakpoints)? {
+                Status::Stopped(Signal::SIGTRAP, rip) if !inf.exec_pending() => rip,
+                other => return Ok(Some(other)),
+            };
+            if plt_sections.iter().any(|(start, end)| *start <= rip && rip < *end) {
+                continue;
+            }
+            if linker.iter().any(|m| m.start <= rip && rip < m.end) {
+                lazy = true;
+                continue;
+            }
+            break rip;
+        };
+        let library = maps
+            .iter()
+            .find(|m| m.start <= function && function < m.end)
+            .map_or("??", |m| m.path.as_str());
+        if lazy {
+            println!("Bound {} to {} at {:#x}", name, library, function);
+        } else {
+            println!("{} was already bound to {} at {:#x}", name, library, function);
+        }
+        self.traced_stubs.remove(&stub);
+        self.update_stub_breakpoint(stub)?;
+        Ok(None)
+    }
+
+    //Start the program for `run`, stopped before its first instruction with the breakpoints
+    //written into it. Returns false if it couldn't be started.
+    fn start_program(&mut self, args: Vec<String>) -> bool {
+        //a core file is put away, there being no process to kill
+        if self.examining_core() {
+            self.forget_process();
+        }
+        //When a new child process is created and run by the debugger, 
+        //there might be another child process that is previously paused
+        //that needs to be dealt with, otherwise it will become a zombie process.
+        //So at the beginning of the Run command, 
+        //the debugger will check if there is any child process that has not been reaped and reap it
+        if self.inferior.is_some() {
+            let inf = self.inferior.as_mut().unwrap();
+            match inf.kill_child() {
+                Ok(_) => println!("Child {} killed", inf.process_id()),
+                Err(_) => println!("No chlld to be killed"),
+            }
+            self.forget_process();
+        }
+        //the arguments are remembered for the next run
+        if !args.is_empty() {
+            self.settings.args = args;
+        }
+        let (args, redirections) = match Redirections::parse(&self.settings.args) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                println!("{}", e);
+                return false;
+            }
+        };
+        //breakpoints in shared libraries are set again once the libraries are loaded, which may
+        //be somewhere else this time
+        let in_libraries: Vec<usize> = self
+            .breakpoints
+            .values()
+            .filter(|b| b.number.is_some() && b.location.as_deref().is_some_and(is_function_name))
+            .filter(|b| !self.debug_data.contains(b.addr))
+            .map(|b| b.addr)
+            .collect();
+        for addr in in_libraries {
+            let mut breakpoint = self.breakpoints.remove(&addr).unwrap();
+            breakpoint.orig_byte = 0;
+            self.pending_breakpoints.push(breakpoint);
+        }
+        self.pending_breakpoints.sort_by_key(|b| b.number);
+        for breakpoint in self.breakpoints.values_mut().chain(self.pending_breakpoints.iter_mut()) {
+            breakpoint.hit_count = 0;
+        }
+        //every function is bound again in the new process
+        if self.settings.trace_plt {
+            self.start_plt_trace();
+        }
+        //Create the inferior to manipulate the child process
+        println!("Starting program: {} {}", self.target, self.settings.args.join(" "));
+        if let Some(mut inferior) =
+            Inferior::new(&self.target, &args, &redirections, &self.breakpoints, &self.settings)
+        {
+            inferior.set_signals(self.signals.clone());
+            self.inferior = Some(inferior);
+            self.relocate();
+            self.inferior.as_mut().unwrap().write_breakpoints(&mut self.breakpoints);
+            self.watch_library_loads();
+            self.rearm_watchpoints();
+            true
+        } else {
+            println!("Error starting subprocess");
+            false
+        }
+    }
+
+    //Handle `signal <signal>`: resume the child with the signal instead of the one it stopped
+    //with, or with no signal for `signal 0`
+    fn continue_with_signal(&mut self, name: &str) -> Result<(), String> {
+        if self.inferior.is_none() {
+            return Err("The program is not being run.".to_string());
+        }
+        let signal = match name {
+            "" => return Err("Argument required (signal number).".to_string()),
+            "0" => None,
+            _ => Some(signals::parse_signal(name)?),
+        };
+        match signal {
+            Some(signal) => println!("Continuing with signal {}.", signal),
+            None => println!("Continuing with no signal."),
+        }
+        self.inferior.as_ref().unwrap().queue_signal(signal);
+        match self.resume() {
+            Ok(status) => self.print_child_status(status),
+            Err(e) => println!("Cannot run child process. Error: {}", e),
+        }
+        Ok(())
+    }
+
+    //Handle `info signals`: show what is done with the signal named, or with every signal
+    fn print_signals(&self, args: &str) {
+        let signals = match args {
+            "" => Vec::new(),
+            _ => match signals::parse_signals(args) {
+                Ok(signals) => signals,
+                Err(e) => {
+                    println!("{}", e);
+                    return;
+                }
+            },
+        };
+        self.signals.lines(&signals).iter().for_each(|line| println!("{}", line));
+        if args.is_empty() {
+            println!();
+            println!("Use the \"handle\" command to change these tables.");
+        }
+    }
+
+    //Resume the child until it stops somewhere the user should know about. With a software
+    //watchpoint the child is single stepped so that its memory can be checked after every
+    //instruction. Hardware watchpoints trigger on any write, so the child is resumed again if the
+    //written value is the same as before.
+    fn resume(&mut self) -> Result<Status, nix::Error> {
+        loop {
+            let single_step = self.software_watchpoints();
+            let status = match self.inferior.as_mut().unwrap().start(&self.breakpoints, single_step)? {
+                Some(status) => status,
+                None => self.wait_resumed(single_step, true)?.unwrap(),
+            };
+            if let Some(status) = self.should_stop(status, single_step)? {
+                return Ok(status);
+            }
+            if let Some(status) = self.interrupted()? {
+                return Ok(status);
+            }
+        }
+    }
+
+    //Whether the child has to be single stepped for a software watchpoint
+    fn software_watchpoints(&self) -> bool {
+        self.watchpoints.iter().any(|w| w.enabled && w.hardware_slots.is_empty())
+    }
+
+    //Wait for the child set running by `Inferior::start`, or only check on it unless `block`
+    fn wait_resumed(&mut self, single_step: bool, block: bool) -> Result<Option<Status>, nix::Error> {
+        let inf = self.inferior.as_mut().unwrap();
+        let status = if block {
+            inf.wait(None, &self.breakpoints)?
+        } else {
+            match inf.poll(&self.breakpoints)? {
+                Some(status) => status,
+                None => return Ok(None),
+            }
+        };
+        if single_step {
+            Ok(Some(status))
+        } else {
+            inf.stopped(status, &self.breakpoints).map(Some)
+        }
+    }
+
+    //The status to report for a stop of the resumed child, or None if it should carry on, e.g.
+    //at a breakpoint whose condition is false
+    fn should_stop(&mut self, status: Status, single_step: bool) -> Result<Option<Status>, nix::Error> {
+        //the program goes on running once a new program it exec'd has been loaded
+        if self.follow_exec() {
+            return Ok(None);
+        }
+        let rip = match status {
+            Status::Stopped(Signal::SIGTRAP, rip) => rip,
+            other => return Ok(Some(other)),
+        };
+        //a catchpoint on the same stub takes priority, and the binding is logged next time
+        if self.traced_stubs.contains(&rip) && self.catchpoint_at(rip).is_none() {
+            match self.log_plt_binding(rip)? {
+                Some(status) if !self.follow_exec() => return Ok(Some(status)),
+                _ => return Ok(None),
+            }
+        }
+        if self.shlib_event == Some(rip) {
+            self.set_pending_breakpoints();
+            if self.breakpoints.get(&rip).is_none_or(|b| b.number.is_none()) {
+                return Ok(None);
+            }
+        }
+        if self.leave_watchpoint_scopes(rip)? {
+            return Ok(Some(status));
+        }
+        if self.is_scope_breakpoint(rip) || !self.breakpoint_should_stop(rip) {
+            return Ok(None);
+        }
+        if !self.watchpoints.iter().any(|w| w.enabled) {
+            return Ok(Some(status));
+        }
+        let triggered: Vec<usize> = self
+            .inferior
+            .as_ref()
+            .unwrap()
+            .triggered_hardware_watchpoints()?
+            .into_iter()
+            .filter(|slot| self.watchpoints.iter().any(|w| w.hardware_slots.contains(slot)))
+            .collect();
+        if self.check_watchpoints() {
+            return Ok(Some(status));
+        }
+        if single_step && !self.breakpoints.get(&rip).is_some_and(|b| b.enabled) {
+            return Ok(None);
+        }
+        if !single_step && !triggered.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(status))
+    }
+
+    //A stop that doesn't count while the child is being interrupted, by Ctrl+C or `interrupt`, is
+    //reported as the interrupt, since resuming the child would let the pending SIGSTOP through
+    fn interrupted(&self) -> Result<Option<Status>, nix::Error> {
+        let inf = self.inferior.as_ref().unwrap();
+        if !inf.interrupt_pending() {
+            return Ok(None);
+        }
+        let rip = inf.get_registers()?.rip as usize;
+        Ok(Some(Status::Stopped(Signal::SIGINT, rip)))
+    }
+
+    //Set the child running in the background, for `run &` and `continue &`, and report it if it
+    //stops at once, e.g. at a breakpoint right where it is resumed
+    fn resume_in_background(&mut self) {
+        match self.start_in_background() {
+            Ok(Some(status)) => self.print_child_status(status),
+            Ok(None) => (),
+            Err(e) => println!("Cannot run child process. Error: {}", e),
+        }
+    }
+
+    fn start_in_background(&mut self) -> Result<Option<Status>, nix::Error> {
+        loop {
+            let single_step = self.software_watchpoints();
+            let status = match self.inferior.as_mut().unwrap().start(&self.breakpoints, single_step)? {
+                Some(status) => status,
+                None => {
+                    self.running = Some(single_step);
+                    return Ok(None);
+                }
+            };
+            if let Some(status) = self.should_stop(status, single_step)? {
+                return Ok(Some(status));
+            }
+            if let Some(status) = self.interrupted()? {
+                return Ok(Some(status));
+            }
+        }
+    }
+
+    //Check on the child running in the background, and return its status once it has stopped
+    //somewhere the user should know about. Stops that don't count are dealt with for a while
+    //before giving up, so that single stepping for a software watchpoint gets somewhere.
+    fn check_background(&mut self) -> Result<Option<Status>, nix::Error> {
+        let deadline = Instant::now() + Duration::from_millis(50);
+        while let Some(single_step) = self.running {
+            //a single instruction is done at once, so there is no point in not waiting for it
+            let status = match self.wait_resumed(single_step, single_step)? {
+                Some(status) => status,
+                None => return Ok(None),
+            };
+            self.running = None;
+            if let Some(status) = self.should_stop(status, single_step)? {
+                return Ok(Some(status));
+            }
+            if let Some(status) = self.interrupted()? {
+                return Ok(Some(status));
+            }
+            if let Some(status) = self.start_in_background()? {
+                return Ok(Some(status));
+            }
+            if Instant::now() > deadline {
+                break;
+            }
+        }
+        Ok(None)
+    }
+
+    //Stop the child running in the background, for a command typed while it runs. Returns false
+    //if it stopped by itself first, somewhere the user should know about, which is reported.
+    fn pause(&mut self) -> Result<bool, nix::Error> {
+        let single_step = match self.running.take() {
+            Some(single_step) => single_step,
+            None => return Ok(false),
+        };
+        self.inferior.as_ref().unwrap().interrupt()?;
+        let status = self.wait_resumed(single_step, true)?.unwrap();
+        self.selected_frame = 0;
+        //the SIGSTOP is reported as a SIGINT once it has stopped the child
+        if let Status::Stopped(Signal::SIGINT, _) = status {
+            if !self.inferior.as_ref().unwrap().interrupt_pending() {
+                return Ok(true);
+            }
+        }
+        //the SIGSTOP is still pending, and is let through when the child is resumed
+        match self.should_stop(status, single_step)? {
+            Some(status) => {
+                self.print_child_status(status);
+                Ok(false)
+            }
+            None => Ok(true),
+        }
+    }
+
+    //Handle `interrupt`: stop the child running in the background, and report it like a SIGINT
+    fn interrupt(&mut self) -> Result<(), nix::Error> {
+        if self.pause()? {
+            let rip = self.inferior.as_ref().unwrap().get_registers()?.rip as usize;
+            self.print_child_status(Status::Stopped(Signal::SIGINT, rip));
+        }
+        Ok(())
+    }
+
+    //Carry out a command typed while the child runs in the background. The child is paused for
+    //the command, e.g. to set a breakpoint, and then carries on unless it stopped by itself.
+    fn execute_while_running(&mut self, cmd: DebuggerCommand) -> bool {
+        if cmd.resumes() {
+            println!("Cannot execute this command while the program is running.");
+            println!("Use the \"interrupt\" command to stop the program and then try again.");
+            return true;
+        }
+        if let DebuggerCommand::Interrupt = cmd {
+            if let Err(e) = self.interrupt() {
+                println!("Cannot interrupt the program. Error: {}", e);
+            }
+            return true;
+        }
+        match self.pause() {
+            Ok(true) => (),
+            Ok(false) => return self.run_pending_commands() && self.execute(cmd),
+            Err(e) => {
+                println!("Cannot interrupt the program. Error: {}", e);
+                return true;
+            }
+        }
+        if !self.execute(cmd) {
+            return false;
+        }
+        //the command may have ended the program, e.g. `kill`
+        if self.inferior.is_some() {
+            self.resume_in_background();
+        }
+        true
+    }
+
+    //Wait for a command while the child runs in the background, reporting it if it stops, in
+    //which case there is no command. The line is read without the line editor, which would keep
+    //the terminal from the child and couldn't be interrupted when the child stops.
+    #[cfg(feature = "cli")]
+    fn next_command_while_running(&mut self) -> Option<DebuggerCommand> {
+        print!("(deet) ");
+        std::io::stdout().flush().ok();
+        loop {
+            if let Some(inferior) = &self.inferior {
+                if let Err(e) = inferior.check_interrupt() {
+                    println!("Cannot interrupt the program. Error: {}", e);
+                }
+            }
+            match self.check_background() {
+                Ok(Some(status)) => {
+                    println!();
+                    self.events.clear();
+                    self.pending_commands = None;
+                    self.print_child_status(status);
+                    return None;
+                }
+                Ok(None) => (),
+                Err(e) => {
+                    println!("Cannot run child process. Error: {}", e);
+                    self.running = None;
+                    return None;
+                }
+            }
+            let mut fds = [PollFd::new(libc::STDIN_FILENO, PollFlags::POLLIN)];
+            if !matches!(poll(&mut fds, 100), Ok(n) if n > 0) {
+                continue;
+            }
+            let mut line = String::new();
+            match std::io::stdin().read_line(&mut line) {
+                Ok(0) => return Some(DebuggerCommand::Quit),
+                Ok(_) => (),
+                Err(_) => continue,
+            }
+            if line.trim().is_empty() {
+                print!("(deet) ");
+                std::io::stdout().flush().ok();
+                continue;
+            }
+            let line = self.take_line(line.trim_end());
+            //the lines of a block would be read with the line editor
+            if DebuggerCommand::starts_block(&line) {
+                println!("Cannot give a command with a block while the program is running.");
+            } else if let Some(cmd) = self.parse_line(&line) {
+                return Some(cmd);
+            } else {
+                println!("Unrecognized command.");
+            }
+            print!("(deet) ");
+            std::io::stdout().flush().ok();
+        }
+    }
+
+    //Step the child to the next source line, into any calls with debug info if `into_calls`. A
+    //call through a PLT stub traced by `set trace-plt on` is logged, and then run until it
+    //returns like any other call into a shared library.
+    fn step(&mut self, into_calls: bool) -> Result<Status, nix::Error> {
+        let rip = self.inferior.as_ref().unwrap().get_registers()?.rip as usize;
+        let start = self.debug_data.get_line_from_addr(rip);
+        loop {
+            let inf = self.inferior.as_mut().unwrap();
+            let status = inf.step_line(&self.debug_data, &self.breakpoints, into_calls)?;
+            //stepping stops at the start of a new program the child execs
+            if self.follow_exec() {
+                return Ok(status);
+            }
+            if !self.watchpoints.is_empty() {
+                self.check_watchpoints();
+            }
+            //returning from the frame of a watched variable only stops the child if the
+            //watchpoint is deleted, otherwise the step carries on
+            if let Status::Stopped(Signal::SIGTRAP, rip) = status {
+                if self.leave_watchpoint_scopes(rip)? {
+                    return Ok(status);
+                }
+                if self.is_scope_breakpoint(rip) {
+                    continue;
+                }
+            }
+            let stub = match status {
+                Status::Stopped(Signal::SIGTRAP, rip)
+                    if self.traced_stubs.contains(&rip)
+                        && self.catchpoint_at(rip).is_none() =>
+                {
+                    rip
+                }
+                other => return Ok(other),
+            };
+            let inf = self.inferior.as_mut().unwrap();
+            let sp = inf.get_registers()?.rsp as usize;
+            let ret = read_uint(&inf.read_mem(sp, 8)?) as usize;
+            if let Some(status) = self.log_plt_binding(stub)? {
+                self.follow_exec();
+                return Ok(status);
+            }
+            match self.run_to(ret, sp + 8)? {
+                //the call may have been the last instruction of the line
+                Status::Stopped(Signal::SIGTRAP, rip)
+                    if rip == ret
+                        && !(self.debug_data.is_line_start(ret)
+                            && self.debug_data.get_line_from_addr(ret) != start) =>
+                {
+                    continue
+                }
+                other => return Ok(other),
+            }
+        }
+    }
+
+    //Execute a single instruction, or with `into_calls` false a whole call
+    fn step_instruction(&mut self, into_calls: bool) -> Result<Status, nix::Error> {
+        let inf = self.inferior.as_mut().unwrap();
+        let before = inf.get_registers()?;
+        let status = inf.step_instruction(&self.breakpoints)?;
+        if self.follow_exec() {
+            return Ok(status);
+        }
+        if !self.watchpoints.is_empty() {
+            self.check_watchpoints();
+        }
+        if let Status::Stopped(Signal::SIGTRAP, rip) = status {
+            self.leave_watchpoint_scopes(rip)?;
+        }
+        if into_calls {
+            return Ok(status);
+        }
+        let inf = self.inferior.as_ref().unwrap();
+        match status {
+            Status::Stopped(Signal::SIGTRAP, rip)
+                if !self.breakpoints.get(&rip).is_some_and(|b| b.enabled) =>
+            {
+                match inf.call_return_address(&before)? {
+                    Some(ret) => self.run_to(ret, before.rsp as usize),
+                    None => Ok(status),
+                }
+            }
+            other => Ok(other),
+        }
+    }
+
+    //Print the address the child is stopped at after stepping an instruction, followed by the
+    //source line if the address is in the middle of one, the way gdb does
+    fn print_instruction_location(&mut self, s: Status) {
+        self.add_kept_inferiors();
+        self.report_thread_switch(&s);
+        self.record_event(&s);
+        self.selected_frame = 0;
+        let rip = match s {
+            Status::Stopped(_, rip) => rip,
+            _ => return,
+        };
+        self.stop_hook_pending = true;
+        match self.debug_data.get_line_from_addr(rip) {
+            Some(line) => {
+                self.center_listing(&line.file, line.number);
+                let text = self.sources.line(&line.file, line.number);
+                if self.debug_data.is_line_start(rip) {
+                    println!("{}\t{}", line.number, text);
+                } else {
+                    println!("{:#018x}\t{}\t{}", rip, line.number, text);
+                }
+            }
+            None => match self.debug_data.get_plt_name(rip) {
+                Some(name) => println!("{:#018x} in {}@plt", rip, name),
+                None => println!("{:#018x} in ??", rip),
+            },
+        }
+        self.show_displays();
+    }
+
+    //Resume the child until it reaches `addr` with the stack pointer at or above `sp`, logging
+    //any calls through traced PLT stubs on the way and passing breakpoints whose condition is false
+    fn run_to(&mut self, addr: usize, sp: usize) -> Result<Status, nix::Error> {
+        self.run_to_any(&[(addr, sp)])
+    }
+
+    //Like `run_to`, for whichever of several addresses, each with its own stack pointer, the
+    //child reaches first
+    fn run_to_any(&mut self, targets: &[(usize, usize)]) -> Result<Status, nix::Error> {
+        let is_target = |rip: usize| targets.iter().any(|(addr, _)| *addr == rip);
+        loop {
+            let inf = self.inferior.as_mut().unwrap();
+            let status = inf.continue_to_any(targets, &self.breakpoints)?;
+            if self.follow_exec() {
+                return Ok(status);
+            }
+            let changed = !self.watchpoints.is_empty() && self.check_watchpoints();
+            if let Status::Stopped(Signal::SIGTRAP, rip) = status {
+                if changed || self.leave_watchpoint_scopes(rip)? {
+                    return Ok(status);
+                }
+                //a hardware watchpoint triggers even if the value written is the same
+                let at_breakpoint = self.breakpoints.get(&rip).is_some_and(|b| b.enabled);
+                if !is_target(rip) && (!at_breakpoint || self.is_scope_breakpoint(rip)) {
+                    continue;
+                }
+            }
+            match status {
+                Status::Stopped(Signal::SIGTRAP, rip)
+                    if self.traced_stubs.contains(&rip)
+                        && self.catchpoint_at(rip).is_none() =>
+                {
+                    if let Some(status) = self.log_plt_binding(rip)? {
+                        return Ok(status);
+                    }
+                }
+                Status::Stopped(Signal::SIGTRAP, rip) if !is_target(rip) && !self.breakpoint_should_stop(rip) => {}
+                other => return Ok(other),
+            }
+        }
+    }
+
+    //Run until the function the child is stopped in returns, with a temporary breakpoint on its
+    //return address, and print the value it returned
+    fn finish(&mut self) -> Result<(), String> {
+        let inf = match &self.inferior {
+            Some(inf) => inf,
+            None => return Err("The program is not being run.".to_string()),
+        };
+        let rip = inf.get_registers().map_err(|e| e.to_string())?.rip as usize;
+        let func = match self.debug_data.get_function_at(rip) {
+            Some(func) => func,
+            None => return Err("Cannot find bounds of current function".to_string()),
+        };
+        let (name, return_type) = (func.name.clone(), func.return_type);
+        //the return address is just below the frame base, and is popped by the return
+        let frame_base = inf.frame_base(rip, func.address).map_err(|e| e.to_string())?;
+        let ret = read_uint(&inf.read_mem(frame_base - 8, 8).map_err(|e| e.to_string())?) as usize;
+        println!("Run till exit from {}", name);
+        let status = self.run_to(ret, frame_base).map_err(|e| e.to_string())?;
+        let returned = match status {
+            Status::Stopped(Signal::SIGTRAP, rip) => rip == ret,
+            _ => false,
+        };
+        self.print_child_status(status);
+        if returned {
+            if let Some(value) = self.return_value(return_type) {
+                println!("Value returned is {}", value);
+            }
+        }
+        Ok(())
+    }
+
+    //Handle `until`, which goes on like `next` until the program reaches a line after the current
+    //one in the current frame, so that the jump back at the end of a loop doesn't stop it, or
+    //returns from the frame. `until <location>` runs until the program reaches the location in
+    //the current frame or one it returns to, or the current frame returns.
+    fn until(&mut self, location: &str) -> Result<Status, String> {
+        if self.inferior.is_none() {
+            return Err("The program is not being run.".to_string());
+        }
+        if !location.is_empty() {
+            return self.advance(location, true);
+        }
+        let frames = self.frames()?;
+        let line_number = |debugger: &Debugger, rip: usize| {
+            debugger.debug_data.get_line_from_addr(rip).map_or(0, |line| line.number)
+        };
+        let start = line_number(self, frames[0].pc);
+        loop {
+            let status = self.step(false).map_err(|e| format!("Cannot step child process. Error: {}", e))?;
+            let rip = match status {
+                Status::Stopped(Signal::SIGTRAP, rip) => rip,
+                other => return Ok(other),
+            };
+            if self.breakpoints.get(&rip).is_some_and(|b| b.enabled && b.number.is_some()) {
+                return Ok(status);
+            }
+            let cfa = self.frames()?[0].cfa;
+            if cfa > frames[0].cfa || (cfa == frames[0].cfa && line_number(self, rip) > start) {
+                return Ok(status);
+            }
+        }
+    }
+
+    //Handle `advance <location>`, and `until <location>` with `same_frame`: run until the program
+    //reaches the location, or the current frame returns, with temporary breakpoints on both that
+    //are taken out again once it stops. For `until` the location only counts in the current frame
+    //or one it returns to, not in a deeper one, e.g. of a recursive call.
+    fn advance(&mut self, location: &str, same_frame: bool) -> Result<Status, String> {
+        if self.inferior.is_none() {
+            return Err("The program is not being run.".to_string());
+        }
+        if location.is_empty() {
+            return Err("Argument required (a location).".to_string());
+        }
+        let addr = self
+            .parse_address(location)
+            .ok_or(format!("No location found for \"{}\".", location))?;
+        let frames = self.frames()?;
+        let sp = if same_frame { frames[0].regs.rsp as usize } else { 0 };
+        let mut targets = vec![(addr, sp)];
+        if let Some(caller) = frames.get(1) {
+            targets.push((caller.pc, frames[0].cfa));
+        }
+        self.run_to_any(&targets).map_err(|e| e.to_string())
+    }
+
+    //Handle `jump <location>`: move the program to the location and carry on from there, once
+    //the user has said so, as the program is likely to crash if the location is somewhere the
+    //stack and registers aren't ready for, e.g. in another function. It stops there at once if
+    //there is a breakpoint at the location.
+    fn jump(&mut self, location: &str) -> Result<Status, String> {
+        let rip = match &self.inferior {
+            Some(inf) => inf.get_registers().map_err(|e| e.to_string())?.rip as usize,
+            None => return Err("The program is not being run.".to_string()),
+        };
+        if location.is_empty() {
+            return Err("Argument required (starting address).".to_string());
+        }
+        let addr = self
+            .parse_address(location)
+            .ok_or(format!("No location found for \"{}\".", location))?;
+        let place = match location.rsplit(':').next().map(str::parse::<usize>) {
+            Some(Ok(number)) => format!("Line {}", number),
+            _ => format!("{:#x}", addr),
+        };
+        let function = self.debug_data.get_function_at(rip).map(|f| (f.address, f.name.clone()));
+        let question = match function {
+            Some((start, name)) if self.debug_data.get_function_at(addr).map(|f| f.address) != Some(start) => {
+                format!("{} is not in `{}'.  Jump anyway?", place, name)
+            }
+            _ => format!("Continue at {}?", place.to_lowercase()),
+        };
+        if !self.query(&question) {
+            return Err("Not confirmed.".to_string());
+        }
+        let inf = self.inferior.as_mut().unwrap();
+        let mut registers = inf.get_registers().map_err(|e| e.to_string())?;
+        registers.rip = addr as u64;
+        inf.set_registers(registers).map_err(|e| e.to_string())?;
+        println!("Continuing at {:#x}.", addr);
+        if self.breakpoints.get(&addr).is_some_and(|b| b.enabled && b.number.is_some()) {
+            return Ok(Status::Stopped(Signal::SIGTRAP, addr));
+        }
+        self.resume().map_err(|e| format!("Cannot run child process. Error: {}", e))
+    }
+
+    //Handle `return [<expression>]`, which pops the selected frame, and any inside it, so that
+    //its function returns to the caller at once, with the value of the expression in the register
+    //the caller expects it in. The caller's registers are restored as the unwinder finds them.
+    fn force_return(&mut self, text: &str) -> Result<(), String> {
+        if self.inferior.is_none() {
+            return Err("The program is not being run.".to_string());
+        }
+        let frames = self.frames()?;
+        let level = self.selected_frame;
+        let frame = frames.get(level).ok_or(format!("No frame at level {}.", level))?;
+        let caller = match frames.get(level + 1) {
+            Some(caller) => caller,
+            None => return Err("Can not force return from the outermost frame.".to_string()),
+        };
+        let function = self.debug_data.get_function_at(frame.lookup_pc(level));
+        let (name, return_type) = match function {
+            Some(func) => (Some(func.name.clone()), func.return_type),
+            None => (None, None),
+        };
+        //the value is evaluated in the selected frame, before it is popped
+        let value = if text.is_empty() { None } else { Some(self.evaluate_text(text)?) };
+        let question = match &name {
+            Some(name) => format!("Make {} return now?", name),
+            None => "Make selected stack frame return now?".to_string(),
+        };
+        if !self.query(&question) {
+            return Err("Not confirmed.".to_string());
+        }
+        let mut registers = caller.regs;
+        let debug_data = &self.debug_data;
+        let inf = self.inferior.as_mut().unwrap();
+        if let Some(value) = value {
+            let resolved = debug_data.get_type(return_type).map(|ty| debug_data.resolve_type(ty));
+            let scalar = value.scalar(debug_data)?;
+            match resolved.map(|ty| (&ty.kind, ty.size)) {
+                Some((TypeKind::Base(Encoding::Float), 4)) => {
+                    let mut fp_registers = inf.get_fp_registers().map_err(|e| e.to_string())?;
+                    fp_registers.xmm_space[0] = (scalar.as_f64() as f32).to_bits();
+                    inf.set_fp_registers(fp_registers).map_err(|e| e.to_string())?;
+                }
+                Some((TypeKind::Base(Encoding::Float), 8)) => {
+                    let mut fp_registers = inf.get_fp_registers().map_err(|e| e.to_string())?;
+                    let bits = scalar.as_f64().to_bits();
+                    fp_registers.xmm_space[0] = bits as u32;
+                    fp_registers.xmm_space[1] = (bits >> 32) as u32;
+                    inf.set_fp_registers(fp_registers).map_err(|e| e.to_string())?;
+                }
+                Some((TypeKind::Base(Encoding::Float), _))
+                | Some((TypeKind::Struct(_), _))
+                | Some((TypeKind::Union(_), _))
+                | Some((TypeKind::Array(..), _)) => {
+                    return Err("Returning a value of this type is not supported.".to_string());
+                }
+                _ => registers.rax = scalar.as_u64(),
+            }
+        }
+        inf.set_registers(registers).map_err(|e| e.to_string())?;
+        self.select_frame(0)
+    }
+
+    //The value a function just returned, given the offset of its return type. Integers and
+    //pointers are returned in %rax, floats and doubles in %xmm0, structs of up to 16 bytes in
+    //%rax and %rdx (assuming they have no floating point members, which would be in SSE
+    //registers), and %rax points to a bigger struct, which the caller made room for.
+    fn return_value(&self, return_type: Option<usize>) -> Option<String> {
+        let ty = self.debug_data.get_type(return_type)?;
+        let resolved = self.debug_data.resolve_type(ty);
+        let inf = self.inferior.as_ref()?;
+        let registers = inf.get_registers().ok()?;
+        let bytes = match resolved.kind {
+            TypeKind::Base(Encoding::Float) if resolved.size <= 8 => {
+                let xmm0 = &inf.get_fp_registers().ok()?.xmm_space[..2];
+                xmm0.iter().flat_map(|word| word.to_le_bytes().to_vec()).collect()
+            }
+            //long double is returned on the x87 stack
+            TypeKind::Base(Encoding::Float) => return None,
+            TypeKind::Struct(_) | TypeKind::Union(_) if resolved.size > 16 => {
+                inf.read_mem(registers.rax as usize, resolved.size).ok()?
+            }
+            _ => [registers.rax.to_le_bytes(), registers.rdx.to_le_bytes()].concat(),
+        };
+        let formatter = ValueFormatter::new(&self.debug_data, Some(inf), &self.settings);
+        Some(formatter.format(ty, &bytes[..resolved.size.min(bytes.len())]))
+    }
+
+    //Compare the watched memory with its old contents, and report any changes. Returns whether
+    //anything changed.
+    fn check_watchpoints(&mut self) -> bool {
+        let inf = self.inferior.as_ref().unwrap();
+        let formatter = ValueFormatter::new(&self.debug_data, Some(inf), &self.settings);
+        let mut changed = false;
+        for watchpoint in self.watchpoints.iter_mut().filter(|w| w.enabled) {
+            let new_value = match inf.read_mem(watchpoint.addr, watchpoint.len) {
+                Ok(bytes) => bytes,
+                Err(_) => continue,
+            };
+            if new_value == watchpoint.old_value {
+                continue;
+            }
+            if let Some(mask) = watchpoint.mask {
+                //changes outside the mask are remembered but not reported
+                if read_uint(&new_value) & mask == read_uint(&watchpoint.old_value) & mask {
+                    watchpoint.old_value = new_value;
+                    continue;
+                }
+            }
+            let ty = self.debug_data.get_type(Some(watchpoint.type_offset)).unwrap();
+            println!();
+            println!("{}", watchpoint_title(watchpoint));
+            println!();
+            println!("Old value = {}", formatter.format(ty, &watchpoint.old_value));
+            println!("New value = {}", formatter.format(ty, &new_value));
+            watchpoint.old_value = new_value;
+            changed = true;
+        }
+        changed
+    }
+
+    //Evaluate expressions in the current frame. Constant expressions can be evaluated without a
+    //running process.
+    fn evaluate(&mut self, exprs: &[Expr]) -> Result<Vec<Value>, String> {
+        let frame = match &self.inferior {
+            Some(_) => Some(self.current_frame()?),
+            None => None,
+        };
+        let mut evaluator = Evaluator::new(
+            &mut self.debug_data,
+            self.inferior.as_ref(),
+            frame.as_ref(),
+            &self.value_history,
+        );
+        exprs.iter().map(|expr| evaluator.eval(expr)).collect()
+    }
+
+    //Evaluate an expression typed at the prompt. An assignment, such as `x = 1` or
+    //`{int}0x601048 = 42`, is written into the child's memory, and the value stored is returned.
+    fn evaluate_text(&mut self, text: &str) -> Result<Value, String> {
+        let (lhs, rhs) = match expression::parse(text, &self.debug_data)? {
+            Expr::Assign(lhs, rhs) => (lhs, rhs),
+            expr => return Ok(self.evaluate(&[expr])?.remove(0)),
+        };
+        if let Expr::Register(name) = &*lhs {
+            if !name.chars().all(|c| c.is_ascii_digit()) {
+                return self.set_register(name, &rhs);
+            }
+        }
+        let frame = match &self.inferior {
+            Some(_) => Some(self.current_frame()?),
+            None => None,
+        };
+        let value = Evaluator::new(
+            &mut self.debug_data,
+            self.inferior.as_ref(),
+            frame.as_ref(),
+            &self.value_history,
+        )
+        .eval_assignment(&lhs, &rhs)?;
+        self.write_memory(value.address.unwrap(), &value.bytes)?;
+        Ok(value)
+    }
+
+    //Handle `$<register> = <expression>`, changing a register of the stopped child
+    fn set_register(&mut self, name: &str, rhs: &Expr) -> Result<Value, String> {
+        let value = self.evaluate(std::slice::from_ref(rhs))?.remove(0).scalar(&self.debug_data)?.as_u64();
+        let inf = self.inferior.as_mut().ok_or("No registers.".to_string())?;
+        let mut registers = inf
+            .get_registers()
+            .map_err(|e| format!("Cannot read registers. Error: {}", e))?;
+        *register_field(&mut registers, name).ok_or(format!("Invalid register ${}.", name))? = value;
+        inf.set_registers(registers)
+            .map_err(|e| format!("Cannot write registers. Error: {}", e))?;
+        Ok(self.evaluate(&[Expr::Register(name.to_string())])?.remove(0))
+    }
+
+    //Write into the child's memory. Breakpoints in the memory are kept, with the new bytes put
+    //back when they are removed, and watchpoints don't report what the debugger wrote.
+    fn write_memory(&mut self, addr: usize, bytes: &[u8]) -> Result<(), String> {
+        let inf = self.inferior.as_mut().ok_or("The program is not being run.".to_string())?;
+        let range = addr..addr + bytes.len();
+        let mut bytes = bytes.to_vec();
+        for breakpoint in self.breakpoints.values_mut() {
+            if breakpoint.enabled && breakpoint.hardware_slot.is_none() && range.contains(&breakpoint.addr) {
+                breakpoint.orig_byte = bytes[breakpoint.addr - addr];
+                bytes[breakpoint.addr - addr] = 0xcc;
+            }
+        }
+        inf.write_bytes(addr, &bytes)
+            .map_err(|_| format!("Cannot access memory at address {:#x}", addr))?;
+        for watchpoint in self.watchpoints.iter_mut() {
+            if watchpoint.addr < range.end && range.start < watchpoint.addr + watchpoint.len {
+                if let Ok(value) = inf.read_mem(watchpoint.addr, watchpoint.len) {
+                    watchpoint.old_value = value;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    //Handle `print <expression>`, which shows the value along with its number in the value
+    //history, so that later expressions can use it as `$<number>`. Without an expression, the
+    //last value is shown again. `format` comes from `print/<letter>`.
+    fn print(&mut self, text: &str, format: Option<Format>) -> Result<(), String> {
+        let text = if text.is_empty() { "$" } else { text };
+        let mut value = self.evaluate_text(text)?;
+        let ty = self.debug_data.get_type(Some(value.type_offset)).unwrap();
+        let formatter = ValueFormatter::new(&self.debug_data, self.inferior.as_ref(), &self.settings)
+            .with_format(format);
+        let formatted = formatter.format(ty, &value.bytes);
+        //a script may print values of the type its own way, unless a format is asked for
+        #[cfg(feature = "scripting")]
+        let formatted = match format {
+            None => self.script_print(value.type_offset, text).unwrap_or(Ok(formatted))?,
+            Some(_) => formatted,
+        };
+        //the history keeps the value as it is now, not the memory it came from
+        value.address = None;
+        self.value_history.push(value);
+        println!("${} = {}", self.value_history.len(), formatted);
+        Ok(())
+    }
+
+    //Handle `x/<count><format><size> <address>`: show `count` units of memory, each `size` bytes,
+    //starting at an address. The format and size are remembered for the next `x`.
+    fn examine(&mut self, letters: &str, text: &str) -> Result<(), String> {
+        for line in self.examine_lines(letters, text)? {
+            println!("{}", line);
+        }
+        Ok(())
+    }
+
+    //The lines `x` shows
+    fn examine_lines(&mut self, letters: &str, text: &str) -> Result<Vec<String>, String> {
+        let (count, format, size) = parse_examine_letters(letters)?;
+        let format = format.unwrap_or(self.examine_format);
+        let size = match (format, size) {
+            (Format::Address, _) => 8,
+            (_, Some(size)) => size,
+            (Format::Char, None) | (Format::String, None) => 1,
+            (Format::Float, None) if self.examine_size != 4 => 8,
+            (_, None) => self.examine_size,
+        };
+        let mut addr = if text.is_empty() {
+            self.examine_next
+                .ok_or("Argument required (starting display address).".to_string())?
+        } else {
+            self.address_of(text)?
+        };
+        self.examine_format = format;
+        if format != Format::String {
+            self.examine_size = size;
+        }
+        let inf = self.inferior.as_ref().ok_or("The program is not being run.".to_string())?;
+        let formatter = ValueFormatter::new(&self.debug_data, Some(inf), &self.settings);
+        let label = |addr: usize| match symbol_label(&self.debug_data, addr) {
+            Some(label) => format!("{:#x} {}:", addr, label),
+            None => format!("{:#x}:", addr),
+        };
+        let mut lines = Vec::new();
+        if format == Format::String {
+            for _ in 0..count {
+                let (string, len) = formatter.format_c_string(addr)?;
+                lines.push(format!("{}\t{}", label(addr), string));
+                addr += len;
+            }
+            self.examine_next = Some(addr);
+            return Ok(lines);
+        }
+        //the memory is read all at once
+        let bytes = inf
+            .read_mem(addr, count * size)
+            .map_err(|_| format!("Cannot access memory at address {:#x}", addr))?;
+        //hex and binary are padded so that the columns line up
+        let format = if format == Format::Hex { Format::ZeroHex } else { format };
+        let format_unit = |unit: &[u8]| match format {
+            Format::Binary => format!("{:0>width$}", formatter.format_unit(format, unit), width = size * 8),
+            _ => formatter.format_unit(format, unit),
+        };
+        let per_line = match (format, size) {
+            (Format::Address, _) | (_, 8) => 2,
+            (_, 4) => 4,
+            _ => 8,
+        };
+        for (i, line) in bytes.chunks(per_line * size).enumerate() {
+            let line_addr = addr + i * per_line * size;
+            let units: Vec<String> = line.chunks(size).map(format_unit).collect();
+            lines.push(format!("{}\t{}", label(line_addr), units.join("\t")));
+        }
+        self.examine_next = Some(addr + bytes.len());
+        Ok(lines)
+    }
+
+    //Evaluate an expression for the address `x` or `disassemble` starts at. Arrays, structs and
+    //functions are taken where they are, anything else is taken to be the address.
+    fn address_of(&mut self, text: &str) -> Result<usize, String> {
+        let value = self.evaluate(&[expression::parse(text, &self.debug_data)?])?.remove(0);
+        match (&value.ty(&self.debug_data).kind, value.address) {
+            (TypeKind::Array(..), Some(addr))
+            | (TypeKind::Struct(_), Some(addr))
+            | (TypeKind::Union(_), Some(addr))
+            | (TypeKind::Function, Some(addr)) => Ok(addr),
+            _ => Ok(value.scalar(&self.debug_data)?.as_u64() as usize),
+        }
+    }
+
+    //Handle `disassemble`: the instructions of the function `args` names or has an address in,
+    //of the addresses `<start>,<end>` or `<start>,+<length>`, or of the function of the selected
+    //frame. The instruction the selected frame is at is marked `=>` as in gdb, and those with a
+    //breakpoint `b`. With the `r` letter the bytes of each instruction are shown too.
+    pub fn disassemble(&mut self, letters: &str, args: &str) -> Result<Vec<String>, String> {
+        //the letters can also come after a space, as in `disassemble /r main`
+        let (letters, args) = match args.strip_prefix('/') {
+            Some(rest) if letters.is_empty() => match rest.find(char::is_whitespace) {
+                Some(end) => (&rest[..end], rest[end..].trim()),
+                None => (rest, ""),
+            },
+            _ => (letters, args),
+        };
+        if letters.chars().any(|letter| letter != 'r') {
+            return Err("Invalid disassembly modifier.".to_string());
+        }
+        let pc = self.current_frame().ok().map(|frame| frame.pc);
+        let (start, end, function) = if let Some((first, last)) = args.split_once(',') {
+            let start = self.address_of(first.trim())?;
+            let end = match last.trim().strip_prefix('+') {
+                Some(length) => start + self.address_of(length.trim())?,
+                None => self.address_of(last.trim())?,
+            };
+            (start, end.max(start), None)
+        } else {
+            let addr = match (args, self.debug_data.get_function(None, args)) {
+                ("", _) => pc.ok_or("No frame selected.".to_string())?,
+                (_, Some(func)) => func.address,
+                _ => self.address_of(args)?,
+            };
+            let func = self.debug_data.get_function_at(addr).ok_or(match args {
+                "" => "No function contains program counter for selected frame.".to_string(),
+                _ => "No function contains specified address.".to_string(),
+            })?;
+            (func.address, func.address + func.text_length, Some(func.name.clone()))
+        };
+        //the last instruction can go past the end of a range
+        let code = self
+            .code_bytes(start, end - start + MAX_INSTRUCTION_LEN)
+            .or_else(|_| self.code_bytes(start, end - start))?;
+        let breakpoints: HashSet<usize> = self
+            .breakpoints
+            .values()
+            .filter(|breakpoint| breakpoint.enabled && breakpoint.number.is_some())
+            .map(|breakpoint| breakpoint.addr)
+            .collect();
+        let mut lines = vec![match &function {
+            Some(name) => format!("Dump of assembler code for function {}:", name),
+            None => format!("Dump of assembler code from {:#x} to {:#x}:", start, end),
+        }];
+        for instruction in disassemble::disassemble(&code, start)? {
+            if instruction.addr >= end {
+                break;
+            }
+            let marker = match (breakpoints.contains(&instruction.addr), pc == Some(instruction.addr)) {
+                (false, false) => "   ",
+                (false, true) => "=> ",
+                (true, false) => "b  ",
+                (true, true) => "b> ",
+            };
+            //within a function the offset is enough, a range can span several functions
+            let label = match (&function, self.debug_data.get_symbol_at(instruction.addr)) {
+                (Some(_), _) => format!(" <+{}>", instruction.addr - start),
+                (None, Some((name, offset))) => format!(" <{}+{}>", name, offset),
+                (None, None) => String::new(),
+            };
+            let bytes = if letters.contains('r') {
+                let bytes: Vec<String> = instruction.bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+                format!("\t{}", bytes.join(" "))
+            } else {
+                String::new()
+            };
+            //calls and jumps name where they go, as in `call   0x401126 <add>`
+            let target = match instruction.text.rsplit(' ').next().map(|operand| operand.trim_start_matches("0x")) {
+                Some(operand) if instruction.text.starts_with('j') || instruction.text.starts_with("call") => {
+                    usize::from_str_radix(operand, 16).ok().and_then(|target| {
+                        match self.debug_data.get_plt_name(target) {
+                            Some(name) => Some(format!(" <{}@plt>", name)),
+                            None => symbol_label(&self.debug_data, target).map(|label| format!(" {}", label)),
+                        }
+                    })
+                }
+                _ => None,
+            };
+            lines.push(format!(
+                "{}{:#018x}{}:{}\t{}{}",
+                marker,
+                instruction.addr,
+                label,
+                bytes,
+                instruction.text,
+                target.unwrap_or_default()
+            ));
+        }
+        lines.push("End of assembler dump.".to_string());
+        Ok(lines)
+    }
+
+    //Read the code at `addr`, from the child process if it is running and from the executable
+    //otherwise. The bytes the breakpoints' int3 instructions replaced are put back.
+    fn code_bytes(&self, addr: usize, len: usize) -> Result<Vec<u8>, String> {
+        let error = || format!("Cannot access memory at address {:#x}", addr);
+        let inf = match &self.inferior {
+            Some(inf) => inf,
+            None => {
+                let linked = addr.wrapping_sub(self.debug_data.load_bias());
+                let offset = self.debug_data.get_elf().and_then(|elf| elf.file_offset(linked)).ok_or_else(error)?;
+                let data = std::fs::read(&self.target).map_err(|_| error())?;
+                return data.get(offset..offset + len).map(|bytes| bytes.to_vec()).ok_or_else(error);
+            }
+        };
+        let mut bytes = inf.read_mem(addr, len).map_err(|_| error())?;
+        for breakpoint in self.breakpoints.values() {
+            if breakpoint.enabled && breakpoint.hardware_slot.is_none() && (addr..addr + len).contains(&breakpoint.addr) {
+                bytes[breakpoint.addr - addr] = breakpoint.orig_byte;
+            }
+        }
+        Ok(bytes)
+    }
+
+    //Evaluate an expression and format its value
+    fn format_expression(&mut self, text: &str, format: Option<Format>) -> Result<String, String> {
+        let value = self.evaluate_text(text)?;
+        let ty = self.debug_data.get_type(Some(value.type_offset)).unwrap();
+        let formatter = ValueFormatter::new(&self.debug_data, self.inferior.as_ref(), &self.settings)
+            .with_format(format);
+        Ok(formatter.format(ty, &value.bytes))
+    }
+
+    //Handle `display/<format> <expression>`, which shows the expression now, if the program is
+    //running, and whenever it stops, and `display` on its own, which shows them all now
+    fn display(&mut self, letters: &str, text: &str) -> Result<(), String> {
+        if text.is_empty() {
+            self.show_displays();
+            return Ok(());
+        }
+        if parse_print_format(letters, "display").is_err() {
+            parse_examine_letters(letters)?;
+        }
+        let mut display = Display {
+            number: self.next_display,
+            expression: text.to_string(),
+            letters: letters.to_string(),
+            last: None,
+        };
+        self.next_display += 1;
+        if self.inferior.is_some() {
+            for line in self.display_lines(&mut display) {
+                println!("{}", line);
+            }
+        }
+        self.displays.push(display);
+        Ok(())
+    }
+
+    //Handle `undisplay <numbers>` and `delete display <numbers>`, or all displays with no numbers
+    fn undisplay(&mut self, args: &str) -> Result<(), String> {
+        let tokens: Vec<&str> = args.split_whitespace().collect();
+        if tokens.is_empty() {
+            self.displays.clear();
+            return Ok(());
+        }
+        let numbers = parse_numbers(&tokens).ok_or(format!("Arguments must be display numbers: {}", args))?;
+        for number in numbers {
+            match self.displays.iter().position(|d| d.number == number) {
+                Some(index) => {
+                    self.displays.remove(index);
+                }
+                None => println!("No display number {}.", number),
+            }
+        }
+        Ok(())
+    }
+
+    //Show every display, as is done when the program stops
+    fn show_displays(&mut self) {
+        if self.inferior.is_none() {
+            return;
+        }
+        let mut displays = std::mem::take(&mut self.displays);
+        for display in &mut displays {
+            for line in self.display_lines(display) {
+                println!("{}", line);
+            }
+        }
+        self.displays = displays;
+    }
+
+    //What a display shows: `1: x = 5`, or `1: x: old = 4, new = 5` if the value has changed since
+    //it was last shown, or for a format of `x`, such as `display/4xb buf`, the expression followed
+    //by the memory at its address. An expression that can't be evaluated where the program is shows
+    //the error instead.
+    fn display_lines(&mut self, display: &mut Display) -> Vec<String> {
+        let (number, letters, text) = (display.number, display.letters.as_str(), display.expression.as_str());
+        match parse_print_format(letters, "display") {
+            Ok(format) => {
+                let title = match letters {
+                    "" => text.to_string(),
+                    letters => format!("/{} {}", letters, text),
+                };
+                let value = self.format_expression(text, format);
+                let line = match (&value, &display.last) {
+                    (Ok(value), Some(last)) if value != last => {
+                        format!("{}: {}: old = {}, new = {}", number, title, last, value)
+                    }
+                    (Ok(value), _) => format!("{}: {} = {}", number, title, value),
+                    (Err(e), _) => format!("{}: {} = <error: {}>", number, title, e),
+                };
+                display.last = value.ok();
+                vec![line]
+            }
+            Err(_) => {
+                let mut lines = vec![format!("{}: x/{} {}", number, letters, text)];
+                match self.examine_lines(letters, text) {
+                    Ok(memory) => lines.extend(memory),
+                    Err(e) => lines.push(format!("<error: {}>", e)),
+                }
+                lines
+            }
+        }
+    }
+
+    //Handle `info display`
+    fn print_displays(&self) {
+        if self.displays.is_empty() {
+            println!("There are no auto-display expressions now.");
+            return;
+        }
+        println!("Auto-display expressions now in effect:");
+        println!("Num Enb Expression");
+        for display in &self.displays {
+            match display.letters.as_str() {
+                "" => println!("{}:   y  {}", display.number, display.expression),
+                letters => println!("{}:   y  /{} {}", display.number, letters, display.expression),
+            }
+        }
+    }
+
+    //Handle `printf "format", args...`
+    fn printf(&mut self, args: &str) -> Result<(), String> {
+        if !args.starts_with('"') {
+            return Err("Bad format string".to_string());
+        }
+        let mut values = self.evaluate(&expression::parse_list(args, &self.debug_data)?)?;
+        let format = values.remove(0);
+        let end = format.bytes.iter().position(|b| *b == 0).unwrap_or(format.bytes.len());
+        let formatter = ValueFormatter::new(&self.debug_data, self.inferior.as_ref(), &self.settings);
+        let output = formatter.printf(&format.bytes[..end], &values)?;
+        print!("{}", output);
+        let _ = std::io::stdout().flush();
+        Ok(())
+    }
+
+    //Handle `info registers`: print the general purpose registers, a group of registers (`float`,
+    //`vector` or `all`), or the registers named
+    fn print_registers(&mut self, args: &str) {
+        let mut names: Vec<String> = Vec::new();
+        let general = GENERAL_REGISTERS.iter().map(|name| name.to_string());
+        let float = (0..8)
+            .map(|n| format!("st{}", n))
+            .chain(X87_CONTROL_REGISTERS.iter().map(|name| name.to_string()));
+        let vector = (0..16)
+            .map(|n| format!("xmm{}", n))
+            .chain((0..16).map(|n| format!("ymm{}", n)))
+            .chain(std::iter::once("mxcsr".to_string()));
+        match args {
+            "" | "general" => names.extend(general),
+            "float" => names.extend(float),
+            "vector" => names.extend(vector),
+            "all" => names.extend(general.chain(float).chain(vector)),
+            _ => names.extend(args.split_whitespace().map(|name| name.trim_start_matches('$').to_string())),
+        }
+        let named = !["", "general", "float", "vector", "all"].contains(&args);
+        for name in names {
+            let value = match self.evaluate(&[Expr::Register(name.clone())]) {
+                Ok(mut values) => values.remove(0),
+                //without AVX there are no ymm registers to show
+                Err(_) if !named && name.starts_with("ymm") => continue,
+                Err(e) if e.starts_with("Invalid register") => {
+                    println!("Invalid register `{}'", name);
+                    return;
+                }
+                Err(e) => {
+                    println!("{}", e);
+                    return;
+                }
+            };
+            let ty = self.debug_data.get_type(Some(value.type_offset)).unwrap();
+            let formatter = ValueFormatter::new(&self.debug_data, self.inferior.as_ref(), &self.settings);
+            let natural = formatter.format(ty, &value.bytes);
+            match &self.debug_data.resolve_type(ty).kind {
+                TypeKind::Struct(_) => println!("{:<15}{}", name, natural),
+                TypeKind::Base(Encoding::Float) => {
+                    let raw: String = value.bytes[..10].iter().rev().map(|b| format!("{:02x}", b)).collect();
+                    println!("{:<15}{:<19}(raw 0x{})", name, natural, raw);
+                }
+                _ => {
+                    let bits = read_uint(&value.bytes);
+                    let natural = match name.as_str() {
+                        "rip" | "pc" => formatter.format_unit(Format::Address, &value.bytes),
+                        "eflags" => format_flags(bits, &EFLAGS),
+                        "mxcsr" => format_flags(bits, &MXCSR_FLAGS),
+                        _ => natural,
+                    };
+                    println!("{:<15}{:<19}{}", name, format!("{:#x}", bits), natural);
+                }
+            }
+        }
+    }
+
+    //How far the program is loaded from the addresses it was linked at, which for a position
+    //independent executable is only known once it is running
+    fn load_bias(&self) -> Option<usize> {
+        let elf = self.debug_data.get_elf()?;
+        if !elf.pie {
+            return Some(0);
+        }
+        //where a program debugged remotely is loaded isn't known from its mappings, but from
+        //where it starts
+        let inf = self.inferior.as_ref()?;
+        if let Some(entry) = inf.remote().and_then(|remote| remote.entry()) {
+            return Some(entry.wrapping_sub(elf.entry));
+        }
+        let start = inf.load_address(&self.target)?;
+        let first = elf.segments.iter().filter(|s| s.kind == PT_LOAD).map(|s| s.addr).min();
+        Some(start - (first.unwrap_or(0) & !(PAGE_SIZE - 1)))
+    }
+
+    //Move the program's symbols to where it has been loaded this time it runs, along with the
+    //breakpoints, watchpoints and catchpoints in it, so that all of them are at the addresses
+    //the program really uses. Nothing moves unless it is a position independent executable.
+    fn relocate(&mut self) {
+        let bias = match self.load_bias() {
+            Some(bias) => bias,
+            None => return,
+        };
+        let delta = bias.wrapping_sub(self.debug_data.load_bias());
+        if delta == 0 {
+            return;
+        }
+        //only what is in the program itself moves, not e.g. the stack
+        let debug_data = &self.debug_data;
+        let shift = |addr: usize| {
+            if debug_data.contains(addr) {
+                addr.wrapping_add(delta)
+            } else {
+                addr
+            }
+        };
+        self.breakpoints = self
+            .breakpoints
+            .drain()
+            .map(|(addr, mut breakpoint)| {
+                breakpoint.addr = shift(addr);
+                (breakpoint.addr, breakpoint)
+            })
+            .collect();
+        for watchpoint in &mut self.watchpoints {
+            watchpoint.addr = shift(watchpoint.addr);
+            watchpoint.scope = watchpoint.scope.map(|(ret, base)| (shift(ret), base));
+        }
+        for catchpoint in &mut self.catchpoints {
+            catchpoint.addr = shift(catchpoint.addr);
+        }
+        self.traced_stubs = self.traced_stubs.iter().map(|addr| shift(*addr)).collect();
+        self.examine_next = self.examine_next.map(shift);
+        self.debug_data.relocate(bias);
+    }
+
+    //Handle `info files`: print where the program's segments and sections are in memory. For a
+    //position independent executable they are moved by the load bias once the program is running.
+    fn print_files(&self) {
+        let elf = match self.debug_data.get_elf() {
+            Some(elf) => elf,
+            None => {
+                println!("Cannot read the ELF headers of {}", self.target);
+                return;
+            }
+        };
+        let bias = self.load_bias();
+        println!("Symbols from \"{}\".", self.target);
+        println!("Local exec file:");
+        println!("\t`{}', file type elf64-x86-64.", self.target);
+        let bias_value = bias.unwrap_or(0);
+        println!("\tEntry point: {:#x}", elf.entry + bias_value);
+        match bias {
+            Some(bias) => println!("\tLoad bias: {:#x}", bias),
+            None => println!("\tLoad bias: not known until the program is run"),
+        }
+        println!("Program headers:");
+        for segment in &elf.segments {
+            println!(
+                "\t{:#018x} - {:#018x} {} {} offset {:#x}, file size {:#x}, align {:#x}",
+                segment.addr + bias_value,
+                segment.addr + bias_value + segment.mem_size,
+                segment.permissions(),
+                segment.kind_name(),
+                segment.offset,
+                segment.file_size,
+                segment.align,
+            );
+        }
+        println!("Sections:");
+        for section in elf.sections.iter().filter(|s| s.is_loaded()) {
+            println!(
+                "\t{:#018x} - {:#018x} is {} ({})",
+                section.addr + bias_value,
+                section.addr + bias_value + section.size,
+                section.name,
+                section.flag_letters(),
+            );
+        }
+    }
+
+    //Print the variables of a function along with their current values
+    fn print_variables(&self, func_name: &str, rip: usize) {
+        let func = match self.debug_data.get_function(None, func_name) {
+            Some(func) => func,
+            None => {
+                println!("Cannot find function {}", func_name);
+                return;
+            }
+        };
+        let inf = self.inferior.as_ref().unwrap();
+        let frame_base = inf.frame_base(rip, func.address).unwrap_or(0);
+        let formatter = ValueFormatter::new(&self.debug_data, Some(inf), &self.settings);
+        for var in &func.variables {
+            let value = match inf.read_mem(var.location.address(frame_base), var.entity_type.size) {
+                Ok(bytes) => formatter.format(&var.entity_type, &bytes),
+                Err(_) => "<unavailable>".to_string(),
+            };
+            println!(
+                "    * Variable: {} ({}, located at {}, declared at line {}) = {}",
+                var.name, var.entity_type.name, var.location, var.line_number, value
+            );
+        }
+    }
+
+    /// Wait for the child running in the background to stop, and report it like a command that
+    /// resumed it would, running the commands of a breakpoint it stops at
+    pub fn wait_for_stop(&mut self) -> Result<(), String> {
+        if self.running.is_none() {
+            return Err("The program is not running in the background.".to_string());
+        }
+        self.events.clear();
+        self.pending_commands = None;
+        while self.running.is_some() {
+            match self.check_background() {
+                Ok(Some(status)) => self.print_child_status(status),
+                Ok(None) => std::thread::sleep(Duration::from_millis(10)),
+                Err(e) => {
+                    self.running = None;
+                    return Err(format!("Cannot run child process. Error: {}", e));
+                }
+            }
+        }
+        self.run_pending_commands();
+        Ok(())
+    }
+
+    /// Take the events that happened to the child process during the last command
+    pub fn take_events(&mut self) -> Vec<Event> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// The addresses of the breakpoints the user has set
+    pub fn breakpoint_addresses(&self) -> Vec<usize> {
+        let mut addrs: Vec<usize> = self
+            .breakpoints
+            .values()
+            .filter(|b| b.number.is_some())
+            .map(|b| b.addr)
+            .collect();
+        addrs.sort();
+        addrs
+    }
+
+    /// Set a breakpoint at a location as `break <location>` does, e.g. `calls.c:11` or `twice`,
+    /// and return its number, or that of the breakpoint already there
+    pub fn break_at(&mut self, location: &str) -> Result<usize, String> {
+        let location = location.trim();
+        let addr = match self.parse_address(location) {
+            Some(addr) => addr,
+            None => {
+                return Err(self
+                    .suggest_functions(location)
+                    .unwrap_or_else(|| format!("Function \"{}\" not defined.", location)))
+            }
+        };
+        match self.add_breakpoint(addr, None, false) {
+            Ok((number, true)) => Ok(number),
+            Ok((number, false)) => {
+                self.breakpoints.get_mut(&addr).unwrap().location = Some(location.to_string());
+                Ok(number)
+            }
+            Err(_) => Err(format!("Cannot set breakpoint at {}", location)),
+        }
+    }
+
+    /// The address a location such as `calls.c:11`, `twice` or `*0x401136` is at, as `break`
+    /// would find it
+    pub fn location_address(&self, location: &str) -> Option<usize> {
+        self.parse_address(location.trim())
+    }
+
+    /// The function, source file and line an address in the program is in
+    pub fn location_of(&self, addr: usize) -> (Option<String>, Option<Line>) {
+        (self.debug_data.get_function_from_addr(addr), self.debug_data.get_line_from_addr(addr))
+    }
+
+    /// The names of the functions the program defines
+    pub fn functions(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .debug_data
+            .get_functions()
+            .iter()
+            .filter(|func| func.address != 0)
+            .map(|func| func.name.clone())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// The call stack of the stopped child process, innermost frame first
+    pub fn backtrace(&self) -> Result<Vec<BacktraceFrame>, String> {
+        match &self.inferior {
+            Some(inf) => inf.backtrace(&self.debug_data).map_err(|e| e.to_string()),
+            None => Err("No process is currently being run".to_string()),
+        }
+    }
+
+    /// The arguments and local variables of frame number `level` of a backtrace with their
+    /// values, as `bt full` prints them, or None if the frame has no debug info
+    pub fn frame_variables(&self, level: usize, frame: &BacktraceFrame) -> Option<Vec<(String, String)>> {
+        let inf = self.inferior.as_ref()?;
+        inf.frame_variables(&self.debug_data, &self.settings, level, frame)
+    }
+
+    /// The local variables of the selected frame, not including its parameters, with their
+    /// values as `info locals` prints them
+    pub fn locals(&self) -> Result<Vec<(String, String)>, String> {
+        let inf = match &self.inferior {
+            Some(inf) => inf,
+            None => return Err("No frame selected.".to_string()),
+        };
+        let frame = self.current_frame()?;
+        let func = self
+            .debug_data
+            .get_function_at(frame.lookup_pc(self.selected_frame))
+            .ok_or("No symbol table info available.".to_string())?;
+        let locals = func.variables.iter().filter(|var| !var.is_parameter);
+        Ok(inf.variable_values(&self.debug_data, &self.settings, locals, frame.cfa))
+    }
+
+    /// Evaluate an expression in the current frame, and format its value as `output/<letters>`
+    /// would
+    pub fn evaluate_expression(&mut self, text: &str, letters: &str) -> Result<String, String> {
+        let format = parse_print_format(letters, "output")?;
+        self.format_expression(text, format)
+    }
+
+    /// Read `len` bytes of the stopped child's memory, starting at `addr`
+    pub fn read_memory(&self, addr: usize, len: usize) -> Result<Vec<u8>, String> {
+        match &self.inferior {
+            Some(inf) => inf
+                .read_mem(addr, len)
+                .map_err(|_| format!("Cannot access memory at address {:#x}", addr)),
+            None => Err("The program is not being run.".to_string()),
+        }
+    }
+
+    /// The lines `x/<letters> <address>` would print
+    pub fn examine_memory(&mut self, letters: &str, address: &str) -> Result<Vec<String>, String> {
+        self.examine_lines(letters, address)
+    }
+
+    //Describe a status of the child process as an event
+    fn record_event(&mut self, s: &Status) {
+        let event = match *s {
+            Status::Exited(code) => Event::Exited(code),
+            Status::Signaled(sig) => Event::Signaled(sig),
+            Status::Syscall(number, addr) => Event::Syscall { number, addr },
+            Status::PtraceEvent(event, addr) => Event::PtraceEvent { event, addr },
+            Status::Continued => Event::Continued,
+            Status::Stopped(signal, addr) => {
+                let line = self.debug_data.get_line_from_addr(addr);
+                Event::Stopped {
+                    signal,
+                    addr,
+                    function: self.debug_data.get_function_from_addr(addr),
+                    file: line.as_ref().map(|line| line.file.clone()),
+                    line: line.map(|line| line.number),
+                    catchpoint: match signal {
+                        Signal::SIGTRAP => {
+                            self.catchpoint_at(addr).map(|c| c.number)
+                        }
+                        _ => None,
+                    },
+                }
+            }
+        };
+        self.events.push(event);
+    }
+
+    //Print the status of the child process being examined, and there are 6 statuses
+    //1. Existed
+    //2. Stopped
+    //3. Signaled
+    //4. Stopped at a system call
+    //5. Stopped for a ptrace event the debugger doesn't follow
+    //6. Continued by a SIGCONT
+    fn print_child_status(&mut self, s: Status) {
+        self.add_kept_inferiors();
+        self.report_thread_switch(&s);
+        self.record_event(&s);
+        self.selected_frame = 0;
+        match s {
+            Status::Exited(code) => {
+                println!("Child existed (status {})", code);
+                //the child has been reaped, so there is nothing left to kill or change
+                self.forget_process();
+            }
+            //Child process is stopped because of some signals sent by debugger 
+            Status::Stopped(sig, rip) => {
+                self.stop_hook_pending = true;
+                if sig == Signal::SIGTRAP {
+                    if let Some(catchpoint) = self.catchpoint_at(rip) {
+                        self.print_catchpoint(catchpoint);
+                        self.show_displays();
+                        return;
+                    }
+                }
+                println!("Child stopped (signal: {})", sig);
+                self.print_stop_location(rip);
+                if sig == Signal::SIGTRAP {
+                    match self.breakpoints.get(&rip) {
+                        Some(breakpoint) if breakpoint.enabled && breakpoint.number.is_some()
+                            && !breakpoint.commands.is_empty() => {
+                                self.pending_commands = Some(breakpoint.commands.clone());
+                            }
+                        _ => (),
+                    }
+                    self.delete_temporary_breakpoint(rip);
+                }
+                self.show_displays();
+            }
+            //Child process is stopped because it has executed some instruction that causes itself to be stopped 
+            Status::Signaled(sig) => {
+                println!("Program stopped due to signal {}", sig);
+                self.forget_process();
+            }
+            //the child stopped for something the debugger doesn't ask for, and is left stopped
+            //so that the user can see where
+            Status::Syscall(number, rip) => {
+                self.stop_hook_pending = true;
+                println!("Child stopped (system call {})", number);
+                self.print_stop_location(rip);
+                self.show_displays();
+            }
+            Status::PtraceEvent(event, rip) => {
+                self.stop_hook_pending = true;
+                println!("Child stopped (ptrace event {})", event);
+                self.print_stop_location(rip);
+                self.show_displays();
+            }
+            //the child is running again, and is waited for in the background
+            Status::Continued => {
+                println!("Child continued (signal: SIGCONT)");
+                self.running = Some(false);
+            }
+        }
+    }
+
+    //Show the source line and variables where the child stopped, if it has debug info there
+    fn print_stop_location(&mut self, rip: usize) {
+        if let (Some(line), Some(func_name)) = (
+            DwarfData::get_line_from_addr(&self.debug_data, rip),
+            DwarfData::get_function_from_addr(&self.debug_data, rip),
+        ) {
+            println!("Stopped at {}", line);
+            println!("Inside function {}", func_name);
+            self.print_source_lines(&line.file, line.number.saturating_sub(1).max(1), line.number + 1);
+            self.center_listing(&line.file, line.number);
+            self.print_variables(&func_name, rip);
+        }
+    }
+}
+
+//How `info inferiors` and `inferior` describe an inferior: its process, or `<null>` if it has none
+fn describe_inferior(inferior: &Option<Inferior>) -> String {
+    match inferior {
+        Some(inferior) => format!("process {}", inferior.process_id()),
+        None => "<null>".to_string(),
+    }
+}
+
+//Whether a file mapped into the child is the dynamic linker, e.g. /lib64/ld-linux-x86-64.so.2
+//How far a shared library is loaded from the addresses it was linked at, from the lowest of its
+//mappings
+fn library_bias(maps: &[MemoryMap], path: &str, elf: &elf::ElfFile) -> Option<usize> {
+    let start = maps.iter().filter(|map| map.path == path).map(|map| map.start).min()?;
+    let first = elf.segments.iter().filter(|s| s.kind == PT_LOAD).map(|s| s.addr).min().unwrap_or(0);
+    Some(start - (first & !(PAGE_SIZE - 1)))
+}
+
+//Whether a breakpoint location is just the name of a function, which may be in a shared library
+fn is_function_name(location: &str) -> bool {
+    location.chars().next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && location.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+//Split the lines after one that starts a block into the lines of the block and those after the
+//`end` closing it. Blocks can be nested.
+fn split_block(lines: &[String]) -> (Vec<String>, &[String]) {
+    let mut depth = 0;
+    for (i, line) in lines.iter().enumerate() {
+        if line == "end" {
+            if depth == 0 {
+                return (lines[..i].to_vec(), &lines[i + 1..]);
+            }
+            depth -= 1;
+        } else if DebuggerCommand::starts_block(line) {
+            depth += 1;
+        }
+    }
+    (lines.to_vec(), &[])
+}
+
+//Replace `$argc` in a line of a user-defined command with the number of arguments, and `$arg0`,
+//`$arg1`, ... with the arguments
+fn substitute_args(line: &str, args: &[String]) -> Result<String, String> {
+    let regex = Regex::new(r"\$arg(c|\d+)").unwrap();
+    let mut missing = None;
+    let line = regex.replace_all(line, |captures: &regex::Captures| match &captures[1] {
+        "c" => args.len().to_string(),
+        index => match index.parse::<usize>().ok().and_then(|i| args.get(i)) {
+            Some(arg) => arg.clone(),
+            None => {
+                missing = Some(index.to_string());
+                String::new()
+            }
+        },
+    });
+    match missing {
+        Some(index) => Err(format!("Missing argument {} in user function.", index)),
+        None => Ok(line.into_owned()),
+    }
+}
+
+//Handle `apropos <regex>`, listing the commands whose name or description matches the regex,
+//ignoring case, like gdb does
+fn apropos(pattern: &str) {
+    if pattern.is_empty() {
+        println!("REGEXP string is empty");
+        return;
+    }
+    let regex = match Regex::new(&format!("(?i){}", pattern)) {
+        Ok(regex) => regex,
+        Err(e) => {
+            println!("Invalid regex {}: {}", pattern, e);
+            return;
+        }
+    };
+    for (name, description) in COMMANDS.iter() {
+        if regex.is_match(name) || regex.is_match(description) {
+            println!("{} -- {}", name, description);
+        }
+    }
+}
+
+//The names closest to `name`, closest first: those a few typing mistakes away from it, or that
+//contain it, as when only part of a long name was typed
+fn close_matches<'a>(name: &str, names: impl Iterator<Item = &'a str>) -> Vec<&'a str> {
+    const MAX_MATCHES: usize = 5;
+    let name = name.to_lowercase();
+    let allowed = (name.chars().count() / 3).max(1);
+    let mut matches: Vec<(usize, &str)> = names
+        .filter_map(|candidate| {
+            let lower = candidate.to_lowercase();
+            let distance = edit_distance(&name, &lower);
+            if distance <= allowed || lower.starts_with(&name) || (name.len() >= 3 && lower.contains(&name)) {
+                Some((distance, candidate))
+            } else {
+                None
+            }
+        })
+        .collect();
+    matches.sort();
+    matches.dedup();
+    matches.into_iter().take(MAX_MATCHES).map(|(_, candidate)| candidate).collect()
+}
+
+//The number of characters to insert, delete or replace, or pairs of neighbouring characters to
+//swap, to turn one string into the other
+fn edit_distance(a: &str, b: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    //distances[i][j] is the distance between the first i characters of a and the first j of b
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for i in 0..=a.len() {
+        for j in 0..=b.len() {
+            distances[i][j] = if i == 0 || j == 0 {
+                i.max(j)
+            } else {
+                let replace = distances[i - 1][j - 1] + if a[i - 1] == b[j - 1] { 0 } else { 1 };
+                let mut distance = replace.min(distances[i - 1][j] + 1).min(distances[i][j - 1] + 1);
+                if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                    distance = distance.min(distances[i - 2][j - 2] + 1);
+                }
+                distance
+            };
+        }
+    }
+    distances[a.len()][b.len()]
+}
+
+fn is_dynamic_linker(path: &str) -> bool {
+    match std::path::Path::new(path).file_name().and_then(|name| name.to_str()) {
+        Some(name) => name.starts_with("ld-") && name.contains(".so"),
+        None => false,
+    }
+}
+
+//Parse the arguments of `enable`, `disable` and `delete`, which are numbers or ranges of numbers
+//such as `2-4`
+fn parse_numbers(tokens: &[&str]) -> Option<Vec<usize>> {
+    let mut numbers = Vec::new();
+    for token in tokens {
+        match token.find('-') {
+            Some(dash) => {
+                let first: usize = token[..dash].parse().ok()?;
+                let last: usize = token[dash + 1..].parse().ok()?;
+                numbers.extend(first..=last);
+            }
+            None => numbers.push(token.parse().ok()?),
+        }
+    }
+    Some(numbers)
+}
+
+//The registers `info registers` shows by default
+const GENERAL_REGISTERS: [&str; 26] = [
+    "rax", "rbx", "rcx", "rdx", "rsi", "rdi", "rbp", "rsp", "r8", "r9", "r10", "r11", "r12", "r13",
+    "r14", "r15", "rip", "eflags", "cs", "ss", "ds", "es", "fs", "gs", "fs_base", "gs_base",
+];
+
+const X87_CONTROL_REGISTERS: [&str; 8] = ["fctrl", "fstat", "ftag", "fiseg", "fioff", "foseg", "fooff", "fop"];
+
+//The bits of eflags and mxcsr that `info registers` names
+const EFLAGS: [(u32, &str); 9] = [
+    (0, "CF"),
+    (2, "PF"),
+    (4, "AF"),
+    (6, "ZF"),
+    (7, "SF"),
+    (8, "TF"),
+    (9, "IF"),
+    (10, "DF"),
+    (11, "OF"),
+];
+const MXCSR_FLAGS: [(u32, &str); 14] = [
+    (0, "IE"),
+    (1, "DE"),
+    (2, "ZE"),
+    (3, "OE"),
+    (4, "UE"),
+    (5, "PE"),
+    (6, "DAZ"),
+    (7, "IM"),
+    (8, "DM"),
+    (9, "ZM"),
+    (10, "OM"),
+    (11, "UM"),
+    (12, "PM"),
+    (15, "FZ"),
+];
+
+//List the flags set in a register, e.g. `[ ZF PF ]`
+fn format_flags(value: u64, flags: &[(u32, &str)]) -> String {
+    let set: Vec<&str> = flags
+        .iter()
+        .filter(|(bit, _)| value & (1 << bit) != 0)
+        .map(|(_, name)| *name)
+        .collect();
+    format!("[ {} ]", set.join(" "))
+}
+
+//Parse the letters after `x/`: a count, then any of a format letter and a size letter (`b`, `h`,
+//`w` or `g` for 1, 2, 4 or 8 bytes)
+fn parse_examine_letters(letters: &str) -> Result<(usize, Option<Format>, Option<usize>), String> {
+    let digits = letters.chars().take_while(|c| c.is_ascii_digit()).count();
+    let count = match &letters[..digits] {
+        "" => 1,
+        digits => digits.parse().map_err(|_| format!("Invalid number \"{}\".", digits))?,
+    };
+    let mut format = None;
+    let mut size = None;
+    for letter in letters[digits..].chars() {
+        match letter {
+            'b' => size = Some(1),
+            'h' => size = Some(2),
+            'w' => size = Some(4),
+            'g' => size = Some(8),
+            _ => format = Some(Format::from_letter(letter).ok_or(format!("Undefined output format \"{}\".", letter))?),
+        }
+    }
+    Ok((count, format, size))
+}
+
+//Parse the letters after `print/` or `output/`, which may only choose a format
+fn parse_print_format(letters: &str, command: &str) -> Result<Option<Format>, String> {
+    let mut format = None;
+    for letter in letters.chars() {
+        if letter.is_ascii_digit() {
+            return Err(format!("Item count other than 1 is meaningless in \"{}\" command.", command));
+        }
+        if "bhwg".contains(letter) {
+            return Err(format!("Size letters are meaningless in \"{}\" command.", command));
+        }
+        format = Some(Format::from_letter(letter).ok_or(format!("Undefined output format \"{}\".", letter))?);
+    }
+    Ok(format)
+}
+
+fn watchpoint_title(watchpoint: &Watchpoint) -> String {
+    let kind = if watchpoint.hardware_slots.is_empty() {
+        "Watchpoint"
+    } else {
+        "Hardware watchpoint"
+    };
+    format!("{} {}: {}", kind, watchpoint.number, watchpoint.expression)
+}
+
+//Split the memory at `addr` into the aligned 1, 2, 4 and 8 byte pieces that debug registers can
+//watch
+fn hardware_watch_ranges(addr: usize, len: usize) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let end = addr + len;
+    let mut start = addr;
+    while start < end {
+        let mut size = 8;
+        while size > 1 && (!start.is_multiple_of(size) || start + size > end) {
+            size /= 2;
+        }
+        ranges.push((start, size));
+        start += size;
+    }
+    ranges
+}
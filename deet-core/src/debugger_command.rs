@@ -0,0 +1,590 @@
+use crate::settings::Settings;
+
+// All types of command that the debugger support
+pub enum DebuggerCommand {
+    //quite the debugger
+    Quit,
+
+    //Run the debugger. The argument is a vector of strings that serve as the arguments to the program being run by the debugger
+    //Without any, the arguments of the last run are used again. They can include redirections,
+    //such as `< in.txt`
+    Run(Vec<String>),
+
+    //Continue from the breakpoints 
+    Continue,
+
+    //execute until the next source line, stepping into function calls. The argument is how
+    //many lines to step
+    Step(usize),
+
+    //execute until the next source line, stepping over function calls. The argument is how many
+    //lines to step
+    Next(usize),
+
+    //execute machine instructions, stepping into calls. The argument is how many instructions
+    StepInstruction(usize),
+
+    //execute machine instructions, stepping over calls. The argument is how many instructions
+    NextInstruction(usize),
+
+    //run until the current function returns, and print the value it returns
+    Finish,
+    //run until a line after the current one in the current frame is reached, e.g. to get out of
+    //a loop, or until a location is, or the current frame returns. The argument is the location,
+    //if any
+    Until(String),
+    //run until a location is reached in any frame, or the current frame returns. The argument is
+    //the location
+    Advance(String),
+    //carry on from another line or address, after asking. The argument is the location.
+    Jump(String),
+    //pop the selected frame, after asking. The argument is the value to return, if any.
+    Return(String),
+
+    //take the breakpoints out of the program and let it carry on running without the debugger
+    Detach,
+
+    //end the program without starting it again
+    Kill,
+
+    //stop the program running in the background
+    Interrupt,
+
+    //`run` or `continue` with the program left running in the background, so that commands can
+    //be typed while it runs. Written with a `&` at the end, as in `continue &`
+    Background(Box<DebuggerCommand>),
+
+    //print the call stack at the current breakpoint. The arguments are `full` to also print the
+    //variables of every frame, and how many frames to print
+    Backtrace(String),
+
+    //show lines of the source. The argument is a line number or function to show the lines
+    //around, `-` for the lines before the last listing, or nothing for the lines after it
+    List(String),
+
+    //find the next line of the source after the last one listed that matches a regex, and make
+    //`list` show the lines around it. The argument is the regex
+    Search(String),
+
+    //find the line before the last one listed that matches a regex, like Search
+    ReverseSearch(String),
+
+    //select a frame of the call stack, which expressions are then evaluated in, and print it.
+    //The argument is the frame's number, 0 being the innermost; without one the selected frame is
+    //printed
+    Frame(Option<usize>),
+
+    //select a frame further out, by the given number of frames, i.e. a caller
+    Up(usize),
+
+    //select a frame further in, by the given number of frames, i.e. a callee
+    Down(usize),
+
+    //set the breakpoint in the program. The argument is the address of the breakpoint to be set,
+    //optionally followed by `group=<name>` and then `if <condition>`
+    Break(String),
+
+    //set a breakpoint that is deleted the first time it stops the program. The argument is the
+    //same as for Break
+    TemporaryBreak(String),
+
+    //set a breakpoint with a debug register rather than an int3 instruction, so that the code
+    //isn't changed. The argument is the same as for Break
+    HardwareBreak(String),
+
+    //set a breakpoint on every function whose name matches a regex. The argument is the regex
+    RegexBreak(String),
+
+    //set the commands run every time a breakpoint stops the program. The arguments are the
+    //breakpoint's number, or an empty string for the last breakpoint set, and the commands
+    Commands(String, Vec<String>),
+
+    //define a command that runs a list of other commands. The arguments are its name and the
+    //commands, in which `$arg0`, `$arg1`, ... stand for its arguments and `$argc` for how many
+    //there are
+    Define(String, Vec<String>),
+
+    //run a Rhai script. The argument is its file name.
+    Script(String),
+
+    //run the debugger commands in a file. The argument is its file name.
+    Source(String),
+
+    //run a command defined with `define`. The arguments are its name and its arguments.
+    User(String, Vec<String>),
+
+    //set a breakpoint that prints formatted output and lets the program carry on. The argument
+    //is the location followed by the format string and expressions, as for Printf
+    Dprintf(String),
+
+    //make a breakpoint stop the program only when an expression is true. The argument is the
+    //breakpoint's number followed by the expression, or just the number to remove the condition
+    Condition(String),
+
+    //let the program pass a breakpoint a number of times before it stops. The arguments are the
+    //breakpoint's number and how many times to pass it
+    Ignore(String),
+
+    //enable, disable or delete breakpoints, watchpoints and catchpoints. The argument is a list of
+    //their numbers, `group <name>`, or empty for all of them
+    Enable(String),
+    Disable(String),
+    Delete(String),
+
+    //stop the program when a C++ exception is thrown or caught. The argument is `throw` or `catch`
+    Catch(String),
+
+    //print information about the program being debugged. The argument says what to print, e.g. `files`
+    Info(String),
+
+    //list the commands whose name or description mentions a keyword
+    Apropos(String),
+
+    //define a shortcut for a command, or list them. The argument is `<name> = <command>`.
+    Alias(String),
+
+    //remove an alias. The argument is its name.
+    Unalias(String),
+
+    //change a debugger setting. The arguments are the name of the setting and its new value
+    Set(String, String),
+
+    //store a value in the program's memory or registers. The argument is an assignment such as
+    //`x = 5` or `{int}0x601048 = 42`
+    Assign(String),
+
+    //print the current value of a debugger setting
+    Show(String),
+
+    //resume the program with a signal, as if it had been sent to it, or with none for `signal 0`.
+    //The argument is the signal, such as `SIGUSR1`
+    Signal(String),
+
+    //change what is done when the program gets a signal. The argument is the signals, such as
+    //`SIGUSR1` or `all`, followed by actions: `stop`, `nostop`, `print`, `noprint`, `pass` or
+    //`nopass`
+    Handle(String),
+
+    //look at the process a core file was dumped from, as it was when it died. The argument is the
+    //core file, or nothing to stop looking at it
+    CoreFile(String),
+
+    //debug a program running elsewhere under a gdb remote protocol stub. The argument is
+    //`remote <host>:<port>`
+    Target(String),
+
+    //write the memory and registers of the program to a core file, to look at later with
+    //CoreFile. The argument is the file, or nothing for `core.<pid>`
+    GenerateCore(String),
+
+    //keep a copy of the stopped program, to go back to later with Restart
+    Checkpoint,
+
+    //go back to a copy of the program kept with Checkpoint. The argument is its number.
+    Restart(String),
+
+    //start or stop recording the program, for the reverse commands. The argument is empty or
+    //`full` to start, or `stop`
+    Record(String),
+
+    //take the recorded program back to the line before, the instruction before, or the last
+    //breakpoint it passed
+    ReverseStep,
+    ReverseStepInstruction,
+    ReverseContinue,
+
+    //switch to another inferior, such as the other side of a fork, by its number, or say which
+    //is current with no argument
+    Inferior(String),
+
+    //switch to another thread of the program by its number in `info threads`, or say which is
+    //current with no argument
+    Thread(String),
+
+    //stop the program when some memory changes. The argument is an expression such as a
+    //variable, `-l <expression>` or `<address> len <bytes>`
+    Watch(String),
+
+    //print formatted output. The argument is the format string followed by the expressions to format
+    Printf(String),
+
+    //print text, after replacing escape sequences such as \n
+    Echo(String),
+
+    //print the value of an expression, numbered in the value history. The arguments are the
+    //letters after a `/`, such as `x` for hex, and the expression
+    Print(String, String),
+
+    //print the value of an expression, without a trailing newline. The arguments are the same as
+    //for Print
+    Output(String, String),
+
+    //show the value of an expression every time the program stops, or show them all now with no
+    //expression. The arguments are the letters after a `/`, a format as for Print or for Examine,
+    //and the expression
+    Display(String, String),
+
+    //stop showing the expressions with these numbers when the program stops, or all of them
+    Undisplay(String),
+
+    //show the contents of memory. The arguments are the letters after a `/`, giving how many
+    //units to show, their format and their size, and an expression for the address
+    Examine(String, String),
+
+    //show the machine instructions of a function or a range of addresses. The arguments are the
+    //letters after a `/`, `r` to also show the bytes of each instruction, and the function, an
+    //address in it, or `<start>,<end>`; without one, the function of the selected frame
+    Disassemble(String, String),
+}
+
+//Where `run` sends the program's standard input, output and error, as in
+//`run < in.txt > out.txt 2> err.txt`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Redirections {
+    pub stdin: Option<String>,
+    //the file, and whether to append to it (`>>`) rather than replace it
+    pub stdout: Option<(String, bool)>,
+    pub stderr: Option<(String, bool)>,
+}
+
+impl Redirections {
+    //Take the redirections out of the arguments of `run`. The file can be right after the
+    //operator or the next argument, as in `<in.txt` or `< in.txt`.
+    pub fn parse(args: &[String]) -> Result<(Vec<String>, Redirections), String> {
+        let mut redirections = Redirections::default();
+        let mut rest = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let operator = match ["2>>", "2>", ">>", ">", "<"].iter().find(|op| arg.starts_with(*op)) {
+                Some(operator) => *operator,
+                None => {
+                    rest.push(arg.clone());
+                    continue;
+                }
+            };
+            let file = match &arg[operator.len()..] {
+                "" => args
+                    .next()
+                    .ok_or(format!("Missing file name after \"{}\".", operator))?
+                    .clone(),
+                file => file.to_string(),
+            };
+            match operator {
+                "<" => redirections.stdin = Some(file),
+                ">" | ">>" => redirections.stdout = Some((file, operator == ">>")),
+                _ => redirections.stderr = Some((file, operator == "2>>")),
+            }
+        }
+        Ok((rest, redirections))
+    }
+}
+
+//The full names of the commands with what they do, for tab completion and `apropos`
+pub const COMMANDS: [(&str, &str); 67] = [
+    ("advance", "Run until a location is reached in any frame, or the current function returns."),
+    ("alias", "Define a shortcut for a command and its arguments."),
+    ("apropos", "Search for commands whose name or description mentions a keyword."),
+    ("backtrace", "Print the call stack, one line per frame."),
+    ("break", "Set a breakpoint at a line, function or address."),
+    ("catch", "Stop when a C++ exception is thrown or caught."),
+    ("cd", "Set the working directory the program runs in."),
+    ("checkpoint", "Fork a snapshot of the program to go back to with restart."),
+    ("commands", "Set commands to run when a breakpoint is hit."),
+    ("condition", "Make a breakpoint stop only when an expression is true."),
+    ("continue", "Continue running the program after it stopped."),
+    ("core-file", "Examine a core dump."),
+    ("delete", "Delete breakpoints, watchpoints, catchpoints, displays or checkpoints."),
+    ("detach", "Let the program carry on without the debugger."),
+    ("disable", "Disable breakpoints, watchpoints or catchpoints."),
+    ("disassemble", "Disassemble a function or a range of memory."),
+    ("display", "Print an expression every time the program stops."),
+    ("down", "Select the frame called by the selected one."),
+    ("define", "Define a command that runs a list of other commands."),
+    ("dprintf", "Set a breakpoint that prints formatted output and carries on."),
+    ("echo", "Print text."),
+    ("enable", "Enable breakpoints, watchpoints or catchpoints."),
+    ("finish", "Run until the current function returns, and print its value."),
+    ("forward-search", "Search forward in the source for a regular expression."),
+    ("frame", "Select a frame of the call stack and print it."),
+    ("generate-core-file", "Save a core dump of the program."),
+    ("handle", "Set what happens when the program gets a signal."),
+    ("hbreak", "Set a breakpoint using a debug register."),
+    ("ignore", "Make a breakpoint carry on the next few times it is hit."),
+    ("inferior", "Switch to another inferior."),
+    ("info", "Show information about the program or the debugger."),
+    ("interrupt", "Stop a program running in the background."),
+    ("jump", "Carry on running at another line or address."),
+    ("kill", "Kill the program."),
+    ("list", "List source lines."),
+    ("next", "Step over function calls to the next source line."),
+    ("nexti", "Step over calls to the next machine instruction."),
+    ("output", "Print the value of an expression without a newline."),
+    ("print", "Print the value of an expression."),
+    ("printf", "Print formatted output, like C's printf."),
+    ("quit", "Exit the debugger."),
+    ("rbreak", "Set breakpoints on every function matching a regular expression."),
+    ("record", "Record execution so that it can be run backwards."),
+    ("restart", "Go back to a checkpoint."),
+    ("return", "Make the selected function return to its caller at once."),
+    ("reverse-continue", "Run backwards to the last breakpoint or the start of the recording."),
+    ("reverse-search", "Search backward in the source for a regular expression."),
+    ("reverse-step", "Run backwards to the start of the previous source line."),
+    ("reverse-stepi", "Run backwards by one machine instruction."),
+    ("run", "Start the program, with arguments and redirections."),
+    ("script", "Run a Rhai script, which can decide whether breakpoints stop and print values."),
+    ("search", "Search forward in the source for a regular expression."),
+    ("set", "Change a setting, or assign to a variable."),
+    ("show", "Show a setting."),
+    ("signal", "Continue the program with a signal."),
+    ("source", "Run the debugger commands in a file."),
+    ("step", "Step into function calls to the next source line."),
+    ("stepi", "Step into calls to the next machine instruction."),
+    ("target", "Connect to a program running under gdbserver or another remote stub."),
+    ("tbreak", "Set a breakpoint that is deleted when it is first hit."),
+    ("thread", "Switch to another thread."),
+    ("unalias", "Remove an alias."),
+    ("undisplay", "Stop printing expressions every time the program stops."),
+    ("until", "Run until a line after the current one, so loops are run through."),
+    ("up", "Select the frame that called the selected one."),
+    ("watch", "Set a watchpoint, stopping when an expression changes."),
+    ("x", "Examine memory."),
+];
+
+//The things `info` shows
+#[cfg(feature = "cli")]
+pub const INFO_COMMANDS: [&str; 16] = [
+    "all-registers",
+    "breakpoints",
+    "checkpoints",
+    "display",
+    "files",
+    "handle",
+    "inferiors",
+    "locals",
+    "proc",
+    "record",
+    "registers",
+    "sharedlibrary",
+    "signals",
+    "target",
+    "threads",
+    "watchpoints",
+];
+
+//Commands that are followed by a block of lines ending with `end`
+const BLOCK_COMMANDS: [&str; 5] = ["commands", "define", "document", "if", "while"];
+
+impl DebuggerCommand {
+    //Whether the command resumes the program, or may, as a user-defined command can, which it
+    //can't while the program runs in the background
+    pub fn resumes(&self) -> bool {
+        matches!(
+            self,
+            DebuggerCommand::Run(_)
+                | DebuggerCommand::Continue
+                | DebuggerCommand::Step(_)
+                | DebuggerCommand::Next(_)
+                | DebuggerCommand::StepInstruction(_)
+                | DebuggerCommand::NextInstruction(_)
+                | DebuggerCommand::Finish
+                | DebuggerCommand::Until(_)
+                | DebuggerCommand::Advance(_)
+                | DebuggerCommand::Jump(_)
+                | DebuggerCommand::Signal(_)
+                | DebuggerCommand::Background(_)
+                | DebuggerCommand::User(_, _)
+        )
+    }
+
+    //Whether a line starts a command that is followed by a block of lines
+    pub fn starts_block(line: &str) -> bool {
+        match line.split_whitespace().next() {
+            Some(name) => BLOCK_COMMANDS.contains(&name),
+            None => false,
+        }
+    }
+
+    //Parse a command followed by a block of lines. `line` is the line that started the block and
+    //`body` the lines up to its `end`. The blocks of commands that aren't supported are read and
+    //rejected as a whole rather than each line being run as a command.
+    pub fn from_block(line: &str, body: Vec<String>) -> Option<DebuggerCommand> {
+        let line = line.trim();
+        let (name, rest) = match line.find(char::is_whitespace) {
+            Some(end) => (&line[..end], line[end..].trim()),
+            None => (line, ""),
+        };
+        match name {
+            "commands" => Some(DebuggerCommand::Commands(rest.to_string(), body)),
+            "define" => Some(DebuggerCommand::Define(rest.to_string(), body)),
+            _ => None,
+        }
+    }
+
+    //Parse a line typed at the prompt. Commands whose argument is an expression get the rest of
+    //the line untouched, so that spaces inside string literals are kept.
+    pub fn from_line(line: &str) -> Option<DebuggerCommand> {
+        let line = line.trim();
+        //a `&` on its own isn't a command to run in the background
+        if let Some(command) = line.strip_suffix('&').filter(|command| !command.trim().is_empty()) {
+            match DebuggerCommand::from_line(command) {
+                Some(cmd @ DebuggerCommand::Run(_)) | Some(cmd @ DebuggerCommand::Continue) => {
+                    return Some(DebuggerCommand::Background(Box::new(cmd)))
+                }
+                _ => (),
+            }
+        }
+        let (name, rest) = match line.find(char::is_whitespace) {
+            Some(end) => (&line[..end], line[end..].trim()),
+            None => (line, ""),
+        };
+        //some commands take letters after a `/`, as in `print/x`
+        let (name, letters) = match name.find('/') {
+            Some(slash) => (&name[..slash], Some(&name[slash + 1..])),
+            None => (name, None),
+        };
+        match name {
+            "p" | "print" => Some(DebuggerCommand::Print(
+                letters.unwrap_or("").to_string(),
+                rest.to_string(),
+            )),
+            "output" => Some(DebuggerCommand::Output(
+                letters.unwrap_or("").to_string(),
+                rest.to_string(),
+            )),
+            "display" => Some(DebuggerCommand::Display(
+                letters.unwrap_or("").to_string(),
+                rest.to_string(),
+            )),
+            "undisplay" => Some(DebuggerCommand::Undisplay(rest.to_string())),
+            "x" => Some(DebuggerCommand::Examine(
+                letters.unwrap_or("").to_string(),
+                rest.to_string(),
+            )),
+            "disas" | "disassemble" => Some(DebuggerCommand::Disassemble(
+                letters.unwrap_or("").to_string(),
+                rest.to_string(),
+            )),
+            _ if letters.is_some() => None,
+            "watch" => Some(DebuggerCommand::Watch(rest.to_string())),
+            "set" => {
+                let (setting, value) = match rest.find(char::is_whitespace) {
+                    Some(end) => (&rest[..end], rest[end..].trim()),
+                    None => (rest, ""),
+                };
+                match setting {
+                    "var" | "variable" => Some(DebuggerCommand::Assign(value.to_string())),
+                    "" => None,
+                    //`set args` on its own runs the program without arguments, and `set cwd`
+                    //in the debugger's working directory
+                    "args" | "cwd" => Some(DebuggerCommand::Set(setting.to_string(), value.to_string())),
+                    _ if !Settings::NAMES.contains(&setting) => Some(DebuggerCommand::Assign(rest.to_string())),
+                    _ if value.is_empty() => None,
+                    _ => {
+                        let value: Vec<&str> = value.split_whitespace().collect();
+                        Some(DebuggerCommand::Set(setting.to_string(), value.join(" ")))
+                    }
+                }
+            }
+            "cd" => Some(DebuggerCommand::Set("cwd".to_string(), rest.to_string())),
+            "condition" => Some(DebuggerCommand::Condition(rest.to_string())),
+            "br" | "break" if !rest.is_empty() => Some(DebuggerCommand::Break(rest.to_string())),
+            "tb" | "tbreak" if !rest.is_empty() => Some(DebuggerCommand::TemporaryBreak(rest.to_string())),
+            "hb" | "hbreak" if !rest.is_empty() => Some(DebuggerCommand::HardwareBreak(rest.to_string())),
+            "rbreak" if !rest.is_empty() => Some(DebuggerCommand::RegexBreak(rest.to_string())),
+            "search" | "forward-search" | "fo" => Some(DebuggerCommand::Search(rest.to_string())),
+            "reverse-search" | "rev" => Some(DebuggerCommand::ReverseSearch(rest.to_string())),
+            "dprintf" => Some(DebuggerCommand::Dprintf(rest.to_string())),
+            "printf" => Some(DebuggerCommand::Printf(rest.to_string())),
+            "echo" => Some(DebuggerCommand::Echo(rest.to_string())),
+            "alias" => Some(DebuggerCommand::Alias(rest.to_string())),
+            "unalias" if !rest.is_empty() => Some(DebuggerCommand::Unalias(rest.to_string())),
+            _ => {
+                let tokens: Vec<&str> = line.split_whitespace().collect();
+                DebuggerCommand::from_tokens(&tokens)
+            }
+        }
+    }
+
+    pub fn from_tokens(tokens: &Vec<&str>) -> Option<DebuggerCommand> {
+        match *tokens.first()? {
+            "q" | "quit" => Some(DebuggerCommand::Quit),
+            "r" | "run" => {
+                let args = tokens[1..].to_vec();
+                Some(DebuggerCommand::Run(
+                    args.iter().map(|s| s.to_string()).collect(),
+                ))
+            },
+            "restart" if tokens.len() == 1 => Some(DebuggerCommand::Run(Vec::new())),
+            "c" | "cont" | "continue" => {
+                Some(DebuggerCommand::Continue)
+            },
+            "s" | "step" => match tokens.get(1) {
+                Some(count) => count.parse().ok().map(DebuggerCommand::Step),
+                None => Some(DebuggerCommand::Step(1)),
+            },
+            "n" | "next" => match tokens.get(1) {
+                Some(count) => count.parse().ok().map(DebuggerCommand::Next),
+                None => Some(DebuggerCommand::Next(1)),
+            },
+            "si" | "stepi" => match tokens.get(1) {
+                Some(count) => count.parse().ok().map(DebuggerCommand::StepInstruction),
+                None => Some(DebuggerCommand::StepInstruction(1)),
+            },
+            "ni" | "nexti" => match tokens.get(1) {
+                Some(count) => count.parse().ok().map(DebuggerCommand::NextInstruction),
+                None => Some(DebuggerCommand::NextInstruction(1)),
+            },
+            "fin" | "finish" => Some(DebuggerCommand::Finish),
+            "u" | "until" => Some(DebuggerCommand::Until(tokens[1..].join(" "))),
+            "advance" => Some(DebuggerCommand::Advance(tokens[1..].join(" "))),
+            "j" | "jump" => Some(DebuggerCommand::Jump(tokens[1..].join(" "))),
+            "return" => Some(DebuggerCommand::Return(tokens[1..].join(" "))),
+            "detach" => Some(DebuggerCommand::Detach),
+            "k" | "kill" => Some(DebuggerCommand::Kill),
+            "interrupt" => Some(DebuggerCommand::Interrupt),
+            "bt" | "back" | "backtrace" => Some(DebuggerCommand::Backtrace(tokens[1..].join(" "))),
+            "l" | "list" => Some(DebuggerCommand::List(tokens[1..].join(" "))),
+            "f" | "frame" => match tokens.get(1) {
+                Some(level) => level.parse().ok().map(|level| DebuggerCommand::Frame(Some(level))),
+                None => Some(DebuggerCommand::Frame(None)),
+            },
+            "up" => match tokens.get(1) {
+                Some(count) => count.parse().ok().map(DebuggerCommand::Up),
+                None => Some(DebuggerCommand::Up(1)),
+            },
+            "down" => match tokens.get(1) {
+                Some(count) => count.parse().ok().map(DebuggerCommand::Down),
+                None => Some(DebuggerCommand::Down(1)),
+            },
+            "enable" => Some(DebuggerCommand::Enable(tokens[1..].join(" "))),
+            "disable" => Some(DebuggerCommand::Disable(tokens[1..].join(" "))),
+            "d" | "delete" => Some(DebuggerCommand::Delete(tokens[1..].join(" "))),
+            "ignore" => Some(DebuggerCommand::Ignore(tokens[1..].join(" "))),
+            "catch" => Some(DebuggerCommand::Catch(tokens[1..].join(" "))),
+            "signal" => Some(DebuggerCommand::Signal(tokens[1..].join(" "))),
+            "handle" => Some(DebuggerCommand::Handle(tokens[1..].join(" "))),
+            "core" | "core-file" => Some(DebuggerCommand::CoreFile(tokens[1..].join(" "))),
+            "target" => Some(DebuggerCommand::Target(tokens[1..].join(" "))),
+            "gcore" | "generate-core-file" => Some(DebuggerCommand::GenerateCore(tokens[1..].join(" "))),
+            "checkpoint" => Some(DebuggerCommand::Checkpoint),
+            "restart" => Some(DebuggerCommand::Restart(tokens[1..].join(" "))),
+            "rec" | "record" => Some(DebuggerCommand::Record(tokens[1..].join(" "))),
+            "rs" | "reverse-step" => Some(DebuggerCommand::ReverseStep),
+            "rsi" | "reverse-stepi" => Some(DebuggerCommand::ReverseStepInstruction),
+            "rc" | "reverse-continue" => Some(DebuggerCommand::ReverseContinue),
+            "inferior" => Some(DebuggerCommand::Inferior(tokens[1..].join(" "))),
+            "thread" => Some(DebuggerCommand::Thread(tokens[1..].join(" "))),
+            "i" | "info" => Some(DebuggerCommand::Info(tokens[1..].join(" "))),
+            "script" => Some(DebuggerCommand::Script(tokens[1..].join(" "))),
+            "source" => Some(DebuggerCommand::Source(tokens[1..].join(" "))),
+            "apropos" => Some(DebuggerCommand::Apropos(tokens[1..].join(" "))),
+            "show" if tokens.len() >= 2 => {
+                Some(DebuggerCommand::Show(tokens[1..].join(" ")))
+            },
+
+            // Default case:
+            _ => None,
+        }
+    }
+}
+
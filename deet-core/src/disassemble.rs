@@ -0,0 +1,40 @@
+use capstone::prelude::*;
+
+//The longest an x86-64 instruction can be
+pub const MAX_INSTRUCTION_LEN: usize = 15;
+
+//A machine instruction decoded by capstone
+pub struct Instruction {
+    pub addr: usize,
+    pub bytes: Vec<u8>,
+    //the mnemonic and operands as gdb shows them, e.g. `mov    %rsp,%rbp`
+    pub text: String,
+}
+
+//Decode the x86-64 machine code in `code`, which is at `addr` in memory, in AT&T syntax like gdb.
+//Decoding stops at the first bytes that aren't a valid instruction.
+pub fn disassemble(code: &[u8], addr: usize) -> Result<Vec<Instruction>, String> {
+    let cs = Capstone::new()
+        .x86()
+        .mode(arch::x86::ArchMode::Mode64)
+        .syntax(arch::x86::ArchSyntax::Att)
+        .build()
+        .map_err(|e| format!("Cannot start the disassembler: {}", e))?;
+    let instructions = cs
+        .disasm_all(code, addr as u64)
+        .map_err(|e| format!("Cannot disassemble at address {:#x}: {}", addr, e))?;
+    Ok(instructions
+        .iter()
+        .map(|insn| {
+            let mnemonic = insn.mnemonic().unwrap_or("(bad)");
+            Instruction {
+                addr: insn.address() as usize,
+                bytes: insn.bytes().to_vec(),
+                text: match insn.op_str() {
+                    Some(operands) if !operands.is_empty() => format!("{:<6} {}", mnemonic, operands),
+                    _ => mnemonic.to_string(),
+                },
+            }
+        })
+        .collect())
+}
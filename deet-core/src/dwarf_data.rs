@@ -0,0 +1,614 @@
+use crate::elf;
+use crate::gimli_wrapper;
+use crate::unwind::{CallFrameInfo, UnwindRow};
+use addr2line::Context;
+use object::Object;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::{fmt, fs};
+
+#[derive(Debug)]
+pub enum Error {
+    ErrorOpeningFile,
+    DwarfFormatError(gimli_wrapper::Error),
+}
+
+//Offsets of types made up by the debugger (e.g. the type of `&x` while evaluating an expression)
+//start here, far beyond any real .debug_info offset
+const SYNTHETIC_TYPE_BASE: usize = 1 << 40;
+
+pub struct DwarfData {
+    files: Vec<File>,
+    types: HashMap<usize, Type>,
+    //names of the types made up by the debugger, mapped to their offsets in `types`
+    synthetic_types: HashMap<String, usize>,
+    //PLT stubs for functions in shared libraries, mapped to the names of the functions
+    plt: HashMap<usize, String>,
+    //the ELF headers of the file
+    elf: Option<elf::ElfFile>,
+    //how to unwind the stack frame of each function
+    call_frame_info: Option<CallFrameInfo>,
+    //how far the program is loaded from the addresses it was linked at, which the addresses of
+    //functions, lines and variables have been moved by. Lookups in the line table and call frame
+    //information, which stay as they are in the file, take it off again.
+    bias: usize,
+    addr2line: Context<addr2line::gimli::EndianRcSlice<addr2line::gimli::RunTimeEndian>>,
+}
+
+impl fmt::Debug for DwarfData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DwarfData {{files: {:?}}}", self.files)
+    }
+}
+
+impl From<gimli_wrapper::Error> for Error {
+    fn from(err: gimli_wrapper::Error) -> Self {
+        Error::DwarfFormatError(err)
+    }
+}
+
+impl DwarfData {
+    pub fn from_file(path: &str) -> Result<DwarfData, Error> {
+        let file = fs::File::open(path).or(Err(Error::ErrorOpeningFile))?;
+        let mmap = unsafe { memmap::Mmap::map(&file).or(Err(Error::ErrorOpeningFile))? };
+        let object = object::File::parse(&mmap).map_err(|e| gimli_wrapper::Error::Object(e.to_string()))?;
+        let endian = if object.is_little_endian() {
+            gimli::RunTimeEndian::Little
+        } else {
+            gimli::RunTimeEndian::Big
+        };
+        let (files, types) = gimli_wrapper::load_file(&object, endian)?;
+        let elf = elf::parse(&mmap);
+        Ok(DwarfData {
+            files,
+            types,
+            synthetic_types: HashMap::new(),
+            plt: elf::plt_entries(&mmap),
+            call_frame_info: elf.as_ref().map(|elf| CallFrameInfo::load(&mmap, elf)),
+            elf,
+            bias: 0,
+            addr2line: Context::new(&object).map_err(gimli_wrapper::Error::from)?,
+        })
+    }
+
+    //Move every address to where the program is loaded, `bias` bytes from where it was linked.
+    //A position independent executable is loaded somewhere else each time it is run.
+    pub fn relocate(&mut self, bias: usize) {
+        let delta = bias.wrapping_sub(self.bias);
+        if delta == 0 {
+            return;
+        }
+        let shift = |addr: &mut usize| *addr = addr.wrapping_add(delta);
+        //a function or variable that is only declared has address 0
+        let shift_variable = |var: &mut Variable| match &mut var.location {
+            Location::Address(addr) if *addr != 0 => shift(addr),
+            _ => (),
+        };
+        for file in &mut self.files {
+            file.lines.iter_mut().for_each(|line| shift(&mut line.address));
+            file.global_variables.iter_mut().for_each(shift_variable);
+            for func in file.functions.iter_mut().filter(|func| func.address != 0) {
+                shift(&mut func.address);
+                func.variables.iter_mut().for_each(shift_variable);
+            }
+        }
+        self.plt = self.plt.drain().map(|(addr, name)| (addr.wrapping_add(delta), name)).collect();
+        self.bias = bias;
+    }
+
+    //How far the program is loaded from where it was linked, 0 until it is relocated
+    pub fn load_bias(&self) -> usize {
+        self.bias
+    }
+
+    //Whether `addr` is in one of the program's own sections, rather than e.g. a shared library,
+    //the stack or the heap
+    pub fn contains(&self, addr: usize) -> bool {
+        let addr = addr.wrapping_sub(self.bias);
+        self.elf.as_ref().is_some_and(|elf| {
+            elf.sections
+                .iter()
+                .any(|section| section.is_loaded() && section.addr <= addr && addr < section.addr + section.size)
+        })
+    }
+
+    //Find how to unwind a stack frame stopped at `addr` from the call frame information, if any
+    pub fn unwind_row(&self, addr: usize) -> Option<UnwindRow> {
+        self.call_frame_info.as_ref()?.unwind_row(addr.wrapping_sub(self.bias))
+    }
+
+    //look up a type by its offset in .debug_info
+    pub fn get_type(&self, offset: Option<usize>) -> Option<&Type> {
+        self.types.get(&offset?)
+    }
+
+    //find a type by its C name, e.g. "int" or "struct point"
+    pub fn get_type_by_name(&self, name: &str) -> Option<usize> {
+        if let Some(offset) = self.synthetic_types.get(name) {
+            return Some(*offset);
+        }
+        self.types
+            .iter()
+            .find(|(offset, ty)| ty.name == name && **offset < SYNTHETIC_TYPE_BASE)
+            .map(|(offset, _)| *offset)
+    }
+
+    //Register a type that doesn't exist in the debug info and return its offset. Types are
+    //identified by name, so making up the same type twice returns the same offset.
+    pub fn intern_type(&mut self, ty: Type) -> usize {
+        if let Some(offset) = self.synthetic_types.get(&ty.name) {
+            return *offset;
+        }
+        let offset = SYNTHETIC_TYPE_BASE + self.synthetic_types.len();
+        self.synthetic_types.insert(ty.name.clone(), offset);
+        self.types.insert(offset, ty);
+        offset
+    }
+
+    //Get the offset of a base type such as "int", making it up if the program doesn't use it
+    pub fn base_type(&mut self, name: &str, size: usize, encoding: Encoding) -> usize {
+        match self.get_type_by_name(name) {
+            Some(offset) => offset,
+            None => self.intern_type(Type::new(name.to_string(), size, TypeKind::Base(encoding))),
+        }
+    }
+
+    //Get the offset of a pointer to the type at `target` (None for `void *`)
+    pub fn pointer_type(&mut self, target: Option<usize>) -> usize {
+        let target_name = match self.get_type(target) {
+            Some(ty) => ty.name.clone(),
+            None => "void".to_string(),
+        };
+        //a pointer to an array is written `int (*)[4]`
+        let name = if let Some(i) = target_name.find("(*") {
+            format!("{}(*{}", &target_name[..i], &target_name[i + 1..])
+        } else if let Some(i) = target_name.find(" [") {
+            format!("{} (*){}", &target_name[..i], &target_name[i + 1..])
+        } else if target_name.ends_with('*') {
+            format!("{}*", target_name)
+        } else {
+            format!("{} *", target_name)
+        };
+        self.intern_type(Type::new(
+            name,
+            std::mem::size_of::<usize>(),
+            TypeKind::Pointer(target),
+        ))
+    }
+
+    //Get the offset of an array of `count` elements of the type at `elem`
+    pub fn array_type(&mut self, elem: usize, count: usize) -> usize {
+        let elem_type = self.get_type(Some(elem)).unwrap();
+        let size = self.resolve_type(elem_type).size * count;
+        //the new dimension goes before any the element type already has
+        let name = match elem_type.name.find('[') {
+            Some(i) => format!("{}[{}]{}", &elem_type.name[..i], count, &elem_type.name[i..]),
+            None if elem_type.name.ends_with('*') => format!("{}[{}]", elem_type.name, count),
+            None => format!("{} [{}]", elem_type.name, count),
+        };
+        self.intern_type(Type::new(name, size, TypeKind::Array(Some(elem), vec![Some(count)])))
+    }
+
+    //Get the offset of the type of a vector register of `size` bytes, such as xmm0. Like gdb's, it
+    //is a union of arrays of each element type that fits, shown here as a struct whose members
+    //all start at 0.
+    pub fn vector_register_type(&mut self, size: usize) -> usize {
+        let name = format!("vec{}", size * 8);
+        if let Some(offset) = self.synthetic_types.get(&name) {
+            return *offset;
+        }
+        let elements = [
+            ("float", 4, Encoding::Float),
+            ("double", 8, Encoding::Float),
+            ("int8", 1, Encoding::Signed),
+            ("int16", 2, Encoding::Signed),
+            ("int32", 4, Encoding::Signed),
+            ("int64", 8, Encoding::Signed),
+            ("uint128", 16, Encoding::Unsigned),
+        ];
+        let mut members = Vec::new();
+        for (elem_name, elem_size, encoding) in elements.iter() {
+            let count = size / elem_size;
+            //made up rather than looked up, so that a typedef of int8_t isn't shown as chars
+            let elem = self.intern_type(Type::new(format!("{}_t", elem_name), *elem_size, TypeKind::Base(*encoding)));
+            let (member_name, type_offset) = match count {
+                1 => (elem_name.to_string(), elem),
+                _ => (format!("v{}_{}", count, elem_name), self.array_type(elem, count)),
+            };
+            members.push(Member {
+                name: member_name,
+                type_offset: Some(type_offset),
+                offset: 0,
+                bit_size: None,
+                bit_offset: 0,
+            });
+        }
+        self.intern_type(Type::new(name, size, TypeKind::Struct(members)))
+    }
+
+    //strip typedefs and cv-qualifiers to get to the type that decides how a value is laid out
+    pub fn resolve_type<'a>(&'a self, ty: &'a Type) -> &'a Type {
+        let mut ty = ty;
+        while let TypeKind::Alias(target) = ty.kind {
+            match self.get_type(target) {
+                Some(t) => ty = t,
+                None => break,
+            }
+        }
+        ty
+    }
+
+    #[allow(dead_code)]
+    fn get_target_file(&self, file: &str) -> Option<&File> {
+        self.files.iter().find(|f| {
+            f.name == file
+                || f.source_paths.first().is_some_and(|path| path == file)
+                || (!file.contains("/") && f.name.ends_with(&format!("/{}", file)))
+        })
+    }
+
+    //Every source file the debug info refers to, such as .c files and the headers they include
+    pub fn get_source_paths(&self) -> Vec<&str> {
+        let mut paths: Vec<&str> = Vec::new();
+        for path in self.files.iter().flat_map(|file| file.source_paths.iter()) {
+            if !paths.contains(&path.as_str()) {
+                paths.push(path);
+            }
+        }
+        paths
+    }
+
+    pub fn get_addr_for_line(&self, file: Option<&str>, line_number: usize) -> Option<usize> {
+        let target_file = match file {
+            Some(filename) => self.get_target_file(filename)?,
+            None => self.files.first()?,
+        };
+        Some(
+            target_file
+                .lines
+                .iter()
+                .find(|line| line.number >= line_number)?
+                .address,
+        )
+    }
+
+    //The entry of a function defined in `file`, or in any file. Functions that a file only
+    //declares, such as ones defined in another file, have no address.
+    pub fn get_addr_for_function(&self, file: Option<&str>, func_name: &str) -> Option<usize> {
+        let is_definition = |func: &&Function| func.name == func_name && func.address != 0;
+        match file {
+            Some(filename) => Some(
+                self.get_target_file(filename)?
+                    .functions
+                    .iter()
+                    .find(is_definition)?
+                    .address,
+            ),
+            None => {
+                for file in &self.files {
+                    if let Some(func) = file.functions.iter().find(is_definition) {
+                        return Some(func.address);
+                    }
+                }
+                None
+            }
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn get_line_from_addr(&self, curr_addr: usize) -> Option<Line> {
+        let location = self
+            .addr2line
+            .find_location(curr_addr.wrapping_sub(self.bias).try_into().unwrap())
+            .ok()??;
+        Some(Line {
+            file: location.file?.to_string(),
+            number: location.line?.try_into().unwrap(),
+            address: curr_addr,
+        })
+    }
+
+    #[allow(dead_code)]
+    pub fn get_function_from_addr(&self, curr_addr: usize) -> Option<String> {
+        let frame = self
+            .addr2line
+            .find_frames(curr_addr.wrapping_sub(self.bias).try_into().unwrap())
+            .ok()?
+            .next()
+            .ok()??;
+        Some(frame.function?.raw_name().ok()?.to_string())
+    }
+
+    //find the function whose code contains `addr`
+    pub fn get_function_at(&self, addr: usize) -> Option<&Function> {
+        self.files.iter().flat_map(|f| f.functions.iter()).find(|func| {
+            func.address != 0 && func.address <= addr && addr < func.address + func.text_length
+        })
+    }
+
+    //whether a row of the line table starts at `addr`, i.e. `addr` is the first instruction of
+    //a source line rather than somewhere in the middle of one
+    pub fn is_line_start(&self, addr: usize) -> bool {
+        self.files
+            .iter()
+            .flat_map(|f| f.lines.iter())
+            .any(|line| line.address == addr)
+    }
+
+    //the address of the first line of a function's body, right after the prologue that sets up
+    //its stack frame, which is the second row of the line table inside the function
+    pub fn get_prologue_end(&self, func: &Function) -> Option<usize> {
+        self.files
+            .iter()
+            .flat_map(|f| f.lines.iter())
+            .map(|line| line.address)
+            .filter(|addr| *addr > func.address && *addr < func.address + func.text_length)
+            .min()
+    }
+
+    //the sections and segments of the file, if it could be read as a 64-bit ELF file
+    pub fn get_elf(&self) -> Option<&elf::ElfFile> {
+        self.elf.as_ref()
+    }
+
+    //find the PLT stub through which the program calls a shared library function
+    pub fn get_plt_entry(&self, func_name: &str) -> Option<usize> {
+        self.plt.iter().find(|(_, name)| *name == func_name).map(|(addr, _)| *addr)
+    }
+
+    //the addresses of all the PLT stubs
+    pub fn get_plt_stubs(&self) -> Vec<usize> {
+        let mut stubs: Vec<usize> = self.plt.keys().copied().collect();
+        stubs.sort();
+        stubs
+    }
+
+    //find the shared library function a PLT stub calls
+    pub fn get_plt_name(&self, addr: usize) -> Option<&str> {
+        self.plt.get(&addr).map(|name| name.as_str())
+    }
+
+    //find the function or global variable that `addr` is in. Returns its name and how far into
+    //it `addr` is.
+    pub fn get_symbol_at(&self, addr: usize) -> Option<(&str, usize)> {
+        if let Some(func) = self.get_function_at(addr) {
+            return Some((&func.name, addr - func.address));
+        }
+        self.files
+            .iter()
+            .flat_map(|f| f.global_variables.iter())
+            .find_map(|var| match var.location {
+                Location::Address(start) if start <= addr && addr < start + var.entity_type.size.max(1) => {
+                    Some((var.name.as_str(), addr - start))
+                }
+                _ => None,
+            })
+    }
+
+    //find a global variable by name in any compilation unit
+    pub fn get_global_variable(&self, name: &str) -> Option<&Variable> {
+        self.files
+            .iter()
+            .flat_map(|f| f.global_variables.iter())
+            .find(|var| var.name == name)
+    }
+
+    //find the functions defined in a source file, which may be a header included in any
+    //compilation unit
+    pub fn get_functions_in_file(&self, file: &str) -> Vec<&Function> {
+        self.get_functions()
+            .into_iter()
+            .filter(|func| {
+                func.decl_file == file
+                    || (!file.contains('/') && func.decl_file.ends_with(&format!("/{}", file)))
+            })
+            .collect()
+    }
+
+    //Every function defined in the program, in the order of their addresses
+    pub fn get_functions(&self) -> Vec<&Function> {
+        let mut functions: Vec<&Function> = self
+            .files
+            .iter()
+            .flat_map(|f| f.functions.iter())
+            .filter(|func| func.address != 0)
+            .collect();
+        functions.sort_by_key(|func| func.address);
+        functions.dedup_by_key(|func| func.address);
+        functions
+    }
+
+    pub fn get_function(&self, filename: Option<&str>, func_name: &str) -> Option<&Function> {
+        let mut file_target = self.files.first()?;
+
+        if let Some(target) = filename{
+            file_target = self.get_target_file(target)?;
+        }
+        file_target.functions.iter().find(|f| f.name == func_name)
+    }
+
+    #[allow(dead_code)]
+    pub fn print(&self) {
+        for file in &self.files {
+            println!("------");
+            println!("{}", file.name);
+            println!("------");
+
+            println!("Global variables:");
+            for var in &file.global_variables {
+                println!(
+                    "  * {} ({}, located at {}, declared at line {})",
+                    var.name, var.entity_type.name, var.location, var.line_number
+                );
+            }
+
+            println!("Functions:");
+            for func in &file.functions {
+                println!(
+                    "  * {} (declared on line {}, located at {:#x}, {} bytes long)",
+                    func.name, func.line_number, func.address, func.text_length
+                );
+                for var in &func.variables {
+                    println!(
+                        "    * Variable: {} ({}, located at {}, declared at line {})",
+                        var.name, var.entity_type.name, var.location, var.line_number
+                    );
+                }
+            }
+
+            println!("Line numbers:");
+            for line in &file.lines {
+                println!("  * {} (at {:#x})", line.number, line.address);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Type {
+    pub name: String,
+    pub size: usize,
+    pub kind: TypeKind,
+}
+
+impl Type {
+    pub fn new(name: String, size: usize, kind: TypeKind) -> Self {
+        Type {
+            name,
+            size,
+            kind,
+        }
+    }
+}
+
+//How the bytes of a base type should be interpreted (DW_AT_encoding)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    Signed,
+    Unsigned,
+    SignedChar,
+    UnsignedChar,
+    Boolean,
+    Float,
+    Utf,
+    Address,
+    Unknown,
+}
+
+//The shape of a type. Types that refer to other types (pointers, arrays, typedefs...) hold the
+//.debug_info offset of the referred type, which can be looked up with DwarfData::get_type. A
+//missing offset means `void`.
+#[derive(Debug, Clone, Default)]
+pub enum TypeKind {
+    Base(Encoding),
+    Pointer(Option<usize>),
+    Struct(Vec<Member>),
+    Union(Vec<Member>),
+    Enum(Vec<Enumerator>),
+    //element type and the number of elements of every dimension (None if unknown)
+    Array(Option<usize>, Vec<Option<usize>>),
+    //typedef, const, volatile and restrict only wrap another type
+    Alias(Option<usize>),
+    Function,
+    #[default]
+    Unknown,
+}
+
+//A field of a struct or union
+#[derive(Debug, Clone)]
+pub struct Member {
+    pub name: String,
+    pub type_offset: Option<usize>,
+    //byte offset of the member from the start of the struct
+    pub offset: usize,
+    //for bitfields: the width in bits and the offset in bits (counted from the least significant
+    //bit) from the start of the struct
+    pub bit_size: Option<usize>,
+    pub bit_offset: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Enumerator {
+    pub name: String,
+    pub value: i64,
+}
+
+#[derive(Clone)]
+pub enum Location {
+    Address(usize),
+    FramePointerOffset(isize),
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Location::Address(addr) => write!(f, "Address({:#x})", addr),
+            Location::FramePointerOffset(offset) => write!(f, "FramePointerOffset({})", offset),
+        }
+    }
+}
+
+impl Location {
+    //Get the address of a variable given the frame base of the function it belongs to
+    pub fn address(&self, frame_base: usize) -> usize {
+        match *self {
+            Location::Address(addr) => addr,
+            Location::FramePointerOffset(offset) => (frame_base as isize + offset) as usize,
+        }
+    }
+}
+
+impl fmt::Debug for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+// For variables and formal parameters
+#[derive(Debug, Clone)]
+pub struct Variable {
+    pub name: String,
+    pub entity_type: Type,
+    //offset of entity_type in .debug_info
+    pub type_offset: usize,
+    pub location: Location,
+    pub line_number: usize, // Line number in source file
+    //whether this is a parameter of a function rather than a local variable
+    pub is_parameter: bool,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Function {
+    pub name: String,
+    pub address: usize,
+    pub text_length: usize,
+    pub line_number: usize, // Line number in source file
+    pub decl_file: String,  // Source file the function is defined in
+    pub return_type: Option<usize>, // Offset of the type it returns, None for void
+    pub variables: Vec<Variable>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct File {
+    pub name: String,
+    //where the source files the line table refers to were when the program was compiled, the
+    //compilation unit's own file first
+    pub source_paths: Vec<String>,
+    pub global_variables: Vec<Variable>,
+    pub functions: Vec<Function>,
+    pub lines: Vec<Line>,
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    pub file: String,
+    pub number: usize,
+    pub address: usize,
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.file, self.number)
+    }
+}
@@ -0,0 +1,377 @@
+use std::collections::HashMap;
+use std::convert::TryInto;
+
+//A section header of a 64-bit little endian ELF file
+pub struct Section {
+    pub name: String,
+    kind: u32,
+    pub flags: u64,
+    pub addr: usize,
+    pub offset: usize,
+    pub size: usize,
+    link: usize,
+    entry_size: usize,
+}
+
+impl Section {
+    //Whether the section takes up memory when the program runs, as opposed to e.g. debug info
+    pub fn is_loaded(&self) -> bool {
+        self.flags & SHF_ALLOC != 0
+    }
+
+    //The section's flags as a string such as "AX", as readelf shows them
+    pub fn flag_letters(&self) -> String {
+        let mut letters = String::new();
+        if self.flags & SHF_WRITE != 0 {
+            letters.push('W');
+        }
+        if self.flags & SHF_ALLOC != 0 {
+            letters.push('A');
+        }
+        if self.flags & SHF_EXECINSTR != 0 {
+            letters.push('X');
+        }
+        letters
+    }
+}
+
+//A program header, describing a segment of the file that is mapped into memory
+pub struct Segment {
+    pub kind: u32,
+    pub flags: u32,
+    pub offset: usize,
+    pub addr: usize,
+    pub file_size: usize,
+    pub mem_size: usize,
+    pub align: usize,
+}
+
+impl Segment {
+    pub fn kind_name(&self) -> String {
+        match self.kind {
+            0 => "NULL".to_string(),
+            1 => "LOAD".to_string(),
+            2 => "DYNAMIC".to_string(),
+            3 => "INTERP".to_string(),
+            4 => "NOTE".to_string(),
+            6 => "PHDR".to_string(),
+            7 => "TLS".to_string(),
+            0x6474_e550 => "GNU_EH_FRAME".to_string(),
+            0x6474_e551 => "GNU_STACK".to_string(),
+            0x6474_e552 => "GNU_RELRO".to_string(),
+            0x6474_e553 => "GNU_PROPERTY".to_string(),
+            kind => format!("{:#x}", kind),
+        }
+    }
+
+    //The segment's permissions, e.g. "r-x"
+    pub fn permissions(&self) -> String {
+        format!(
+            "{}{}{}",
+            if self.flags & PF_R != 0 { 'r' } else { '-' },
+            if self.flags & PF_W != 0 { 'w' } else { '-' },
+            if self.flags & PF_X != 0 { 'x' } else { '-' },
+        )
+    }
+}
+
+//The headers of an ELF file: where its sections and segments are, and where it starts running
+pub struct ElfFile {
+    pub entry: usize,
+    //position independent executables are loaded at a different address each time they are run
+    pub pie: bool,
+    //a core file, which holds the memory of a process that died rather than a program
+    pub core: bool,
+    pub sections: Vec<Section>,
+    pub segments: Vec<Segment>,
+}
+
+impl ElfFile {
+    //Where in the file the byte loaded at `addr` is, or None if it isn't loaded from the file,
+    //e.g. it is in .bss
+    pub fn file_offset(&self, addr: usize) -> Option<usize> {
+        self.sections
+            .iter()
+            .find(|section| {
+                section.is_loaded()
+                    && section.kind != SHT_NOBITS
+                    && section.addr <= addr
+                    && addr - section.addr < section.size
+            })
+            .and_then(|section| section.offset.checked_add(addr - section.addr))
+    }
+}
+
+const ET_DYN: u16 = 3;
+pub const ET_CORE: u16 = 4;
+pub const PT_LOAD: u32 = 1;
+pub const PT_NOTE: u32 = 4;
+
+const SHT_SYMTAB: u32 = 2;
+const SHT_STRTAB: u32 = 3;
+const SHT_RELA: u32 = 4;
+const SHT_NOBITS: u32 = 8;
+const SHT_DYNSYM: u32 = 11;
+const SHF_WRITE: u64 = 1;
+const SHF_ALLOC: u64 = 2;
+const SHF_EXECINSTR: u64 = 4;
+const PF_X: u32 = 1;
+const PF_W: u32 = 2;
+const PF_R: u32 = 4;
+
+//The `size` bytes at `offset`, or None if the file is too short for them. Offsets and sizes are
+//read from the file, so they may be anything, and adding them up must not overflow.
+fn bytes_at(data: &[u8], offset: usize, size: usize) -> Option<&[u8]> {
+    data.get(offset..offset.checked_add(size)?)
+}
+
+pub fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes_at(data, offset, 2)?.try_into().ok()?))
+}
+
+pub fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes_at(data, offset, 4)?.try_into().ok()?))
+}
+
+pub fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(bytes_at(data, offset, 8)?.try_into().ok()?))
+}
+
+//The headers of a table of `count` headers, each `header_size` bytes long, at `table`
+fn headers(data: &[u8], table: usize, header_size: usize, count: usize) -> Option<Vec<&[u8]>> {
+    (0..count)
+        .map(|i| bytes_at(data, i.checked_mul(header_size)?.checked_add(table)?, header_size))
+        .collect()
+}
+
+//Read the ELF header, program headers and section headers of a file
+pub fn parse(data: &[u8]) -> Option<ElfFile> {
+    //only 64-bit little endian files, which is all the debugger runs on
+    if data.get(0..6)? != b"\x7fELF\x02\x01" {
+        return None;
+    }
+    let table = read_u64(data, 0x20)? as usize;
+    let header_size = read_u16(data, 0x36)? as usize;
+    let count = read_u16(data, 0x38)? as usize;
+    let mut segments = Vec::new();
+    for header in headers(data, table, header_size, count)? {
+        segments.push(Segment {
+            kind: read_u32(header, 0)?,
+            flags: read_u32(header, 4)?,
+            offset: read_u64(header, 8)? as usize,
+            addr: read_u64(header, 0x10)? as usize,
+            file_size: read_u64(header, 0x20)? as usize,
+            mem_size: read_u64(header, 0x28)? as usize,
+            align: read_u64(header, 0x30)? as usize,
+        });
+    }
+    Some(ElfFile {
+        entry: read_u64(data, 0x18)? as usize,
+        pie: read_u16(data, 0x10)? == ET_DYN,
+        core: read_u16(data, 0x10)? == ET_CORE,
+        sections: sections(data)?,
+        segments,
+    })
+}
+
+//Read the section headers, along with the name of each section
+fn sections(data: &[u8]) -> Option<Vec<Section>> {
+    let table = read_u64(data, 0x28)? as usize;
+    let header_size = read_u16(data, 0x3a)? as usize;
+    let count = read_u16(data, 0x3c)? as usize;
+    let names_index = read_u16(data, 0x3e)? as usize;
+    //core files have no sections
+    if count == 0 {
+        return Some(Vec::new());
+    }
+    let mut sections = Vec::new();
+    for header in headers(data, table, header_size, count)? {
+        sections.push((
+            read_u32(header, 0)? as usize,
+            Section {
+                name: String::new(),
+                kind: read_u32(header, 4)?,
+                flags: read_u64(header, 8)?,
+                addr: read_u64(header, 0x10)? as usize,
+                offset: read_u64(header, 0x18)? as usize,
+                size: read_u64(header, 0x20)? as usize,
+                link: read_u32(header, 0x28)? as usize,
+                entry_size: read_u64(header, 0x38)? as usize,
+            },
+        ));
+    }
+    let names = &sections.get(names_index)?.1;
+    let names = section_data(data, names)?;
+    Some(
+        sections
+            .into_iter()
+            .map(|(name, section)| Section {
+                name: read_string(names, name).unwrap_or_default(),
+                ..section
+            })
+            .collect(),
+    )
+}
+
+pub fn read_string(table: &[u8], offset: usize) -> Option<String> {
+    let bytes = table.get(offset..)?;
+    let end = bytes.iter().position(|b| *b == 0)?;
+    Some(String::from_utf8_lossy(&bytes[..end]).to_string())
+}
+
+//A note in a PT_NOTE segment, such as the registers of a thread in a core file
+pub struct Note<'a> {
+    pub kind: u32,
+    pub desc: &'a [u8],
+}
+
+//The notes in a PT_NOTE segment. Each is a header giving the sizes of its name and description
+//and its type, followed by the name and the description, each padded to 4 bytes.
+pub fn notes<'a>(data: &'a [u8], segment: &Segment) -> Vec<Note<'a>> {
+    let mut notes = Vec::new();
+    let segment_data = match bytes_at(data, segment.offset, segment.file_size) {
+        Some(segment_data) => segment_data,
+        None => return notes,
+    };
+    //the sizes are 32-bit, so padding them can't overflow
+    let pad = |size: u32| (size as usize + 3) & !3;
+    let mut at = 0;
+    while let Some(header) = bytes_at(segment_data, at, 12) {
+        let (name_size, desc_size, kind) = (
+            read_u32(header, 0).unwrap(),
+            read_u32(header, 4).unwrap(),
+            read_u32(header, 8).unwrap(),
+        );
+        let desc_start = match (at + 12).checked_add(pad(name_size)) {
+            Some(desc_start) => desc_start,
+            None => break,
+        };
+        let desc = match bytes_at(segment_data, desc_start, desc_size as usize) {
+            Some(desc) => desc,
+            None => break,
+        };
+        notes.push(Note { kind, desc });
+        at = match desc_start.checked_add(pad(desc_size)) {
+            Some(next) => next,
+            None => break,
+        };
+    }
+    notes
+}
+
+pub fn section_data<'a>(data: &'a [u8], section: &Section) -> Option<&'a [u8]> {
+    bytes_at(data, section.offset, section.size)
+}
+
+//Find a function or variable defined in a file by name, from its symbol table, or from the
+//dynamic symbol table of a stripped shared library. Returns its address as the file was linked.
+pub fn symbol_value(data: &[u8], name: &str) -> Option<usize> {
+    let sections = parse(data)?.sections;
+    let tables = sections
+        .iter()
+        .filter(|s| s.kind == SHT_SYMTAB)
+        .chain(sections.iter().filter(|s| s.kind == SHT_DYNSYM));
+    for table in tables {
+        let strings = match sections.get(table.link) {
+            Some(strings) if strings.kind == SHT_STRTAB => section_data(data, strings),
+            _ => None,
+        };
+        let (symbols, strings) = match (section_data(data, table), strings) {
+            (Some(symbols), Some(strings)) => (symbols, strings),
+            _ => continue,
+        };
+        let symbol_size = table.entry_size.max(24);
+        for at in (0..symbols.len() / symbol_size).map(|i| i * symbol_size) {
+            //symbols in section 0 are undefined, i.e. defined in another file
+            let value = match (read_u16(symbols, at + 6), read_u64(symbols, at + 8)) {
+                (Some(section), Some(value)) if section != 0 && value != 0 => value,
+                _ => continue,
+            };
+            if read_u32(symbols, at).and_then(|n| read_string(strings, n as usize)).as_deref() == Some(name) {
+                return Some(value as usize);
+            }
+        }
+    }
+    None
+}
+
+//Find the PLT stubs through which the program calls functions in shared libraries, such as
+//`__cxa_throw` or `malloc`. Returns the address of each stub mapped to the function's name.
+//
+//Each stub jumps through a slot in the GOT, and the .rela.plt relocations say which function each
+//GOT slot is for. The stubs are in .plt.sec when the program is built with CET, and in .plt
+//otherwise.
+pub fn plt_entries(data: &[u8]) -> HashMap<usize, String> {
+    let mut entries = HashMap::new();
+    let sections = match parse(data) {
+        Some(elf) => elf.sections,
+        None => return entries,
+    };
+    let find = |name: &str| sections.iter().find(|s| s.name == name);
+    let (relocations, stubs) = match (
+        find(".rela.plt"),
+        find(".plt.sec").or_else(|| find(".plt")),
+    ) {
+        (Some(relocations), Some(stubs)) if relocations.kind == SHT_RELA => (relocations, stubs),
+        _ => return entries,
+    };
+    let symbols = match sections.get(relocations.link) {
+        Some(symbols) if symbols.kind == SHT_DYNSYM || symbols.kind == SHT_SYMTAB => symbols,
+        _ => return entries,
+    };
+    let strings = match sections.get(symbols.link) {
+        Some(strings) if strings.kind == SHT_STRTAB => section_data(data, strings),
+        _ => None,
+    };
+    let (relocation_data, symbol_data, stub_data, strings) = match (
+        section_data(data, relocations),
+        section_data(data, symbols),
+        section_data(data, stubs),
+        strings,
+    ) {
+        (Some(r), Some(s), Some(p), Some(t)) => (r, s, p, t),
+        _ => return entries,
+    };
+
+    //the name of the function each GOT slot is for
+    let mut slots = HashMap::new();
+    let relocation_size = relocations.entry_size.max(24);
+    for at in (0..relocation_data.len() / relocation_size).map(|i| i * relocation_size) {
+        let (slot, info) = match (read_u64(relocation_data, at), read_u64(relocation_data, at + 8)) {
+            (Some(slot), Some(info)) => (slot as usize, info),
+            _ => break,
+        };
+        let symbol = ((info >> 32) as usize).checked_mul(symbols.entry_size.max(24));
+        if let Some(name) = symbol
+            .and_then(|symbol| read_u32(symbol_data, symbol))
+            .and_then(|n| read_string(strings, n as usize))
+        {
+            slots.insert(slot, name);
+        }
+    }
+
+    //each 16 byte stub contains a `jmp *slot(%rip)`, encoded as ff 25 followed by the offset of
+    //the slot from the end of the instruction
+    for start in (0..stub_data.len()).step_by(16) {
+        let stub = &stub_data[start..stub_data.len().min(start + 16)];
+        let jump = match stub.windows(2).position(|w| w == [0xff, 0x25]) {
+            Some(jump) => jump,
+            None => continue,
+        };
+        let offset = match stub.get(jump + 2..jump + 6) {
+            Some(offset) => i32::from_le_bytes(offset.try_into().unwrap()) as isize,
+            None => continue,
+        };
+        //the address comes from the file, so it may be anything
+        let stub_addr = match stubs.addr.checked_add(start) {
+            Some(stub_addr) => stub_addr,
+            None => break,
+        };
+        let next_instruction = stub_addr.wrapping_add(jump + 6);
+        let slot = next_instruction.wrapping_add(offset as usize);
+        if let Some(name) = slots.get(&slot) {
+            entries.insert(stub_addr, name.clone());
+        }
+    }
+    entries
+}
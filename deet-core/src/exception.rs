@@ -0,0 +1,147 @@
+use crate::inferior::Inferior;
+use crate::value_format::read_uint;
+
+//Functions in the C++ runtime that are called when an exception is thrown and when it is caught
+pub const THROW_FUNCTION: &str = "__cxa_throw";
+pub const CATCH_FUNCTION: &str = "__cxa_begin_catch";
+
+//Each exception starts with a __cxa_exception header, which libstdc++ puts right before the
+//_Unwind_Exception passed to __cxa_begin_catch. The header starts with the exception's
+//std::type_info, 80 bytes before the _Unwind_Exception on x86_64.
+const UNWIND_HEADER_OFFSET: usize = 80;
+
+//_Unwind_Exception starts with the class of the exception, which says which language threw it.
+//The class is "GNUCC++" followed by 0, or 1 for an exception rethrown with std::rethrow_exception,
+//packed into an integer starting from the most significant byte.
+const GNU_CXX_EXCEPTION_CLASS: u64 = 0x474e_5543_432b_2b00;
+
+//The type of the exception being thrown or caught, read from the arguments of __cxa_throw or
+//__cxa_begin_catch when the child is stopped on the first instruction of either
+pub fn exception_type(inferior: &Inferior, function: &str) -> Option<String> {
+    let registers = inferior.get_registers().ok()?;
+    let type_info = if function == THROW_FUNCTION {
+        //__cxa_throw(void *object, std::type_info *type, void (*destructor)(void *))
+        registers.rsi as usize
+    } else {
+        //__cxa_begin_catch(_Unwind_Exception *exception)
+        let unwind_header = registers.rdi as usize;
+        if read_uint(&inferior.read_mem(unwind_header, 8).ok()?) & !1 != GNU_CXX_EXCEPTION_CLASS {
+            return None;
+        }
+        read_uint(&inferior.read_mem(unwind_header - UNWIND_HEADER_OFFSET, 8).ok()?) as usize
+    };
+    //std::type_info is a vtable pointer followed by the mangled name of the type
+    let name = read_uint(&inferior.read_mem(type_info + 8, 8).ok()?) as usize;
+    let mut mangled = Vec::new();
+    loop {
+        let byte = *inferior.read_mem(name + mangled.len(), 1).ok()?.first()?;
+        if byte == 0 || mangled.len() > 1024 {
+            break;
+        }
+        mangled.push(byte);
+    }
+    let mangled = String::from_utf8_lossy(&mangled).to_string();
+    Some(demangle_type(&mangled).unwrap_or(mangled))
+}
+
+//Demangle a type name as mangled by the Itanium C++ ABI, e.g. `St13runtime_error` or `PKc`. Only
+//the forms a thrown type usually takes are understood; None is returned for anything else.
+fn demangle_type(mangled: &str) -> Option<String> {
+    let mut demangler = Demangler {
+        text: mangled.as_bytes(),
+        pos: 0,
+    };
+    let name = demangler.parse_type()?;
+    if demangler.pos == mangled.len() {
+        Some(name)
+    } else {
+        None
+    }
+}
+
+struct Demangler<'a> {
+    text: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Demangler<'a> {
+    fn next(&mut self) -> Option<u8> {
+        let c = *self.text.get(self.pos)?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    fn parse_type(&mut self) -> Option<String> {
+        let builtin = match self.next()? {
+            b'v' => "void",
+            b'b' => "bool",
+            b'c' => "char",
+            b'a' => "signed char",
+            b'h' => "unsigned char",
+            b's' => "short",
+            b't' => "unsigned short",
+            b'i' => "int",
+            b'j' => "unsigned int",
+            b'l' => "long",
+            b'm' => "unsigned long",
+            b'x' => "long long",
+            b'y' => "unsigned long long",
+            b'f' => "float",
+            b'd' => "double",
+            b'e' => "long double",
+            b'w' => "wchar_t",
+            b'P' => return Some(format!("{}*", self.parse_type()?)),
+            b'R' => return Some(format!("{}&", self.parse_type()?)),
+            b'K' => return Some(format!("{} const", self.parse_type()?)),
+            b'V' => return Some(format!("{} volatile", self.parse_type()?)),
+            b'N' => return self.parse_nested_name(),
+            b'S' => return self.parse_std_name(),
+            b'0'..=b'9' => {
+                self.pos -= 1;
+                return self.parse_source_name();
+            }
+            _ => return None,
+        };
+        Some(builtin.to_string())
+    }
+
+    //`St` followed by a name in std, or one of the abbreviations for common std types
+    fn parse_std_name(&mut self) -> Option<String> {
+        Some(match self.next()? {
+            b't' => format!("std::{}", self.parse_source_name()?),
+            b's' => "std::string".to_string(),
+            b'a' => "std::allocator".to_string(),
+            b'b' => "std::basic_string".to_string(),
+            b'i' => "std::istream".to_string(),
+            b'o' => "std::ostream".to_string(),
+            b'd' => "std::iostream".to_string(),
+            _ => return None,
+        })
+    }
+
+    //`N` followed by the parts of a qualified name, ending with `E`
+    fn parse_nested_name(&mut self) -> Option<String> {
+        let mut parts = Vec::new();
+        if self.text.get(self.pos..self.pos + 2) == Some(b"St") {
+            self.pos += 2;
+            parts.push("std".to_string());
+        }
+        while self.text.get(self.pos) != Some(&b'E') {
+            parts.push(self.parse_source_name()?);
+        }
+        self.pos += 1;
+        Some(parts.join("::"))
+    }
+
+    //a name preceded by its length, e.g. `5Error`
+    fn parse_source_name(&mut self) -> Option<String> {
+        let start = self.pos;
+        while self.text.get(self.pos).is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let len: usize = std::str::from_utf8(&self.text[start..self.pos]).ok()?.parse().ok()?;
+        let name = self.text.get(self.pos..self.pos + len)?;
+        self.pos += len;
+        Some(String::from_utf8_lossy(name).to_string())
+    }
+}
@@ -0,0 +1,1260 @@
+use crate::dwarf_data::{DwarfData, Encoding, Member, Type, TypeKind};
+use crate::inferior::{register_field, Frame, Inferior};
+use crate::value_format::{member_bytes, read_float, read_int, read_uint};
+
+//A C-like expression typed at the prompt
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Int(i64),
+    Float(f64),
+    Char(u8),
+    Str(Vec<u8>),
+    Variable(String),
+    //a register such as $rip
+    Register(String),
+    Unary(&'static str, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    //`a.b`
+    Member(Box<Expr>, String),
+    //`a[i]`
+    Index(Box<Expr>, Box<Expr>),
+    //`(type)value`
+    Cast(TypeName, Box<Expr>),
+    //`lhs = rhs`, which `set` and `print` carry out
+    Assign(Box<Expr>, Box<Expr>),
+}
+
+//A type written in an expression, such as `struct foo *` or `char (*)[16]`
+#[derive(Debug, Clone)]
+pub struct TypeName {
+    //the type the declarator starts from, such as `int` or `struct foo`
+    base: String,
+    //pointers and arrays wrapped around the base type, innermost first
+    derived: Vec<Derived>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Derived {
+    Pointer,
+    Array(usize),
+}
+
+//Words that can make up the name of a C base type
+const TYPE_SPECIFIERS: [&str; 11] = [
+    "void", "char", "short", "int", "long", "float", "double", "signed", "unsigned", "_Bool", "bool",
+];
+
+//Look up a base type spelled with C keywords, such as `unsigned long`. Returns the name gcc gives
+//the type in debug info, its size and its encoding.
+fn base_type_info(words: &[&str]) -> Option<(&'static str, usize, Encoding)> {
+    let count = |word: &str| words.iter().filter(|w| **w == word).count();
+    let unsigned = count("unsigned") > 0;
+    let (name, size, encoding) = if count("char") > 0 {
+        match (unsigned, count("signed") > 0) {
+            (true, _) => ("unsigned char", 1, Encoding::UnsignedChar),
+            (false, true) => ("signed char", 1, Encoding::SignedChar),
+            (false, false) => ("char", 1, Encoding::SignedChar),
+        }
+    } else if count("_Bool") > 0 || count("bool") > 0 {
+        ("_Bool", 1, Encoding::Boolean)
+    } else if count("float") > 0 {
+        ("float", 4, Encoding::Float)
+    } else if count("double") > 0 {
+        if count("long") > 0 {
+            ("long double", 16, Encoding::Float)
+        } else {
+            ("double", 8, Encoding::Float)
+        }
+    } else if count("short") > 0 {
+        if unsigned {
+            ("short unsigned int", 2, Encoding::Unsigned)
+        } else {
+            ("short int", 2, Encoding::Signed)
+        }
+    } else if count("long") >= 2 {
+        if unsigned {
+            ("long long unsigned int", 8, Encoding::Unsigned)
+        } else {
+            ("long long int", 8, Encoding::Signed)
+        }
+    } else if count("long") == 1 {
+        if unsigned {
+            ("long unsigned int", 8, Encoding::Unsigned)
+        } else {
+            ("long int", 8, Encoding::Signed)
+        }
+    } else if count("int") > 0 || count("signed") > 0 || unsigned {
+        if unsigned {
+            ("unsigned int", 4, Encoding::Unsigned)
+        } else {
+            ("int", 4, Encoding::Signed)
+        }
+    } else {
+        return None;
+    };
+    Some((name, size, encoding))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Int(i64),
+    Float(f64),
+    Char(u8),
+    Str(Vec<u8>),
+    Ident(String),
+    Register(String),
+    Punct(&'static str),
+}
+
+//Longer operators come first so that e.g. `<<` isn't read as two `<`
+const PUNCTUATION: [&str; 32] = [
+    "->", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "+", "-", "*", "/", "%", "&", "|", "^",
+    "!", "~", "<", ">", "(", ")", "[", "]", "{", "}", ".", ",", "@", "=", "?",
+];
+
+//Binary operators from the loosest to the tightest binding
+const BINARY_PRECEDENCE: [&[&str]; 11] = [
+    &["||"],
+    &["&&"],
+    &["|"],
+    &["^"],
+    &["&"],
+    &["==", "!="],
+    &["<", ">", "<=", ">="],
+    &["<<", ">>"],
+    //`a@n`, the array of n values starting with a
+    &["@"],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+
+//Split an expression into tokens. Also returns the index of the character each token starts at.
+fn tokenize(text: &str) -> Result<(Vec<Token>, Vec<usize>), String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut starts = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let token_start = i;
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(|c| c.is_ascii_digit())) {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.') {
+                //exponents may be signed, as in 1e-5
+                if (chars[i] == 'e' || chars[i] == 'E')
+                    && !chars[start..i].iter().any(|c| *c == 'x' || *c == 'X')
+                    && (chars.get(i + 1) == Some(&'-') || chars.get(i + 1) == Some(&'+'))
+                {
+                    i += 1;
+                }
+                i += 1;
+            }
+            tokens.push(parse_number(&chars[start..i].iter().collect::<String>())?);
+        } else if c.is_alphabetic() || c == '_' || c == '$' {
+            let start = i;
+            i += 1;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            if c == '$' {
+                tokens.push(Token::Register(word[1..].to_string()));
+            } else {
+                tokens.push(Token::Ident(word));
+            }
+        } else if c == '\'' {
+            let (bytes, end) = parse_quoted(&chars, i, '\'')?;
+            if bytes.len() != 1 {
+                return Err("Invalid character constant.".to_string());
+            }
+            tokens.push(Token::Char(bytes[0]));
+            i = end;
+        } else if c == '"' {
+            let (bytes, end) = parse_quoted(&chars, i, '"')?;
+            tokens.push(Token::Str(bytes));
+            i = end;
+        } else {
+            let rest: String = chars[i..].iter().take(2).collect();
+            match PUNCTUATION.iter().find(|p| rest.starts_with(*p)) {
+                Some(p) => {
+                    tokens.push(Token::Punct(p));
+                    i += p.len();
+                }
+                None => return Err(format!("Invalid character '{}' in expression.", c)),
+            }
+        }
+        if tokens.len() > starts.len() {
+            starts.push(token_start);
+        }
+    }
+    Ok((tokens, starts))
+}
+
+fn parse_number(text: &str) -> Result<Token, String> {
+    let invalid = || format!("Invalid number \"{}\".", text);
+    let lower = text.to_lowercase();
+    let is_hex = lower.starts_with("0x");
+    if !is_hex && (lower.contains('.') || lower.contains('e')) {
+        let digits = lower.trim_end_matches(['f', 'l']);
+        return digits.parse::<f64>().map(Token::Float).map_err(|_| invalid());
+    }
+    //integer suffixes don't change how we evaluate
+    let digits = lower.trim_end_matches(['u', 'l']);
+    let value = if is_hex {
+        u64::from_str_radix(&digits[2..], 16)
+    } else if let Some(binary) = digits.strip_prefix("0b") {
+        u64::from_str_radix(binary, 2)
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        u64::from_str_radix(&digits[1..], 8)
+    } else {
+        digits.parse::<u64>()
+    };
+    value.map(|v| Token::Int(v as i64)).map_err(|_| invalid())
+}
+
+//Parse a quoted character or string literal starting at `start`, handling C escape sequences.
+//Returns the bytes of the literal and the index just past the closing quote.
+fn parse_quoted(chars: &[char], start: usize, quote: char) -> Result<(Vec<u8>, usize), String> {
+    let mut bytes = Vec::new();
+    let mut i = start + 1;
+    while i < chars.len() && chars[i] != quote {
+        parse_char(chars, &mut i, &mut bytes)?;
+    }
+    if i >= chars.len() {
+        return Err("Unterminated string in expression.".to_string());
+    }
+    Ok((bytes, i + 1))
+}
+
+//Replace the C escape sequences in `text` with the bytes they stand for
+pub fn unescape(text: &str) -> Result<Vec<u8>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut bytes = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        parse_char(&chars, &mut i, &mut bytes)?;
+    }
+    Ok(bytes)
+}
+
+//Append the bytes of the (possibly escaped) character at `i` to `bytes` and move `i` past it
+fn parse_char(chars: &[char], i: &mut usize, bytes: &mut Vec<u8>) -> Result<(), String> {
+    if chars[*i] != '\\' {
+        let mut buf = [0; 4];
+        bytes.extend_from_slice(chars[*i].encode_utf8(&mut buf).as_bytes());
+        *i += 1;
+        return Ok(());
+    }
+    *i += 1;
+    let escaped = *chars.get(*i).ok_or("Unterminated escape sequence.")?;
+    *i += 1;
+    let byte = match escaped {
+        'n' => b'\n',
+        't' => b'\t',
+        'r' => b'\r',
+        'a' => 0x07,
+        'b' => 0x08,
+        'f' => 0x0c,
+        'v' => 0x0b,
+        'e' => 0x1b,
+        'x' => {
+            let digits: String = chars[*i..]
+                .iter()
+                .take_while(|c| c.is_ascii_hexdigit())
+                .take(2)
+                .collect();
+            *i += digits.len();
+            u8::from_str_radix(&digits, 16).map_err(|_| "\\x used with no following hex digits.")?
+        }
+        '0'..='7' => {
+            let digits: String = chars[*i - 1..]
+                .iter()
+                .take_while(|c| ('0'..='7').contains(*c))
+                .take(3)
+                .collect();
+            *i += digits.len() - 1;
+            u32::from_str_radix(&digits, 8).unwrap() as u8
+        }
+        other => other as u8,
+    };
+    bytes.push(byte);
+    Ok(())
+}
+
+struct Parser<'a> {
+    //used to tell typedef names apart from variables
+    debug_data: &'a DwarfData,
+    //the expression being parsed, for error messages
+    text: Vec<char>,
+    tokens: Vec<Token>,
+    starts: Vec<usize>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    //consume the punctuation `p` if it is next
+    fn eat(&mut self, p: &str) -> bool {
+        if let Some(Token::Punct(next)) = self.peek() {
+            if *next == p {
+                self.pos += 1;
+                return true;
+            }
+        }
+        false
+    }
+
+    fn expect(&mut self, p: &str) -> Result<(), String> {
+        if self.eat(p) {
+            Ok(())
+        } else {
+            Err(format!("Expected '{}' in expression.", p))
+        }
+    }
+
+    //Describe a syntax error at the current token, quoting the rest of the expression like gdb
+    fn syntax_error(&self) -> String {
+        let rest: String = match self.starts.get(self.pos) {
+            Some(start) => self.text[*start..].iter().collect(),
+            None => String::new(),
+        };
+        format!("A syntax error in expression, near `{}'.", rest)
+    }
+
+    fn parse_expr(&mut self) -> Result<Expr, String> {
+        let lhs = self.parse_binary(0)?;
+        if self.eat("=") {
+            let rhs = self.parse_expr()?;
+            return Ok(Expr::Assign(Box::new(lhs), Box::new(rhs)));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_binary(&mut self, level: usize) -> Result<Expr, String> {
+        if level == BINARY_PRECEDENCE.len() {
+            return self.parse_unary();
+        }
+        let mut lhs = self.parse_binary(level + 1)?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Punct(p)) if BINARY_PRECEDENCE[level].contains(p) => *p,
+                _ => return Ok(lhs),
+            };
+            self.pos += 1;
+            let rhs = self.parse_binary(level + 1)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, String> {
+        if self.eat("(") {
+            if self.at_type_name() {
+                let type_name = self.parse_type_name()?;
+                self.expect(")")?;
+                let operand = self.parse_unary()?;
+                return Ok(Expr::Cast(type_name, Box::new(operand)));
+            }
+            self.pos -= 1;
+        }
+        //`{type} addr` is the object of that type at an address, like `*(type *) addr`
+        if self.eat("{") {
+            let mut type_name = self.parse_type_name()?;
+            self.expect("}")?;
+            let operand = self.parse_unary()?;
+            type_name.derived.push(Derived::Pointer);
+            return Ok(Expr::Unary("*", Box::new(Expr::Cast(type_name, Box::new(operand)))));
+        }
+        for op in &["-", "+", "!", "~", "*", "&"] {
+            if self.eat(op) {
+                let operand = self.parse_unary()?;
+                return Ok(Expr::Unary(op, Box::new(operand)));
+            }
+        }
+        self.parse_postfix()
+    }
+
+    fn parse_postfix(&mut self) -> Result<Expr, String> {
+        let mut expr = self.parse_primary()?;
+        loop {
+            if self.eat(".") {
+                expr = Expr::Member(Box::new(expr), self.parse_ident()?);
+            } else if self.eat("->") {
+                expr = Expr::Member(Box::new(Expr::Unary("*", Box::new(expr))), self.parse_ident()?);
+            } else if self.eat("[") {
+                let index = self.parse_expr()?;
+                self.expect("]")?;
+                expr = Expr::Index(Box::new(expr), Box::new(index));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    //Whether the next token starts a type name, which after `(` means a cast
+    fn at_type_name(&self) -> bool {
+        match self.peek() {
+            Some(Token::Ident(word)) => {
+                TYPE_SPECIFIERS.contains(&word.as_str())
+                    || ["struct", "union", "enum", "const", "volatile"].contains(&word.as_str())
+                    || self
+                        .debug_data
+                        .get_type_by_name(word)
+                        .and_then(|offset| self.debug_data.get_type(Some(offset)))
+                        .is_some_and(|ty| matches!(ty.kind, TypeKind::Alias(_)))
+            }
+            _ => false,
+        }
+    }
+
+    //Parse a type name such as `unsigned long`, `struct foo *` or `char (*)[16]`
+    fn parse_type_name(&mut self) -> Result<TypeName, String> {
+        let mut words = Vec::new();
+        while let Some(Token::Ident(word)) = self.peek().cloned() {
+            self.pos += 1;
+            match word.as_str() {
+                "const" | "volatile" => {}
+                "struct" | "union" | "enum" => {
+                    let tag = self.parse_ident()?;
+                    words.push(format!("{} {}", word, tag));
+                    break;
+                }
+                _ if TYPE_SPECIFIERS.contains(&word.as_str()) => words.push(word),
+                _ if words.is_empty() => {
+                    words.push(word);
+                    break;
+                }
+                _ => {
+                    self.pos -= 1;
+                    break;
+                }
+            }
+        }
+        //qualifiers may also follow the base type, as in `char const *`
+        while let Some(Token::Ident(word)) = self.peek() {
+            if word != "const" && word != "volatile" {
+                break;
+            }
+            self.pos += 1;
+        }
+        let word_refs: Vec<&str> = words.iter().map(|w| w.as_str()).collect();
+        let base = match base_type_info(&word_refs) {
+            Some((name, _, _)) => name.to_string(),
+            None => words.join(" "),
+        };
+        let mut derived = Vec::new();
+        self.parse_abstract_declarator(&mut derived)?;
+        Ok(TypeName { base, derived })
+    }
+
+    //Parse the `*`s, `(...)`s and `[n]`s after a base type, appending what they wrap the base
+    //type in to `derived`. As in C, `char *[4]` is an array of pointers and `char (*)[4]` a
+    //pointer to an array.
+    fn parse_abstract_declarator(&mut self, derived: &mut Vec<Derived>) -> Result<(), String> {
+        while self.eat("*") {
+            derived.push(Derived::Pointer);
+            while let Some(Token::Ident(word)) = self.peek() {
+                if word != "const" && word != "volatile" && word != "restrict" {
+                    break;
+                }
+                self.pos += 1;
+            }
+        }
+        let mut inner = Vec::new();
+        if self.eat("(") {
+            self.parse_abstract_declarator(&mut inner)?;
+            self.expect(")")?;
+        }
+        let mut dims = Vec::new();
+        while self.eat("[") {
+            match self.next() {
+                Some(Token::Int(count)) if count >= 0 => dims.push(count as usize),
+                _ => return Err("Array size must be a constant.".to_string()),
+            }
+            self.expect("]")?;
+        }
+        //`T [2][3]` is an array of 2 arrays of 3 T, so the last dimension wraps the type first
+        derived.extend(dims.iter().rev().map(|count| Derived::Array(*count)));
+        derived.extend(inner);
+        Ok(())
+    }
+
+    fn parse_ident(&mut self) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Ident(name)) => Ok(name),
+            _ => Err("Expected a member name in expression.".to_string()),
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Int(v)) => Ok(Expr::Int(v)),
+            Some(Token::Float(v)) => Ok(Expr::Float(v)),
+            Some(Token::Char(c)) => Ok(Expr::Char(c)),
+            Some(Token::Str(s)) => Ok(Expr::Str(s)),
+            Some(Token::Ident(name)) => Ok(Expr::Variable(name)),
+            Some(Token::Register(name)) => Ok(Expr::Register(name)),
+            Some(Token::Punct("(")) => {
+                let expr = self.parse_expr()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            _ => {
+                self.pos -= 1;
+                Err(self.syntax_error())
+            }
+        }
+    }
+}
+
+//Parse a single expression
+pub fn parse(text: &str, debug_data: &DwarfData) -> Result<Expr, String> {
+    let mut exprs = parse_list(text, debug_data)?;
+    if exprs.len() != 1 {
+        return Err("A syntax error in expression, near `,'.".to_string());
+    }
+    Ok(exprs.remove(0))
+}
+
+//Parse a comma separated list of expressions, such as the arguments of printf
+pub fn parse_list(text: &str, debug_data: &DwarfData) -> Result<Vec<Expr>, String> {
+    let (tokens, starts) = tokenize(text)?;
+    let mut parser = Parser {
+        debug_data,
+        text: text.chars().collect(),
+        tokens,
+        starts,
+        pos: 0,
+    };
+    let mut exprs = vec![parser.parse_expr()?];
+    while parser.eat(",") {
+        exprs.push(parser.parse_expr()?);
+    }
+    if parser.peek().is_some() {
+        return Err(parser.syntax_error());
+    }
+    Ok(exprs)
+}
+
+//The result of evaluating an expression
+#[derive(Debug, Clone)]
+pub struct Value {
+    //offset of the value's type, see DwarfData::get_type
+    pub type_offset: usize,
+    pub bytes: Vec<u8>,
+    //where the value lives in the inferior's memory, if it does
+    pub address: Option<usize>,
+}
+
+//A number extracted from a value, for doing arithmetic
+#[derive(Debug, Clone, Copy)]
+pub enum Scalar {
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+}
+
+impl Scalar {
+    pub fn as_i64(self) -> i64 {
+        match self {
+            Scalar::Int(v) => v,
+            Scalar::UInt(v) => v as i64,
+            Scalar::Float(v) => v as i64,
+        }
+    }
+
+    pub fn as_u64(self) -> u64 {
+        self.as_i64() as u64
+    }
+
+    pub fn as_f64(self) -> f64 {
+        match self {
+            Scalar::Int(v) => v as f64,
+            Scalar::UInt(v) => v as f64,
+            Scalar::Float(v) => v,
+        }
+    }
+}
+
+impl Value {
+    pub fn ty<'a>(&self, debug_data: &'a DwarfData) -> &'a Type {
+        let ty = debug_data.get_type(Some(self.type_offset)).unwrap();
+        debug_data.resolve_type(ty)
+    }
+
+    //Interpret the value as a number. Pointers, enums, chars and bools count as integers.
+    pub fn scalar(&self, debug_data: &DwarfData) -> Result<Scalar, String> {
+        let ty = self.ty(debug_data);
+        let bytes = &self.bytes[..ty.size.min(self.bytes.len())];
+        match &ty.kind {
+            TypeKind::Base(Encoding::Float) => read_float(bytes)
+                .map(Scalar::Float)
+                .ok_or(format!("Cannot do arithmetic on values of type {}.", ty.name)),
+            TypeKind::Base(Encoding::Signed) | TypeKind::Base(Encoding::SignedChar) => {
+                Ok(Scalar::Int(read_int(bytes)))
+            }
+            TypeKind::Enum(enumerators) if enumerators.iter().any(|e| e.value < 0) => {
+                Ok(Scalar::Int(read_int(bytes)))
+            }
+            TypeKind::Base(_) | TypeKind::Enum(_) | TypeKind::Pointer(_) => {
+                Ok(Scalar::UInt(read_uint(bytes)))
+            }
+            _ => Err(format!("Cannot convert value of type {} to a number.", ty.name)),
+        }
+    }
+
+    pub fn is_pointer(&self, debug_data: &DwarfData) -> bool {
+        matches!(self.ty(debug_data).kind, TypeKind::Pointer(_))
+    }
+}
+
+//Evaluates expressions in the context of one frame of the stopped inferior
+pub struct Evaluator<'a> {
+    debug_data: &'a mut DwarfData,
+    //None when there is no process, in which case only constant expressions can be evaluated
+    inferior: Option<&'a Inferior>,
+    frame: Option<&'a Frame>,
+    //the values printed by `print`, which `$1`, `$2`... refer to, and `$` to the last one
+    history: &'a [Value],
+}
+
+impl<'a> Evaluator<'a> {
+    pub fn new(
+        debug_data: &'a mut DwarfData,
+        inferior: Option<&'a Inferior>,
+        frame: Option<&'a Frame>,
+        history: &'a [Value],
+    ) -> Evaluator<'a> {
+        Evaluator {
+            debug_data,
+            inferior,
+            frame,
+            history,
+        }
+    }
+
+    pub fn eval(&mut self, expr: &Expr) -> Result<Value, String> {
+        match expr {
+            Expr::Int(v) => {
+                if *v >= i32::MIN as i64 && *v <= i32::MAX as i64 {
+                    Ok(self.int_value("int", *v ))
+                } else {
+                    Ok(self.int_value("long", *v))
+                }
+            }
+            Expr::Float(v) => Ok(self.float_value(*v)),
+            Expr::Char(c) => Ok(Value {
+                type_offset: self.debug_data.base_type("char", 1, Encoding::SignedChar),
+                bytes: vec![*c],
+                address: None,
+            }),
+            Expr::Str(s) => {
+                let char_type = self.debug_data.base_type("char", 1, Encoding::SignedChar);
+                let mut bytes = s.clone();
+                bytes.push(0);
+                let type_offset = self.debug_data.intern_type(Type::new(
+                    format!("char [{}]", bytes.len()),
+                    bytes.len(),
+                    TypeKind::Array(Some(char_type), vec![Some(bytes.len())]),
+                ));
+                Ok(Value {
+                    type_offset,
+                    bytes,
+                    address: None,
+                })
+            }
+            Expr::Variable(name) => self.variable(name),
+            Expr::Register(name) => self.register(name),
+            Expr::Unary(op, operand) => {
+                let value = self.eval(operand)?;
+                self.unary(op, value)
+            }
+            Expr::Binary(op, lhs, rhs) => {
+                //&& and || only evaluate their right side when needed
+                if *op == "&&" || *op == "||" {
+                    let lhs = self.eval(lhs)?.scalar(self.debug_data)?.as_f64() != 0.0;
+                    let result = if *op == "&&" && !lhs {
+                        false
+                    } else if *op == "||" && lhs {
+                        true
+                    } else {
+                        self.eval(rhs)?.scalar(self.debug_data)?.as_f64() != 0.0
+                    };
+                    return Ok(self.int_value("int", result as i64));
+                }
+                let lhs = self.eval(lhs)?;
+                let rhs = self.eval(rhs)?;
+                if *op == "@" {
+                    return self.repeat(lhs, rhs);
+                }
+                self.binary(op, lhs, rhs)
+            }
+            Expr::Member(base, name) => {
+                let base = self.eval(base)?;
+                self.member(base, name)
+            }
+            Expr::Index(base, index) => {
+                let base = self.eval(base)?;
+                let index = self.eval(index)?;
+                self.index(base, index)
+            }
+            Expr::Cast(type_name, operand) => {
+                let target = self.lookup_type(type_name)?;
+                let value = self.eval(operand)?;
+                self.cast(value, target)
+            }
+            Expr::Assign(..) => Err("Assignments can only be made by `set` and `print`.".to_string()),
+        }
+    }
+
+    //Work out what `lhs = rhs` stores: the value of `rhs` converted to the type of `lhs`, with the
+    //address of `lhs`. Nothing is written to the inferior.
+    pub fn eval_assignment(&mut self, lhs: &Expr, rhs: &Expr) -> Result<Value, String> {
+        let target = self.eval(lhs)?;
+        let value = self.eval(rhs)?;
+        let address = target
+            .address
+            .ok_or("Left operand of assignment is not an lvalue.".to_string())?;
+        let mut value = self.cast(value, Some(target.type_offset))?;
+        value.type_offset = target.type_offset;
+        value.address = Some(address);
+        Ok(value)
+    }
+
+    //Make an integer value of a base type named with C keywords, such as "unsigned long"
+    pub fn int_value(&mut self, type_name: &str, v: i64) -> Value {
+        let words: Vec<&str> = type_name.split(' ').collect();
+        let (name, size, encoding) = base_type_info(&words).unwrap();
+        Value {
+            type_offset: self.debug_data.base_type(name, size, encoding),
+            bytes: v.to_le_bytes()[..size].to_vec(),
+            address: None,
+        }
+    }
+
+    //Find or make up the type a cast names
+    fn lookup_type(&mut self, type_name: &TypeName) -> Result<Option<usize>, String> {
+        let mut offset = if type_name.base == "void" {
+            None
+        } else {
+            let words: Vec<&str> = type_name.base.split(' ').collect();
+            match base_type_info(&words) {
+                Some((name, size, encoding)) => Some(self.debug_data.base_type(name, size, encoding)),
+                None => Some(
+                    self.debug_data
+                        .get_type_by_name(&type_name.base)
+                        .ok_or(format!("No symbol \"{}\" in current context.", type_name.base))?,
+                ),
+            }
+        };
+        for derived in &type_name.derived {
+            offset = match derived {
+                Derived::Pointer => Some(self.debug_data.pointer_type(offset)),
+                Derived::Array(count) => match offset {
+                    Some(elem) => Some(self.debug_data.array_type(elem, *count)),
+                    None => return Err("Cannot make an array of void.".to_string()),
+                },
+            };
+        }
+        Ok(offset)
+    }
+
+    //Convert a value to another type, as a C cast does. Numbers and pointers are converted;
+    //anything else is reinterpreted in place.
+    fn cast(&mut self, value: Value, target: Option<usize>) -> Result<Value, String> {
+        let target = match target {
+            Some(target) => target,
+            //casting to void discards the value
+            None => return Ok(self.int_value("int", 0)),
+        };
+        let ty = self.debug_data.resolve_type(self.debug_data.get_type(Some(target)).unwrap());
+        let size = ty.size;
+        let scalar = match (&ty.kind, &value.ty(self.debug_data).kind) {
+            //arrays decay to a pointer to their first element
+            (TypeKind::Base(_), TypeKind::Array(..)) | (TypeKind::Pointer(_), TypeKind::Array(..))
+                if value.address.is_some() =>
+            {
+                Scalar::UInt(value.address.unwrap() as u64)
+            }
+            (TypeKind::Base(_), _) | (TypeKind::Enum(_), _) | (TypeKind::Pointer(_), _) => {
+                value.scalar(self.debug_data).map_err(|_| "Invalid cast.".to_string())?
+            }
+            _ => {
+                //structs, unions and arrays are reinterpreted
+                return match value.address {
+                    Some(address) => self.read_value(target, address, size),
+                    None if value.bytes.len() >= size => Ok(Value {
+                        type_offset: target,
+                        bytes: value.bytes[..size].to_vec(),
+                        address: None,
+                    }),
+                    None => Err("Invalid cast.".to_string()),
+                };
+            }
+        };
+        let bytes = match ty.kind {
+            TypeKind::Base(Encoding::Float) => match size {
+                4 => (scalar.as_f64() as f32).to_le_bytes().to_vec(),
+                8 => scalar.as_f64().to_le_bytes().to_vec(),
+                _ => return Err(format!("Cannot convert to {}.", ty.name)),
+            },
+            TypeKind::Base(Encoding::Boolean) => vec![(scalar.as_f64() != 0.0) as u8],
+            _ => {
+                let v = match scalar {
+                    Scalar::Float(v) => v as i64,
+                    other => other.as_i64(),
+                };
+                v.to_le_bytes()[..size.min(8)].to_vec()
+            }
+        };
+        Ok(Value {
+            type_offset: target,
+            bytes,
+            address: None,
+        })
+    }
+
+    fn float_value(&mut self, v: f64) -> Value {
+        Value {
+            type_offset: self.debug_data.base_type("double", 8, Encoding::Float),
+            bytes: v.to_le_bytes().to_vec(),
+            address: None,
+        }
+    }
+
+    fn variable(&mut self, name: &str) -> Result<Value, String> {
+        let frame = self.frame.ok_or(format!("No symbol \"{}\" in current context.", name))?;
+        let local = self
+            .debug_data
+            .get_function_at(frame.pc)
+            .and_then(|func| func.variables.iter().find(|var| var.name == name));
+        let var = match local.or_else(|| self.debug_data.get_global_variable(name)) {
+            Some(var) => var,
+            None => return Err(format!("No symbol \"{}\" in current context.", name)),
+        };
+        let address = var.location.address(frame.cfa);
+        let type_offset = var.type_offset;
+        let size = var.entity_type.size;
+        self.read_value(type_offset, address, size)
+    }
+
+    //Read a value of the given type from the inferior's memory
+    pub fn read_value(&self, type_offset: usize, address: usize, size: usize) -> Result<Value, String> {
+        let bytes = self
+            .inferior
+            .ok_or(format!("Cannot access memory at address {:#x}", address))?
+            .read_mem(address, size)
+            .map_err(|_| format!("Cannot access memory at address {:#x}", address))?;
+        Ok(Value {
+            type_offset,
+            bytes,
+            address: Some(address),
+        })
+    }
+
+    fn register(&mut self, name: &str) -> Result<Value, String> {
+        if name.chars().all(|c| c.is_ascii_digit()) {
+            return self.history_value(name);
+        }
+        let frame = self.frame.ok_or("No registers.".to_string())?;
+        let mut regs = frame.regs;
+        let value = match (name, register_field(&mut regs, name)) {
+            ("rip", _) | ("pc", _) => frame.pc as u64,
+            (_, Some(value)) => *value,
+            (_, None) => return self.float_register(name),
+        };
+        match name {
+            "rip" | "pc" | "rsp" | "sp" | "rbp" | "fp" => Ok(Value {
+                type_offset: self.debug_data.pointer_type(None),
+                bytes: value.to_le_bytes().to_vec(),
+                address: None,
+            }),
+            _ => Ok(self.int_value("long", value as i64)),
+        }
+    }
+
+    //Read an x87, SSE or AVX register: st0 to st7, xmm0 to xmm15, ymm0 to ymm15, or a control
+    //register such as mxcsr
+    fn float_register(&mut self, name: &str) -> Result<Value, String> {
+        let invalid = format!("Invalid register ${}.", name);
+        let inferior = self.inferior.ok_or("No registers.".to_string())?;
+        let registers = inferior
+            .get_float_registers()
+            .map_err(|e| format!("Cannot read registers. Error: {}", e))?;
+        if let Some(value) = registers.control(name) {
+            return Ok(self.int_value("long", value as i64));
+        }
+        let number = |prefix: &str| name.strip_prefix(prefix).and_then(|n| n.parse::<usize>().ok());
+        let (type_offset, bytes) = if let Some(n) = number("st") {
+            let ty = self.debug_data.base_type("long double", 16, Encoding::Float);
+            (ty, registers.st(n).ok_or(invalid)?)
+        } else if let Some(n) = number("xmm") {
+            (self.debug_data.vector_register_type(16), registers.xmm(n).ok_or(invalid)?)
+        } else if let Some(n) = number("ymm") {
+            (self.debug_data.vector_register_type(32), registers.ymm(n).ok_or(invalid)?)
+        } else {
+            return Err(invalid);
+        };
+        Ok(Value {
+            type_offset,
+            bytes,
+            address: None,
+        })
+    }
+
+    //A value from the history, by its number, or the last one for an empty number
+    fn history_value(&self, number: &str) -> Result<Value, String> {
+        if self.history.is_empty() {
+            return Err("History is empty.".to_string());
+        }
+        let number = match number {
+            "" => self.history.len(),
+            number => number.parse().map_err(|_| format!("Invalid history number ${}.", number))?,
+        };
+        match number {
+            0 => Err("History numbers start at $1.".to_string()),
+            n if n > self.history.len() => Err(format!("History has not yet reached ${}.", n)),
+            n => Ok(self.history[n - 1].clone()),
+        }
+    }
+
+    fn unary(&mut self, op: &str, value: Value) -> Result<Value, String> {
+        match op {
+            "*" => self.deref(value),
+            "&" => match value.address {
+                Some(address) => Ok(Value {
+                    type_offset: self.debug_data.pointer_type(Some(value.type_offset)),
+                    bytes: (address as u64).to_le_bytes().to_vec(),
+                    address: None,
+                }),
+                None => Err("Attempt to take address of value not located in memory.".to_string()),
+            },
+            "!" => {
+                let v = value.scalar(self.debug_data)?;
+                Ok(self.int_value("int", (v.as_f64() == 0.0) as i64))
+            }
+            "+" => Ok(value),
+            _ => {
+                let zero = self.int_value("int", 0);
+                if op == "-" {
+                    self.binary("-", zero, value)
+                } else {
+                    match value.scalar(self.debug_data)? {
+                        Scalar::Float(_) => Err("Argument to complement operation not an integer.".to_string()),
+                        v => {
+                            let (type_name, _) = self.integer_result_type(&value, &zero);
+                            Ok(self.int_value(type_name, !v.as_i64()))
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    //Read what a pointer points to
+    pub fn deref(&mut self, value: Value) -> Result<Value, String> {
+        let value = self.decay(value);
+        let target = match value.ty(self.debug_data).kind {
+            TypeKind::Pointer(target) => target,
+            _ => return Err("Attempt to take contents of a non-pointer value.".to_string()),
+        };
+        let target = match target {
+            Some(target) => target,
+            None => return Err("Attempt to take contents of a non-pointer value.".to_string()),
+        };
+        let address = value.scalar(self.debug_data)?.as_u64() as usize;
+        let size = self.type_size(target);
+        self.read_value(target, address, size)
+    }
+
+    //The type of the elements of an array type. The elements of a multidimensional array are
+    //themselves arrays.
+    fn element_type(&mut self, array_type: usize) -> Option<usize> {
+        let ty = self.debug_data.get_type(Some(array_type))?;
+        let (elem, dims) = match &self.debug_data.resolve_type(ty).kind {
+            TypeKind::Array(elem, dims) => ((*elem)?, dims.clone()),
+            _ => return None,
+        };
+        let mut elem = elem;
+        for count in dims.iter().skip(1).rev() {
+            elem = self.debug_data.array_type(elem, count.unwrap_or(0));
+        }
+        Some(elem)
+    }
+
+    //Turn an array in memory into a pointer to its first element, as C does when an array is used
+    //in an expression. Other values are returned unchanged.
+    fn decay(&mut self, value: Value) -> Value {
+        let address = match value.address {
+            Some(address) => address,
+            None => return value,
+        };
+        match self.element_type(value.type_offset) {
+            Some(elem) => Value {
+                type_offset: self.debug_data.pointer_type(Some(elem)),
+                bytes: (address as u64).to_le_bytes().to_vec(),
+                address: None,
+            },
+            None => value,
+        }
+    }
+
+    //The size of what a pointer points to, for scaling pointer arithmetic. Like gcc, void and
+    //functions count as 1 byte.
+    fn pointee_size(&self, pointer: &Value) -> usize {
+        match pointer.ty(self.debug_data).kind {
+            TypeKind::Pointer(Some(target)) => match self.type_size(target) {
+                0 => 1,
+                size => size,
+            },
+            _ => 1,
+        }
+    }
+
+    //Evaluate `base[index]`
+    fn index(&mut self, base: Value, index: Value) -> Result<Value, String> {
+        let i = match index.scalar(self.debug_data)? {
+            Scalar::Float(_) => return Err("Array subscript is not an integer.".to_string()),
+            i => i.as_i64(),
+        };
+        //arrays that aren't in memory, such as string literals, are indexed directly
+        if base.address.is_none() {
+            if let Some(elem) = self.element_type(base.type_offset) {
+                let size = self.type_size(elem);
+                return match base.bytes.get(i as usize * size..(i as usize + 1) * size) {
+                    Some(bytes) if i >= 0 => Ok(Value {
+                        type_offset: elem,
+                        bytes: bytes.to_vec(),
+                        address: None,
+                    }),
+                    _ => Err("no such vector element".to_string()),
+                };
+            }
+        }
+        let base = self.decay(base);
+        if !base.is_pointer(self.debug_data) {
+            return Err(format!(
+                "cannot subscript something of type `{}'",
+                base.ty(self.debug_data).name
+            ));
+        }
+        let element = self.binary("+", base, index)?;
+        self.deref(element)
+    }
+
+    //Evaluate `first@count`, the array of `count` values of the same type as `first` that starts
+    //where it is in memory, such as `*ptr@10`. The elements are read all at once.
+    fn repeat(&mut self, first: Value, count: Value) -> Result<Value, String> {
+        let count = match count.scalar(self.debug_data)? {
+            Scalar::Float(_) => return Err("Invalid number of repetitions.".to_string()),
+            count => count.as_i64(),
+        };
+        let address = first
+            .address
+            .ok_or("Only values in memory can be extended with '@'.".to_string())?;
+        if count <= 0 {
+            return Err(format!("Invalid number {} of repetitions.", count));
+        }
+        let array_type = self.debug_data.array_type(first.type_offset, count as usize);
+        let size = self.type_size(array_type);
+        self.read_value(array_type, address, size)
+    }
+
+    //Evaluate `pointer + n`, `pointer - n` or `pointer - pointer`, scaling by the size of what
+    //the pointers point to
+    fn pointer_arithmetic(&mut self, op: &str, lhs: Value, rhs: Value) -> Result<Value, String> {
+        let lhs_is_pointer = lhs.is_pointer(self.debug_data);
+        let rhs_is_pointer = rhs.is_pointer(self.debug_data);
+        let l = lhs.scalar(self.debug_data)?;
+        let r = rhs.scalar(self.debug_data)?;
+        if let (Scalar::Float(_), _) | (_, Scalar::Float(_)) = (l, r) {
+            return Err("Argument to arithmetic operation not a number or boolean.".to_string());
+        }
+        match (op, lhs_is_pointer, rhs_is_pointer) {
+            ("-", true, true) => {
+                let size = self.pointee_size(&lhs);
+                if size != self.pointee_size(&rhs) {
+                    return Err("Cannot subtract pointers to types of different sizes.".to_string());
+                }
+                let difference = (l.as_i64().wrapping_sub(r.as_i64())) / size as i64;
+                Ok(self.int_value("long", difference))
+            }
+            ("+", true, false) | ("-", true, false) | ("+", false, true) => {
+                let (pointer, pointer_value, offset) = if lhs_is_pointer {
+                    (lhs, l.as_u64(), r.as_i64())
+                } else {
+                    (rhs, r.as_u64(), l.as_i64())
+                };
+                let scaled = offset.wrapping_mul(self.pointee_size(&pointer) as i64);
+                let address = if op == "-" {
+                    pointer_value.wrapping_sub(scaled as u64)
+                } else {
+                    pointer_value.wrapping_add(scaled as u64)
+                };
+                Ok(Value {
+                    type_offset: pointer.type_offset,
+                    bytes: address.to_le_bytes().to_vec(),
+                    address: None,
+                })
+            }
+            _ => Err(format!("Invalid operation {} on pointers.", op)),
+        }
+    }
+
+    fn type_size(&self, type_offset: usize) -> usize {
+        match self.debug_data.get_type(Some(type_offset)) {
+            Some(ty) => self.debug_data.resolve_type(ty).size,
+            None => 0,
+        }
+    }
+
+    fn member(&mut self, base: Value, name: &str) -> Result<Value, String> {
+        let ty = base.ty(self.debug_data);
+        let members = match &ty.kind {
+            TypeKind::Struct(members) | TypeKind::Union(members) => members,
+            _ => {
+                return Err("Attempt to extract a component of a value that is not a structure.".to_string())
+            }
+        };
+        match find_member(self.debug_data, members, name) {
+            Some((member, offset)) => {
+                let member_type = self
+                    .debug_data
+                    .get_type(member.type_offset)
+                    .ok_or(format!("There is no member named {}.", name))?;
+                //Members of anonymous structs/unions are relative to the anonymous member
+                let base_bytes = &base.bytes[offset..];
+                let bytes = member_bytes(self.debug_data, &member, member_type, base_bytes)
+                    .ok_or(format!("Cannot read member {}.", name))?;
+                let address = match (base.address, member.bit_size) {
+                    (Some(address), None) => Some(address + offset + member.offset),
+                    _ => None,
+                };
+                Ok(Value {
+                    type_offset: member.type_offset.unwrap(),
+                    bytes,
+                    address,
+                })
+            }
+            None => Err(format!("There is no member named {}.", name)),
+        }
+    }
+
+    //Work out the type of an integer operation under C's usual arithmetic conversions (simplified
+    //to int and long). Returns the type name and whether it is unsigned.
+    fn integer_result_type(&self, lhs: &Value, rhs: &Value) -> (&'static str, bool) {
+        let lhs_ty = lhs.ty(self.debug_data);
+        let rhs_ty = rhs.ty(self.debug_data);
+        let is_unsigned = |ty: &Type| match ty.kind {
+            TypeKind::Base(Encoding::Unsigned) | TypeKind::Pointer(_) => ty.size >= 4,
+            _ => false,
+        };
+        let long = lhs_ty.size > 4 || rhs_ty.size > 4;
+        let unsigned = is_unsigned(lhs_ty) || is_unsigned(rhs_ty);
+        match (long, unsigned) {
+            (false, false) => ("int", false),
+            (false, true) => ("unsigned int", true),
+            (true, false) => ("long", false),
+            (true, true) => ("unsigned long", true),
+        }
+    }
+
+    fn binary(&mut self, op: &str, lhs: Value, rhs: Value) -> Result<Value, String> {
+        let is_comparison = ["==", "!=", "<", ">", "<=", ">="].contains(&op);
+        let lhs = self.decay(lhs);
+        let rhs = self.decay(rhs);
+        if (lhs.is_pointer(self.debug_data) || rhs.is_pointer(self.debug_data)) && !is_comparison {
+            return self.pointer_arithmetic(op, lhs, rhs);
+        }
+        let l = lhs.scalar(self.debug_data)?;
+        let r = rhs.scalar(self.debug_data)?;
+        if let (Scalar::Float(_), _) | (_, Scalar::Float(_)) = (l, r) {
+            let (l, r) = (l.as_f64(), r.as_f64());
+            let result = match op {
+                "+" => l + r,
+                "-" => l - r,
+                "*" => l * r,
+                "/" => l / r,
+                _ if is_comparison => {
+                    let truth = compare(op, l.partial_cmp(&r));
+                    return Ok(self.int_value("int", truth as i64));
+                }
+                _ => return Err(format!("Integer only operation {}.", op)),
+            };
+            return Ok(self.float_value(result));
+        }
+        let (type_name, unsigned) = self.integer_result_type(&lhs, &rhs);
+        if is_comparison {
+            let ordering = if unsigned {
+                l.as_u64().partial_cmp(&r.as_u64())
+            } else {
+                l.as_i64().partial_cmp(&r.as_i64())
+            };
+            return Ok(self.int_value("int", compare(op, ordering) as i64));
+        }
+        let (a, b) = (l.as_i64(), r.as_i64());
+        if (op == "/" || op == "%") && b == 0 {
+            return Err("Division by zero".to_string());
+        }
+        let result = match op {
+            "+" => a.wrapping_add(b),
+            "-" => a.wrapping_sub(b),
+            "*" => a.wrapping_mul(b),
+            "/" if unsigned => (a as u64 / b as u64) as i64,
+            "/" => a.wrapping_div(b),
+            "%" if unsigned => (a as u64 % b as u64) as i64,
+            "%" => a.wrapping_rem(b),
+            "&" => a & b,
+            "|" => a | b,
+            "^" => a ^ b,
+            "<<" => a.wrapping_shl(b as u32),
+            ">>" if unsigned => ((a as u64) >> (b as u32 & 63)) as i64,
+            ">>" => a.wrapping_shr(b as u32),
+            _ => return Err(format!("Unsupported operator {}.", op)),
+        };
+        Ok(self.int_value(type_name, result))
+    }
+}
+
+fn compare(op: &str, ordering: Option<std::cmp::Ordering>) -> bool {
+    use std::cmp::Ordering::*;
+    match (op, ordering) {
+        (_, None) => op == "!=",
+        ("==", Some(o)) => o == Equal,
+        ("!=", Some(o)) => o != Equal,
+        ("<", Some(o)) => o == Less,
+        (">", Some(o)) => o == Greater,
+        ("<=", Some(o)) => o != Greater,
+        (">=", Some(o)) => o != Less,
+        _ => false,
+    }
+}
+
+//Find a member by name, looking inside anonymous structs and unions too. Returns the member and
+//the offset of the (anonymous) aggregate containing it.
+fn find_member(debug_data: &DwarfData, members: &[Member], name: &str) -> Option<(Member, usize)> {
+    for member in members {
+        if member.name == name {
+            return Some((member.clone(), 0));
+        }
+        if member.name.is_empty() {
+            let ty = debug_data.resolve_type(debug_data.get_type(member.type_offset)?);
+            if let TypeKind::Struct(inner) | TypeKind::Union(inner) = &ty.kind {
+                if let Some((found, offset)) = find_member(debug_data, inner, name) {
+                    return Some((found, member.offset + offset));
+                }
+            }
+        }
+    }
+    None
+}
@@ -1,20 +1,25 @@
 
-use gimli;
 use gimli::{UnitOffset, UnitSectionOffset};
-use object::Object;
+use object::{Object, ObjectSection};
 use std::borrow;
 //use std::io::{BufWriter, Write};
-use crate::dwarf_data::{File, Function, Line, Location, Type, Variable};
+use crate::dwarf_data::{
+    Encoding, Enumerator, File, Function, Line, Location, Member, Type, TypeKind, Variable,
+};
 use std::collections::HashMap;
 use std::convert::TryInto;
 use std::fmt::Write;
 use std::{io, path};
 
-pub fn load_file(object: &object::File, endian: gimli::RunTimeEndian) -> Result<Vec<File>, Error> {
+pub fn load_file(
+    object: &object::File,
+    endian: gimli::RunTimeEndian,
+) -> Result<(Vec<File>, HashMap<usize, Type>), Error> {
     // Load a section and return as `Cow<[u8]>`.
     let load_section = |id: gimli::SectionId| -> Result<borrow::Cow<[u8]>, gimli::Error> {
         Ok(object
-            .section_data_by_name(id.name())
+            .section_by_name(id.name())
+            .and_then(|section| section.uncompressed_data().ok())
             .unwrap_or(borrow::Cow::Borrowed(&[][..])))
     };
     // Load a supplementary section.
@@ -28,7 +33,7 @@ pub fn load_file(object: &object::File, endian: gimli::RunTimeEndian) -> Result<
     let borrow_section: &dyn for<'a> Fn(
         &'a borrow::Cow<[u8]>,
     ) -> gimli::EndianSlice<'a, gimli::RunTimeEndian> =
-        &|section| gimli::EndianSlice::new(&*section, endian);
+        &|section| gimli::EndianSlice::new(section, endian);
 
     // Create `EndianSlice`s for all of the sections.
     let dwarf = dwarf_cow.borrow(&borrow_section);
@@ -43,12 +48,14 @@ pub fn load_file(object: &object::File, endian: gimli::RunTimeEndian) -> Result<
     while let Some(header) = iter.next()? {
         let unit = dwarf.unit(header)?;
 
+        // Collect the types first, since variables may refer to types declared after them
+        load_types(&unit, &dwarf, &mut offset_to_type)?;
+
         // Iterate over the Debugging Information Entries (DIEs) in the unit.
         let mut depth = 0;
         let mut entries = unit.entries();
         while let Some((delta_depth, entry)) = entries.next_dfs()? {
             depth += delta_depth;
-            // Update the offset_to_type mapping for types
             // Update the variable list for formal params/variables
             match entry.tag() {
                 gimli::DW_TAG_compile_unit => {
@@ -61,38 +68,20 @@ pub fn load_file(object: &object::File, endian: gimli::RunTimeEndian) -> Result<
                     } else {
                         "<unknown>".to_string()
                     };
+                    //the name is relative to the compilation directory unless it is absolute
+                    let mut path = path::PathBuf::new();
+                    if let Some(comp_dir) = &unit.comp_dir {
+                        path.push(comp_dir.to_string_lossy().as_ref());
+                    }
+                    path.push(&name);
                     compilation_units.push(File {
                         name,
+                        source_paths: vec![path.to_string_lossy().to_string()],
                         global_variables: Vec::new(),
                         functions: Vec::new(),
                         lines: Vec::new(),
                     });
                 }
-                gimli::DW_TAG_base_type => {
-                    let name = if let Ok(Some(attr)) = entry.attr(gimli::DW_AT_name) {
-                        if let Ok(DebugValue::Str(name)) = get_attr_value(&attr, &unit, &dwarf) {
-                            name
-                        } else {
-                            "<unknown>".to_string()
-                        }
-                    } else {
-                        "<unknown>".to_string()
-                    };
-                    let byte_size = if let Ok(Some(attr)) = entry.attr(gimli::DW_AT_byte_size) {
-                        if let Ok(DebugValue::Uint(byte_size)) =
-                            get_attr_value(&attr, &unit, &dwarf)
-                        {
-                            byte_size
-                        } else {
-                            0
-                        }
-                    } else {
-                        0
-                    };
-                    let type_offset = entry.offset().0;
-                    offset_to_type
-                        .insert(type_offset, Type::new(name, byte_size.try_into().unwrap()));
-                }
                 gimli::DW_TAG_subprogram => {
                     let mut func: Function = Default::default();
                     let mut attrs = entry.attrs();
@@ -121,6 +110,18 @@ pub fn load_file(object: &object::File, endian: gimli::RunTimeEndian) -> Result<
                                     func.line_number = line_number.try_into().unwrap();
                                 }
                             }
+                            gimli::DW_AT_type => {
+                                if let Ok(DebugValue::Size(offset)) = val {
+                                    func.return_type = Some(offset);
+                                }
+                            }
+                            gimli::DW_AT_decl_file => {
+                                //the value is the file's index followed by its path
+                                if let Ok(DebugValue::Str(file)) = val {
+                                    func.decl_file =
+                                        file.split_once(' ').map(|x| x.1).unwrap_or("").to_string();
+                                }
+                            }
                             _ => {}
                         }
                     }
@@ -129,6 +130,7 @@ pub fn load_file(object: &object::File, endian: gimli::RunTimeEndian) -> Result<
                 gimli::DW_TAG_formal_parameter | gimli::DW_TAG_variable => {
                     let mut name = String::new();
                     let mut entity_type: Option<Type> = None;
+                    let mut type_offset = 0;
                     let mut location: Option<Location> = None;
                     let mut line_number = 0;
                     let mut attrs = entry.attrs();
@@ -143,8 +145,9 @@ pub fn load_file(object: &object::File, endian: gimli::RunTimeEndian) -> Result<
                             }
                             gimli::DW_AT_type => {
                                 if let Ok(DebugValue::Size(offset)) = val {
-                                    if let Some(dtype) = offset_to_type.get(&offset).clone() {
+                                    if let Some(dtype) = offset_to_type.get(&offset) {
                                         entity_type = Some(dtype.clone());
+                                        type_offset = offset;
                                     }
                                 }
                             }
@@ -161,12 +164,14 @@ pub fn load_file(object: &object::File, endian: gimli::RunTimeEndian) -> Result<
                             _ => {}
                         }
                     }
-                    if entity_type.is_some() && location.is_some() {
+                    if let (Some(entity_type), Some(location)) = (entity_type, location) {
                         let var = Variable {
                             name,
-                            entity_type: entity_type.unwrap(),
-                            location: location.unwrap(),
+                            entity_type,
+                            type_offset,
+                            location,
                             line_number: line_number.try_into().unwrap(),
+                            is_parameter: entry.tag() == gimli::DW_TAG_formal_parameter,
                         };
                         if depth == 1 {
                             compilation_units
@@ -200,6 +205,9 @@ pub fn load_file(object: &object::File, endian: gimli::RunTimeEndian) -> Result<
                 if !row.end_sequence() {
                     // Determine the path. Real applications should cache this for performance.
                     let mut path = path::PathBuf::new();
+                    if let Some(comp_dir) = &unit.comp_dir {
+                        path.push(comp_dir.to_string_lossy().as_ref());
+                    }
                     if let Some(file) = row.file(header) {
                         if let Some(dir) = file.directory(header) {
                             path.push(dwarf.attr_string(&unit, dir)?.to_string_lossy().as_ref());
@@ -212,10 +220,17 @@ pub fn load_file(object: &object::File, endian: gimli::RunTimeEndian) -> Result<
                         );
                     }
 
-                    // Get the File
+                    // Get the File. Its name is the path the compiler was given, which is
+                    // relative to the compilation directory unless it was absolute
+                    let path_name = path.to_string_lossy();
+                    if let Some(file) = compilation_units.last_mut() {
+                        if !file.source_paths.iter().any(|p| *p == path_name) {
+                            file.source_paths.push(path_name.to_string());
+                        }
+                    }
                     let file = compilation_units
                         .iter_mut()
-                        .find(|f| f.name == path.as_os_str().to_str().unwrap());
+                        .find(|f| path.ends_with(&f.name));
 
                     // Determine line/column. DWARF line/column is never 0, so use that
                     // but other applications may want to display this differently.
@@ -232,47 +247,334 @@ pub fn load_file(object: &object::File, endian: gimli::RunTimeEndian) -> Result<
             }
         }
     }
-    Ok(compilation_units)
+    Ok((compilation_units, offset_to_type))
+}
+
+// Offset of a DIE relative to the start of .debug_info, which is what DW_AT_type refers to
+fn entry_offset<R: Reader>(entry: &gimli::DebuggingInformationEntry<R>, unit: &gimli::Unit<R>) -> usize {
+    match entry.offset().to_unit_section_offset(unit) {
+        UnitSectionOffset::DebugInfoOffset(goff) => goff.0,
+        UnitSectionOffset::DebugTypesOffset(goff) => goff.0,
+    }
+}
+
+fn get_name<R: Reader>(
+    entry: &gimli::DebuggingInformationEntry<R>,
+    unit: &gimli::Unit<R>,
+    dwarf: &gimli::Dwarf<R>,
+) -> Option<String> {
+    match get_attr_value(&entry.attr(gimli::DW_AT_name).ok()??, unit, dwarf) {
+        Ok(DebugValue::Str(name)) => Some(name),
+        _ => None,
+    }
+}
+
+fn get_type_ref<R: Reader>(
+    entry: &gimli::DebuggingInformationEntry<R>,
+    unit: &gimli::Unit<R>,
+    dwarf: &gimli::Dwarf<R>,
+) -> Option<usize> {
+    match get_attr_value(&entry.attr(gimli::DW_AT_type).ok()??, unit, dwarf) {
+        Ok(DebugValue::Size(offset)) => Some(offset),
+        _ => None,
+    }
+}
+
+fn get_udata<R: Reader>(
+    entry: &gimli::DebuggingInformationEntry<R>,
+    name: gimli::DwAt,
+) -> Option<usize> {
+    Some(entry.attr(name).ok()??.udata_value()? as usize)
+}
+
+// A bitfield described with the DWARF 2/3 DW_AT_bit_offset, which counts from the most significant
+// bit of a storage unit. It can only be converted once the size of the member's type is known.
+struct LegacyBitfield {
+    type_offset: usize,
+    member: usize,
+    bit_offset: usize,
+    storage_size: Option<usize>,
+}
+
+// Walk the DIEs of a unit and add every type found to `types`
+fn load_types<R: Reader>(
+    unit: &gimli::Unit<R>,
+    dwarf: &gimli::Dwarf<R>,
+    types: &mut HashMap<usize, Type>,
+) -> Result<(), Error> {
+    // Aggregate types whose children (members, enumerators, subranges) are being read, along
+    // with their depth
+    let mut parents: Vec<(isize, usize)> = Vec::new();
+    let mut legacy_bitfields: Vec<LegacyBitfield> = Vec::new();
+    let mut new_types: Vec<usize> = Vec::new();
+
+    let mut depth = 0;
+    let mut entries = unit.entries();
+    while let Some((delta_depth, entry)) = entries.next_dfs()? {
+        depth += delta_depth;
+        while let Some(&(parent_depth, _)) = parents.last() {
+            if parent_depth >= depth {
+                parents.pop();
+            } else {
+                break;
+            }
+        }
+        let offset = entry_offset(entry, unit);
+        let name = get_name(entry, unit, dwarf).unwrap_or_default();
+        let size = get_udata(entry, gimli::DW_AT_byte_size).unwrap_or(0);
+        let target = get_type_ref(entry, unit, dwarf);
+        let kind = match entry.tag() {
+            gimli::DW_TAG_base_type => {
+                let encoding = match entry.attr_value(gimli::DW_AT_encoding)? {
+                    Some(gimli::AttributeValue::Encoding(ate)) => match ate {
+                        gimli::DW_ATE_signed => Encoding::Signed,
+                        gimli::DW_ATE_unsigned => Encoding::Unsigned,
+                        gimli::DW_ATE_signed_char => Encoding::SignedChar,
+                        gimli::DW_ATE_unsigned_char => Encoding::UnsignedChar,
+                        gimli::DW_ATE_boolean => Encoding::Boolean,
+                        gimli::DW_ATE_float => Encoding::Float,
+                        gimli::DW_ATE_UTF => Encoding::Utf,
+                        gimli::DW_ATE_address => Encoding::Address,
+                        _ => Encoding::Unknown,
+                    },
+                    _ => Encoding::Unknown,
+                };
+                TypeKind::Base(encoding)
+            }
+            gimli::DW_TAG_pointer_type | gimli::DW_TAG_reference_type => TypeKind::Pointer(target),
+            gimli::DW_TAG_typedef
+            | gimli::DW_TAG_const_type
+            | gimli::DW_TAG_volatile_type
+            | gimli::DW_TAG_restrict_type => TypeKind::Alias(target),
+            gimli::DW_TAG_structure_type => TypeKind::Struct(Vec::new()),
+            gimli::DW_TAG_union_type => TypeKind::Union(Vec::new()),
+            gimli::DW_TAG_enumeration_type => TypeKind::Enum(Vec::new()),
+            gimli::DW_TAG_array_type => TypeKind::Array(target, Vec::new()),
+            gimli::DW_TAG_subroutine_type => TypeKind::Function,
+            gimli::DW_TAG_member => {
+                if let Some(&(_, parent)) = parents.last() {
+                    let member_location = match entry.attr_value(gimli::DW_AT_data_member_location)? {
+                        Some(gimli::AttributeValue::Exprloc(expr)) => {
+                            match gimli::Operation::parse(&mut expr.0.clone(), unit.encoding()) {
+                                Ok(gimli::Operation::PlusConstant { value }) => value as usize,
+                                _ => 0,
+                            }
+                        }
+                        Some(value) => value.udata_value().unwrap_or(0) as usize,
+                        None => 0,
+                    };
+                    let bit_size = get_udata(entry, gimli::DW_AT_bit_size);
+                    let mut member = Member {
+                        name,
+                        type_offset: target,
+                        offset: member_location,
+                        bit_size,
+                        bit_offset: member_location * 8,
+                    };
+                    if let Some(data_bit_offset) = get_udata(entry, gimli::DW_AT_data_bit_offset) {
+                        member.offset = data_bit_offset / 8;
+                        member.bit_offset = data_bit_offset;
+                    }
+                    if let Some(parent_type) = types.get_mut(&parent) {
+                        if let TypeKind::Struct(members) | TypeKind::Union(members) =
+                            &mut parent_type.kind
+                        {
+                            if let (Some(bit_offset), Some(_)) =
+                                (get_udata(entry, gimli::DW_AT_bit_offset), bit_size)
+                            {
+                                legacy_bitfields.push(LegacyBitfield {
+                                    type_offset: parent,
+                                    member: members.len(),
+                                    bit_offset,
+                                    storage_size: get_udata(entry, gimli::DW_AT_byte_size),
+                                });
+                            }
+                            members.push(member);
+                        }
+                    }
+                }
+                continue;
+            }
+            gimli::DW_TAG_enumerator => {
+                if let Some(&(_, parent)) = parents.last() {
+                    let value = match entry.attr_value(gimli::DW_AT_const_value)? {
+                        Some(gimli::AttributeValue::Sdata(value)) => value,
+                        Some(value) => value.udata_value().unwrap_or(0) as i64,
+                        None => 0,
+                    };
+                    if let Some(Type {
+                        kind: TypeKind::Enum(enumerators),
+                        ..
+                    }) = types.get_mut(&parent)
+                    {
+                        enumerators.push(Enumerator { name, value });
+                    }
+                }
+                continue;
+            }
+            gimli::DW_TAG_subrange_type => {
+                if let Some(&(_, parent)) = parents.last() {
+                    let count = match get_udata(entry, gimli::DW_AT_count) {
+                        Some(count) => Some(count),
+                        None => get_udata(entry, gimli::DW_AT_upper_bound).map(|upper| upper + 1),
+                    };
+                    if let Some(Type {
+                        kind: TypeKind::Array(_, dims),
+                        ..
+                    }) = types.get_mut(&parent)
+                    {
+                        dims.push(count);
+                    }
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        if let TypeKind::Struct(_) | TypeKind::Union(_) | TypeKind::Enum(_) | TypeKind::Array(..) =
+            kind
+        {
+            if entry.has_children() {
+                parents.push((depth, offset));
+            }
+        }
+        // Names of derived types are filled in once everything they refer to is known
+        let name = match (entry.tag(), name.is_empty()) {
+            (gimli::DW_TAG_structure_type, false) => format!("struct {}", name),
+            (gimli::DW_TAG_union_type, false) => format!("union {}", name),
+            (gimli::DW_TAG_enumeration_type, false) => format!("enum {}", name),
+            _ => name,
+        };
+        types.insert(offset, Type::new(name, size, kind));
+        new_types.push(offset);
+    }
+
+    for offset in &new_types {
+        let size = type_size(types, Some(*offset), 0);
+        let name = type_name(types, Some(*offset), 0);
+        let ty = types.get_mut(offset).unwrap();
+        ty.size = size;
+        ty.name = name;
+    }
+
+    for bitfield in legacy_bitfields {
+        let member_type = match &types[&bitfield.type_offset].kind {
+            TypeKind::Struct(members) | TypeKind::Union(members) => {
+                members[bitfield.member].type_offset
+            }
+            _ => continue,
+        };
+        let storage_size = bitfield
+            .storage_size
+            .unwrap_or_else(|| type_size(types, member_type, 0));
+        if let Some(Type {
+            kind: TypeKind::Struct(members),
+            ..
+        })
+        | Some(Type {
+            kind: TypeKind::Union(members),
+            ..
+        }) = types.get_mut(&bitfield.type_offset)
+        {
+            let member = &mut members[bitfield.member];
+            let bit_size = member.bit_size.unwrap_or(0);
+            // DW_AT_bit_offset counts from the most significant bit of the storage unit, which on a
+            // little endian machine is the last bit of the unit
+            member.bit_offset = (member.offset + storage_size) * 8 - bitfield.bit_offset - bit_size;
+        }
+    }
+    Ok(())
+}
+
+// Guards against malformed (cyclic) type chains
+const MAX_TYPE_DEPTH: usize = 32;
+
+fn type_size(types: &HashMap<usize, Type>, offset: Option<usize>, depth: usize) -> usize {
+    let ty = match offset.and_then(|offset| types.get(&offset)) {
+        Some(ty) if depth < MAX_TYPE_DEPTH => ty,
+        _ => return 0,
+    };
+    if ty.size != 0 {
+        return ty.size;
+    }
+    match &ty.kind {
+        TypeKind::Pointer(_) => std::mem::size_of::<usize>(),
+        TypeKind::Alias(target) => type_size(types, *target, depth + 1),
+        TypeKind::Array(elem, dims) => {
+            dims.iter().map(|count| count.unwrap_or(0)).product::<usize>()
+                * type_size(types, *elem, depth + 1)
+        }
+        _ => 0,
+    }
+}
+
+fn type_name(types: &HashMap<usize, Type>, offset: Option<usize>, depth: usize) -> String {
+    let ty = match offset.and_then(|offset| types.get(&offset)) {
+        Some(ty) if depth < MAX_TYPE_DEPTH => ty,
+        Some(_) => return "<unknown>".to_string(),
+        None => return "void".to_string(),
+    };
+    if !ty.name.is_empty() {
+        return ty.name.clone();
+    }
+    match &ty.kind {
+        TypeKind::Pointer(target) => {
+            let target_name = type_name(types, *target, depth + 1);
+            if target_name.ends_with('*') {
+                format!("{}*", target_name)
+            } else {
+                format!("{} *", target_name)
+            }
+        }
+        TypeKind::Alias(target) => type_name(types, *target, depth + 1),
+        TypeKind::Array(elem, dims) => {
+            let mut name = type_name(types, *elem, depth + 1);
+            name.push(' ');
+            for count in dims {
+                match count {
+                    Some(count) => name.push_str(&format!("[{}]", count)),
+                    None => name.push_str("[]"),
+                }
+            }
+            name
+        }
+        TypeKind::Struct(_) => "struct {...}".to_string(),
+        TypeKind::Union(_) => "union {...}".to_string(),
+        TypeKind::Enum(_) => "enum {...}".to_string(),
+        TypeKind::Function => "function".to_string(),
+        _ => "<unknown>".to_string(),
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum DebugValue {
     Str(String),
     Uint(u64),
-    Int(i64),
     Size(usize),
     NoVal,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Error {
-    GimliError(gimli::Error),
-    Addr2lineError(addr2line::gimli::Error),
-    ObjectError(String),
-    IoError,
+    Gimli(gimli::Error),
+    Object(String),
+    Io,
 }
 
 impl From<gimli::Error> for Error {
     fn from(err: gimli::Error) -> Self {
-        Error::GimliError(err)
-    }
-}
-
-impl From<addr2line::gimli::Error> for Error {
-    fn from(err: addr2line::gimli::Error) -> Self {
-        Error::Addr2lineError(err)
+        Error::Gimli(err)
     }
 }
 
 impl From<io::Error> for Error {
     fn from(_: io::Error) -> Self {
-        Error::IoError
+        Error::Io
     }
 }
 
 impl From<std::fmt::Error> for Error {
     fn from(_: std::fmt::Error) -> Self {
-        Error::IoError
+        Error::Io
     }
 }
 
@@ -335,7 +637,6 @@ fn get_attr_value<R: Reader>(
                 Ok(DebugValue::Str(format!("<.debug_str+0x{:08x}>", offset.0)))
             }
         }
-        gimli::AttributeValue::Sdata(data) => Ok(DebugValue::Int(data)),
         gimli::AttributeValue::Addr(data) => Ok(DebugValue::Uint(data)),
         gimli::AttributeValue::Udata(data) => Ok(DebugValue::Uint(data)),
 
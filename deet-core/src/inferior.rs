@@ -0,0 +1,2265 @@
+use crate::debugger::Breakpoint;
+use crate::core_file::{CoreFile, CoreThread, Snapshot};
+use crate::debugger_command::Redirections;
+use crate::dwarf_data::{DwarfData, Line, Variable};
+use crate::interrupt;
+use crate::remote::{RemoteTarget, Stop};
+use crate::settings::{FollowForkMode, Settings};
+use crate::signals::{self, SignalTable};
+use crate::unwind::{register_name, SavedRegister, RETURN_ADDRESS};
+use crate::value_format::ValueFormatter;
+use nix::sys::ptrace;
+use nix::sys::signal;
+use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
+use nix::sys::stat::{umask, Mode};
+use nix::sys::uio::{process_vm_readv, IoVec, RemoteIoVec};
+use nix::errno::Errno;
+use nix::sys::signal::Signal;
+use nix::unistd::{getpgid, setgid, setgroups, setuid, tcgetpgrp, Gid, Pid, Uid, User};
+use libc::user_regs_struct;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fs::{File, OpenOptions};
+use std::mem::size_of;
+use std::os::unix::fs::FileExt;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+//A stack frame of the stopped child process
+#[derive(Clone)]
+pub struct Frame {
+    //address of the instruction being executed in this frame
+    pub pc: usize,
+    //canonical frame address, which gcc uses as the frame base of every function
+    pub cfa: usize,
+    //registers as they are in this frame
+    pub regs: user_regs_struct,
+}
+
+impl Frame {
+    //The address to look up the function and line of frame number `level` by. In every frame but
+    //the innermost, the pc is a return address, which is just past the call and may already be on
+    //the next line (e.g. after a call that never returns), so the call itself is looked up instead.
+    pub fn lookup_pc(&self, level: usize) -> usize {
+        Frame::lookup(self.pc, level)
+    }
+
+    pub fn lookup(pc: usize, level: usize) -> usize {
+        if level == 0 {
+            pc
+        } else {
+            pc - 1
+        }
+    }
+}
+
+//A frame of the call stack, as found by `backtrace`
+pub struct BacktraceFrame {
+    //the function, or None if the frame isn't in any function with debug info
+    pub function: Option<String>,
+    //the line being executed, or None in a PLT stub or a frame without debug info
+    pub line: Option<Line>,
+    //the address being executed and the canonical frame address, as in Frame
+    pub pc: usize,
+    pub cfa: usize,
+}
+
+//A range of the child's memory, and the file mapped there if any
+#[derive(Clone)]
+pub struct MemoryMap {
+    pub start: usize,
+    pub end: usize,
+    //e.g. `r-xp` for code that is read and executed, and private to the process
+    pub permissions: String,
+    //where in the file the range starts
+    pub offset: usize,
+    pub path: String,
+}
+
+//The canonical frame address of a frame whose registers are `regs`, stopped at `pc` in the
+//function entered at `func_addr`. The usual prologue is `push %rbp; mov %rsp,%rbp`, and until it
+//has run %rbp still belongs to the caller.
+fn frame_address(regs: &user_regs_struct, pc: usize, func_addr: usize) -> usize {
+    if pc == func_addr {
+        regs.rsp as usize + 8
+    } else if pc == func_addr + 1 {
+        regs.rsp as usize + 16
+    } else {
+        regs.rbp as usize + 16
+    }
+}
+
+//Describe frame number `level` of the call stack, stopped at `pc`, the way gdb does, e.g.
+//`#1  0x0000000000401163 in twice at calls.c:11`. The address is left out when it is the start of
+//a line in the innermost frame.
+pub fn frame_description(debug_data: &DwarfData, level: usize, pc: usize) -> String {
+    let lookup_pc = Frame::lookup(pc, level);
+    let line = debug_data.get_line_from_addr(lookup_pc);
+    let func_name = match debug_data.get_function_from_addr(lookup_pc) {
+        Some(name) => name,
+        None => match debug_data.get_plt_name(pc) {
+            Some(name) => format!("{}@plt", name),
+            None => "??".to_string(),
+        },
+    };
+    let location = match line {
+        Some(line) => format!("{} at {}", func_name, line),
+        None => func_name,
+    };
+    if level == 0 && debug_data.is_line_start(pc) {
+        format!("#{:<3}{}", level, location)
+    } else {
+        format!("#{:<3}{:#018x} in {}", level, pc, location)
+    }
+}
+
+/// Status of the child process
+#[derive(Debug)]
+pub enum Status {
+    /// Indicates inferior stopped. Contains the signal that stopped the process, as well as the
+    /// current instruction pointer that it is stopped at.
+    Stopped(signal::Signal, usize),
+
+    /// Indicates inferior exited normally. Contains the exit status code.
+    Exited(i32),
+
+    /// Indicates the inferior exited due to a signal. Contains the signal that killed the
+    /// process.
+    Signaled(signal::Signal),
+
+    /// Indicates inferior stopped at the entry or exit of a system call. Contains the number of
+    /// the system call, as well as the current instruction pointer. Only a remote stub reports
+    /// these.
+    Syscall(usize, usize),
+
+    /// Indicates inferior stopped for a ptrace event that the debugger doesn't follow. Contains
+    /// the event, as well as the current instruction pointer.
+    PtraceEvent(i32, usize),
+
+    /// Indicates inferior was resumed by a SIGCONT, and is running. The debugger doesn't ask to be
+    /// told about these, so they don't happen at the moment.
+    Continued,
+}
+
+impl Status {
+    /// Whether the inferior is stopped, rather than running or gone
+    pub fn is_stop(&self) -> bool {
+        matches!(self, Status::Stopped(..) | Status::Syscall(..) | Status::PtraceEvent(..))
+    }
+}
+
+/// This function calls ptrace with PTRACE_TRACEME to enable debugging on a process.  
+/// Use with pre_exec to call this in the child process.
+fn child_traceme() -> Result<(), std::io::Error> {
+    ptrace::traceme().or(Err(std::io::Error::other(
+        "ptrace TRACEME failed",
+    )))
+}
+
+//Give the child the umask, group and user the program should run with. Called with pre_exec after
+//child_traceme. The group is changed first, since after changing the user we may not be allowed to,
+//and it replaces the supplementary groups, so that none of the debugger's are left.
+fn child_identity(
+    mask: Option<u32>,
+    uid: Option<u32>,
+    gid: Option<u32>,
+) -> Result<(), std::io::Error> {
+    if let Some(mask) = mask {
+        umask(Mode::from_bits_truncate(mask));
+    }
+    if let Some(gid) = gid {
+        let gid = Gid::from_raw(gid);
+        setgroups(&[gid]).map_err(to_io_error)?;
+        setgid(gid).map_err(to_io_error)?;
+    }
+    if let Some(uid) = uid {
+        setuid(Uid::from_raw(uid)).map_err(to_io_error)?;
+    }
+    Ok(())
+}
+
+//Open the files the program's standard input, output and error are redirected to. Relative
+//paths are in the directory the program is run in, as they would be from a shell there.
+fn redirect(cmd: &mut Command, redirections: &Redirections, cwd: Option<&str>) -> Result<(), String> {
+    let path_in_cwd = |path: &str| Path::new(cwd.unwrap_or(".")).join(path);
+    let open_output = |path: &str, append: bool| {
+        OpenOptions::new()
+            .write(true)
+            .create(true)
+            .append(append)
+            .truncate(!append)
+            .open(path_in_cwd(path))
+            .map_err(|e| format!("{}: {}.", path, e))
+    };
+    if let Some(path) = &redirections.stdin {
+        cmd.stdin(File::open(path_in_cwd(path)).map_err(|e| format!("{}: {}.", path, e))?);
+    }
+    if let Some((path, append)) = &redirections.stdout {
+        cmd.stdout(open_output(path, *append)?);
+    }
+    if let Some((path, append)) = &redirections.stderr {
+        cmd.stderr(open_output(path, *append)?);
+    }
+    Ok(())
+}
+
+fn to_io_error(e: nix::Error) -> std::io::Error {
+    std::io::Error::other(e.to_string())
+}
+
+//Offset of u_debugreg in struct user on x86_64, for reading and writing the debug registers with
+//PTRACE_PEEKUSER and PTRACE_POKEUSER
+const DEBUG_REGISTER_OFFSET: usize = 848;
+
+//Number of debug registers (DR0 to DR3) x86 has for hardware watchpoints and breakpoints
+pub const HARDWARE_SLOTS: usize = 4;
+
+fn align_addr_to_word(addr: usize) -> usize {
+    addr & (-(size_of::<usize>() as isize) as usize)
+}
+
+//The regset of PTRACE_GETREGSET that holds the XSAVE area, which has the AVX registers as well as
+//the x87 and SSE ones
+const NT_X86_XSTATE: usize = 0x202;
+
+//Offsets into the XSAVE area. The first 512 bytes are laid out as FXSAVE (and PTRACE_GETFPREGS)
+//lays them out.
+const XSAVE_ST0: usize = 32;
+const XSAVE_XMM0: usize = 160;
+const XSAVE_XSTATE_BV: usize = 512;
+const XSAVE_YMM_HIGH: usize = 576;
+
+//The x87, SSE and AVX registers, kept as the bytes of an XSAVE area
+pub struct FloatRegisters {
+    area: Vec<u8>,
+}
+
+impl FloatRegisters {
+    //st0 to st7, 80-bit floats each in a 16 byte slot
+    pub fn st(&self, n: usize) -> Option<Vec<u8>> {
+        self.slot(XSAVE_ST0, n, 8)
+    }
+
+    pub fn xmm(&self, n: usize) -> Option<Vec<u8>> {
+        self.slot(XSAVE_XMM0, n, 16)
+    }
+
+    //ymm0 to ymm15, or None if the CPU doesn't have AVX. Their low halves are the xmm registers.
+    pub fn ymm(&self, n: usize) -> Option<Vec<u8>> {
+        if self.area.len() < XSAVE_YMM_HIGH + 16 * 16 {
+            return None;
+        }
+        let mut ymm = self.xmm(n)?;
+        //while the AVX state is unused it isn't saved and reads as zero
+        let xstate_bv = self.area[XSAVE_XSTATE_BV];
+        match self.slot(XSAVE_YMM_HIGH, n, 16) {
+            Some(high) if xstate_bv & 4 != 0 => ymm.extend(high),
+            _ => ymm.extend(&[0; 16]),
+        }
+        Some(ymm)
+    }
+
+    //The x87 control and status registers and mxcsr, by the names gdb gives them
+    pub fn control(&self, name: &str) -> Option<u64> {
+        let field = |offset: usize, size: usize| {
+            let mut bytes = [0; 8];
+            bytes[..size].copy_from_slice(&self.area[offset..offset + size]);
+            u64::from_le_bytes(bytes)
+        };
+        Some(match name {
+            "fctrl" => field(0, 2),
+            "fstat" => field(2, 2),
+            //FXSAVE only keeps whether each register is empty; 0b11 is the full tag of an empty
+            //register
+            "ftag" => (0..8)
+                .filter(|i| field(4, 1) & (1 << i) == 0)
+                .fold(0, |tag, i| tag | (0b11 << (2 * i))),
+            "fop" => field(6, 2),
+            "fioff" => field(8, 4),
+            "fiseg" => field(12, 2),
+            "fooff" => field(16, 4),
+            "foseg" => field(20, 2),
+            "mxcsr" => field(24, 4),
+            _ => return None,
+        })
+    }
+
+    fn slot(&self, base: usize, n: usize, count: usize) -> Option<Vec<u8>> {
+        if n >= count {
+            return None;
+        }
+        self.area.get(base + 16 * n..base + 16 * (n + 1)).map(|bytes| bytes.to_vec())
+    }
+}
+
+//Read a general purpose register by its DWARF number
+fn dwarf_register(regs: &user_regs_struct, number: u16) -> Option<u64> {
+    let mut regs = *regs;
+    register_field(&mut regs, register_name(number)?).map(|value| *value)
+}
+
+//Find a general purpose register by the name expressions use for it, such as `rax` or `sp`
+pub fn register_field<'a>(regs: &'a mut user_regs_struct, name: &str) -> Option<&'a mut u64> {
+    Some(match name {
+        "rip" | "pc" => &mut regs.rip,
+        "rsp" | "sp" => &mut regs.rsp,
+        "rbp" | "fp" => &mut regs.rbp,
+        "rax" => &mut regs.rax,
+        "rbx" => &mut regs.rbx,
+        "rcx" => &mut regs.rcx,
+        "rdx" => &mut regs.rdx,
+        "rsi" => &mut regs.rsi,
+        "rdi" => &mut regs.rdi,
+        "r8" => &mut regs.r8,
+        "r9" => &mut regs.r9,
+        "r10" => &mut regs.r10,
+        "r11" => &mut regs.r11,
+        "r12" => &mut regs.r12,
+        "r13" => &mut regs.r13,
+        "r14" => &mut regs.r14,
+        "r15" => &mut regs.r15,
+        "eflags" => &mut regs.eflags,
+        "cs" => &mut regs.cs,
+        "ss" => &mut regs.ss,
+        "ds" => &mut regs.ds,
+        "es" => &mut regs.es,
+        "fs" => &mut regs.fs,
+        "gs" => &mut regs.gs,
+        "fs_base" => &mut regs.fs_base,
+        "gs_base" => &mut regs.gs_base,
+        _ => return None,
+    })
+}
+
+//The bytes that breakpoints replaced, and the address of each
+type SavedBytes = Vec<(usize, u8)>;
+
+pub struct Inferior {
+    //the thread being debugged, which is the one that last stopped, and the process it is in.
+    //Both change if a forked child is followed.
+    pid: Cell<Pid>,
+    process: Cell<Pid>,
+    //the threads of the process, numbered from 1 in the order they were created, as `info
+    //threads` shows them
+    threads: RefCell<Vec<(usize, Pid)>>,
+    next_thread: Cell<usize>,
+    //whether the threads other than the one being debugged are running. They run while the
+    //program is continued, but not while a single instruction is stepped.
+    others_running: Cell<bool>,
+    //signals other threads got while being stopped, which are passed to them when they resume
+    thread_signals: RefCell<HashMap<Pid, Signal>>,
+    //new threads and processes whose first stop was reported before the clone or fork event
+    //that created them
+    early_stops: RefCell<HashSet<Pid>>,
+    //the thread last reported to have stopped, or selected with `thread`
+    shown_thread: Cell<Pid>,
+    //threads switched away from with `thread`, which may be at the breakpoint they stopped at
+    //and have to step over it before they resume
+    left_threads: RefCell<Vec<Pid>>,
+    //whether the child has been sent a SIGSTOP for a Ctrl+C, which is reported as a SIGINT
+    interrupt_sent: Cell<Option<Pid>>,
+    //what to do with each signal the child gets, set with `handle`
+    signals: SignalTable,
+    //the signal the child last stopped with, which is delivered when the child is resumed unless
+    //`handle` says it isn't passed to the program
+    stop_signal: Cell<Option<Signal>>,
+    //a signal given with `signal`, delivered instead when the child is resumed
+    queued_signal: Cell<Option<Signal>>,
+    //whether the child was last resumed for a single instruction, so that it can be resumed the
+    //same way after a signal that doesn't stop it
+    single_stepping: Cell<bool>,
+    //the registers of the thread being debugged, read once while it is stopped rather than by
+    //everything that looks at them. They are forgotten whenever a thread is resumed.
+    registers: Cell<Option<(Pid, user_regs_struct)>>,
+    //the program the child was started with, to tell whether it execs the same program again
+    program: PathBuf,
+    //where the program is loaded, which it may not be again when it execs itself
+    load_base: Cell<Option<usize>>,
+    //which process is debugged after a fork, set with `set follow-fork-mode`
+    follow_fork_mode: FollowForkMode,
+    //the parent that vforked the child being debugged, with the bytes its breakpoints replaced.
+    //It shares its memory with the child, breakpoints and all, until the child execs or exits, and
+    //is only let go then.
+    vfork_parent: RefCell<Option<(Pid, SavedBytes)>>,
+    //a different program the process has exec'd, whose symbols the debugger hasn't loaded yet.
+    //The process is kept stopped at the new program's entry point, with no breakpoints in it,
+    //until it has (see `take_exec`).
+    execed: RefCell<Option<PathBuf>>,
+    //whether the process that isn't followed after a fork is let go, set with
+    //`set detach-on-fork`
+    detach_on_fork: bool,
+    //`set scheduler-locking`
+    scheduler_locking: bool,
+    //processes from forks that are kept stopped under the debugger, for it to make inferiors of
+    //(see `take_kept`)
+    kept: RefCell<Vec<Pid>>,
+    //the core file the memory and registers come from, for a process that has died, rather than
+    //from a live process through ptrace
+    core: Option<CoreFile>,
+    //the stub the program runs under, for a program debugged with `target remote`, which is
+    //read, written and run through it rather than through ptrace
+    remote: Option<RemoteTarget>,
+    //Some while the program is recorded with `record`: the address of each instruction it has
+    //run since, in order. It is then only ever run an instruction at a time.
+    history: RefCell<Option<Vec<usize>>>,
+}
+
+impl Inferior {
+    /// Attempts to start a new inferior process. Returns Some(Inferior) if successful, or None if
+    /// an error is encountered. The child is stopped before it runs anything of the program, and
+    /// the breakpoints are written into it with `write_breakpoints`, once they are where the
+    /// program has been loaded.
+    pub fn new(
+        target: &str,
+        args: &Vec<String>,
+        redirections: &Redirections,
+        breakpoints: &HashMap<usize, Breakpoint>,
+        settings: &Settings,
+    ) -> Option<Inferior> {
+        //the program is found before moving to the directory it is run in
+        let program = std::fs::canonicalize(target).unwrap_or_else(|_| target.into());
+        let mut cmd = Command::new(&program);
+        cmd.args(args);
+        if let Some(dir) = &settings.cwd {
+            cmd.current_dir(dir);
+        }
+        if let Err(e) = redirect(&mut cmd, redirections, settings.cwd.as_deref()) {
+            println!("{}", e);
+            return None;
+        }
+        if settings.exec_clean_env {
+            cmd.env_clear();
+        }
+        let (mask, uid) = (settings.exec_umask, settings.exec_uid);
+        //a program run as another user is in that user's group, unless another is given, rather
+        //than the debugger's. The user is looked up here, since that isn't safe after forking.
+        let gid = match (uid, settings.exec_gid) {
+            (Some(uid), None) => match User::from_uid(Uid::from_raw(uid)) {
+                Ok(Some(user)) => Some(user.gid.as_raw()),
+                _ => {
+                    println!("User {} has no group to run the program with. Set exec-gid as well.", uid);
+                    return None;
+                }
+            },
+            (_, gid) => gid,
+        };
+        unsafe {
+            cmd.pre_exec(child_traceme);
+            cmd.pre_exec(move || child_identity(mask, uid, gid));
+        }
+
+        //Set the inferior for the child process
+        let pid = Pid::from_raw(cmd.spawn().ok()?.id() as i32);
+        let inferior = Inferior {
+            pid: Cell::new(pid),
+            process: Cell::new(pid),
+            threads: RefCell::new(vec![(1, pid)]),
+            next_thread: Cell::new(2),
+            others_running: Cell::new(false),
+            thread_signals: RefCell::new(HashMap::new()),
+            early_stops: RefCell::new(HashSet::new()),
+            shown_thread: Cell::new(pid),
+            left_threads: RefCell::new(Vec::new()),
+            interrupt_sent: Cell::new(None),
+            signals: SignalTable::new(),
+            stop_signal: Cell::new(None),
+            queued_signal: Cell::new(None),
+            single_stepping: Cell::new(false),
+            registers: Cell::new(None),
+            program,
+            load_base: Cell::new(None),
+            follow_fork_mode: settings.follow_fork_mode,
+            vfork_parent: RefCell::new(None),
+            execed: RefCell::new(None),
+            detach_on_fork: settings.detach_on_fork,
+            scheduler_locking: settings.scheduler_locking,
+            kept: RefCell::new(Vec::new()),
+            core: None,
+            remote: None,
+            history: RefCell::new(None),
+        };
+
+        //Calls wait on child to get its status(non-blocking)
+        let status = inferior.wait(None, breakpoints).ok()?;
+        //If child is stopped, write the breakpoints addresses into into its address space
+        if let Status::Stopped(signal::Signal::SIGTRAP, _rip) = status {
+            //stop when the program forks, clones or execs, rather than getting a status
+            //wait doesn't expect, and have it killed if the debugger dies rather than left
+            //behind stopped or with breakpoints in it. Threads and processes it starts that
+            //are traced get the same options.
+            let options = ptrace::Options::PTRACE_O_TRACEFORK
+                | ptrace::Options::PTRACE_O_TRACEVFORK
+                | ptrace::Options::PTRACE_O_TRACEVFORKDONE
+                | ptrace::Options::PTRACE_O_TRACECLONE
+                | ptrace::Options::PTRACE_O_TRACEEXEC
+                | ptrace::Options::PTRACE_O_EXITKILL;
+            if let Err(e) = ptrace::setoptions(inferior.pid(), options) {
+                println!("cannot set tracing options. Error: {}", e);
+            }
+            inferior.load_base.set(inferior.load_address(&inferior.program.to_string_lossy()));
+            return Some(inferior);
+        }
+
+        None
+    }
+
+    /// Look at the process a core file was dumped from, as if it were stopped where it died. It
+    /// can be looked at but not run: nothing goes through ptrace, and memory and registers that
+    /// are changed are only changed in the debugger.
+    pub fn from_core(core: CoreFile, target: &str, settings: &Settings) -> Inferior {
+        let pid = core.threads[0].tid;
+        let threads = core.threads.iter().enumerate().map(|(i, thread)| (i + 1, thread.tid)).collect();
+        let stop_signal = Signal::try_from(core.threads[0].signal).ok();
+        Inferior {
+            pid: Cell::new(pid),
+            process: Cell::new(pid),
+            threads: RefCell::new(threads),
+            next_thread: Cell::new(core.threads.len() + 1),
+            others_running: Cell::new(false),
+            thread_signals: RefCell::new(HashMap::new()),
+            early_stops: RefCell::new(HashSet::new()),
+            shown_thread: Cell::new(pid),
+            left_threads: RefCell::new(Vec::new()),
+            interrupt_sent: Cell::new(None),
+            signals: SignalTable::new(),
+            stop_signal: Cell::new(stop_signal),
+            queued_signal: Cell::new(None),
+            single_stepping: Cell::new(false),
+            registers: Cell::new(None),
+            program: std::fs::canonicalize(target).unwrap_or_else(|_| target.into()),
+            load_base: Cell::new(None),
+            follow_fork_mode: settings.follow_fork_mode,
+            vfork_parent: RefCell::new(None),
+            execed: RefCell::new(None),
+            detach_on_fork: settings.detach_on_fork,
+            scheduler_locking: settings.scheduler_locking,
+            kept: RefCell::new(Vec::new()),
+            core: Some(core),
+            remote: None,
+            history: RefCell::new(None),
+        }
+    }
+
+    /// Debug a program running under a gdb remote protocol stub, stopped where the stub has it.
+    /// Only its memory and registers are looked at, and its threads and the processes it forks
+    /// aren't followed.
+    pub fn from_remote(remote: RemoteTarget, target: &str, settings: &Settings) -> Inferior {
+        let pid = remote.pid();
+        Inferior {
+            pid: Cell::new(pid),
+            process: Cell::new(pid),
+            threads: RefCell::new(vec![(1, pid)]),
+            next_thread: Cell::new(2),
+            others_running: Cell::new(false),
+            thread_signals: RefCell::new(HashMap::new()),
+            early_stops: RefCell::new(HashSet::new()),
+            shown_thread: Cell::new(pid),
+            left_threads: RefCell::new(Vec::new()),
+            interrupt_sent: Cell::new(None),
+            signals: SignalTable::new(),
+            stop_signal: Cell::new(None),
+            queued_signal: Cell::new(None),
+            single_stepping: Cell::new(false),
+            registers: Cell::new(None),
+            program: std::fs::canonicalize(target).unwrap_or_else(|_| target.into()),
+            load_base: Cell::new(None),
+            follow_fork_mode: settings.follow_fork_mode,
+            vfork_parent: RefCell::new(None),
+            execed: RefCell::new(None),
+            detach_on_fork: settings.detach_on_fork,
+            scheduler_locking: settings.scheduler_locking,
+            kept: RefCell::new(Vec::new()),
+            core: None,
+            remote: Some(remote),
+            history: RefCell::new(None),
+        }
+    }
+
+    //The threads, memory and command line of the process, to write to a core file with `gcore`.
+    //The memory is as the program has it, without the breakpoints.
+    pub fn snapshot(&self, breakpoints: &HashMap<usize, Breakpoint>) -> Result<Snapshot, nix::Error> {
+        let current = self.pid();
+        let mut threads = Vec::new();
+        for tid in std::iter::once(current).chain(self.other_threads()) {
+            let signal = match self.thread_signals.borrow().get(&tid) {
+                _ if tid == current => self.stop_signal.get().unwrap_or(Signal::SIGTRAP) as i32,
+                Some(signal) => *signal as i32,
+                None => 0,
+            };
+            self.pid.set(tid);
+            let thread = self.get_registers().and_then(|registers| {
+                let float_area = self.get_float_registers()?.area;
+                Ok(CoreThread { tid, signal, registers, float_area })
+            });
+            self.pid.set(current);
+            threads.push(thread?);
+        }
+        let maps = self.memory_maps();
+        let mut memory = Vec::new();
+        for map in &maps {
+            let mut bytes = if map.permissions.starts_with('r') {
+                self.read_mem(map.start, map.end - map.start).ok()
+            } else {
+                None
+            };
+            if let Some(bytes) = bytes.as_mut() {
+                for b in breakpoints.values().filter(|b| b.enabled && b.hardware_slot.is_none()) {
+                    if (map.start..map.end).contains(&b.addr) {
+                        bytes[b.addr - map.start] = b.orig_byte;
+                    }
+                }
+            }
+            memory.push(bytes);
+        }
+        let process = self.process_id();
+        let cmdline = std::fs::read(format!("/proc/{}/cmdline", process)).unwrap_or_default();
+        let args: Vec<String> = cmdline
+            .split(|byte| *byte == 0)
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| String::from_utf8_lossy(arg).to_string())
+            .collect();
+        Ok(Snapshot {
+            pid: process,
+            threads,
+            maps,
+            memory,
+            command: args.join(" "),
+            auxv: std::fs::read(format!("/proc/{}/auxv", process)).unwrap_or_default(),
+        })
+    }
+
+    //The core file being looked at, if the process is one that has died
+    pub fn core(&self) -> Option<&CoreFile> {
+        self.core.as_ref()
+    }
+
+    //The stub the program runs under, if it is debugged with `target remote`
+    pub fn remote(&self) -> Option<&RemoteTarget> {
+        self.remote.as_ref()
+    }
+
+    //Write the breakpoints addresses into the child's address space
+    pub fn write_breakpoints(&mut self, breakpoints: &mut HashMap<usize, Breakpoint>) {
+        //breakpoints are grouped by the word they are in, so that each word is read and written
+        //once however many breakpoints share it
+        let mut words: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for b in breakpoints.values().filter(|b| b.enabled) {
+            //a hardware breakpoint is set in a debug register instead of the code
+            if let Some(slot) = b.hardware_slot {
+                if let Err(e) = self.set_hardware_breakpoint(slot, b.addr) {
+                    println!("cannot set hardware breakpoint at {}. Error: {}", b.addr, e)
+                }
+                continue;
+            }
+            words.entry(align_addr_to_word(b.addr)).or_default().push(b.addr);
+        }
+        for (word_addr, addrs) in words {
+            match self.fill_word(word_addr, &addrs, 0xcc) {
+                Ok(orig_bytes) => {
+                    for (addr, orig_byte) in addrs.iter().zip(orig_bytes) {
+                        breakpoints.get_mut(addr).unwrap().orig_byte = orig_byte;
+                    }
+                }
+                Err(e) => {
+                    for addr in addrs {
+                        println!("cannot set breakpoints at {}. Error: {}", addr, e)
+                    }
+                }
+            }
+        }
+    }
+
+    //Set the bytes at `addrs`, which are all in the word at `word_addr`, to `val` with a single
+    //read and write of the word. Returns the bytes that were there before, in the order of `addrs`.
+    fn fill_word(&mut self, word_addr: usize, addrs: &[usize], val: u8) -> Result<Vec<u8>, nix::Error> {
+        let mut word = self.read_word(word_addr)?.to_le_bytes();
+        let orig_bytes = addrs.iter().map(|addr| word[addr - word_addr]).collect();
+        for addr in addrs {
+            word[addr - word_addr] = val;
+        }
+        self.write_bytes(word_addr, &word)?;
+        Ok(orig_bytes)
+    }
+
+    //Resume the child process until it stops or exits
+    //When a breakpoint (int3) is hit, %rip is one past it, so %rip is moved back to the breakpoint
+    //and the child looks as if it stopped just before the instruction the breakpoint replaced.
+    //To resume from a breakpoint, the original instruction is written back and executed with a
+    //single step, and then the breakpoint is written again so that it is hit next time too.
+    //A hardware breakpoint stops the child before the instruction at it runs, so %rip is already
+    //at the breakpoint.
+    pub fn cont(&mut self, breakpoints: &HashMap<usize, Breakpoint>) -> Result<Status, nix::Error> {
+        match self.start(breakpoints, false)? {
+            Some(status) => Ok(status),
+            None => {
+                let status = self.wait(None, breakpoints)?;
+                self.stopped(status, breakpoints)
+            }
+        }
+    }
+
+    //Execute a single instruction
+    pub fn step_instruction(
+        &mut self,
+        breakpoints: &HashMap<usize, Breakpoint>,
+    ) -> Result<Status, nix::Error> {
+        match self.start(breakpoints, true)? {
+            Some(status) => Ok(status),
+            None => self.wait(None, breakpoints),
+        }
+    }
+
+    //Set the child running, like `cont`, or for a single instruction if `single_step`, without
+    //waiting for it to stop. Returns the status if it has stopped already, for something other
+    //than stepping over the breakpoint it is at.
+    pub fn start(
+        &mut self,
+        breakpoints: &HashMap<usize, Breakpoint>,
+        single_step: bool,
+    ) -> Result<Option<Status>, nix::Error> {
+        //a SIGSTOP sent to interrupt the child is still pending if it stopped for something else
+        //first, and is let through now so that it doesn't stop the child again at once
+        if self.interrupt_sent.get().is_some() {
+            self.resume(false)?;
+            match self.wait(None, breakpoints)? {
+                Status::Stopped(Signal::SIGINT, _) if self.interrupt_sent.get().is_none() => (),
+                other => return Ok(Some(other)),
+            }
+        }
+        if let Some(status) = self.run_signal_handler(breakpoints)? {
+            return Ok(Some(status));
+        }
+        for tid in self.left_threads.take() {
+            let current = self.pid.replace(tid);
+            let status = self.step_over_breakpoint(breakpoints);
+            self.pid.set(current);
+            match status? {
+                Some(Status::Stopped(signal::Signal::SIGTRAP, _)) | None => (),
+                Some(other) => return Ok(Some(other)),
+            }
+        }
+        if let Some(status) = self.step_over_breakpoint(breakpoints)? {
+            match status {
+                Status::Stopped(signal::Signal::SIGTRAP, _) if !single_step => (),
+                other => return Ok(Some(other)),
+            }
+        }
+        self.resume(single_step)?;
+        Ok(None)
+    }
+
+    //Resume the child, or run a single instruction if `single_step`, with the signal it last
+    //stopped with if that is passed to the program. The other threads only run when the child is
+    //resumed, not while it is stepped, and not at all with `set scheduler-locking on`.
+    fn resume(&self, single_step: bool) -> Result<(), nix::Error> {
+        if !single_step && !self.scheduler_locking {
+            self.resume_other_threads()?;
+        }
+        self.resume_with(single_step, self.signal_to_pass())
+    }
+
+    fn resume_other_threads(&self) -> Result<(), nix::Error> {
+        if self.others_running.replace(true) {
+            return Ok(());
+        }
+        self.registers.set(None);
+        for tid in self.other_threads() {
+            let signal = self.thread_signals.borrow_mut().remove(&tid);
+            ptrace::cont(tid, signal)?;
+        }
+        Ok(())
+    }
+
+    //Stop the threads still running once one has stopped, so that the whole program is stopped
+    //while it is looked at, as in gdb. A thread that hits a breakpoint meanwhile has %rip moved
+    //back to hit it again when resumed, and a signal it gets is passed to it then.
+    fn stop_other_threads(&self, breakpoints: &HashMap<usize, Breakpoint>) -> Result<(), nix::Error> {
+        if !self.others_running.replace(false) {
+            return Ok(());
+        }
+        let others = self.other_threads();
+        for tid in &others {
+            unsafe {
+                libc::syscall(libc::SYS_tgkill, self.process.get().as_raw(), tid.as_raw(), libc::SIGSTOP);
+            }
+        }
+        for tid in others {
+            loop {
+                match waitpid(tid, Some(WaitPidFlag::__WALL)) {
+                    Ok(WaitStatus::Stopped(_, Signal::SIGSTOP)) => {
+                        if self.interrupt_sent.get() == Some(tid) {
+                            self.interrupt_sent.set(None);
+                        }
+                        break;
+                    }
+                    Ok(WaitStatus::Stopped(_, Signal::SIGTRAP)) => {
+                        let mut regs = ptrace::getregs(tid)?;
+                        let addr = (regs.rip - 1) as usize;
+                        if breakpoints.get(&addr).is_some_and(|b| b.enabled && b.hardware_slot.is_none()) {
+                            regs.rip = addr as u64;
+                            ptrace::setregs(tid, regs)?;
+                        }
+                    }
+                    Ok(WaitStatus::Stopped(_, signal)) => {
+                        self.thread_signals.borrow_mut().insert(tid, signal);
+                    }
+                    Ok(WaitStatus::PtraceEvent(_, _, event)) => {
+                        let current = self.pid.replace(tid);
+                        let result = self.ptrace_event(event, breakpoints);
+                        self.pid.set(current);
+                        result?;
+                    }
+                    Ok(WaitStatus::Exited(..)) | Ok(WaitStatus::Signaled(..)) | Err(_) => {
+                        self.remove_thread(tid);
+                        break;
+                    }
+                    Ok(_) => (),
+                }
+                self.registers.set(None);
+                ptrace::cont(tid, None)?;
+            }
+        }
+        Ok(())
+    }
+
+    //The threads other than the one being debugged
+    fn other_threads(&self) -> Vec<Pid> {
+        let threads = self.threads.borrow();
+        threads.iter().map(|(_, tid)| *tid).filter(|tid| *tid != self.pid()).collect()
+    }
+
+    fn is_thread(&self, pid: Pid) -> bool {
+        self.threads.borrow().iter().any(|(_, tid)| *tid == pid)
+    }
+
+    //Forget a thread that has exited. If it was the one being debugged, the main thread is
+    //debugged instead.
+    fn remove_thread(&self, tid: Pid) {
+        self.threads.borrow_mut().retain(|(_, thread)| *thread != tid);
+        self.thread_signals.borrow_mut().remove(&tid);
+        if self.pid() == tid {
+            self.pid.set(self.process.get());
+        }
+    }
+
+    //Forget the threads, when the process is replaced by a forked child or a new program, which
+    //start with a single thread
+    fn reset_threads(&self, process: Pid) {
+        self.pid.set(process);
+        self.process.set(process);
+        self.registers.set(None);
+        self.threads.replace(vec![(1, process)]);
+        self.next_thread.set(2);
+        self.shown_thread.set(process);
+        self.left_threads.borrow_mut().clear();
+        self.others_running.set(false);
+        self.thread_signals.borrow_mut().clear();
+    }
+
+    fn resume_with(&self, single_step: bool, signal: Option<Signal>) -> Result<(), nix::Error> {
+        self.single_stepping.set(single_step);
+        let mut single_step = single_step;
+        //a recorded program is stepped through everything it runs, see `recorded_step`
+        if let Some(history) = self.history.borrow_mut().as_mut() {
+            history.push(self.get_registers()?.rip as usize);
+            single_step = true;
+        }
+        self.registers.set(None);
+        if let Some(remote) = &self.remote {
+            return remote.resume(single_step, signal);
+        }
+        if single_step {
+            ptrace::step(self.pid(), signal)
+        } else {
+            ptrace::cont(self.pid(), signal)
+        }
+    }
+
+    //The signal the child last stopped with, if it is passed to the program. The table is looked
+    //at now rather than when the child stopped, so that `handle` in between counts.
+    fn signal_to_pass(&self) -> Option<Signal> {
+        let stop_signal = self.stop_signal.take().filter(|signal| self.signals.get(*signal).pass);
+        self.queued_signal.take().or(stop_signal)
+    }
+
+    //Deliver `signal` when the child is next resumed, instead of the signal it stopped with, or
+    //no signal at all if None
+    pub fn queue_signal(&self, signal: Option<Signal>) {
+        self.queued_signal.set(signal);
+        self.stop_signal.set(None);
+    }
+
+    //Change what is done with each signal the child gets
+    pub fn set_signals(&mut self, signals: SignalTable) {
+        self.signals = signals;
+    }
+
+    //How the child stopped after `start` without `single_step`, with %rip moved back if it hit a
+    //software breakpoint
+    pub fn stopped(
+        &mut self,
+        status: Status,
+        breakpoints: &HashMap<usize, Breakpoint>,
+    ) -> Result<Status, nix::Error> {
+        //a recorded program is stopped before a breakpoint rather than by running into it
+        if self.exec_pending() || self.history.borrow().is_some() {
+            return Ok(status);
+        }
+        if let Status::Stopped(signal::Signal::SIGTRAP, rip) = status {
+            if breakpoints.get(&rip).is_some_and(|b| b.enabled && b.hardware_slot.is_some()) {
+                //forget which debug register triggered, so it isn't taken for a watchpoint
+                self.write_debug_register(6, 0)?;
+                return Ok(status);
+            }
+            if breakpoints.get(&(rip - 1)).is_some_and(|b| b.enabled) {
+                let mut registers = self.get_registers()?;
+                registers.rip = (rip - 1) as u64;
+                self.set_registers(registers)?;
+                return Ok(Status::Stopped(signal::Signal::SIGTRAP, rip - 1));
+            }
+        }
+        Ok(status)
+    }
+
+    //Resume the child until it reaches `addr` with the stack pointer above `sp`, by putting a
+    //temporary breakpoint there. The stack pointer is checked so that a recursive call reaching
+    //`addr` in a deeper frame doesn't count. Stops earlier if anything else stops the child,
+    //such as a breakpoint or a signal.
+    pub fn continue_to(
+        &mut self,
+        addr: usize,
+        sp: usize,
+        breakpoints: &HashMap<usize, Breakpoint>,
+    ) -> Result<Status, nix::Error> {
+        self.continue_to_any(&[(addr, sp)], breakpoints)
+    }
+
+    //Like `continue_to`, for whichever of several addresses, each with its own stack pointer, the
+    //child reaches first
+    pub fn continue_to_any(
+        &mut self,
+        targets: &[(usize, usize)],
+        breakpoints: &HashMap<usize, Breakpoint>,
+    ) -> Result<Status, nix::Error> {
+        let mut temporary = breakpoints.clone();
+        let mut inserted = Vec::new();
+        for (addr, _) in targets {
+            if temporary.get(addr).is_some_and(|b| b.enabled) {
+                continue;
+            }
+            let orig_byte = self.write_byte(*addr, 0xcc)?;
+            temporary.insert(
+                *addr,
+                Breakpoint {
+                    number: None,
+                    addr: *addr,
+                    orig_byte,
+                    enabled: true,
+                    group: None,
+                    condition: None,
+                    hit_count: 0,
+                    ignore_count: 0,
+                    temporary: false,
+                    commands: Vec::new(),
+                    dprintf: None,
+                    hardware_slot: None,
+                    location: None,
+                },
+            );
+            inserted.push(*addr);
+        }
+        let status = loop {
+            let status = self.cont(&temporary)?;
+            match status {
+                Status::Stopped(signal::Signal::SIGTRAP, rip) if targets.iter().any(|(addr, _)| *addr == rip) => {
+                    let rsp = self.get_registers()?.rsp as usize;
+                    if targets.iter().any(|(addr, sp)| *addr == rip && rsp >= *sp) {
+                        break status;
+                    }
+                }
+                other => break other,
+            }
+        };
+        if status.is_stop() && !self.exec_pending() {
+            for addr in inserted {
+                self.write_byte(addr, temporary[&addr].orig_byte)?;
+            }
+        }
+        Ok(status)
+    }
+
+    //Execute until the child reaches the start of a different source line. With `into_calls`
+    //functions that have debug info are stepped into, otherwise every call is run until it
+    //returns, as is always done for a function without debug info, such as one in libc. Stops at
+    //a breakpoint reached on the way, even in the middle of a line or on a PLT stub.
+    pub fn step_line(
+        &mut self,
+        debug_data: &DwarfData,
+        breakpoints: &HashMap<usize, Breakpoint>,
+        into_calls: bool,
+    ) -> Result<Status, nix::Error> {
+        let start = match debug_data.get_line_from_addr(self.get_registers()?.rip as usize) {
+            Some(line) => line,
+            //nowhere to step to without line info, so just let the child run
+            None => return self.cont(breakpoints),
+        };
+        loop {
+            let before = self.get_registers()?;
+            let status = self.step_instruction(breakpoints)?;
+            let rip = match status {
+                Status::Stopped(signal::Signal::SIGTRAP, rip) if !self.exec_pending() => rip,
+                other => return Ok(other),
+            };
+            if breakpoints.get(&rip).is_some_and(|b| b.enabled) {
+                return Ok(status);
+            }
+            let call = self.call_return_address(&before)?;
+            let has_line_info = debug_data.get_line_from_addr(rip).is_some();
+            //run the called function until it returns, with a temporary breakpoint on the
+            //return address
+            let (status, rip) = match call {
+                Some(ret) if !(has_line_info && into_calls) => {
+                    match self.continue_to(ret, before.rsp as usize, breakpoints)? {
+                        Status::Stopped(signal::Signal::SIGTRAP, rip) if rip == ret => {
+                            (Status::Stopped(signal::Signal::SIGTRAP, rip), rip)
+                        }
+                        other => return Ok(other),
+                    }
+                }
+                //returned from the outermost function with debug info, e.g. from main
+                None if !has_line_info => return self.cont(breakpoints),
+                _ => (status, rip),
+            };
+            let line = match debug_data.get_line_from_addr(rip) {
+                Some(line) => line,
+                None => continue,
+            };
+            //after returning from a call the child is in the middle of the caller's line, which
+            //is finished before stopping
+            if (line.file == start.file && line.number == start.number)
+                || !debug_data.is_line_start(rip)
+            {
+                continue;
+            }
+            //stepped into a function: stop after its prologue, where its arguments have been
+            //stored and the first line of its body starts
+            if let Some(func) = debug_data.get_function_at(rip).filter(|f| f.address == rip) {
+                if let Some(body) = debug_data.get_prologue_end(func) {
+                    return self.continue_to(body, 0, breakpoints);
+                }
+            }
+            return Ok(status);
+        }
+    }
+
+    //Called after executing one instruction, with the registers from before it. If it was a call,
+    //returns the address the call will return to. A call pushes the return address, which is the
+    //end of the call instruction.
+    pub fn call_return_address(&self, before: &user_regs_struct) -> Result<Option<usize>, nix::Error> {
+        let sp = self.get_registers()?.rsp as usize;
+        if sp + 8 != before.rsp as usize {
+            return Ok(None);
+        }
+        let ret = self.read_word(sp)? as usize;
+        //instructions are at most 15 bytes long
+        if ret > before.rip as usize && ret <= before.rip as usize + 15 {
+            Ok(Some(ret))
+        } else {
+            Ok(None)
+        }
+    }
+
+    //A signal delivered to the child at a breakpoint runs its handler, which returns to the
+    //breakpoint and would hit it again. So the handler is run first, with the breakpoint in place,
+    //and the breakpoint is stepped over once the handler has returned to it. Returns the status
+    //if the child stopped anywhere else, e.g. at a breakpoint in the handler.
+    fn run_signal_handler(
+        &mut self,
+        breakpoints: &HashMap<usize, Breakpoint>,
+    ) -> Result<Option<Status>, nix::Error> {
+        let regs = self.get_registers()?;
+        let rip = regs.rip as usize;
+        if !breakpoints.get(&rip).is_some_and(|b| b.enabled) {
+            return Ok(None);
+        }
+        let signal = match self.signal_to_pass() {
+            Some(signal) => signal,
+            None => return Ok(None),
+        };
+        self.resume_with(false, Some(signal))?;
+        let status = self.wait(None, breakpoints)?;
+        match self.stopped(status, breakpoints)? {
+            Status::Stopped(Signal::SIGTRAP, at)
+                if at == rip && self.get_registers()?.rsp == regs.rsp => Ok(None),
+            other => Ok(Some(other)),
+        }
+    }
+
+    //If the child is stopped at a breakpoint, execute the instruction it replaced and put the
+    //breakpoint back. A hardware breakpoint is turned off for the step instead, as it would stop
+    //the child again before the instruction runs. Returns the status after the step, or None if
+    //there was no breakpoint.
+    fn step_over_breakpoint(
+        &mut self,
+        breakpoints: &HashMap<usize, Breakpoint>,
+    ) -> Result<Option<Status>, nix::Error> {
+        let rip = self.get_registers()?.rip as usize;
+        let breakpoint = match breakpoints.get(&rip) {
+            Some(breakpoint) if breakpoint.enabled => breakpoint,
+            _ => return Ok(None),
+        };
+        match breakpoint.hardware_slot {
+            Some(slot) => self.clear_hardware_slot(slot)?,
+            None => {
+                self.write_byte(breakpoint.addr, breakpoint.orig_byte)?;
+            }
+        }
+        self.resume(true)?;
+        let status = self.wait(None, breakpoints)?;
+        if status.is_stop() && !self.exec_pending() {
+            match breakpoint.hardware_slot {
+                Some(slot) => self.set_hardware_breakpoint(slot, breakpoint.addr)?,
+                None => {
+                    self.write_byte(breakpoint.addr, 0xcc)?;
+                }
+            }
+        }
+        Ok(Some(status))
+    }
+
+    /// Returns the pid of this inferior.
+    pub fn pid(&self) -> Pid {
+        self.pid.get()
+    }
+
+    /// Calls waitpid on this inferior and returns a Status to indicate the state of the process
+    /// after the waitpid call. `breakpoints` are those written into the process, which are taken
+    /// out of a process it forks.
+    pub fn wait(
+        &self,
+        options: Option<WaitPidFlag>,
+        breakpoints: &HashMap<usize, Breakpoint>,
+    ) -> Result<Status, nix::Error> {
+        //Ctrl+C interrupts the wait. If the child doesn't get the SIGINT itself it is stopped
+        //with a SIGSTOP, which can't be blocked.
+        //any thread may stop
+        if let Some(remote) = &self.remote {
+            loop {
+                if let Some(status) = self.remote_status(remote.wait()?)? {
+                    return Ok(status);
+                }
+            }
+        }
+        let options = options.unwrap_or(WaitPidFlag::empty()) | WaitPidFlag::__WALL;
+        loop {
+            match waitpid(Pid::from_raw(-1), Some(options)) {
+                Err(nix::Error::Sys(Errno::EINTR)) => self.check_interrupt()?,
+                status => {
+                    if let Some(status) = self.status(status?, breakpoints)? {
+                        return Ok(status);
+                    }
+                }
+            }
+        }
+    }
+
+    //The status of the child if it has stopped or exited, without waiting for it
+    pub fn poll(&self, breakpoints: &HashMap<usize, Breakpoint>) -> Result<Option<Status>, nix::Error> {
+        if let Some(remote) = &self.remote {
+            return match remote.poll()? {
+                Some(stop) => self.remote_status(stop),
+                None => Ok(None),
+            };
+        }
+        match waitpid(Pid::from_raw(-1), Some(WaitPidFlag::WNOHANG | WaitPidFlag::__WALL))? {
+            WaitStatus::StillAlive => Ok(None),
+            status => self.status(status, breakpoints),
+        }
+    }
+
+    //The status of the child from waitpid, or None if it got a signal that doesn't stop it, in
+    //which case it has been resumed, with the signal if that is passed to the program. It is
+    //resumed too after a fork, clone or exec, or when a thread other than the main one exits.
+    //The thread that stopped becomes the one being debugged, and the others are stopped too.
+    fn status(
+        &self,
+        status: WaitStatus,
+        breakpoints: &HashMap<usize, Breakpoint>,
+    ) -> Result<Option<Status>, nix::Error> {
+        let pid = match status.pid() {
+            Some(pid) => pid,
+            None => return Ok(None),
+        };
+        if !self.is_thread(pid) {
+            //a new thread or process can stop before the event that created it is reported.
+            //Anything else is from another inferior.
+            if let WaitStatus::Stopped(_, Signal::SIGSTOP) = status {
+                self.early_stops.borrow_mut().insert(pid);
+            }
+            return Ok(None);
+        }
+        if let WaitStatus::Exited(..) | WaitStatus::Signaled(..) = status {
+            if pid != self.process.get() {
+                let stepping = pid == self.pid() && !self.others_running.get();
+                self.remove_thread(pid);
+                //the program carries on if the thread being stepped, or run on its own, exits
+                if stepping {
+                    self.resume_other_threads()?;
+                    self.resume_with(false, self.signal_to_pass())?;
+                }
+                return Ok(None);
+            }
+            if let Some((parent, bytes)) = self.vfork_parent.take() {
+                release(parent, &bytes, "vfork")?;
+            }
+        }
+        self.pid.set(pid);
+        let status = match status {
+            WaitStatus::Exited(_pid, exit_code) => Status::Exited(exit_code),
+            WaitStatus::Signaled(_pid, signal, _core_dumped) => Status::Signaled(signal),
+            //the debugger's own SIGSTOP always stops the child, and is never passed to it
+            WaitStatus::Stopped(pid, Signal::SIGSTOP) if self.interrupt_sent.get() == Some(pid) => {
+                self.interrupt_sent.set(None);
+                let regs = self.get_registers()?;
+                Status::Stopped(Signal::SIGINT, regs.rip as usize)
+            }
+            WaitStatus::Stopped(_pid, signal) => {
+                if signal != Signal::SIGTRAP {
+                    let disposition = self.signals.get(signal);
+                    //a stop of the whole process, for a SIGSTOP that has already been delivered,
+                    //has no signal information and nothing left to pass on
+                    if ptrace::getsiginfo(self.pid()).is_ok() {
+                        self.stop_signal.set(Some(signal));
+                    }
+                    if !disposition.stop {
+                        if disposition.print {
+                            println!("\nProgram received signal {}, {}.", signal, signals::description(signal));
+                        }
+                        self.resume_with(self.single_stepping.get(), self.signal_to_pass())?;
+                        return Ok(None);
+                    }
+                }
+                let regs = self.get_registers()?;
+                if signal == Signal::SIGTRAP && self.recorded_step(regs.rip as usize, breakpoints)? {
+                    self.resume_with(false, None)?;
+                    return Ok(None);
+                }
+                Status::Stopped(signal, regs.rip as usize)
+            }
+            WaitStatus::PtraceEvent(_pid, _signal, event) => {
+                //an event the debugger doesn't follow is reported, with the child stopped for it
+                if !self.ptrace_event(event, breakpoints)? {
+                    Status::PtraceEvent(event, self.get_registers()?.rip as usize)
+                } else if self.exec_pending() {
+                    //stop at the entry point of a new program, like a SIGTRAP after exec without
+                    //PTRACE_O_TRACEEXEC, so that the debugger can load it
+                    let regs = self.get_registers()?;
+                    return Ok(Some(Status::Stopped(Signal::SIGTRAP, regs.rip as usize)));
+                } else {
+                    self.resume_with(self.single_stepping.get(), None)?;
+                    return Ok(None);
+                }
+            }
+            //a local child is traced without PTRACE_O_TRACESYSGOOD and waited for without
+            //WCONTINUED, so neither of these happens. Only a remote stub reports system calls, in
+            //`remote_status`.
+            WaitStatus::PtraceSyscall(_pid) => {
+                let regs = self.get_registers()?;
+                Status::Syscall(regs.orig_rax as usize, regs.rip as usize)
+            }
+            WaitStatus::Continued(_) => Status::Continued,
+            //only from a waitpid that doesn't block, when the child hasn't changed state
+            WaitStatus::StillAlive => return Ok(None),
+        };
+        if status.is_stop() {
+            self.stop_other_threads(breakpoints)?;
+        } else if !matches!(status, Status::Continued) {
+            self.others_running.set(false);
+        }
+        Ok(Some(status))
+    }
+
+    //The status of a program under a remote stub from the stop it reported, or None if it got a
+    //signal that doesn't stop it, in which case it has been resumed as in `status`. The stub
+    //reports the SIGINT from `interrupt` itself.
+    fn remote_status(&self, stop: Stop) -> Result<Option<Status>, nix::Error> {
+        let signal = match stop {
+            Stop::Exited(code) => return Ok(Some(Status::Exited(code))),
+            Stop::Killed(number) => return Ok(Some(Status::Signaled(Signal::try_from(number)?))),
+            Stop::Syscall(number) => return Ok(Some(Status::Syscall(number, self.get_registers()?.rip as usize))),
+            Stop::Signal(number) => Signal::try_from(number).unwrap_or(Signal::SIGTRAP),
+        };
+        if signal == Signal::SIGINT && self.interrupt_sent.take().is_some() {
+            return Ok(Some(Status::Stopped(signal, self.get_registers()?.rip as usize)));
+        }
+        if signal != Signal::SIGTRAP {
+            let disposition = self.signals.get(signal);
+            self.stop_signal.set(Some(signal));
+            if !disposition.stop {
+                if disposition.print {
+                    println!("\nProgram received signal {}, {}.", signal, signals::description(signal));
+                }
+                self.resume_with(self.single_stepping.get(), self.signal_to_pass())?;
+                return Ok(None);
+            }
+        }
+        Ok(Some(Status::Stopped(signal, self.get_registers()?.rip as usize)))
+    }
+
+    //Whether the child, being recorded, has only stopped after one of the instructions it is
+    //stepped through in place of running, rather than somewhere it was being run to: a breakpoint,
+    //which it is stopped at before the int3 runs, or a hardware watchpoint that has triggered.
+    fn recorded_step(&self, rip: usize, breakpoints: &HashMap<usize, Breakpoint>) -> Result<bool, nix::Error> {
+        if self.history.borrow().is_none() || self.single_stepping.get() {
+            return Ok(false);
+        }
+        if breakpoints.get(&rip).is_some_and(|b| b.enabled) {
+            return Ok(false);
+        }
+        Ok(self.read_debug_register(6)? & 0xf == 0)
+    }
+
+    //Report a fork, clone or exec the child stopped for, and return whether it was one of them. A
+    //new thread is debugged along with the others. A new process is let run on its own, without
+    //the breakpoints in the memory it was given a copy of, or shares with the child until it
+    //execs or exits after a vfork. An exec leaves the breakpoints behind with the old memory, so
+    //they are written again if the same program is run.
+    fn ptrace_event(&self, event: i32, breakpoints: &HashMap<usize, Breakpoint>) -> Result<bool, nix::Error> {
+        let software = breakpoints.values().filter(|b| b.enabled && b.hardware_slot.is_none());
+        let kind = match event {
+            libc::PTRACE_EVENT_FORK => "fork",
+            libc::PTRACE_EVENT_VFORK => "vfork",
+            libc::PTRACE_EVENT_CLONE => "clone",
+            libc::PTRACE_EVENT_VFORK_DONE => {
+                for breakpoint in software {
+                    write_process_bytes(self.pid(), breakpoint.addr, &[0xcc])?;
+                }
+                return Ok(true);
+            }
+            libc::PTRACE_EVENT_EXEC => {
+                if let Some((parent, bytes)) = self.vfork_parent.take() {
+                    release(parent, &bytes, "vfork")?;
+                }
+                //the other threads are gone, and the one that exec'd has become the main thread
+                self.reset_threads(self.process.get());
+                let program = std::fs::read_link(format!("/proc/{}/exe", self.pid())).unwrap_or_default();
+                println!("process {} is executing new program: {}", self.pid(), program.display());
+                //a position independent executable may be loaded somewhere else when it runs
+                //again, in which case it is followed like a different program
+                let base = self.load_address(&program.to_string_lossy());
+                if program != self.program || base != self.load_base.replace(base) {
+                    self.execed.replace(Some(program));
+                    return Ok(true);
+                }
+                for breakpoint in breakpoints.values().filter(|b| b.enabled) {
+                    match breakpoint.hardware_slot {
+                        Some(slot) => self.set_hardware_breakpoint(slot, breakpoint.addr)?,
+                        None => write_process_bytes(self.pid(), breakpoint.addr, &[0xcc])?,
+                    }
+                }
+                return Ok(true);
+            }
+            _ => return Ok(false),
+        };
+        //the new process or thread starts stopped, and is reported once it has
+        let new = Pid::from_raw(ptrace::getevent(self.pid())? as i32);
+        if !self.early_stops.borrow_mut().remove(&new) {
+            waitpid(new, Some(WaitPidFlag::__WALL))?;
+        }
+        let bytes: SavedBytes = software.map(|b| (b.addr, b.orig_byte)).collect();
+        match kind {
+            "clone" => {
+                let number = self.next_thread.get();
+                self.next_thread.set(number + 1);
+                self.threads.borrow_mut().push((number, new));
+                println!("[New thread {}]", new);
+                //it runs along with the others, unless an instruction is being stepped
+                if self.others_running.get() {
+                    ptrace::cont(new, None)?;
+                }
+                Ok(())
+            }
+            _ if self.follow_fork_mode == FollowForkMode::Child => self.follow_child(new, kind, bytes),
+            //a vforked child shares the parent's memory, and runs while the parent waits for it,
+            //so it is always let go
+            "fork" if !self.detach_on_fork => {
+                self.copy_debug_registers(new)?;
+                self.kept.borrow_mut().push(new);
+                Ok(())
+            }
+            _ => {
+                for (addr, orig_byte) in bytes {
+                    write_process_bytes(new, addr, &[orig_byte])?;
+                }
+                println!("[Detaching after {} from child process {}]", kind, new);
+                ptrace::detach(new, None)
+            }
+        }
+        .map(|()| true)
+    }
+
+    //Go on debugging the child the program forked, and let the parent go without the breakpoints,
+    //unless it is kept with `set detach-on-fork off`
+    fn follow_child(&self, child: Pid, kind: &str, bytes: SavedBytes) -> Result<(), nix::Error> {
+        let parent = self.pid();
+        println!("[Attaching after process {} {} to child process {}]", parent, kind, child);
+        self.copy_debug_registers(child)?;
+        self.reset_threads(child);
+        if kind == "vfork" {
+            self.vfork_parent.replace(Some((parent, bytes)));
+            Ok(())
+        } else if !self.detach_on_fork {
+            self.kept.borrow_mut().push(parent);
+            Ok(())
+        } else {
+            release(parent, &bytes, kind)
+        }
+    }
+
+    //A forked child has a copy of the breakpoints written into the parent's memory, but not of
+    //its debug registers, which are copied for the hardware breakpoints and watchpoints
+    fn copy_debug_registers(&self, child: Pid) -> Result<(), nix::Error> {
+        for index in &[0, 1, 2, 3, 7] {
+            write_process_debug_register(child, *index, self.read_debug_register(*index)?)?;
+        }
+        Ok(())
+    }
+
+    //Make the stopped thread fork, by having it run a fork system call in place of the instruction
+    //it is stopped at, and put it back as it was. The new process is kept stopped, with the same
+    //registers, and without the breakpoints, the bytes they replaced being written back into it.
+    pub fn fork_process(&mut self, breakpoints: &HashMap<usize, Breakpoint>) -> Result<Pid, nix::Error> {
+        let pid = self.pid();
+        let registers = self.get_registers()?;
+        let rip = registers.rip as usize;
+        let code = self.read_mem(rip, 2)?;
+        let mut call = registers;
+        call.rax = libc::SYS_fork as u64;
+        //not in a system call, so that the kernel doesn't restart one instead
+        call.orig_rax = u64::MAX;
+        self.write_bytes(rip, &[0x0f, 0x05])?;
+        self.set_registers(call)?;
+        let forked = loop {
+            ptrace::step(pid, None)?;
+            match waitpid(pid, Some(WaitPidFlag::__WALL))? {
+                WaitStatus::PtraceEvent(_, _, libc::PTRACE_EVENT_FORK) => {
+                    break Ok(Pid::from_raw(ptrace::getevent(pid)? as i32))
+                }
+                //a signal that came in first is dropped, and the system call tried again
+                WaitStatus::Stopped(_, signal) if signal != Signal::SIGTRAP => continue,
+                //the system call ran and failed, leaving the error in %rax
+                _ => {
+                    let errno = -(ptrace::getregs(pid)?.rax as i64);
+                    break Err(nix::Error::Sys(Errno::from_i32(errno as i32)));
+                }
+            }
+        };
+        if forked.is_ok() {
+            //finish the system call in the parent
+            ptrace::step(pid, None)?;
+            waitpid(pid, Some(WaitPidFlag::__WALL))?;
+        }
+        self.write_bytes(rip, &code)?;
+        self.registers.set(None);
+        self.set_registers(registers)?;
+        let child = forked?;
+        waitpid(child, Some(WaitPidFlag::__WALL))?;
+        write_process_bytes(child, rip, &code)?;
+        for breakpoint in breakpoints.values().filter(|b| b.enabled && b.hardware_slot.is_none()) {
+            write_process_bytes(child, breakpoint.addr, &[breakpoint.orig_byte])?;
+        }
+        ptrace::setregs(child, registers)?;
+        Ok(child)
+    }
+
+    /// Start recording the program, logging every instruction it runs from now on, which carries
+    /// on from `history` for a copy of a recorded program run forward with `replay`.
+    pub fn start_recording(&self, history: Vec<usize>) {
+        self.history.replace(Some(history));
+    }
+
+    /// Stop recording the program, which is run normally again.
+    pub fn stop_recording(&self) {
+        self.history.replace(None);
+    }
+
+    /// The addresses of the instructions the program has run since it started being recorded, or
+    /// None if it isn't.
+    pub fn history(&self) -> Option<Vec<usize>> {
+        self.history.borrow().clone()
+    }
+
+    //Run the process, a copy of a recorded one from where the recording started, forward through
+    //the instructions in `history` a step at a time, which brings it to the same point as long as
+    //the program does the same things again. Returns how many it got through before the program
+    //did something else, e.g. after a system call returned something different.
+    pub fn replay(&mut self, history: &[usize]) -> Result<usize, nix::Error> {
+        let pid = self.pid();
+        let mut count = 0;
+        let mut signal = None;
+        while count < history.len() {
+            if self.get_registers()?.rip as usize != history[count] {
+                break;
+            }
+            self.registers.set(None);
+            ptrace::step(pid, signal.take())?;
+            match waitpid(pid, Some(WaitPidFlag::__WALL))? {
+                WaitStatus::Stopped(_, Signal::SIGTRAP) => count += 1,
+                //the signal is delivered as the instruction is stepped again
+                WaitStatus::Stopped(_, other) => signal = Some(other),
+                WaitStatus::Exited(..) | WaitStatus::Signaled(..) => return Err(nix::Error::Sys(Errno::ESRCH)),
+                _ => break,
+            }
+        }
+        self.registers.set(None);
+        Ok(count)
+    }
+
+    //The processes from forks kept under the debugger since this was last called
+    pub fn take_kept(&self) -> Vec<Pid> {
+        self.kept.take()
+    }
+
+    //An inferior for a process forked from this one that has been kept, which is debugged the
+    //same way. It is stopped, and any signal it was stopped by isn't passed on.
+    pub fn fork_of(&self, pid: Pid) -> Inferior {
+        Inferior {
+            pid: Cell::new(pid),
+            process: Cell::new(pid),
+            threads: RefCell::new(vec![(1, pid)]),
+            next_thread: Cell::new(2),
+            others_running: Cell::new(false),
+            thread_signals: RefCell::new(HashMap::new()),
+            early_stops: RefCell::new(HashSet::new()),
+            shown_thread: Cell::new(pid),
+            left_threads: RefCell::new(Vec::new()),
+            interrupt_sent: Cell::new(None),
+            signals: self.signals.clone(),
+            stop_signal: Cell::new(None),
+            queued_signal: Cell::new(None),
+            single_stepping: Cell::new(false),
+            registers: Cell::new(None),
+            program: self.program.clone(),
+            load_base: Cell::new(self.load_base.get()),
+            follow_fork_mode: self.follow_fork_mode,
+            vfork_parent: RefCell::new(None),
+            execed: RefCell::new(None),
+            detach_on_fork: self.detach_on_fork,
+            scheduler_locking: self.scheduler_locking,
+            kept: RefCell::new(Vec::new()),
+            core: None,
+            remote: None,
+            history: RefCell::new(None),
+        }
+    }
+
+    //The program the process is running
+    pub fn program(&self) -> &Path {
+        &self.program
+    }
+
+    //The id of the process, which is that of its main thread
+    pub fn process_id(&self) -> Pid {
+        self.process.get()
+    }
+
+    //The threads of the process, with the numbers `info threads` shows them by
+    pub fn threads(&self) -> Vec<(usize, Pid)> {
+        self.threads.borrow().clone()
+    }
+
+    //The number of the thread being debugged
+    pub fn thread_number(&self) -> usize {
+        let threads = self.threads.borrow();
+        threads.iter().find(|(_, tid)| *tid == self.pid()).map_or(1, |(number, _)| *number)
+    }
+
+    //Debug another thread, by its number. The signal each thread stopped with stays with it, to
+    //be passed to it when it resumes.
+    pub fn select_thread(&self, number: usize) -> Result<Pid, String> {
+        let tid = self
+            .threads
+            .borrow()
+            .iter()
+            .find(|(n, _)| *n == number)
+            .map(|(_, tid)| *tid)
+            .ok_or(format!("Invalid thread ID: {}", number))?;
+        if tid != self.pid() {
+            if let Some(signal) = self.stop_signal.take() {
+                self.thread_signals.borrow_mut().insert(self.pid(), signal);
+            }
+            self.stop_signal.set(self.thread_signals.borrow_mut().remove(&tid));
+            self.left_threads.borrow_mut().push(self.pid());
+            self.pid.set(tid);
+        }
+        self.shown_thread.set(tid);
+        Ok(tid)
+    }
+
+    //Whether the thread that stopped is a different one from the one last reported or selected
+    pub fn thread_switched(&self) -> bool {
+        self.shown_thread.replace(self.pid()) != self.pid()
+    }
+
+    //The address a thread is stopped at
+    pub fn thread_rip(&self, tid: Pid) -> Result<usize, nix::Error> {
+        if let Some(core) = &self.core {
+            return Ok(core.thread(tid).ok_or(nix::Error::Sys(Errno::ESRCH))?.registers.rip as usize);
+        }
+        if self.remote.is_some() {
+            return Ok(self.get_registers()?.rip as usize);
+        }
+        Ok(ptrace::getregs(tid)?.rip as usize)
+    }
+
+    //Whether the process has exec'd a program the debugger hasn't loaded yet
+    pub fn exec_pending(&self) -> bool {
+        self.execed.borrow().is_some()
+    }
+
+    //The new program the process has exec'd, if the debugger has yet to load it. Breakpoints in
+    //the program are put back from then on whenever it execs itself again.
+    pub fn take_exec(&mut self) -> Option<PathBuf> {
+        let program = self.execed.take()?;
+        self.program = program.clone();
+        Some(program)
+    }
+
+    //Change which process is debugged the next time the program forks
+    pub fn set_follow_fork_mode(&mut self, mode: FollowForkMode) {
+        self.follow_fork_mode = mode;
+    }
+
+    //Change whether the process that isn't followed after a fork is let go
+    pub fn set_detach_on_fork(&mut self, detach: bool) {
+        self.detach_on_fork = detach;
+    }
+
+    //Change whether the other threads run along with the one being debugged
+    pub fn set_scheduler_locking(&mut self, locking: bool) {
+        self.scheduler_locking = locking;
+    }
+
+    //Stop the running child if Ctrl+C has been pressed and it doesn't stop for the SIGINT itself
+    pub fn check_interrupt(&self) -> Result<(), nix::Error> {
+        if interrupt::take_interrupt() && (self.remote.is_some() || !self.stops_for_interrupt()) {
+            self.interrupt()?;
+        }
+        Ok(())
+    }
+
+    //Stop the running child with a SIGSTOP, which is reported as a SIGINT when it stops. It is
+    //sent to the thread being debugged rather than the whole process, which the other threads
+    //would each stop for.
+    pub fn interrupt(&self) -> Result<(), nix::Error> {
+        if let Some(remote) = &self.remote {
+            remote.interrupt()?;
+            self.interrupt_sent.set(Some(self.pid()));
+            return Ok(());
+        }
+        let ret = unsafe {
+            libc::syscall(libc::SYS_tgkill, self.process.get().as_raw(), self.pid().as_raw(), libc::SIGSTOP)
+        };
+        Errno::result(ret)?;
+        self.interrupt_sent.set(Some(self.pid()));
+        Ok(())
+    }
+
+    //Whether a SIGSTOP from `interrupt` hasn't stopped the child yet
+    pub fn interrupt_pending(&self) -> bool {
+        self.interrupt_sent.get().is_some()
+    }
+
+    //Stop tracing the child and let it run on its own. Its hardware breakpoints and watchpoints
+    //are turned off first; the int3 instructions have to be taken out by whoever wrote them.
+    pub fn detach(&mut self) -> Result<(), nix::Error> {
+        if let Some(remote) = &self.remote {
+            return remote.detach();
+        }
+        self.write_debug_register(7, 0)?;
+        for tid in self.other_threads() {
+            let signal = self.thread_signals.borrow_mut().remove(&tid);
+            ptrace::detach(tid, signal)?;
+        }
+        ptrace::detach(self.pid(), self.signal_to_pass())
+    }
+
+    //Whether the child stops for a Ctrl+C by itself: it doesn't block SIGINT, and either gets it
+    //from the terminal, being in the foreground process group as it is unless it moves itself out,
+    //or has got it some other way, e.g. sent to the debugger's whole process group
+    fn stops_for_interrupt(&self) -> bool {
+        let status = match std::fs::read_to_string(format!("/proc/{}/status", self.pid())) {
+            Ok(status) => status,
+            Err(_) => return false,
+        };
+        let mask = |name: &str| {
+            status
+                .lines()
+                .find_map(|line| line.strip_prefix(name))
+                .and_then(|mask| u64::from_str_radix(mask.trim(), 16).ok())
+                .unwrap_or(0)
+        };
+        let sigint = 1 << (Signal::SIGINT as u64 - 1);
+        if mask("SigBlk:") & sigint != 0 {
+            return false;
+        }
+        let foreground = match (tcgetpgrp(libc::STDIN_FILENO), getpgid(Some(self.pid()))) {
+            (Ok(foreground), Ok(group)) => foreground == group,
+            _ => false,
+        };
+        foreground || (mask("SigPnd:") | mask("ShdPnd:")) & sigint != 0 || status.contains("State:\tt")
+    }
+
+    pub fn kill_child(&mut self) -> Result<(), nix::Error> {
+        //the process of a core file is already gone, and its pid may belong to another one now
+        if self.core.is_some() {
+            return Ok(());
+        }
+        if let Some(remote) = &self.remote {
+            return remote.kill();
+        }
+        let process = self.process.get();
+        signal::kill(process, Signal::SIGKILL)?;
+        //a vforked child's parent gets its memory back, and has the breakpoints taken out of it
+        if let Some((parent, bytes)) = self.vfork_parent.take() {
+            waitpid(process, Some(WaitPidFlag::__WALL))?;
+            return release(parent, &bytes, "vfork");
+        }
+        //the killed process is reaped, so that it isn't left a zombie. Its main thread is only
+        //reported once the others have been.
+        for (_, tid) in self.threads.take().into_iter().filter(|(_, tid)| *tid != process) {
+            loop {
+                match waitpid(tid, Some(WaitPidFlag::__WALL)) {
+                    Ok(WaitStatus::Exited(..)) | Ok(WaitStatus::Signaled(..)) | Err(_) => break,
+                    _ => (),
+                }
+            }
+        }
+        loop {
+            match waitpid(process, Some(WaitPidFlag::__WALL))? {
+                WaitStatus::Exited(..) | WaitStatus::Signaled(..) => return Ok(()),
+                _ => (),
+            }
+        }
+    }
+
+    //Read the child's memory mappings from /proc/<pid>/maps
+    pub fn memory_maps(&self) -> Vec<MemoryMap> {
+        if let Some(core) = &self.core {
+            return core.maps.clone();
+        }
+        //the program isn't on this machine
+        if self.remote.is_some() {
+            return Vec::new();
+        }
+        let maps = match std::fs::read_to_string(format!("/proc/{}/maps", self.pid())) {
+            Ok(maps) => maps,
+            Err(_) => return Vec::new(),
+        };
+        maps.lines()
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                let mut range = fields.first()?.split('-');
+                Some(MemoryMap {
+                    start: usize::from_str_radix(range.next()?, 16).ok()?,
+                    end: usize::from_str_radix(range.next()?, 16).ok()?,
+                    permissions: fields.get(1)?.to_string(),
+                    offset: usize::from_str_radix(fields.get(2)?, 16).ok()?,
+                    path: fields.get(5).unwrap_or(&"").to_string(),
+                })
+            })
+            .collect()
+    }
+
+    //Find where the file at `path` is mapped into the child's memory, from the lowest of its
+    //mappings
+    pub fn load_address(&self, path: &str) -> Option<usize> {
+        let path = std::fs::canonicalize(path).ok()?;
+        self.memory_maps()
+            .iter()
+            .filter(|map| std::path::Path::new(&map.path) == path)
+            .map(|map| map.start)
+            .min()
+    }
+
+    //Print the call stack backtrace, numbering every frame. `full` also prints the variables of
+    //every frame. `limit` is how many frames to print: the innermost ones, or the outermost ones
+    //if it's negative.
+    pub fn print_backtrace(
+        &self,
+        debug_data: &DwarfData,
+        settings: &Settings,
+        full: bool,
+        limit: Option<isize>,
+    ) -> Result<(), nix::Error> {
+        let frames = self.backtrace(debug_data)?;
+        let shown = match limit {
+            Some(n) if n >= 0 => 0..frames.len().min(n as usize),
+            Some(n) => frames.len().saturating_sub(n.unsigned_abs())..frames.len(),
+            None => 0..frames.len(),
+        };
+        for level in shown.clone() {
+            let frame = &frames[level];
+            println!("{}", frame_description(debug_data, level, frame.pc));
+            if !full {
+                continue;
+            }
+            match self.frame_variables(debug_data, settings, level, frame) {
+                Some(values) => {
+                    if values.is_empty() {
+                        println!("        No locals.");
+                    }
+                    for (name, value) in values {
+                        println!("        {} = {}", name, value);
+                    }
+                }
+                None => println!("No symbol table info available."),
+            }
+        }
+        if shown.end < frames.len() {
+            println!("(More stack frames follow...)");
+        }
+        Ok(())
+    }
+
+    //The arguments and local variables of frame number `level` of a backtrace with their values,
+    //or None if the frame has no debug info
+    pub fn frame_variables(
+        &self,
+        debug_data: &DwarfData,
+        settings: &Settings,
+        level: usize,
+        frame: &BacktraceFrame,
+    ) -> Option<Vec<(String, String)>> {
+        let func = debug_data.get_function_at(Frame::lookup(frame.pc, level))?;
+        Some(self.variable_values(debug_data, settings, &func.variables, frame.cfa))
+    }
+
+    //Read the values of variables in a frame whose canonical frame address is `cfa`, and format
+    //them for printing
+    pub fn variable_values<'v>(
+        &self,
+        debug_data: &DwarfData,
+        settings: &Settings,
+        variables: impl IntoIterator<Item = &'v Variable>,
+        cfa: usize,
+    ) -> Vec<(String, String)> {
+        let formatter = ValueFormatter::new(debug_data, Some(self), settings);
+        variables
+            .into_iter()
+            .map(|var| {
+                //a static local is at a fixed address, the others relative to the frame
+                let value = match self.read_mem(var.location.address(cfa), var.entity_type.size) {
+                    Ok(bytes) => formatter.format(&var.entity_type, &bytes),
+                    Err(_) => "<error: Cannot access memory>".to_string(),
+                };
+                (var.name.clone(), value)
+            })
+            .collect()
+    }
+
+    //Walk the call stack, innermost frame first, up to main or the first frame that can't be
+    //found in the debug info, which is included with neither a function nor a line
+    pub fn backtrace(&self, debug_data: &DwarfData) -> Result<Vec<BacktraceFrame>, nix::Error> {
+        let mut frames = Vec::new();
+        for (i, frame) in self.frames(debug_data)?.iter().enumerate() {
+            //Stopped on a call into a shared library (e.g. at a catchpoint)
+            if i == 0 {
+                if let Some(name) = debug_data.get_plt_name(frame.pc) {
+                    frames.push(BacktraceFrame {
+                        function: Some(format!("{}@plt", name)),
+                        line: None,
+                        pc: frame.pc,
+                        cfa: frame.cfa,
+                    });
+                    continue;
+                }
+            }
+            let lookup_ptr = frame.lookup_pc(i);
+            let addr = DwarfData::get_line_from_addr(debug_data, lookup_ptr);
+            let func_name = DwarfData::get_function_from_addr(debug_data, lookup_ptr);
+            if addr.is_none() || func_name.is_none() {
+                frames.push(BacktraceFrame {
+                    function: None,
+                    line: None,
+                    pc: frame.pc,
+                    cfa: frame.cfa,
+                });
+                break;
+            }
+            frames.push(BacktraceFrame {
+                function: func_name,
+                line: addr,
+                pc: frame.pc,
+                cfa: frame.cfa,
+            });
+        }
+        Ok(frames)
+    }
+
+    //Unwind the call stack, innermost frame first. Each frame is unwound by the call frame
+    //information if there is any for it, and otherwise by following the chain of saved %rbp. It
+    //stops at main or at the first frame outside any function with debug info, which is included.
+    pub fn frames(&self, debug_data: &DwarfData) -> Result<Vec<Frame>, nix::Error> {
+        let mut frames: Vec<Frame> = Vec::new();
+        let mut regs = self.get_registers()?;
+        loop {
+            let pc = regs.rip as usize;
+            let lookup_pc = Frame::lookup(pc, frames.len());
+            let func = debug_data.get_function_at(lookup_pc);
+            //a PLT stub pushes nothing, so the return address is on top of the stack just as on
+            //entry to a function
+            let func_addr = match func {
+                Some(func) => func.address,
+                None => pc,
+            };
+            let row = debug_data.unwind_row(lookup_pc);
+            let cfa = match &row {
+                Some(row) => match dwarf_register(&regs, row.cfa_register) {
+                    Some(base) => (base as i64 + row.cfa_offset) as usize,
+                    None => break,
+                },
+                None => frame_address(&regs, pc, func_addr),
+            };
+            let last = match func {
+                Some(func) => func.name == "main",
+                None => !frames.is_empty() || debug_data.get_plt_name(pc).is_none(),
+            };
+            frames.push(Frame { pc, cfa, regs });
+            if last {
+                break;
+            }
+            //the caller's stack pointer is the canonical frame address, by definition
+            let mut caller = regs;
+            caller.rsp = cfa as u64;
+            match row {
+                Some(row) => {
+                    caller.rip = 0;
+                    for (number, saved) in row.registers {
+                        let value = match saved {
+                            SavedRegister::AtCfa(offset) => {
+                                self.read_word((cfa as i64 + offset) as usize)?
+                            }
+                            SavedRegister::CfaPlus(offset) => (cfa as i64 + offset) as u64,
+                            SavedRegister::InRegister(other) => dwarf_register(&regs, other).unwrap_or(0),
+                            //the return address of the outermost frame is undefined
+                            SavedRegister::Undefined if number == RETURN_ADDRESS => 0,
+                            SavedRegister::Undefined => continue,
+                        };
+                        if let Some(field) = register_name(number).and_then(|name| register_field(&mut caller, name)) {
+                            *field = value;
+                        }
+                    }
+                }
+                None => {
+                    //the caller's %rbp was pushed by the prologue, unless it hasn't run yet
+                    caller.rip = self.read_word(cfa - 8)?;
+                    if func.is_some() && pc != func_addr && pc != func_addr + 1 {
+                        caller.rbp = self.read_word(cfa - 16)?;
+                    }
+                }
+            }
+            //a corrupt stack could otherwise loop forever
+            if caller.rsp <= regs.rsp || caller.rip == 0 {
+                break;
+            }
+            regs = caller;
+        }
+        Ok(frames)
+    }
+
+    //Read `len` bytes starting at `addr` from the child's address space in one go with
+    //process_vm_readv, falling back to /proc/<pid>/mem where that isn't allowed. Reading any
+    //unmapped byte is an error, like PTRACE_PEEKDATA.
+    pub fn read_mem(&self, addr: usize, len: usize) -> Result<Vec<u8>, nix::Error> {
+        if let Some(core) = &self.core {
+            return core.read_mem(addr, len);
+        }
+        if let Some(remote) = &self.remote {
+            return remote.read_mem(addr, len);
+        }
+        let mut bytes = vec![0; len];
+        if len == 0 {
+            return Ok(bytes);
+        }
+        let read = {
+            let local = [IoVec::from_mut_slice(&mut bytes)];
+            process_vm_readv(self.pid(), &local, &[RemoteIoVec { base: addr, len }])
+        };
+        match read {
+            Ok(n) if n == len => return Ok(bytes),
+            //a short read stops at the first page it can't read
+            Ok(_) => return Err(nix::Error::Sys(Errno::EIO)),
+            Err(_) => {}
+        }
+        let mem = File::open(format!("/proc/{}/mem", self.pid()))
+            .map_err(|e| nix::Error::Sys(Errno::from_i32(e.raw_os_error().unwrap_or(0))))?;
+        let mut done = 0;
+        while done < len {
+            match mem.read_at(&mut bytes[done..], (addr + done) as u64) {
+                Ok(0) => return Err(nix::Error::Sys(Errno::EIO)),
+                Ok(n) => done += n,
+                Err(e) => return Err(nix::Error::Sys(Errno::from_i32(e.raw_os_error().unwrap_or(0)))),
+            }
+        }
+        Ok(bytes)
+    }
+
+    //Read the 8-byte word at `addr`
+    pub fn read_word(&self, addr: usize) -> Result<u64, nix::Error> {
+        let bytes = self.read_mem(addr, size_of::<u64>())?;
+        let mut word = [0; 8];
+        word.copy_from_slice(&bytes);
+        Ok(u64::from_le_bytes(word))
+    }
+
+    //The floating point and SSE registers, which hold float and double return values in xmm0
+    pub fn get_fp_registers(&self) -> Result<libc::user_fpregs_struct, nix::Error> {
+        if self.remote.is_some() {
+            return Err(nix::Error::Sys(Errno::EOPNOTSUPP));
+        }
+        let mut registers: libc::user_fpregs_struct = unsafe { std::mem::zeroed() };
+        let ret = unsafe {
+            libc::ptrace(
+                libc::PTRACE_GETFPREGS,
+                self.pid().as_raw(),
+                std::ptr::null_mut::<libc::c_void>(),
+                &mut registers as *mut libc::user_fpregs_struct as *mut libc::c_void,
+            )
+        };
+        nix::errno::Errno::result(ret).map(|_| registers)
+    }
+
+    pub fn set_fp_registers(&mut self, mut registers: libc::user_fpregs_struct) -> Result<(), nix::Error> {
+        if self.remote.is_some() {
+            return Err(nix::Error::Sys(Errno::EOPNOTSUPP));
+        }
+        let ret = unsafe {
+            libc::ptrace(
+                libc::PTRACE_SETFPREGS,
+                self.pid().as_raw(),
+                std::ptr::null_mut::<libc::c_void>(),
+                &mut registers as *mut libc::user_fpregs_struct as *mut libc::c_void,
+            )
+        };
+        nix::errno::Errno::result(ret).map(drop)
+    }
+
+    //The x87, SSE and AVX registers. Without PTRACE_GETREGSET, such as on old kernels, the AVX
+    //registers are missing.
+    pub fn get_float_registers(&self) -> Result<FloatRegisters, nix::Error> {
+        if let Some(core) = &self.core {
+            let thread = core.thread(self.pid()).ok_or(nix::Error::Sys(Errno::ESRCH))?;
+            return Ok(FloatRegisters { area: thread.float_area.clone() });
+        }
+        if self.remote.is_some() {
+            return Err(nix::Error::Sys(Errno::EOPNOTSUPP));
+        }
+        let mut area = vec![0u8; 4096];
+        let mut iov = libc::iovec {
+            iov_base: area.as_mut_ptr() as *mut libc::c_void,
+            iov_len: area.len(),
+        };
+        let ret = unsafe {
+            libc::ptrace(
+                libc::PTRACE_GETREGSET,
+                self.pid().as_raw(),
+                NT_X86_XSTATE as *mut libc::c_void,
+                &mut iov as *mut libc::iovec as *mut libc::c_void,
+            )
+        };
+        if nix::errno::Errno::result(ret).is_ok() {
+            area.truncate(iov.iov_len);
+            return Ok(FloatRegisters { area });
+        }
+        let registers = self.get_fp_registers()?;
+        let area = unsafe {
+            std::slice::from_raw_parts(
+                &registers as *const libc::user_fpregs_struct as *const u8,
+                size_of::<libc::user_fpregs_struct>(),
+            )
+        };
+        Ok(FloatRegisters { area: area.to_vec() })
+    }
+
+    //Compute the canonical frame address of the innermost frame, which gcc uses as the frame base
+    //(DW_OP_call_frame_cfa) that local variables are located relative to.
+    //`pc` is the address of the instruction the child is stopped at and `func_addr` the entry of
+    //the function containing it.
+    pub fn frame_base(&self, pc: usize, func_addr: usize) -> Result<usize, nix::Error> {
+        Ok(frame_address(&self.get_registers()?, pc, func_addr))
+    }
+
+    //The registers of the thread being debugged, which are only read from it once per stop
+    pub fn get_registers(&self) -> Result<user_regs_struct, nix::Error> {
+        if let Some((pid, registers)) = self.registers.get() {
+            if pid == self.pid() {
+                return Ok(registers);
+            }
+        }
+        let registers = match (&self.core, &self.remote) {
+            (Some(core), _) => core.thread(self.pid()).ok_or(nix::Error::Sys(Errno::ESRCH))?.registers,
+            (_, Some(remote)) => remote.get_registers()?,
+            _ => ptrace::getregs(self.pid())?,
+        };
+        self.registers.set(Some((self.pid(), registers)));
+        Ok(registers)
+    }
+
+    //The registers of a core file are only changed in the cache, which is never emptied as the
+    //process doesn't run
+    pub fn set_registers(&mut self, registers: user_regs_struct) -> Result<(), nix::Error> {
+        self.registers.set(None);
+        if let Some(remote) = &self.remote {
+            remote.set_registers(&registers)?;
+        } else if self.core.is_none() {
+            ptrace::setregs(self.pid(), registers)?;
+        }
+        self.registers.set(Some((self.pid(), registers)));
+        Ok(())
+    }
+
+    //A remote stub's debug registers aren't reachable, so none are in use, and only turning them
+    //off works
+    fn read_debug_register(&self, index: usize) -> Result<u64, nix::Error> {
+        if self.remote.is_some() {
+            return Ok(0);
+        }
+        let offset = DEBUG_REGISTER_OFFSET + index * size_of::<u64>();
+        unsafe {
+            nix::errno::Errno::clear();
+            let value = libc::ptrace(
+                libc::PTRACE_PEEKUSER,
+                self.pid().as_raw(),
+                offset as *mut libc::c_void,
+                std::ptr::null_mut::<libc::c_void>(),
+            );
+            match nix::errno::Errno::last() {
+                nix::errno::Errno::UnknownErrno => Ok(value as u64),
+                errno => Err(nix::Error::Sys(errno)),
+            }
+        }
+    }
+
+    fn write_debug_register(&self, index: usize, value: u64) -> Result<(), nix::Error> {
+        if self.remote.is_some() {
+            return if value == 0 { Ok(()) } else { Err(nix::Error::Sys(Errno::EOPNOTSUPP)) };
+        }
+        write_process_debug_register(self.pid(), index, value)
+    }
+
+    //Make the CPU stop the child after it writes to `len` bytes at `addr`, using debug register
+    //`slot`. `len` must be 1, 2, 4 or 8 and `addr` aligned to it.
+    pub fn set_hardware_watchpoint(&self, slot: usize, addr: usize, len: usize) -> Result<(), nix::Error> {
+        let len_bits = match len {
+            1 => 0b00,
+            2 => 0b01,
+            8 => 0b10,
+            _ => 0b11,
+        };
+        self.write_debug_register(slot, addr as u64)?;
+        //DR7 has an enable bit per slot, and 4 bits per slot saying when to trigger (01 = on
+        //writes) and how many bytes to watch
+        let mut control = self.read_debug_register(7)?;
+        control &= !(0b11 << (2 * slot) | 0b1111 << (16 + 4 * slot));
+        control |= 1 << (2 * slot) | (0b01 | len_bits << 2) << (16 + 4 * slot);
+        self.write_debug_register(7, control)
+    }
+
+    //Make the CPU stop the child before it executes the instruction at `addr`, using debug
+    //register `slot`
+    pub fn set_hardware_breakpoint(&self, slot: usize, addr: usize) -> Result<(), nix::Error> {
+        self.write_debug_register(slot, addr as u64)?;
+        //00 in both the trigger and length bits of the slot means on executing the instruction
+        let mut control = self.read_debug_register(7)?;
+        control &= !(0b11 << (2 * slot) | 0b1111 << (16 + 4 * slot));
+        control |= 1 << (2 * slot);
+        self.write_debug_register(7, control)
+    }
+
+    //Turn off the hardware watchpoint or breakpoint using debug register `slot`
+    pub fn clear_hardware_slot(&self, slot: usize) -> Result<(), nix::Error> {
+        let control = self.read_debug_register(7)?;
+        self.write_debug_register(7, control & !(0b11 << (2 * slot)))
+    }
+
+    //Find which hardware watchpoint slots caused the last stop, and reset the record of it
+    pub fn triggered_hardware_watchpoints(&self) -> Result<Vec<usize>, nix::Error> {
+        let status = self.read_debug_register(6)?;
+        self.write_debug_register(6, 0)?;
+        Ok((0..HARDWARE_SLOTS)
+            .filter(|slot| status & (1 << slot) != 0)
+            .collect())
+    }
+
+    pub fn write_byte(&mut self, addr: usize, val: u8) -> Result<u8, nix::Error> {
+        let orig_byte = self.read_mem(addr, 1)?[0];
+        self.write_bytes(addr, &[val])?;
+        Ok(orig_byte)
+    }
+
+    //Write bytes into the inferior's memory
+    pub fn write_bytes(&mut self, addr: usize, bytes: &[u8]) -> Result<(), nix::Error> {
+        if let Some(core) = &self.core {
+            return core.write_mem(addr, bytes);
+        }
+        if let Some(remote) = &self.remote {
+            return remote.write_mem(addr, bytes);
+        }
+        write_process_bytes(self.pid(), addr, bytes)
+    }
+}
+
+//Let go of the parent of a followed child, putting back the bytes the breakpoints replaced and
+//turning off its debug registers
+fn release(parent: Pid, bytes: &[(usize, u8)], kind: &str) -> Result<(), nix::Error> {
+    for (addr, orig_byte) in bytes {
+        write_process_bytes(parent, *addr, &[*orig_byte])?;
+    }
+    write_process_debug_register(parent, 7, 0)?;
+    ptrace::detach(parent, None)?;
+    println!("[Detaching after {} from parent process {}]", kind, parent);
+    Ok(())
+}
+
+//Write bytes into the memory of a traced process a word at a time, keeping the bytes of the first
+//and last words that are outside the range
+fn write_process_debug_register(pid: Pid, index: usize, value: u64) -> Result<(), nix::Error> {
+    let offset = DEBUG_REGISTER_OFFSET + index * size_of::<u64>();
+    let ret = unsafe {
+        libc::ptrace(
+            libc::PTRACE_POKEUSER,
+            pid.as_raw(),
+            offset as *mut libc::c_void,
+            value as *mut libc::c_void,
+        )
+    };
+    nix::errno::Errno::result(ret).map(drop)
+}
+
+fn write_process_bytes(pid: Pid, addr: usize, bytes: &[u8]) -> Result<(), nix::Error> {
+    let end = addr + bytes.len();
+    let mut word_addr = align_addr_to_word(addr);
+    while word_addr < end {
+        let word_end = word_addr + size_of::<usize>();
+        let mut word = if word_addr >= addr && word_end <= end {
+            [0; 8]
+        } else {
+            (ptrace::read(pid, word_addr as ptrace::AddressType)? as u64).to_le_bytes()
+        };
+        for (i, byte) in word.iter_mut().enumerate() {
+            if (addr..end).contains(&(word_addr + i)) {
+                *byte = bytes[word_addr + i - addr];
+            }
+        }
+        ptrace::write(
+            pid,
+            word_addr as ptrace::AddressType,
+            u64::from_le_bytes(word) as *mut std::ffi::c_void,
+        )?;
+        word_addr = word_end;
+    }
+    Ok(())
+}
@@ -0,0 +1,26 @@
+#[cfg(feature = "cli")]
+use nix::sys::signal::{sigaction, SaFlags, SigAction, SigHandler, SigSet, Signal};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+//Set when Ctrl+C is pressed, until the debugger deals with it
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(feature = "cli")]
+extern "C" fn on_interrupt(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+//Catch the SIGINT Ctrl+C sends the debugger, rather than being killed by it. The handler is
+//installed without SA_RESTART, so that a waitpid the debugger is blocked in while the program
+//runs returns EINTR, and the debugger can stop the program if it didn't get the SIGINT too. Only
+//the prompt does this; a program embedding the debugger keeps its own handling of SIGINT.
+#[cfg(feature = "cli")]
+pub fn catch_interrupts() -> nix::Result<()> {
+    let action = SigAction::new(SigHandler::Handler(on_interrupt), SaFlags::empty(), SigSet::empty());
+    unsafe { sigaction(Signal::SIGINT, &action) }.map(|_| ())
+}
+
+//Whether Ctrl+C has been pressed since the last time this was called
+pub fn take_interrupt() -> bool {
+    INTERRUPTED.swap(false, Ordering::SeqCst)
+}
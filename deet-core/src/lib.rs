@@ -0,0 +1,39 @@
+//The debugger engine, for the `deet` command line and for programs that embed it, which drive it
+//through `Session`, or through the `Inferior` being debugged and the program's `DwarfData` for
+//more control. The prompt and its line editing are only built with the `cli` feature.
+
+//declared first, so that its `print!` and `println!` are the ones used in the other modules
+#[macro_use]
+mod logging;
+mod core_file;
+pub mod debugger;
+mod debugger_command;
+mod disassemble;
+pub mod inferior;
+pub mod dwarf_data;
+mod elf;
+mod exception;
+mod expression;
+mod gimli_wrapper;
+mod interrupt;
+#[cfg(feature = "cli")]
+mod line_editor;
+mod listing;
+mod remote;
+#[cfg(feature = "scripting")]
+mod script;
+pub mod server;
+pub mod session;
+pub mod settings;
+mod signals;
+mod source_cache;
+mod unwind;
+mod value_format;
+
+pub use debugger::{Breakpoint, Debugger};
+pub use debugger_command::Redirections;
+pub use dwarf_data::{DwarfData, Function, Line, Variable};
+pub use inferior::{Inferior, Status};
+pub use logging::capture;
+pub use session::{Event, Frame, Location, Session};
+pub use settings::Settings;
@@ -0,0 +1,375 @@
+use crate::debugger_command::{COMMANDS, INFO_COMMANDS};
+use crate::settings::{EditingMode, PrintSettings, Settings};
+use rustyline::completion::{Completer, FilenameCompleter, Pair};
+use rustyline::config::{Config, Configurer, EditMode};
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Cmd, Context, Editor, Helper, KeyPress, Movement};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::ffi::OsStrExt;
+
+//The number of lines of history kept for each target
+const HISTORY_SIZE: usize = 500;
+
+//Create the prompt's line editor, which completes the names of the given functions as well as
+//commands. A line typed twice in a row is only added to the history once.
+pub fn new_editor(functions: Vec<String>) -> Editor<Completions> {
+    let config = Config::builder()
+        .history_ignore_dups(true)
+        .max_history_size(HISTORY_SIZE)
+        .build();
+    let mut editor = Editor::with_config(config);
+    editor.set_helper(Some(Completions {
+        functions,
+        numbers: Vec::new(),
+        commands: Vec::new(),
+        files: FilenameCompleter::new(),
+    }));
+    editor
+}
+
+//What tab completes at the prompt: command names, the names of `info`, `set` and `show`
+//subcommands, function names where a location goes, file names after the redirections of `run`,
+//and the numbers of breakpoints where `delete`, `enable` and the like expect them
+pub struct Completions {
+    pub functions: Vec<String>,
+    //the numbers of the breakpoints, watchpoints and catchpoints, which the debugger keeps up to
+    //date before each prompt
+    pub numbers: Vec<usize>,
+    //aliases and commands defined with `define`, kept up to date the same way
+    pub commands: Vec<String>,
+    files: FilenameCompleter,
+}
+
+impl Completer for Completions {
+    type Candidate = Pair;
+
+    fn complete(&self, line: &str, pos: usize, ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let line = &line[..pos];
+        let start = line.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let word = &line[start..];
+        let words: Vec<&str> = line[..start].split_whitespace().collect();
+        let command = match words.first() {
+            Some(command) => *command,
+            None => {
+                let names = COMMANDS.iter().map(|(name, _)| *name).chain(self.commands.iter().map(String::as_str));
+                return Ok((start, matching(names, word)));
+            }
+        };
+        let names: Vec<String> = match command {
+            "r" | "run" => {
+                //the file can be right after the operator, as in `>out.txt`
+                let redirected = words.last().is_some_and(|w| is_redirection(w))
+                    || word.starts_with(['<', '>'])
+                    || word.starts_with("2>");
+                return if redirected { self.files.complete(line, pos, ctx) } else { Ok((pos, Vec::new())) };
+            }
+            "core" | "core-file" | "gcore" | "generate-core-file" | "cd" | "source" | "script" if words.len() == 1 => {
+                return self.files.complete(line, pos, ctx);
+            }
+            "i" | "info" if words.len() == 1 => INFO_COMMANDS.iter().map(|s| s.to_string()).collect(),
+            "set" | "show" if words.len() == 1 => Settings::NAMES.iter().map(|s| s.to_string()).collect(),
+            "set" | "show" if words.len() == 2 && words[1] == "print" => {
+                PrintSettings::NAMES.iter().map(|s| s.to_string()).collect()
+            }
+            "br" | "break" | "tb" | "tbreak" | "hb" | "hbreak" | "u" | "until" | "advance" | "j" | "jump"
+            | "l" | "list" | "disas" | "disassemble" | "dprintf"
+                if words.len() == 1 =>
+            {
+                self.functions.clone()
+            }
+            "d" | "delete" | "enable" | "disable" => {
+                let mut names: Vec<String> = self.numbers.iter().map(usize::to_string).collect();
+                if words.len() == 1 {
+                    names.extend(vec!["checkpoint".to_string(), "display".to_string()]);
+                }
+                names
+            }
+            "condition" | "ignore" | "commands" if words.len() == 1 => {
+                self.numbers.iter().map(usize::to_string).collect()
+            }
+            "catch" if words.len() == 1 => vec!["catch".to_string(), "throw".to_string()],
+            "rec" | "record" if words.len() == 1 => vec!["full".to_string(), "stop".to_string()],
+            _ => Vec::new(),
+        };
+        Ok((start, matching(names.iter().map(String::as_str), word)))
+    }
+}
+
+impl Hinter for Completions {}
+
+impl Highlighter for Completions {}
+
+impl Validator for Completions {}
+
+impl Helper for Completions {}
+
+//The names starting with what has been typed so far, in order and without repeats
+fn matching<'a>(names: impl Iterator<Item = &'a str>, word: &str) -> Vec<Pair> {
+    let mut names: Vec<&str> = names.filter(|name| name.starts_with(word)).collect();
+    names.sort();
+    names.dedup();
+    names
+        .into_iter()
+        .map(|name| Pair {
+            display: name.to_string(),
+            replacement: name.to_string(),
+        })
+        .collect()
+}
+
+fn is_redirection(word: &str) -> bool {
+    ["<", ">", ">>", "2>", "2>>"].contains(&word)
+}
+
+//Set up the prompt's line editor: the editing mode, searching history with the arrow keys, and
+//any settings and key bindings in the user's inputrc file
+pub fn configure(editor: &mut Editor<Completions>, settings: &mut Settings) {
+    //Up and down find earlier and later lines starting with what has been typed so far
+    editor.bind_sequence(KeyPress::Up, Cmd::HistorySearchBackward);
+    editor.bind_sequence(KeyPress::Down, Cmd::HistorySearchForward);
+    //Ctrl-R searches the history as you type, in vi mode as well as emacs mode
+    editor.bind_sequence(KeyPress::Ctrl('R'), Cmd::ReverseSearchHistory);
+    if let Some(file) = inputrc_path().and_then(|path| File::open(path).ok()) {
+        //Sections in `$if <name>` are for other programs, unless the name is ours
+        let mut skipping = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+            let line = line.trim();
+            if let Some(condition) = line.strip_prefix("$if") {
+                skipping.push(condition.trim() != "deet");
+            } else if line.starts_with("$else") {
+                if let Some(skip) = skipping.last_mut() {
+                    *skip = !*skip;
+                }
+            } else if line.starts_with("$endif") {
+                skipping.pop();
+            } else if !skipping.contains(&true) {
+                //The file is shared with other programs, so like readline we quietly ignore
+                //anything we don't understand
+                let _ = apply_inputrc_line(editor, settings, line);
+            }
+        }
+    }
+    set_editing_mode(editor, settings.editing_mode);
+}
+
+pub fn set_editing_mode(editor: &mut Editor<Completions>, mode: EditingMode) {
+    editor.set_edit_mode(match mode {
+        EditingMode::Emacs => EditMode::Emacs,
+        EditingMode::Vi => EditMode::Vi,
+    });
+}
+
+//Each target gets its own history file in ~/.deet_history.d, named after a hash of the target's
+//full path, so that commands for one program don't show up when debugging another. The hash is
+//64-bit FNV-1a, which unlike the standard library's hasher gives the same name with every build.
+pub fn history_path(target: &str) -> Option<String> {
+    let dir = format!("{}/.deet_history.d", std::env::var("HOME").ok()?);
+    std::fs::create_dir_all(&dir).ok()?;
+    let target = std::fs::canonicalize(target).unwrap_or_else(|_| target.into());
+    let hash = target.as_os_str().as_bytes().iter().fold(0xcbf29ce484222325u64, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    Some(format!("{}/{:016x}", dir, hash))
+}
+
+//Load the history file. Since lines are only ever appended to it, it is rewritten with just the
+//lines that are kept once it grows well past the history size.
+pub fn load_history(editor: &mut Editor<Completions>, path: &str) {
+    if editor.load_history(path).is_err() {
+        return;
+    }
+    let lines = match File::open(path) {
+        Ok(file) => BufReader::new(file).lines().count(),
+        Err(_) => return,
+    };
+    if lines > 2 * HISTORY_SIZE {
+        let _ = editor.save_history(path);
+    }
+}
+
+//Add a line to the end of the history file, rather than rewriting the whole file
+pub fn append_history(path: &str, line: &str) -> std::io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)
+}
+
+//Like readline, use $INPUTRC or else ~/.inputrc
+fn inputrc_path() -> Option<String> {
+    match std::env::var("INPUTRC") {
+        Ok(path) => Some(path),
+        Err(_) => Some(format!("{}/.inputrc", std::env::var("HOME").ok()?)),
+    }
+}
+
+//Apply a line of an inputrc file. Only `set editing-mode` and key bindings such as
+//`"\C-p": history-search-backward` or `Control-u: unix-line-discard` are understood; other
+//settings, which configure other programs' readline, are skipped.
+fn apply_inputrc_line(
+    editor: &mut Editor<Completions>,
+    settings: &mut Settings,
+    line: &str,
+) -> Result<(), String> {
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(());
+    }
+    if let Some(setting) = line.strip_prefix("set ") {
+        let mut parts = setting.split_whitespace();
+        if parts.next() == Some("editing-mode") {
+            settings.set("editing-mode", parts.next().unwrap_or(""))?;
+        }
+        return Ok(());
+    }
+    let separator = match line.strip_prefix('"') {
+        Some(rest) => rest.find('"').map(|end| end + 2),
+        None => line.find(':'),
+    };
+    let (key, function) = match separator {
+        Some(end) if line[end..].trim_start().starts_with(':') => {
+            (&line[..end], line[end..].trim_start()[1..].trim())
+        }
+        _ => return Err(format!("Cannot parse key binding \"{}\"", line)),
+    };
+    let key = parse_key(key.trim()).ok_or(format!("Unsupported key \"{}\"", key.trim()))?;
+    let cmd = parse_function(function).ok_or(format!("Unsupported command \"{}\"", function))?;
+    editor.bind_sequence(key, cmd);
+    Ok(())
+}
+
+//Parse a key as written in inputrc, either a name such as `Control-r` or `M-f`, or a quoted key
+//sequence such as `"\C-r"`, `"\e[A"` or `"x"`
+fn parse_key(key: &str) -> Option<KeyPress> {
+    if let Some(sequence) = key.strip_prefix('"').and_then(|k| k.strip_suffix('"')) {
+        return match sequence {
+            "\\e[A" => Some(KeyPress::Up),
+            "\\e[B" => Some(KeyPress::Down),
+            "\\e[C" => Some(KeyPress::Right),
+            "\\e[D" => Some(KeyPress::Left),
+            "\\e[H" => Some(KeyPress::Home),
+            "\\e[F" => Some(KeyPress::End),
+            "\\t" => Some(KeyPress::Tab),
+            _ => {
+                if let Some(c) = sequence.strip_prefix("\\C-") {
+                    single_char(c).map(|c| KeyPress::Ctrl(c.to_ascii_uppercase()))
+                } else if let Some(c) = sequence.strip_prefix("\\M-").or(sequence.strip_prefix("\\e")) {
+                    single_char(c).map(KeyPress::Meta)
+                } else {
+                    single_char(sequence).map(KeyPress::Char)
+                }
+            }
+        };
+    }
+    let (modifier, name) = match key.rfind('-') {
+        Some(i) if i + 1 < key.len() => (&key[..i], &key[i + 1..]),
+        _ => ("", key),
+    };
+    let named = match name.to_lowercase().as_str() {
+        "up" => Some(KeyPress::Up),
+        "down" => Some(KeyPress::Down),
+        "left" => Some(KeyPress::Left),
+        "right" => Some(KeyPress::Right),
+        "tab" => Some(KeyPress::Tab),
+        "home" => Some(KeyPress::Home),
+        "end" => Some(KeyPress::End),
+        "del" | "rubout" => Some(KeyPress::Backspace),
+        "esc" | "escape" => Some(KeyPress::Esc),
+        _ => None,
+    };
+    match modifier.to_lowercase().as_str() {
+        "" => named.or_else(|| single_char(name).map(KeyPress::Char)),
+        "control" | "c" => single_char(name).map(|c| KeyPress::Ctrl(c.to_ascii_uppercase())),
+        "meta" | "m" => single_char(name).map(KeyPress::Meta),
+        _ => None,
+    }
+}
+
+fn single_char(text: &str) -> Option<char> {
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+//Translate the name of a readline function to the line editor command that does the same
+fn parse_function(name: &str) -> Option<Cmd> {
+    Some(match name {
+        "beginning-of-line" => Cmd::Move(Movement::BeginningOfLine),
+        "end-of-line" => Cmd::Move(Movement::EndOfLine),
+        "forward-char" => Cmd::Move(Movement::ForwardChar(1)),
+        "backward-char" => Cmd::Move(Movement::BackwardChar(1)),
+        "kill-line" => Cmd::Kill(Movement::EndOfLine),
+        "unix-line-discard" | "backward-kill-line" => Cmd::Kill(Movement::BeginningOfLine),
+        "kill-whole-line" => Cmd::Kill(Movement::WholeLine),
+        "previous-history" => Cmd::PreviousHistory,
+        "next-history" => Cmd::NextHistory,
+        "beginning-of-history" => Cmd::BeginningOfHistory,
+        "end-of-history" => Cmd::EndOfHistory,
+        "history-search-backward" => Cmd::HistorySearchBackward,
+        "history-search-forward" => Cmd::HistorySearchForward,
+        "reverse-search-history" => Cmd::ReverseSearchHistory,
+        "forward-search-history" => Cmd::ForwardSearchHistory,
+        "clear-screen" => Cmd::ClearScreen,
+        "complete" => Cmd::Complete,
+        "transpose-chars" => Cmd::TransposeChars,
+        "transpose-words" => Cmd::TransposeWords(1),
+        "upcase-word" => Cmd::UpcaseWord,
+        "downcase-word" => Cmd::DowncaseWord,
+        "capitalize-word" => Cmd::CapitalizeWord,
+        "yank" => Cmd::Yank(1, rustyline::Anchor::Before),
+        "yank-pop" => Cmd::YankPop,
+        "quoted-insert" => Cmd::QuotedInsert,
+        "accept-line" => Cmd::AcceptLine,
+        "abort" => Cmd::Abort,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustyline::history::History;
+
+    //What tab offers at the end of `line`
+    fn complete(line: &str) -> Vec<String> {
+        let completions = Completions {
+            functions: vec!["main".to_string(), "make_list".to_string(), "add".to_string()],
+            numbers: vec![1, 12],
+            commands: vec!["mine".to_string()],
+            files: FilenameCompleter::new(),
+        };
+        let history = History::new();
+        let (_, pairs) = completions.complete(line, line.len(), &Context::new(&history)).unwrap();
+        pairs.into_iter().map(|pair| pair.replacement).collect()
+    }
+
+    #[test]
+    fn completes_command_names() {
+        assert_eq!(complete("disa"), ["disable", "disassemble"]);
+        assert_eq!(complete("mi"), ["mine"]);
+        assert!(complete("xyz").is_empty());
+    }
+
+    #[test]
+    fn completes_settings() {
+        assert_eq!(complete("set lis"), ["listsize"]);
+        assert_eq!(complete("show print e"), ["elements"]);
+        assert_eq!(complete("info sig"), ["signals"]);
+    }
+
+    #[test]
+    fn completes_functions_and_breakpoint_numbers() {
+        assert_eq!(complete("break ma"), ["main", "make_list"]);
+        assert_eq!(complete("tbreak a"), ["add"]);
+        assert_eq!(complete("delete 1"), ["1", "12"]);
+        //only the first argument is a location
+        assert!(complete("break main if ma").is_empty());
+        assert!(complete("run ma").is_empty());
+    }
+}
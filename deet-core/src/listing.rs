@@ -0,0 +1,102 @@
+//Where `list` is in a source file, so that `list` on its own carries on from the last listing,
+//or shows the lines around where the program stopped. Line numbers start at 1, and a `size` of 0
+//lists the whole file.
+pub struct Listing {
+    //path of the file
+    file: String,
+    //how many lines the file has
+    total: usize,
+    //the first and last lines the last listing showed
+    shown: Option<(usize, usize)>,
+    //the line the next `list` on its own is centered on, such as the line the program stopped at
+    center: Option<usize>,
+}
+
+impl Listing {
+    pub fn new(file: &str, total: usize) -> Listing {
+        Listing {
+            file: file.to_string(),
+            total,
+            shown: None,
+            center: None,
+        }
+    }
+
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    //The line searches start from: the last line listed, or the line centered on if nothing has
+    //been listed since, or 0 if neither
+    pub fn position(&self) -> usize {
+        match (self.shown, self.center) {
+            (Some((_, last)), _) => last,
+            (None, Some(line)) => line,
+            (None, None) => 0,
+        }
+    }
+
+    //Make the next `list` on its own show the lines around `line`
+    pub fn center_on(&mut self, line: usize) {
+        self.center = Some(line);
+        self.shown = None;
+    }
+
+    //The lines around `line`, half of them before it as in gdb
+    pub fn around(&mut self, line: usize, size: usize) -> Result<(usize, usize), String> {
+        if size == 0 {
+            return self.show(1, self.total);
+        }
+        let first = line.saturating_sub(size / 2).max(1);
+        self.show(first, first + size - 1)
+    }
+
+    //The lines after the last listing, or around the line centered on if nothing has been listed
+    //since. `default` is the line to center on if there is neither, e.g. the start of main.
+    pub fn forward(&mut self, size: usize, default: usize) -> Result<(usize, usize), String> {
+        match (self.shown, self.center) {
+            (Some((_, last)), _) if size == 0 => self.show(last + 1, self.total),
+            (Some((_, last)), _) => self.show(last + 1, last + size),
+            (None, Some(line)) => self.around(line, size),
+            (None, None) => self.around(default, size),
+        }
+    }
+
+    //The lines before the last listing, for `list -`
+    pub fn backward(&mut self, size: usize) -> Result<(usize, usize), String> {
+        let first = match (self.shown, self.center) {
+            (Some((first, _)), _) => first,
+            (None, Some(line)) => return self.around(line, size),
+            (None, None) => 1,
+        };
+        if first <= 1 {
+            return Err(format!("Already at the start of {}.", self.file_name()));
+        }
+        let last = first - 1;
+        let first = if size == 0 { 1 } else { last.saturating_sub(size - 1).max(1) };
+        self.show(first, last)
+    }
+
+    //The name of the file without its directory, for messages
+    fn file_name(&self) -> String {
+        match std::path::Path::new(&self.file).file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => self.file.clone(),
+        }
+    }
+
+    //Remember the lines about to be shown, cut off at the end of the file
+    fn show(&mut self, first: usize, last: usize) -> Result<(usize, usize), String> {
+        if first > self.total {
+            return Err(format!(
+                "Line number {} out of range; \"{}\" has {} lines.",
+                first,
+                self.file_name(),
+                self.total
+            ));
+        }
+        self.shown = Some((first, last.min(self.total)));
+        self.center = None;
+        Ok((first, last.min(self.total)))
+    }
+}
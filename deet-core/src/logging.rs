@@ -0,0 +1,89 @@
+//Copying the debugger's output to a log file, for `set logging on`. Everything the debugger prints
+//goes through the `print!` and `println!` below, which take the place of the standard ones in the
+//rest of the crate and also write to the log file while one is open. The program's own output
+//goes straight to the terminal, and isn't logged. A `Session` captures what would be printed
+//instead, so that a program embedding the debugger keeps its own output to itself.
+use std::cell::RefCell;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+
+thread_local! {
+    //the log file, while logging is on
+    static LOG: RefCell<Option<File>> = const { RefCell::new(None) };
+    //what has been printed while output is captured, by `capture`
+    static CAPTURED: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+macro_rules! print {
+    ($($arg:tt)*) => {
+        $crate::logging::print(format_args!($($arg)*))
+    };
+}
+
+macro_rules! println {
+    () => {
+        $crate::logging::print(format_args!("\n"))
+    };
+    ($($arg:tt)*) => {
+        $crate::logging::print(format_args!("{}\n", format_args!($($arg)*)))
+    };
+}
+
+//Write to standard output, or to the captured output while it is captured, and to the log file
+//if there is one
+pub fn print(args: fmt::Arguments) {
+    let captured = CAPTURED.with(|captured| match captured.borrow_mut().as_mut() {
+        Some(text) => fmt::Write::write_fmt(text, args).is_ok(),
+        None => false,
+    });
+    if !captured {
+        std::io::stdout().write_fmt(args).ok();
+    }
+    LOG.with(|log| {
+        if let Some(file) = log.borrow_mut().as_mut() {
+            file.write_fmt(args).ok();
+        }
+    });
+}
+
+//Start copying output to a file, in place of any log file already open. The file is added to
+//unless `overwrite`.
+pub fn start(path: &str, overwrite: bool) -> Result<(), String> {
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .append(!overwrite)
+        .truncate(overwrite)
+        .open(path)
+        .map_err(|e| format!("{}: {}.", path, e))?;
+    //output printed before logging started shouldn't turn up after what is printed next
+    std::io::stdout().flush().ok();
+    LOG.with(|log| log.replace(Some(file)));
+    Ok(())
+}
+
+//Stop copying output, closing the log file
+pub fn stop() {
+    LOG.with(|log| log.replace(None));
+}
+
+//Write a command that was typed to the log file, after the prompt, as it appeared on the terminal
+pub fn log_command(line: &str) {
+    LOG.with(|log| {
+        if let Some(file) = log.borrow_mut().as_mut() {
+            writeln!(file, "(deet) {}", line).ok();
+        }
+    });
+}
+
+/// Run `f` with what the debugger prints added to `output` rather than written to standard
+/// output, such as the threads and processes an `Inferior` reports as it runs. Captures can be
+/// nested, each going back to the one around it when it is done, so that every `Session` keeps
+/// its own output.
+pub fn capture<T>(output: &mut String, f: impl FnOnce() -> T) -> T {
+    let outer = CAPTURED.with(|captured| captured.replace(Some(std::mem::take(output))));
+    let result = f();
+    *output = CAPTURED.with(|captured| captured.replace(outer)).unwrap_or_default();
+    result
+}
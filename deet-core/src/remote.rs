@@ -0,0 +1,455 @@
+//The gdb remote serial protocol, for debugging a program that runs under gdbserver, or a stub
+//such as QEMU's, with `target remote <host>:<port>`. Packets are `$<data>#<checksum>`, each
+//acknowledged with `+`, or `-` to have it sent again. The inferior reads and writes the
+//program's memory and registers and resumes it through here rather than through ptrace.
+use crate::interrupt;
+use crate::inferior::register_field;
+use libc::user_regs_struct;
+use nix::errno::Errno;
+use nix::sys::signal::Signal;
+use nix::unistd::Pid;
+use std::cell::RefCell;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+//The registers at the start of a `g` packet for x86-64, in the order gdb numbers them: the
+//general purpose registers and %rip, 8 bytes each, then the flags and segment registers, 4 bytes
+//each. The x87 and SSE registers that follow aren't used.
+pub const REGISTERS: [(&str, usize); 24] = [
+    ("rax", 8),
+    ("rbx", 8),
+    ("rcx", 8),
+    ("rdx", 8),
+    ("rsi", 8),
+    ("rdi", 8),
+    ("rbp", 8),
+    ("rsp", 8),
+    ("r8", 8),
+    ("r9", 8),
+    ("r10", 8),
+    ("r11", 8),
+    ("r12", 8),
+    ("r13", 8),
+    ("r14", 8),
+    ("r15", 8),
+    ("rip", 8),
+    ("eflags", 4),
+    ("cs", 4),
+    ("ss", 4),
+    ("ds", 4),
+    ("es", 4),
+    ("fs", 4),
+    ("gs", 4),
+];
+
+//The most memory read or written with one packet
+const MEMORY_CHUNK: usize = 0x800;
+
+//How long to wait for the reply to anything but resuming the program
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+
+//How often to look for Ctrl+C while waiting for the program to stop
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+//Type of the auxiliary vector entry holding the program's entry point
+const AT_ENTRY: u64 = 9;
+
+//Why the program stopped, from a stop reply packet
+pub enum Stop {
+    //stopped with a signal
+    Signal(i32),
+    //exited with a status
+    Exited(i32),
+    //was killed by a signal
+    Killed(i32),
+    //stopped at the entry or exit of the system call with this number
+    Syscall(usize),
+}
+
+pub struct RemoteTarget {
+    stream: TcpStream,
+    reader: RefCell<BufReader<TcpStream>>,
+    //the process the stub debugs, which is only a number to show for a stub without processes
+    pid: Pid,
+    //where the program starts, from its auxiliary vector, to tell where a position independent
+    //executable has been loaded
+    entry: Option<usize>,
+}
+
+impl RemoteTarget {
+    //Connect to a stub listening at `address`, which has the program stopped
+    pub fn connect(address: &str) -> Result<RemoteTarget, String> {
+        let stream = TcpStream::connect(address).map_err(|e| format!("{}: {}.", address, e))?;
+        stream.set_nodelay(true).ok();
+        let reader = stream.try_clone().map_err(|e| format!("{}: {}.", address, e))?;
+        let mut remote = RemoteTarget {
+            stream,
+            reader: RefCell::new(BufReader::new(reader)),
+            pid: Pid::from_raw(1),
+            entry: None,
+        };
+        let failed = |e: nix::Error| format!("Remote connection to {} failed: {}.", address, e);
+        //a `+` first, as gdb sends, in case the stub is waiting to know acknowledgements are on
+        (&remote.stream).write_all(b"+").map_err(|e| failed(to_nix_error(e)))?;
+        remote.request("qSupported:swbreak+;hwbreak+;xmlRegisters=i386").map_err(failed)?;
+        remote.request("?").map_err(failed)?;
+        let current = remote.request("qC").map_err(failed)?;
+        if let Some(thread) = current.strip_prefix("QC") {
+            remote.pid = parse_thread(thread).unwrap_or(remote.pid);
+        }
+        remote.entry = remote.read_auxv().ok().and_then(|auxv| auxv_entry(&auxv));
+        Ok(remote)
+    }
+
+    pub fn pid(&self) -> Pid {
+        self.pid
+    }
+
+    //Where the program starts, if the stub says
+    pub fn entry(&self) -> Option<usize> {
+        self.entry
+    }
+
+    pub fn get_registers(&self) -> Result<user_regs_struct, nix::Error> {
+        let block = decode_hex(self.request("g")?.as_bytes())?;
+        let mut registers: user_regs_struct = unsafe { std::mem::zeroed() };
+        update_registers(&mut registers, &block);
+        Ok(registers)
+    }
+
+    //Write the registers with a `G` packet, which has to hold all of them, so those the debugger
+    //doesn't use are read first and sent back as they were
+    pub fn set_registers(&self, registers: &user_regs_struct) -> Result<(), nix::Error> {
+        let mut block = decode_hex(self.request("g")?.as_bytes())?;
+        registers_into_block(registers, &mut block);
+        self.expect_ok(&format!("G{}", encode_hex(&block)))
+    }
+
+    pub fn read_mem(&self, addr: usize, len: usize) -> Result<Vec<u8>, nix::Error> {
+        let mut bytes = Vec::with_capacity(len);
+        while bytes.len() < len {
+            let chunk = (len - bytes.len()).min(MEMORY_CHUNK);
+            let reply = self.request(&format!("m{:x},{:x}", addr + bytes.len(), chunk))?;
+            let read = decode_hex(reply.as_bytes())?;
+            //a stub may return less than asked for, up to memory it can't read
+            if read.is_empty() {
+                return Err(nix::Error::Sys(Errno::EIO));
+            }
+            bytes.extend(read);
+        }
+        bytes.truncate(len);
+        Ok(bytes)
+    }
+
+    pub fn write_mem(&self, addr: usize, bytes: &[u8]) -> Result<(), nix::Error> {
+        for (i, chunk) in bytes.chunks(MEMORY_CHUNK).enumerate() {
+            let at = addr + i * MEMORY_CHUNK;
+            self.expect_ok(&format!("M{:x},{:x}:{}", at, chunk.len(), encode_hex(chunk)))?;
+        }
+        Ok(())
+    }
+
+    //Continue the program, or run a single instruction if `single_step`, delivering `signal` to
+    //it. The stop reply is read by `wait`.
+    pub fn resume(&self, single_step: bool, signal: Option<Signal>) -> Result<(), nix::Error> {
+        let packet = match (single_step, signal) {
+            (false, None) => "c".to_string(),
+            (true, None) => "s".to_string(),
+            (false, Some(signal)) => format!("C{:02x}", signal as i32),
+            (true, Some(signal)) => format!("S{:02x}", signal as i32),
+        };
+        self.send(&packet)
+    }
+
+    //Wait for the program to stop after `resume`. Ctrl+C meanwhile has the stub stop it.
+    pub fn wait(&self) -> Result<Stop, nix::Error> {
+        loop {
+            let reply = self.receive(None)?;
+            if let Some(stop) = self.stop_reply(&reply)? {
+                return Ok(stop);
+            }
+        }
+    }
+
+    //Why the program stopped if it has, without waiting for it
+    pub fn poll(&self) -> Result<Option<Stop>, nix::Error> {
+        loop {
+            if self.reader.borrow().buffer().is_empty() {
+                self.stream.set_nonblocking(true).map_err(to_nix_error)?;
+                let mut byte = [0];
+                let waiting = self.stream.peek(&mut byte);
+                self.stream.set_nonblocking(false).map_err(to_nix_error)?;
+                match waiting {
+                    Ok(0) => return Err(nix::Error::Sys(Errno::ECONNRESET)),
+                    Ok(_) => (),
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(None),
+                    Err(e) => return Err(to_nix_error(e)),
+                }
+            }
+            let reply = self.receive(None)?;
+            if let Some(stop) = self.stop_reply(&reply)? {
+                return Ok(Some(stop));
+            }
+        }
+    }
+
+    //Have the stub stop the running program, which it reports as a SIGINT
+    pub fn interrupt(&self) -> Result<(), nix::Error> {
+        (&self.stream).write_all(&[0x03]).map_err(to_nix_error)
+    }
+
+    //Kill the program. Stubs don't agree on replying to `k`, and gdbserver exits once its only
+    //program is gone, so no reply is waited for.
+    pub fn kill(&self) -> Result<(), nix::Error> {
+        self.send("k")
+    }
+
+    //Let the program run on its own, and end the connection
+    pub fn detach(&self) -> Result<(), nix::Error> {
+        self.expect_ok("D")
+    }
+
+    //The stop a reply reports, printing output from the program that comes instead
+    fn stop_reply(&self, reply: &[u8]) -> Result<Option<Stop>, nix::Error> {
+        let number = |text: &[u8]| {
+            let digits = String::from_utf8_lossy(text.get(..2).unwrap_or(&[]));
+            i32::from_str_radix(&digits, 16).map_err(|_| nix::Error::Sys(Errno::EPROTO))
+        };
+        match reply.first() {
+            //a `T` reply can say the program stopped at a system call, as in
+            //`T05syscall_entry:e7;thread:p4d2.4d2;`
+            Some(b'T') => {
+                let signal = number(&reply[1..])?;
+                let text = String::from_utf8_lossy(&reply[3.min(reply.len())..]);
+                let syscall = text.split(';').find_map(|pair| match pair.split_once(':') {
+                    Some(("syscall_entry", number)) | Some(("syscall_return", number)) => {
+                        usize::from_str_radix(number, 16).ok()
+                    }
+                    _ => None,
+                });
+                Ok(Some(syscall.map_or(Stop::Signal(signal), Stop::Syscall)))
+            }
+            Some(b'S') => Ok(Some(Stop::Signal(number(&reply[1..])?))),
+            Some(b'W') => Ok(Some(Stop::Exited(number(&reply[1..])?))),
+            Some(b'X') => Ok(Some(Stop::Killed(number(&reply[1..])?))),
+            Some(b'O') if reply.len() > 1 => {
+                let output = decode_hex(&reply[1..])?;
+                print!("{}", String::from_utf8_lossy(&output));
+                std::io::stdout().flush().ok();
+                Ok(None)
+            }
+            _ => Err(nix::Error::Sys(Errno::EPROTO)),
+        }
+    }
+
+    //Read the auxiliary vector of the program, a part at a time
+    fn read_auxv(&self) -> Result<Vec<u8>, nix::Error> {
+        let mut auxv = Vec::new();
+        loop {
+            let reply = self.request_bytes(&format!("qXfer:auxv:read::{:x},{:x}", auxv.len(), MEMORY_CHUNK))?;
+            match reply.split_first() {
+                Some((b'm', data)) if !data.is_empty() => auxv.extend_from_slice(data),
+                Some((b'l', data)) => {
+                    auxv.extend_from_slice(data);
+                    return Ok(auxv);
+                }
+                _ => return Err(nix::Error::Sys(Errno::EOPNOTSUPP)),
+            }
+        }
+    }
+
+    //Send a packet and return the reply, which for requests is text
+    fn request(&self, packet: &str) -> Result<String, nix::Error> {
+        let reply = self.request_bytes(packet)?;
+        if reply.first() == Some(&b'E') && reply.len() == 3 {
+            return Err(nix::Error::Sys(Errno::EIO));
+        }
+        Ok(String::from_utf8_lossy(&reply).into_owned())
+    }
+
+    fn request_bytes(&self, packet: &str) -> Result<Vec<u8>, nix::Error> {
+        self.send(packet)?;
+        self.receive(Some(Instant::now() + REPLY_TIMEOUT))
+    }
+
+    //Send a packet whose reply is `OK` or an error
+    fn expect_ok(&self, packet: &str) -> Result<(), nix::Error> {
+        match self.request(packet)?.as_str() {
+            "OK" => Ok(()),
+            "" => Err(nix::Error::Sys(Errno::EOPNOTSUPP)),
+            _ => Err(nix::Error::Sys(Errno::EPROTO)),
+        }
+    }
+
+    //Send a packet, again until the stub acknowledges it
+    fn send(&self, packet: &str) -> Result<(), nix::Error> {
+        let framed = encode_packet(packet.as_bytes());
+        loop {
+            (&self.stream).write_all(&framed).map_err(to_nix_error)?;
+            match self.read_byte(Some(Instant::now() + REPLY_TIMEOUT))? {
+                b'+' => return Ok(()),
+                b'-' => continue,
+                _ => return Err(nix::Error::Sys(Errno::EPROTO)),
+            }
+        }
+    }
+
+    //Receive a packet, acknowledging it, before `deadline` if there is one. Without one, Ctrl+C
+    //meanwhile interrupts the program.
+    fn receive(&self, deadline: Option<Instant>) -> Result<Vec<u8>, nix::Error> {
+        loop {
+            while self.read_byte(deadline)? != b'$' {}
+            let mut data = Vec::new();
+            let mut sum: u8 = 0;
+            loop {
+                match self.read_byte(deadline)? {
+                    b'#' => break,
+                    byte => {
+                        sum = sum.wrapping_add(byte);
+                        data.push(byte);
+                    }
+                }
+            }
+            let digits = [self.read_byte(deadline)?, self.read_byte(deadline)?];
+            let expected = u8::from_str_radix(&String::from_utf8_lossy(&digits), 16).ok();
+            if expected == Some(sum) {
+                (&self.stream).write_all(b"+").map_err(to_nix_error)?;
+                return Ok(unescape(&data));
+            }
+            (&self.stream).write_all(b"-").map_err(to_nix_error)?;
+        }
+    }
+
+    fn read_byte(&self, deadline: Option<Instant>) -> Result<u8, nix::Error> {
+        let mut reader = self.reader.borrow_mut();
+        self.stream.set_read_timeout(Some(POLL_INTERVAL)).map_err(to_nix_error)?;
+        loop {
+            match reader.fill_buf() {
+                Ok([]) => return Err(nix::Error::Sys(Errno::ECONNRESET)),
+                Ok(buffer) => {
+                    let byte = buffer[0];
+                    reader.consume(1);
+                    return Ok(byte);
+                }
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut | ErrorKind::Interrupted) => {
+                    match deadline {
+                        Some(deadline) if Instant::now() >= deadline => return Err(nix::Error::Sys(Errno::ETIMEDOUT)),
+                        Some(_) => (),
+                        None if interrupt::take_interrupt() => self.interrupt()?,
+                        None => (),
+                    }
+                }
+                Err(e) => return Err(to_nix_error(e)),
+            }
+        }
+    }
+}
+
+//`$<data>#<checksum>`, with the characters that mean something in a packet escaped
+pub fn encode_packet(data: &[u8]) -> Vec<u8> {
+    let mut framed = vec![b'$'];
+    for byte in data {
+        if matches!(byte, b'$' | b'#' | b'}' | b'*') {
+            framed.extend_from_slice(&[b'}', byte ^ 0x20]);
+        } else {
+            framed.push(*byte);
+        }
+    }
+    let sum = framed[1..].iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+    framed.extend(format!("#{:02x}", sum).bytes());
+    framed
+}
+
+//Undo the escaping of a packet's data, and its run-length encoding, in which `*` and a count
+//stand for the byte before it repeated
+pub fn unescape(data: &[u8]) -> Vec<u8> {
+    let mut bytes: Vec<u8> = Vec::with_capacity(data.len());
+    let mut i = 0;
+    while i < data.len() {
+        match data[i] {
+            b'}' if i + 1 < data.len() => {
+                bytes.push(data[i + 1] ^ 0x20);
+                i += 1;
+            }
+            b'*' if i + 1 < data.len() && !bytes.is_empty() => {
+                let last = *bytes.last().unwrap();
+                let count = data[i + 1].saturating_sub(29) as usize;
+                bytes.extend(std::iter::repeat_n(last, count));
+                i += 1;
+            }
+            byte => bytes.push(byte),
+        }
+        i += 1;
+    }
+    bytes
+}
+
+pub fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+//Bytes from pairs of hex digits. Bytes a stub doesn't know are sent as `xx`, and read as 0.
+pub fn decode_hex(text: &[u8]) -> Result<Vec<u8>, nix::Error> {
+    if !text.len().is_multiple_of(2) {
+        return Err(nix::Error::Sys(Errno::EPROTO));
+    }
+    text.chunks(2)
+        .map(|pair| match pair {
+            b"xx" => Ok(0),
+            _ => u8::from_str_radix(&String::from_utf8_lossy(pair), 16).map_err(|_| nix::Error::Sys(Errno::EPROTO)),
+        })
+        .collect()
+}
+
+//Set the registers a `g` or `G` packet's block has, leaving the others as they are
+pub fn update_registers(registers: &mut user_regs_struct, block: &[u8]) {
+    let mut offset = 0;
+    for (name, size) in REGISTERS.iter() {
+        if let (Some(field), Some(bytes)) = (register_field(registers, name), block.get(offset..offset + size)) {
+            let mut value = [0; 8];
+            value[..*size].copy_from_slice(bytes);
+            *field = u64::from_le_bytes(value);
+        }
+        offset += size;
+    }
+}
+
+//Put the registers into a `g` packet's block, which is made longer if it is too short for them
+pub fn registers_into_block(registers: &user_regs_struct, block: &mut Vec<u8>) {
+    let mut registers = *registers;
+    let mut offset = 0;
+    for (name, size) in REGISTERS.iter() {
+        if block.len() < offset + size {
+            block.resize(offset + size, 0);
+        }
+        let value = register_field(&mut registers, name).map_or(0, |field| *field);
+        block[offset..offset + size].copy_from_slice(&value.to_le_bytes()[..*size]);
+        offset += size;
+    }
+}
+
+//A thread id as `qC` gives it, `p<pid>.<tid>` or just `<tid>`, in hex. The process id is the one
+//shown, or the thread's for a stub that doesn't say.
+fn parse_thread(thread: &str) -> Option<Pid> {
+    let thread = thread.strip_prefix('p').unwrap_or(thread);
+    let id = thread.split('.').next()?;
+    i32::from_str_radix(id, 16).ok().filter(|id| *id > 0).map(Pid::from_raw)
+}
+
+//The entry point from an auxiliary vector, which is pairs of 8-byte type and value
+fn auxv_entry(auxv: &[u8]) -> Option<usize> {
+    auxv.chunks_exact(16).find_map(|entry| {
+        let mut word = [0; 8];
+        word.copy_from_slice(&entry[..8]);
+        if u64::from_le_bytes(word) != AT_ENTRY {
+            return None;
+        }
+        word.copy_from_slice(&entry[8..]);
+        Some(u64::from_le_bytes(word) as usize)
+    })
+}
+
+fn to_nix_error(e: std::io::Error) -> nix::Error {
+    nix::Error::Sys(Errno::from_i32(e.raw_os_error().unwrap_or(libc::EIO)))
+}
@@ -0,0 +1,184 @@
+//Scripts in the Rhai language, loaded with `script <file>`. They can run debugger commands,
+//evaluate expressions, read memory and registers, decide whether a breakpoint stops the program
+//with an `on_stop` function, and print values of a type their own way with `add_printer`. Only
+//built with the `scripting` feature.
+use crate::debugger::Debugger;
+use crate::debugger_command::DebuggerCommand;
+use rhai::{Blob, CallFnOptions, Dynamic, Engine, EvalAltResult, FnPtr, Map, Scope, AST};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+thread_local! {
+    //the debugger while it runs a script, for the functions scripts call
+    static DEBUGGER: Cell<*mut Debugger> = const { Cell::new(std::ptr::null_mut()) };
+    //whether `on_stop` is running, while the program is in the middle of being resumed
+    static IN_STOP_HANDLER: Cell<bool> = const { Cell::new(false) };
+}
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+//the function a script defines to decide whether a breakpoint stops the program
+const STOP_HANDLER: &str = "on_stop";
+
+pub struct Scripts {
+    engine: Engine,
+    //the global variables of the scripts, which stay around for `on_stop` and printers
+    scope: Scope<'static>,
+    //the functions of every script loaded so far
+    ast: AST,
+    //the printers added with `add_printer`, with the names of the types they print
+    printers: Rc<RefCell<Vec<(String, FnPtr)>>>,
+}
+
+impl Scripts {
+    pub fn new() -> Scripts {
+        let mut engine = Engine::new();
+        engine.register_fn("execute", |command: &str| -> ScriptResult<()> {
+            with_current_debugger(|debugger| execute(debugger, command))
+        });
+        engine.register_fn("eval", |expression: &str| -> ScriptResult<Dynamic> {
+            with_current_debugger(|debugger| debugger.evaluate_expression(expression, "")).map(to_dynamic)
+        });
+        engine.register_fn("register", |name: &str| -> ScriptResult<Dynamic> {
+            with_current_debugger(|debugger| debugger.evaluate_expression(&format!("${}", name), "x")).map(to_dynamic)
+        });
+        engine.register_fn("read_memory", |address: i64, length: i64| -> ScriptResult<Blob> {
+            with_current_debugger(|debugger| debugger.read_memory(address as usize, length as usize))
+        });
+        let printers = Rc::new(RefCell::new(Vec::new()));
+        let added = printers.clone();
+        engine.register_fn("add_printer", move |type_name: &str, printer: FnPtr| {
+            added.borrow_mut().push((type_name.to_string(), printer));
+        });
+        Scripts {
+            engine,
+            scope: Scope::new(),
+            ast: AST::empty(),
+            printers,
+        }
+    }
+
+    //Run a script. The functions it defines can call those of scripts loaded before it, and
+    //replace them if they have the same name.
+    pub fn load(&mut self, debugger: &mut Debugger, path: &str) -> Result<(), String> {
+        let script = self.engine.compile_file(path.into()).map_err(|e| format!("{}: {}", path, e))?;
+        let ast = self.ast.merge(&script);
+        let (engine, scope) = (&self.engine, &mut self.scope);
+        with_debugger(debugger, || engine.run_ast_with_scope(scope, &ast)).map_err(|e| format!("{}: {}", path, e))?;
+        self.ast = ast.clone_functions_only();
+        Ok(())
+    }
+
+    //Whether a breakpoint the program has reached should stop it, which is up to the scripts'
+    //`on_stop` function if they have one. It is given a map of the breakpoint's number and
+    //where it is, and anything but `false` stops the program.
+    pub fn should_stop(
+        &mut self,
+        debugger: &mut Debugger,
+        number: usize,
+        addr: usize,
+        function: Option<String>,
+        line: Option<(String, usize)>,
+    ) -> bool {
+        if !self.ast.iter_functions().any(|f| f.name == STOP_HANDLER && f.params.len() == 1) {
+            return true;
+        }
+        let mut event = Map::new();
+        event.insert("breakpoint".into(), (number as i64).into());
+        event.insert("address".into(), (addr as i64).into());
+        event.insert("function".into(), function.map_or(Dynamic::UNIT, Dynamic::from));
+        let (file, line) = match line {
+            Some((file, number)) => (Dynamic::from(file), Dynamic::from(number as i64)),
+            None => (Dynamic::UNIT, Dynamic::UNIT),
+        };
+        event.insert("file".into(), file);
+        event.insert("line".into(), line);
+        let options = CallFnOptions::new().eval_ast(false);
+        let (engine, scope, ast) = (&self.engine, &mut self.scope, &self.ast);
+        IN_STOP_HANDLER.with(|handling| handling.set(true));
+        let result = with_debugger(debugger, || {
+            engine.call_fn_with_options::<Dynamic>(options, scope, ast, STOP_HANDLER, (event,))
+        });
+        IN_STOP_HANDLER.with(|handling| handling.set(false));
+        match result {
+            Ok(value) => value.as_bool().unwrap_or(true),
+            Err(e) => {
+                println!("Error in {}: {}", STOP_HANDLER, e);
+                true
+            }
+        }
+    }
+
+    //What the printer added last for a type makes of a value of it, given the expression the
+    //value came from. None if no printer was added for the type.
+    pub fn print(&mut self, debugger: &mut Debugger, type_name: &str, expression: &str) -> Option<Result<String, String>> {
+        let printer = self
+            .printers
+            .borrow()
+            .iter()
+            .rev()
+            .find(|(name, _)| name == type_name)
+            .map(|(_, printer)| printer.clone())?;
+        let (engine, ast) = (&self.engine, &self.ast);
+        let result = with_debugger(debugger, || printer.call::<Dynamic>(engine, ast, (expression.to_string(),)));
+        Some(result.map(|value| value.to_string()).map_err(|e| e.to_string()))
+    }
+}
+
+//Run `f` with the debugger available to the functions scripts call. Rhai only takes functions
+//that own what they use, so the debugger is handed to them as a pointer in `DEBUGGER`, which is
+//only set while `f` runs. `debugger` stays borrowed for that long and isn't used by anything but
+//`with_current_debugger`, and `Debugger` takes its `Scripts` out of itself while they run, so a
+//script can't start another one.
+fn with_debugger<T>(debugger: &mut Debugger, f: impl FnOnce() -> T) -> T {
+    let previous = DEBUGGER.with(|current| current.replace(debugger as *mut Debugger));
+    let result = f();
+    DEBUGGER.with(|current| current.set(previous));
+    result
+}
+
+//Call `f` on the debugger running the script. The pointer is taken out of `DEBUGGER` while `f`
+//runs, so that a script function called from inside it finds no debugger rather than making a
+//second `&mut Debugger`.
+fn with_current_debugger<T>(f: impl FnOnce(&mut Debugger) -> Result<T, String>) -> ScriptResult<T> {
+    let debugger = DEBUGGER.with(|current| current.replace(std::ptr::null_mut()));
+    if debugger.is_null() {
+        return Err("No program is being debugged.".into());
+    }
+    //SAFETY: the pointer came from the `&mut Debugger` given to `with_debugger`, which outlives
+    //this call and isn't used while the script runs, and it was taken out of `DEBUGGER`, so this
+    //is the only reference to the debugger until it is put back.
+    let result = f(unsafe { &mut *debugger });
+    DEBUGGER.with(|current| current.set(debugger));
+    result.map_err(|e| e.into())
+}
+
+//Run a debugger command for a script. `on_stop` runs while the program is being resumed, so it
+//can't resume the program itself.
+fn execute(debugger: &mut Debugger, command: &str) -> Result<(), String> {
+    match debugger.parse_line(command) {
+        Some(DebuggerCommand::Quit) => Err("Scripts cannot quit the debugger.".to_string()),
+        Some(cmd) if cmd.resumes() && IN_STOP_HANDLER.with(Cell::get) => {
+            Err(format!("\"{}\" cannot be run in {}.", command.trim(), STOP_HANDLER))
+        }
+        _ => {
+            debugger.run_script_command(command);
+            Ok(())
+        }
+    }
+}
+
+//A value as a number if it is one, so that scripts can do arithmetic with it, and otherwise the
+//text `print` would show
+fn to_dynamic(text: String) -> Dynamic {
+    if let Ok(number) = text.parse::<i64>() {
+        return number.into();
+    }
+    if let Some(number) = text.strip_prefix("0x").and_then(|hex| u64::from_str_radix(hex, 16).ok()) {
+        return (number as i64).into();
+    }
+    match text.parse::<f64>() {
+        Ok(number) => number.into(),
+        Err(_) => text.into(),
+    }
+}
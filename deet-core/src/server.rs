@@ -0,0 +1,418 @@
+//A stub for the gdb remote serial protocol, started with `deet --server <[host]:port> <program>`.
+//It runs the program under ptrace like `run` does and lets one frontend, such as gdb or another
+//deet with `target remote`, read and write its memory and registers, set breakpoints and run it
+//over a TCP connection. The server exits once the program does, or the frontend kills it or
+//detaches from it.
+use crate::debugger::Breakpoint;
+use crate::debugger_command::Redirections;
+use crate::inferior::{Inferior, Status};
+use crate::remote::{decode_hex, encode_hex, encode_packet, registers_into_block, unescape, update_registers, REGISTERS};
+use crate::settings::Settings;
+use nix::sys::signal::Signal;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+//How often to look for an interrupt from the frontend while the program runs
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+//The most bytes sent in one packet, which the frontend is told of
+const PACKET_SIZE: usize = 0x4000;
+
+//The connection to the frontend
+struct Connection {
+    stream: TcpStream,
+    reader: BufReader<TcpStream>,
+}
+
+impl Connection {
+    //The data of the next packet, acknowledged, or None once the frontend has gone
+    fn receive(&mut self) -> Option<Vec<u8>> {
+        self.stream.set_read_timeout(None).ok()?;
+        loop {
+            let mut start = Vec::new();
+            self.reader.read_until(b'$', &mut start).ok()?;
+            if start.last() != Some(&b'$') {
+                return None;
+            }
+            let mut data = Vec::new();
+            self.reader.read_until(b'#', &mut data).ok()?;
+            if data.pop() != Some(b'#') {
+                return None;
+            }
+            let mut digits = [0; 2];
+            std::io::Read::read_exact(&mut self.reader, &mut digits).ok()?;
+            let sum = data.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+            if u8::from_str_radix(&String::from_utf8_lossy(&digits), 16).ok() == Some(sum) {
+                self.stream.write_all(b"+").ok()?;
+                return Some(unescape(&data));
+            }
+            self.stream.write_all(b"-").ok()?;
+        }
+    }
+
+    //Send a packet, again until the frontend acknowledges it
+    fn send(&mut self, data: &[u8]) -> Result<(), String> {
+        let framed = encode_packet(data);
+        self.stream.set_read_timeout(None).map_err(|e| e.to_string())?;
+        loop {
+            self.stream.write_all(&framed).map_err(|e| e.to_string())?;
+            let mut ack = [0];
+            match std::io::Read::read(&mut self.reader, &mut ack) {
+                Ok(1) if ack[0] == b'+' => return Ok(()),
+                Ok(1) if ack[0] == b'-' => continue,
+                Ok(_) => return Err("Remote side has terminated connection.".to_string()),
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+    }
+
+    //Whether the frontend has sent a Ctrl+C (a 0x03 byte) to stop the running program, without
+    //waiting long for it
+    fn interrupted(&mut self) -> bool {
+        if self.stream.set_read_timeout(Some(POLL_INTERVAL)).is_err() {
+            return false;
+        }
+        match self.reader.fill_buf() {
+            Ok([0x03, ..]) => {
+                self.reader.consume(1);
+                true
+            }
+            Ok(_) => false,
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => false,
+            Err(_) => false,
+        }
+    }
+}
+
+struct Server {
+    inferior: Inferior,
+    //the breakpoints the frontend set with `Z0`, with the bytes they replaced
+    breakpoints: HashMap<usize, Breakpoint>,
+    connection: Connection,
+    //the reply to `?`, for the last time the program stopped
+    last_stop: String,
+}
+
+/// Run a program under the debugger and serve the gdb remote protocol for it at `address`,
+/// `host:port` or `:port` for every interface, until the session ends
+pub fn serve(address: &str, target: &str, args: &[String]) -> Result<(), String> {
+    let address = match address.strip_prefix(':') {
+        Some(port) => format!("0.0.0.0:{}", port),
+        None => address.to_string(),
+    };
+    let listener = TcpListener::bind(&address).map_err(|e| format!("Can't bind address {}: {}.", address, e))?;
+    let breakpoints = HashMap::new();
+    let inferior = Inferior::new(target, &args.to_vec(), &Redirections::default(), &breakpoints, &Settings::new())
+        .ok_or(format!("Cannot exec {}.", target))?;
+    println!("Process {} created; pid = {}", target, inferior.process_id());
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+    println!("Listening on port {}", port);
+    let (stream, peer) = listener.accept().map_err(|e| e.to_string())?;
+    println!("Remote debugging from host {}, port {}", peer.ip(), peer.port());
+    stream.set_nodelay(true).ok();
+    let reader = BufReader::new(stream.try_clone().map_err(|e| e.to_string())?);
+    let mut server = Server {
+        last_stop: format!("S{:02x}", Signal::SIGTRAP as i32),
+        inferior,
+        breakpoints,
+        connection: Connection { stream, reader },
+    };
+    server.run()
+}
+
+impl Server {
+    //Answer packets until the program is gone or the frontend lets it go
+    fn run(&mut self) -> Result<(), String> {
+        loop {
+            let packet = match self.connection.receive() {
+                Some(packet) => String::from_utf8_lossy(&packet).into_owned(),
+                //the program isn't left stopped with no one to run it
+                None => {
+                    println!("Remote side has terminated connection.  Killing the program.");
+                    self.inferior.kill_child().ok();
+                    return Ok(());
+                }
+            };
+            let reply = match packet.chars().next() {
+                Some('c') | Some('C') | Some('s') | Some('S') => {
+                    let (reply, ended) = self.resume(&packet);
+                    self.connection.send(reply.as_bytes())?;
+                    if ended {
+                        return Ok(());
+                    }
+                    continue;
+                }
+                Some('k') => {
+                    println!("Killing process {}", self.inferior.process_id());
+                    self.inferior.kill_child().ok();
+                    return Ok(());
+                }
+                Some('D') => {
+                    let reply = self.detach();
+                    self.connection.send(reply.as_bytes())?;
+                    println!("Detaching from process {}", self.inferior.process_id());
+                    return Ok(());
+                }
+                _ => self.answer(&packet),
+            };
+            self.connection.send(&reply)?;
+        }
+    }
+
+    //The reply to a packet that doesn't run the program. An empty reply means it isn't
+    //supported.
+    fn answer(&mut self, packet: &str) -> Vec<u8> {
+        let pid = self.inferior.process_id();
+        let reply = match packet {
+            "?" => self.last_stop.clone(),
+            "g" => match self.inferior.get_registers() {
+                Ok(registers) => {
+                    let mut block = Vec::new();
+                    registers_into_block(&registers, &mut block);
+                    encode_hex(&block)
+                }
+                Err(e) => error(e),
+            },
+            "qC" => format!("QCp{:x}.{:x}", pid.as_raw(), self.inferior.pid().as_raw()),
+            "qAttached" => "0".to_string(),
+            "qfThreadInfo" => {
+                let threads: Vec<String> =
+                    self.inferior.threads().iter().map(|(_, tid)| format!("p{:x}.{:x}", pid.as_raw(), tid.as_raw())).collect();
+                format!("m{}", threads.join(","))
+            }
+            "qsThreadInfo" => "l".to_string(),
+            _ if packet.starts_with("qSupported") => format!("PacketSize={:x};qXfer:auxv:read+", PACKET_SIZE),
+            _ if packet.starts_with("qXfer:auxv:read::") => return self.read_auxv(&packet["qXfer:auxv:read::".len()..]),
+            _ if packet.starts_with('H') || packet.starts_with('T') => "OK".to_string(),
+            _ if packet.starts_with('G') => self.write_registers(&packet[1..]),
+            _ if packet.starts_with('P') => self.write_register(&packet[1..]),
+            _ if packet.starts_with('m') => self.read_memory(&packet[1..]),
+            _ if packet.starts_with('M') => self.write_memory(&packet[1..]),
+            _ if packet.starts_with("Z0,") => self.insert_breakpoint(&packet[3..]),
+            _ if packet.starts_with("z0,") => self.remove_breakpoint(&packet[3..]),
+            _ => String::new(),
+        };
+        reply.into_bytes()
+    }
+
+    //Run the program for `c`, `C<signal>`, `s` or `S<signal>`, delivering the signal if there is
+    //one, and wait for it to stop, or for the frontend to interrupt it. Returns the stop reply, and
+    //whether the program is gone.
+    fn resume(&mut self, packet: &str) -> (String, bool) {
+        let single_step = packet.starts_with(['s', 'S']);
+        let signal = packet
+            .get(1..3)
+            .filter(|_| packet.starts_with(['C', 'S']))
+            .and_then(|number| i32::from_str_radix(number, 16).ok())
+            .and_then(|number| Signal::try_from(number).ok());
+        //the frontend says which signal the program gets, if any
+        self.inferior.queue_signal(signal);
+        let status = match self.run_until_stopped(single_step) {
+            Ok(status) => status,
+            Err(e) => return (error(e), false),
+        };
+        let stopped = |signal: Signal| {
+            format!(
+                "T{:02x}thread:p{:x}.{:x};",
+                signal as i32,
+                self.inferior.process_id().as_raw(),
+                self.inferior.pid().as_raw()
+            )
+        };
+        let (reply, ended) = match status {
+            Status::Stopped(signal, _) => (stopped(signal), false),
+            //stops the frontend can't have asked for look like a breakpoint to it. The program
+            //carrying on after a SIGCONT has been waited past.
+            Status::Syscall(..) | Status::PtraceEvent(..) | Status::Continued => (stopped(Signal::SIGTRAP), false),
+            Status::Exited(code) => {
+                println!("\nChild exited with status {}", code);
+                (format!("W{:02x}", code & 0xff), true)
+            }
+            Status::Signaled(signal) => {
+                println!("\nChild terminated with signal = {}", signal);
+                (format!("X{:02x}", signal as i32), true)
+            }
+        };
+        self.last_stop = reply.clone();
+        (reply, ended)
+    }
+
+    fn run_until_stopped(&mut self, single_step: bool) -> Result<Status, nix::Error> {
+        if let Some(status) = self.inferior.start(&self.breakpoints, single_step)? {
+            return Ok(status);
+        }
+        loop {
+            if let Some(status) = self.inferior.poll(&self.breakpoints)? {
+                if let Status::Continued = status {
+                    continue;
+                }
+                return if single_step { Ok(status) } else { self.inferior.stopped(status, &self.breakpoints) };
+            }
+            if self.connection.interrupted() && !self.inferior.interrupt_pending() {
+                self.inferior.interrupt()?;
+            }
+        }
+    }
+
+    //Take the breakpoints out and let the program go
+    fn detach(&mut self) -> String {
+        for breakpoint in self.breakpoints.values() {
+            self.inferior.write_byte(breakpoint.addr, breakpoint.orig_byte).ok();
+        }
+        match self.inferior.detach() {
+            Ok(()) => "OK".to_string(),
+            Err(e) => error(e),
+        }
+    }
+
+    fn write_registers(&mut self, hex: &str) -> String {
+        let result = decode_hex(hex.as_bytes()).and_then(|block| {
+            let mut registers = self.inferior.get_registers()?;
+            update_registers(&mut registers, &block);
+            self.inferior.set_registers(registers)
+        });
+        result.map_or_else(error, |_| "OK".to_string())
+    }
+
+    //`P<number>=<value>`, which sets one register, numbered as in a `g` packet
+    fn write_register(&mut self, args: &str) -> String {
+        let (number, value) = match args.split_once('=') {
+            Some((number, value)) => (usize::from_str_radix(number, 16).ok(), value),
+            None => (None, ""),
+        };
+        let number = match number.filter(|number| *number < REGISTERS.len()) {
+            Some(number) => number,
+            None => return "E01".to_string(),
+        };
+        let offset: usize = REGISTERS[..number].iter().map(|(_, size)| size).sum();
+        let result = decode_hex(value.as_bytes()).and_then(|bytes| {
+            let mut registers = self.inferior.get_registers()?;
+            let mut block = Vec::new();
+            registers_into_block(&registers, &mut block);
+            let end = (offset + bytes.len()).min(offset + REGISTERS[number].1);
+            block[offset..end].copy_from_slice(&bytes[..end - offset]);
+            update_registers(&mut registers, &block);
+            self.inferior.set_registers(registers)
+        });
+        result.map_or_else(error, |_| "OK".to_string())
+    }
+
+    //`m<addr>,<len>`, with the bytes the breakpoints replaced shown rather than the breakpoints
+    fn read_memory(&self, args: &str) -> String {
+        let (addr, len) = match parse_range(args) {
+            Some(range) => range,
+            None => return "E01".to_string(),
+        };
+        match self.inferior.read_mem(addr, len.min(PACKET_SIZE / 2)) {
+            Ok(mut bytes) => {
+                for (i, byte) in bytes.iter_mut().enumerate() {
+                    if let Some(breakpoint) = self.breakpoints.get(&(addr + i)) {
+                        *byte = breakpoint.orig_byte;
+                    }
+                }
+                encode_hex(&bytes)
+            }
+            Err(e) => error(e),
+        }
+    }
+
+    //`M<addr>,<len>:<bytes>`. A byte under a breakpoint becomes the one the breakpoint puts back.
+    fn write_memory(&mut self, args: &str) -> String {
+        let (range, hex) = match args.split_once(':') {
+            Some((range, hex)) => (parse_range(range), hex),
+            None => (None, ""),
+        };
+        let (addr, mut bytes) = match (range, decode_hex(hex.as_bytes())) {
+            (Some((addr, len)), Ok(bytes)) if bytes.len() == len => (addr, bytes),
+            _ => return "E01".to_string(),
+        };
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            if let Some(breakpoint) = self.breakpoints.get_mut(&(addr + i)) {
+                breakpoint.orig_byte = std::mem::replace(byte, 0xcc);
+            }
+        }
+        self.inferior.write_bytes(addr, &bytes).map_or_else(error, |_| "OK".to_string())
+    }
+
+    //`Z0,<addr>,<kind>`: a software breakpoint
+    fn insert_breakpoint(&mut self, args: &str) -> String {
+        let addr = match args.split(',').next().and_then(|addr| usize::from_str_radix(addr, 16).ok()) {
+            Some(addr) => addr,
+            None => return "E01".to_string(),
+        };
+        if self.breakpoints.contains_key(&addr) {
+            return "OK".to_string();
+        }
+        match self.inferior.write_byte(addr, 0xcc) {
+            Ok(orig_byte) => {
+                self.breakpoints.insert(addr, breakpoint(addr, orig_byte));
+                "OK".to_string()
+            }
+            Err(e) => error(e),
+        }
+    }
+
+    fn remove_breakpoint(&mut self, args: &str) -> String {
+        let addr = match args.split(',').next().and_then(|addr| usize::from_str_radix(addr, 16).ok()) {
+            Some(addr) => addr,
+            None => return "E01".to_string(),
+        };
+        match self.breakpoints.remove(&addr) {
+            Some(breakpoint) => {
+                self.inferior.write_byte(addr, breakpoint.orig_byte).map_or_else(error, |_| "OK".to_string())
+            }
+            None => "OK".to_string(),
+        }
+    }
+
+    //`qXfer:auxv:read::<offset>,<length>`, a part of the program's auxiliary vector, which tells
+    //the frontend where a position independent executable was loaded
+    fn read_auxv(&self, args: &str) -> Vec<u8> {
+        let (offset, len) = match parse_range(args) {
+            Some(range) => range,
+            None => return b"E01".to_vec(),
+        };
+        let auxv = match std::fs::read(format!("/proc/{}/auxv", self.inferior.process_id())) {
+            Ok(auxv) => auxv,
+            Err(_) => return b"E01".to_vec(),
+        };
+        let start = offset.min(auxv.len());
+        let end = (start + len.min(PACKET_SIZE / 2)).min(auxv.len());
+        let mut reply = vec![if end == auxv.len() { b'l' } else { b'm' }];
+        reply.extend_from_slice(&auxv[start..end]);
+        reply
+    }
+}
+
+//A breakpoint set by the frontend, which the inferior steps over when it resumes from it
+fn breakpoint(addr: usize, orig_byte: u8) -> Breakpoint {
+    Breakpoint {
+        number: None,
+        addr,
+        orig_byte,
+        enabled: true,
+        group: None,
+        condition: None,
+        hit_count: 0,
+        ignore_count: 0,
+        temporary: false,
+        commands: Vec::new(),
+        dprintf: None,
+        hardware_slot: None,
+        location: None,
+    }
+}
+
+//`<addr>,<len>` in hex
+fn parse_range(args: &str) -> Option<(usize, usize)> {
+    let (addr, len) = args.split_once(',')?;
+    Some((usize::from_str_radix(addr, 16).ok()?, usize::from_str_radix(len, 16).ok()?))
+}
+
+//An error reply, with the errno
+fn error(e: nix::Error) -> String {
+    format!("E{:02x}", e.as_errno().map_or(libc::EIO, |errno| errno as i32) & 0xff)
+}
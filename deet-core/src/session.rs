@@ -0,0 +1,232 @@
+use crate::debugger::Debugger;
+use crate::debugger_command::DebuggerCommand;
+use crate::logging;
+use nix::sys::signal::Signal;
+use nix::unistd::Pid;
+
+/// Something that happened to the program being debugged while a command ran
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// The program stopped, at a breakpoint, watchpoint or catchpoint, or because of a signal.
+    /// The function and source line are None if `addr` has no debug info.
+    Stopped {
+        signal: Signal,
+        addr: usize,
+        function: Option<String>,
+        file: Option<String>,
+        line: Option<usize>,
+        /// the number of the catchpoint that stopped the program, if any
+        catchpoint: Option<usize>,
+    },
+
+    /// The program exited normally, with this exit status
+    Exited(i32),
+
+    /// The program was killed by a signal
+    Signaled(Signal),
+
+    /// The program stopped at the entry or exit of the system call with this number, at `addr`
+    Syscall { number: usize, addr: usize },
+
+    /// The program stopped at `addr` for a ptrace event the debugger doesn't follow
+    PtraceEvent { event: i32, addr: usize },
+
+    /// The program was resumed by a SIGCONT, and carries on running in the background
+    Continued,
+
+    /// The debugger detached from the program, which carries on running on its own as this
+    /// process
+    Detached(Pid),
+}
+
+/// A frame of the call stack of the stopped program
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    /// None if the frame is not in any function with debug info
+    pub function: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<usize>,
+    /// the arguments and local variables of the frame and their values, as `bt full` shows them
+    pub variables: Vec<(String, String)>,
+}
+
+/// Where an address is in the source of the program
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    /// None if the address is not in any function with debug info
+    pub function: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<usize>,
+}
+
+/// A debugging session that is driven by code rather than the terminal, e.g. from tests:
+///
+/// ```no_run
+/// use deet_core::session::{Event, Session};
+///
+/// let mut session = Session::new("samples/function_calls").unwrap();
+/// session.set_breakpoint("func2").unwrap();
+/// let events = session.run_command("run").unwrap();
+/// assert!(matches!(&events[0], Event::Stopped { function: Some(f), .. } if f == "func2"));
+/// ```
+///
+/// What commands would print is kept rather than printed, and `take_output` returns it; the
+/// events and results returned are the part meant for checking. The program's own output still
+/// goes to standard output.
+pub struct Session {
+    debugger: Debugger,
+    //what the commands run have printed since `take_output` was last called
+    output: String,
+}
+
+impl Session {
+    /// Load the debug info of the program at `target`, without running it yet
+    pub fn new(target: &str) -> Result<Session, String> {
+        let debugger = Debugger::load(target)?;
+        Ok(Session {
+            debugger,
+            output: String::new(),
+        })
+    }
+
+    /// What the commands run have printed since the session started, or since this was last
+    /// called
+    pub fn take_output(&mut self) -> String {
+        std::mem::take(&mut self.output)
+    }
+
+    /// Run a command as if it was typed at the prompt, and return what happened to the program
+    /// while it ran. Commands that start a block (such as `commands`) go through `run_block`.
+    pub fn run_command(&mut self, line: &str) -> Result<Vec<Event>, String> {
+        if line.trim().is_empty() || DebuggerCommand::starts_block(line) {
+            return Err(format!("Unsupported command \"{}\"", line.trim()));
+        }
+        self.captured(|debugger| {
+            let cmd = debugger
+                .parse_line(line)
+                .ok_or(format!("Unrecognized command \"{}\"", line.trim()))?;
+            debugger.execute(cmd);
+            Ok(debugger.take_events())
+        })
+    }
+
+    /// Run a command that starts a block, such as `commands 1`, with the lines of the block
+    /// before its `end`
+    pub fn run_block(&mut self, line: &str, body: &[&str]) -> Result<Vec<Event>, String> {
+        let body = body.iter().map(|line| line.trim().to_string()).collect();
+        let cmd = DebuggerCommand::from_block(line, body)
+            .ok_or(format!("Unsupported command \"{}\"", line.trim()))?;
+        self.captured(|debugger| {
+            debugger.execute(cmd);
+            Ok(debugger.take_events())
+        })
+    }
+
+    /// Wait for the program running in the background, after `run &` or `continue &`, to stop,
+    /// and return what happened to it
+    pub fn wait(&mut self) -> Result<Vec<Event>, String> {
+        self.captured(|debugger| {
+            debugger.wait_for_stop()?;
+            Ok(debugger.take_events())
+        })
+    }
+
+    /// The call stack of the stopped program, innermost frame first
+    pub fn backtrace(&self) -> Result<Vec<Frame>, String> {
+        Ok(self
+            .debugger
+            .backtrace()?
+            .into_iter()
+            .enumerate()
+            .map(|(level, frame)| Frame {
+                variables: self.debugger.frame_variables(level, &frame).unwrap_or_default(),
+                function: frame.function,
+                file: frame.line.as_ref().map(|line| line.file.clone()),
+                line: frame.line.map(|line| line.number),
+            })
+            .collect())
+    }
+
+    /// Evaluate a C expression in the current frame, formatted as `output` would print it
+    pub fn evaluate(&mut self, expression: &str) -> Result<String, String> {
+        self.captured(|debugger| debugger.evaluate_expression(expression, ""))
+    }
+
+    /// Evaluate a C expression like `evaluate`, with its numbers shown in the format given by a
+    /// letter such as `x` for hex, as `output/x` would print it
+    pub fn evaluate_formatted(&mut self, format: &str, expression: &str) -> Result<String, String> {
+        self.captured(|debugger| debugger.evaluate_expression(expression, format))
+    }
+
+    /// Show memory as `x/<format> <address>` would, e.g. `examine("4xw", "&x")`, and return the
+    /// lines it would print
+    pub fn examine(&mut self, format: &str, address: &str) -> Result<Vec<String>, String> {
+        self.captured(|debugger| debugger.examine_memory(format, address))
+    }
+
+    /// The lines `disassemble <args>` would show, e.g. `disassemble("/r add")`
+    pub fn disassemble(&mut self, args: &str) -> Result<Vec<String>, String> {
+        self.captured(|debugger| debugger.disassemble("", args))
+    }
+
+    /// The source lines `list <args>` would show, each after its number and a tab
+    pub fn list(&mut self, args: &str) -> Result<Vec<String>, String> {
+        self.captured(|debugger| debugger.list(args))
+    }
+
+    /// The line `search <regex>` would find, after its number, or the one `reverse-search` would
+    /// with `forward` false
+    pub fn search(&mut self, pattern: &str, forward: bool) -> Result<String, String> {
+        self.captured(|debugger| debugger.search(pattern, forward))
+    }
+
+    /// The local variables of the selected frame and their values, as `info locals` shows them
+    pub fn locals(&self) -> Result<Vec<(String, String)>, String> {
+        self.debugger.locals()
+    }
+
+    /// The addresses of the breakpoints that have been set
+    pub fn breakpoints(&self) -> Vec<usize> {
+        self.debugger.breakpoint_addresses()
+    }
+
+    /// Set a breakpoint as `break <location>` does, e.g. `set_breakpoint("calls.c:11")`, and
+    /// return its number
+    pub fn set_breakpoint(&mut self, location: &str) -> Result<usize, String> {
+        self.captured(|debugger| debugger.break_at(location))
+    }
+
+    /// The address of a function or a line, given as `break` takes them
+    pub fn address_of(&self, location: &str) -> Option<usize> {
+        self.debugger.location_address(location)
+    }
+
+    /// The function and source line an address is in
+    pub fn location_of(&self, addr: usize) -> Location {
+        let (function, line) = self.debugger.location_of(addr);
+        Location {
+            function,
+            file: line.as_ref().map(|line| line.file.clone()),
+            line: line.map(|line| line.number),
+        }
+    }
+
+    /// The names of the functions the program defines
+    pub fn functions(&self) -> Vec<String> {
+        self.debugger.functions()
+    }
+
+    //Run `f` on the debugger, keeping what it prints in this session's output rather than that of
+    //another session on the thread
+    fn captured<T>(&mut self, f: impl FnOnce(&mut Debugger) -> T) -> T {
+        let debugger = &mut self.debugger;
+        logging::capture(&mut self.output, || f(debugger))
+    }
+}
+
+impl Drop for Session {
+    //don't leave the program behind, stopped and traced
+    fn drop(&mut self) {
+        self.captured(|debugger| debugger.execute(DebuggerCommand::Quit));
+    }
+}
@@ -0,0 +1,419 @@
+use std::fmt;
+
+//Character set used to decode narrow (char) strings read from the inferior
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Charset {
+    Utf8,
+    Ascii,
+    Latin1,
+}
+
+impl Charset {
+    fn from_name(name: &str) -> Option<Charset> {
+        match name.to_uppercase().as_str() {
+            "UTF-8" | "UTF8" => Some(Charset::Utf8),
+            "ASCII" | "US-ASCII" => Some(Charset::Ascii),
+            "ISO-8859-1" | "LATIN1" | "LATIN-1" => Some(Charset::Latin1),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Charset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Charset::Utf8 => write!(f, "UTF-8"),
+            Charset::Ascii => write!(f, "ASCII"),
+            Charset::Latin1 => write!(f, "ISO-8859-1"),
+        }
+    }
+}
+
+//Key bindings used to edit the line typed at the prompt
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EditingMode {
+    Emacs,
+    Vi,
+}
+
+impl fmt::Display for EditingMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            EditingMode::Emacs => write!(f, "emacs"),
+            EditingMode::Vi => write!(f, "vi"),
+        }
+    }
+}
+
+//Which process is debugged after the program forks
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FollowForkMode {
+    Parent,
+    Child,
+}
+
+impl fmt::Display for FollowForkMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            FollowForkMode::Parent => write!(f, "parent"),
+            FollowForkMode::Child => write!(f, "child"),
+        }
+    }
+}
+
+//Settings controlling how values are printed, changed with `set print <name> <value>`
+pub struct PrintSettings {
+    //print one struct member per line, indented by nesting level
+    pub pretty: bool,
+    //maximum number of array elements or string characters printed, or None for no limit
+    pub elements: Option<usize>,
+    //structs, unions and arrays nested deeper than this are shown as {...}
+    pub max_depth: Option<usize>,
+    //how a null pointer is shown
+    pub null_pointer: String,
+}
+
+impl PrintSettings {
+    fn new() -> PrintSettings {
+        PrintSettings {
+            pretty: false,
+            elements: Some(200),
+            max_depth: Some(20),
+            null_pointer: "0x0".to_string(),
+        }
+    }
+
+    pub const NAMES: [&'static str; 4] = ["pretty", "elements", "max-depth", "null-pointer"];
+
+    fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match name {
+            "pretty" => self.pretty = parse_bool(value)?,
+            "elements" => self.elements = parse_limit(value)?,
+            "max-depth" => self.max_depth = parse_limit(value)?,
+            "null-pointer" => {
+                if value.is_empty() {
+                    return Err("Argument required (text to show for null pointers).".to_string());
+                }
+                self.null_pointer = value.to_string();
+            }
+            _ => return Err(format!("No print setting named \"{}\"", name)),
+        }
+        Ok(())
+    }
+
+    fn show(&self, name: &str) -> Result<String, String> {
+        match name {
+            "pretty" => Ok(format!(
+                "Pretty formatting of structures is {}.",
+                if self.pretty { "on" } else { "off" }
+            )),
+            "elements" => Ok(format!(
+                "Limit on string chars or array elements to print is {}.",
+                format_limit(self.elements)
+            )),
+            "max-depth" => Ok(format!(
+                "Maximum print depth is {}.",
+                format_limit(self.max_depth)
+            )),
+            "null-pointer" => Ok(format!(
+                "Null pointers are printed as \"{}\".",
+                self.null_pointer
+            )),
+            _ => Err(format!("No print setting named \"{}\"", name)),
+        }
+    }
+}
+
+//User-adjustable debugger settings, changed with `set <name> <value>` and shown with `show <name>`
+pub struct Settings {
+    pub charset: Charset,
+    pub print: PrintSettings,
+    pub editing_mode: EditingMode,
+    //run the program with an empty environment
+    pub exec_clean_env: bool,
+    //umask, user id and group id to run the program with, instead of the debugger's own
+    pub exec_umask: Option<u32>,
+    pub exec_uid: Option<u32>,
+    pub exec_gid: Option<u32>,
+    //log which library each shared library function comes from the first time it is called
+    pub trace_plt: bool,
+    //how many lines `list` shows, or None for the whole file
+    pub listsize: Option<usize>,
+    //the arguments the program is run with, from the last `run` given any or `set args`
+    pub args: Vec<String>,
+    //the directory the program is run in, or None for the debugger's own
+    pub cwd: Option<String>,
+    //the process that goes on being debugged after a fork, while the other one is let go
+    pub follow_fork_mode: FollowForkMode,
+    //with this off, the process that isn't followed after a fork is kept stopped as another
+    //inferior, rather than let go
+    pub detach_on_fork: bool,
+    //with this on, only the thread being debugged runs when the program is continued, while the
+    //others stay stopped
+    pub scheduler_locking: bool,
+    //ask before doing something that can't be undone or may crash the program, such as `jump`
+    pub confirm: bool,
+    //copy the debugger's output to `logging_file`, overwriting it rather than appending to it
+    //with `logging_overwrite`, and the commands typed too with `logging_commands`
+    pub logging: bool,
+    pub logging_file: String,
+    pub logging_overwrite: bool,
+    pub logging_commands: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Settings {
+    //The settings `set` changes. Anything else after `set` is an assignment to a variable.
+    pub const NAMES: [&'static str; 16] = [
+        "args",
+        "charset",
+        "confirm",
+        "cwd",
+        "detach-on-fork",
+        "editing-mode",
+        "exec-clean-env",
+        "exec-gid",
+        "exec-uid",
+        "exec-umask",
+        "follow-fork-mode",
+        "listsize",
+        "logging",
+        "print",
+        "scheduler-locking",
+        "trace-plt",
+    ];
+
+    pub fn new() -> Settings {
+        Settings {
+            charset: Charset::Utf8,
+            print: PrintSettings::new(),
+            editing_mode: EditingMode::Emacs,
+            exec_clean_env: false,
+            exec_umask: None,
+            exec_uid: None,
+            exec_gid: None,
+            trace_plt: false,
+            listsize: Some(10),
+            args: Vec::new(),
+            cwd: None,
+            follow_fork_mode: FollowForkMode::Parent,
+            detach_on_fork: true,
+            scheduler_locking: false,
+            confirm: true,
+            logging: false,
+            logging_file: "deet.txt".to_string(),
+            logging_overwrite: false,
+            logging_commands: false,
+        }
+    }
+
+    //Apply `set <name> <value>`. Returns an error message if the setting or value is invalid.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match name {
+            "charset" => {
+                self.charset = Charset::from_name(value).ok_or(format!(
+                    "Undefined charset \"{}\". Supported charsets: UTF-8, ASCII, ISO-8859-1",
+                    value
+                ))?;
+            }
+            "editing-mode" => {
+                self.editing_mode = match value {
+                    "emacs" => EditingMode::Emacs,
+                    "vi" => EditingMode::Vi,
+                    _ => return Err("Requires an argument. Valid arguments are emacs, vi.".to_string()),
+                };
+            }
+            "follow-fork-mode" => {
+                self.follow_fork_mode = match value {
+                    "parent" => FollowForkMode::Parent,
+                    "child" => FollowForkMode::Child,
+                    _ => return Err("Requires an argument. Valid arguments are child, parent.".to_string()),
+                };
+            }
+            "args" => self.args = value.split_whitespace().map(|arg| arg.to_string()).collect(),
+            "cwd" if value.is_empty() => self.cwd = None,
+            "cwd" => {
+                if !std::path::Path::new(value).is_dir() {
+                    return Err(format!("{}: No such directory.", value));
+                }
+                self.cwd = Some(value.to_string());
+            }
+            "exec-clean-env" => self.exec_clean_env = parse_bool(value)?,
+            "detach-on-fork" => self.detach_on_fork = parse_bool(value)?,
+            "scheduler-locking" => self.scheduler_locking = parse_bool(value)?,
+            "confirm" => self.confirm = parse_bool(value)?,
+            "trace-plt" => self.trace_plt = parse_bool(value)?,
+            "listsize" => self.listsize = parse_limit(value)?,
+            "exec-umask" => {
+                self.exec_umask = match value {
+                    "default" => None,
+                    _ => Some(u32::from_str_radix(value, 8).ok().filter(|mask| *mask <= 0o777).ok_or(
+                        format!("Invalid umask \"{}\". Expected an octal number such as 022, or \"default\".", value),
+                    )?),
+                };
+            }
+            "exec-uid" | "exec-gid" => {
+                let id = match value {
+                    "default" => None,
+                    _ => Some(value.parse::<u32>().map_err(|_| format!("Invalid id \"{}\".", value))?),
+                };
+                //Only root may switch the program to another user or group
+                if id.is_some() && !nix::unistd::geteuid().is_root() {
+                    return Err(format!("Setting {} requires running the debugger as root.", name));
+                }
+                if name == "exec-uid" {
+                    self.exec_uid = id;
+                } else {
+                    self.exec_gid = id;
+                }
+            }
+            "logging" => self.set_logging(value)?,
+            "print" => {
+                let mut parts = value.splitn(2, ' ');
+                let print_name = parts.next().unwrap_or("");
+                let print_value = parts.next().unwrap_or("").trim();
+                self.print.set(print_name, print_value)?;
+            }
+            _ => return Err(format!("No setting named \"{}\"", name)),
+        }
+        Ok(())
+    }
+
+    //Apply `set logging on [file]`, `set logging off`, `set logging file <file>`, `set logging
+    //overwrite` or `set logging commands`. Opening and closing the log file is up to the debugger.
+    fn set_logging(&mut self, value: &str) -> Result<(), String> {
+        let (what, arg) = match value.find(' ') {
+            Some(end) => (&value[..end], value[end..].trim()),
+            None => (value, ""),
+        };
+        match what {
+            "on" | "enabled" => {
+                if !arg.is_empty() {
+                    self.logging_file = arg.to_string();
+                }
+                self.logging = true;
+            }
+            "off" | "disabled" => self.logging = false,
+            "file" if arg.is_empty() => return Err("Argument required (filename to set it to.).".to_string()),
+            "file" => self.logging_file = arg.to_string(),
+            "overwrite" => self.logging_overwrite = parse_bool(arg)?,
+            "commands" => self.logging_commands = parse_bool(arg)?,
+            _ => {
+                return Err("\"set logging\" must be followed by on, off, file, overwrite or commands.".to_string())
+            }
+        }
+        Ok(())
+    }
+
+    //Describe the current value of a setting for `show <name>`
+    pub fn show(&self, name: &str) -> Result<String, String> {
+        match name {
+            "args" => Ok(format!(
+                "Argument list to give program being debugged when it is started is \"{}\".",
+                self.args.join(" ")
+            )),
+            "charset" => Ok(format!("The charset is \"{}\".", self.charset)),
+            "cwd" => Ok(match &self.cwd {
+                Some(dir) => format!("The program is run in \"{}\".", dir),
+                None => "The program is run in the debugger's working directory.".to_string(),
+            }),
+            "editing-mode" => Ok(format!("Editing mode is {}.", self.editing_mode)),
+            "follow-fork-mode" => Ok(format!(
+                "Debugger response to a program call of fork or vfork is \"{}\".",
+                self.follow_fork_mode
+            )),
+            "exec-clean-env" => Ok(format!(
+                "Running the program with an empty environment is {}.",
+                if self.exec_clean_env { "on" } else { "off" }
+            )),
+            "detach-on-fork" => Ok(format!(
+                "Whether the debugger will detach the child of a fork is {}.",
+                if self.detach_on_fork { "on" } else { "off" }
+            )),
+            "scheduler-locking" => Ok(format!(
+                "Mode for locking scheduler during execution is \"{}\".",
+                if self.scheduler_locking { "on" } else { "off" }
+            )),
+            "confirm" => Ok(format!(
+                "Whether to confirm potentially dangerous operations is {}.",
+                if self.confirm { "on" } else { "off" }
+            )),
+            "trace-plt" => Ok(format!(
+                "Tracing of shared library function binding is {}.",
+                if self.trace_plt { "on" } else { "off" }
+            )),
+            "listsize" => Ok(format!(
+                "Number of source lines listed by default is {}.",
+                format_limit(self.listsize)
+            )),
+            "exec-umask" => Ok(match self.exec_umask {
+                Some(mask) => format!("The program is run with umask {:04o}.", mask),
+                None => "The program is run with the debugger's umask.".to_string(),
+            }),
+            "exec-uid" => Ok(match self.exec_uid {
+                Some(uid) => format!("The program is run as user id {}.", uid),
+                None => "The program is run as the debugger's user.".to_string(),
+            }),
+            "exec-gid" => Ok(match self.exec_gid {
+                Some(gid) => format!("The program is run as group id {}.", gid),
+                None => "The program is run as the debugger's group.".to_string(),
+            }),
+            "logging" => Ok([
+                format!("Logging is {}.", if self.logging { "enabled" } else { "disabled" }),
+                format!("The current logfile is \"{}\".", self.logging_file),
+                format!(
+                    "Whether logging overwrites or appends to the log file is {}.",
+                    if self.logging_overwrite { "on" } else { "off" }
+                ),
+                format!(
+                    "Whether commands are copied to the log file is {}.",
+                    if self.logging_commands { "on" } else { "off" }
+                ),
+            ]
+            .join("\n")),
+            "print" => {
+                let lines: Vec<String> = PrintSettings::NAMES
+                    .iter()
+                    .map(|n| format!("print {}:  {}", n, self.print.show(n).unwrap()))
+                    .collect();
+                Ok(lines.join("\n"))
+            }
+            _ => match name.strip_prefix("print ") {
+                Some(print_name) => self.print.show(print_name.trim()),
+                None => Err(format!("No setting named \"{}\"", name)),
+            },
+        }
+    }
+}
+
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value {
+        "on" | "" | "1" | "yes" | "enable" => Ok(true),
+        "off" | "0" | "no" | "disable" => Ok(false),
+        _ => Err("\"on\" or \"off\" expected.".to_string()),
+    }
+}
+
+//Parse a numeric limit, where "unlimited" means no limit
+//Parse a limit such as `print elements`, which is None for `unlimited` or 0
+fn parse_limit(value: &str) -> Result<Option<usize>, String> {
+    match value {
+        "unlimited" | "0" => Ok(None),
+        _ => value
+            .parse::<usize>()
+            .map(Some)
+            .map_err(|_| format!("integer {} out of range", value)),
+    }
+}
+
+fn format_limit(limit: Option<usize>) -> String {
+    match limit {
+        Some(limit) => limit.to_string(),
+        None => "unlimited".to_string(),
+    }
+}
@@ -0,0 +1,148 @@
+use nix::sys::signal::Signal;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::ffi::CStr;
+
+//What the debugger does when the program gets a signal, changed with `handle`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Disposition {
+    //stop the program and return to the prompt
+    pub stop: bool,
+    //say that the program got the signal, even if it isn't stopped
+    pub print: bool,
+    //let the program have the signal when it is resumed, rather than throwing it away
+    pub pass: bool,
+}
+
+//Signals that are part of a program's normal running, which are passed to it without a word
+const QUIET: [Signal; 7] = [
+    Signal::SIGALRM,
+    Signal::SIGURG,
+    Signal::SIGCHLD,
+    Signal::SIGWINCH,
+    Signal::SIGIO,
+    Signal::SIGVTALRM,
+    Signal::SIGPROF,
+];
+
+//How the debugger handles each signal, with the same defaults as gdb
+#[derive(Clone)]
+pub struct SignalTable {
+    dispositions: HashMap<Signal, Disposition>,
+}
+
+impl SignalTable {
+    pub fn new() -> SignalTable {
+        let dispositions = Signal::iterator()
+            .map(|signal| {
+                let disposition = match signal {
+                    _ if QUIET.contains(&signal) => Disposition { stop: false, print: false, pass: true },
+                    //the debugger's own signals, for breakpoints and Ctrl+C
+                    Signal::SIGTRAP | Signal::SIGINT => Disposition { stop: true, print: true, pass: false },
+                    _ => Disposition { stop: true, print: true, pass: true },
+                };
+                (signal, disposition)
+            })
+            .collect();
+        SignalTable { dispositions }
+    }
+
+    pub fn get(&self, signal: Signal) -> Disposition {
+        self.dispositions[&signal]
+    }
+
+    //Change how signals are handled for `handle <signals> <actions>`, and return the signals
+    //named. `stop` implies `print`, and `noprint` implies `nostop`; `ignore` and `noignore` are
+    //the same as `nopass` and `pass`.
+    pub fn handle(&mut self, args: &str) -> Result<Vec<Signal>, String> {
+        let mut signals = Vec::new();
+        let mut actions = Vec::new();
+        for word in args.split_whitespace() {
+            match word.to_lowercase().as_str() {
+                action @ "stop" | action @ "nostop" | action @ "print" | action @ "noprint"
+                | action @ "pass" | action @ "nopass" | action @ "ignore" | action @ "noignore" => {
+                    actions.push(action.to_string())
+                }
+                _ => signals.extend(parse_signals(word)?),
+            }
+        }
+        if signals.is_empty() {
+            return Err("Argument required (signal and action to apply).".to_string());
+        }
+        //breakpoints don't work without it
+        if signals.contains(&Signal::SIGTRAP) && !actions.is_empty() {
+            return Err("SIGTRAP is used by the debugger.".to_string());
+        }
+        for signal in &signals {
+            let disposition = self.dispositions.get_mut(signal).unwrap();
+            for action in &actions {
+                match action.as_str() {
+                    "stop" => {
+                        disposition.stop = true;
+                        disposition.print = true;
+                    }
+                    "nostop" => disposition.stop = false,
+                    "print" => disposition.print = true,
+                    "noprint" => {
+                        disposition.print = false;
+                        disposition.stop = false;
+                    }
+                    "pass" | "noignore" => disposition.pass = true,
+                    _ => disposition.pass = false,
+                }
+            }
+        }
+        Ok(signals)
+    }
+
+    //The lines of `info signals`, for the given signals, or all of them
+    pub fn lines(&self, signals: &[Signal]) -> Vec<String> {
+        let all: Vec<Signal> = Signal::iterator().collect();
+        let signals = if signals.is_empty() { &all[..] } else { signals };
+        let yes = |flag: bool| if flag { "Yes" } else { "No" };
+        let mut lines = vec!["Signal        Stop\tPrint\tPass to program\tDescription".to_string()];
+        for signal in signals {
+            let disposition = self.get(*signal);
+            lines.push(format!(
+                "{:<14}{}\t{}\t{}\t\t{}",
+                signal.as_ref(),
+                yes(disposition.stop),
+                yes(disposition.print),
+                yes(disposition.pass),
+                description(*signal)
+            ));
+        }
+        lines
+    }
+}
+
+//Parse a signal named in `handle` or `info signals`: a name with or without `SIG`, a number, or
+//`all`, which leaves out the signals the debugger uses itself
+pub fn parse_signals(word: &str) -> Result<Vec<Signal>, String> {
+    if word == "all" {
+        return Ok(Signal::iterator()
+            .filter(|signal| *signal != Signal::SIGTRAP && *signal != Signal::SIGINT)
+            .collect());
+    }
+    parse_signal(word).map(|signal| vec![signal])
+}
+
+//Parse a single signal, named with or without `SIG` or by its number
+pub fn parse_signal(word: &str) -> Result<Signal, String> {
+    let signal = match word.parse::<libc::c_int>() {
+        Ok(number) => Signal::try_from(number).ok(),
+        Err(_) => {
+            let name = word.to_uppercase();
+            let name = if name.starts_with("SIG") { name } else { format!("SIG{}", name) };
+            Signal::iterator().find(|signal| signal.as_ref() == name)
+        }
+    };
+    signal.ok_or(format!("Unrecognized or ambiguous flag word: \"{}\".", word))
+}
+
+//What a signal means, e.g. `User defined signal 1`
+pub fn description(signal: Signal) -> String {
+    unsafe { CStr::from_ptr(libc::strsignal(signal as libc::c_int)) }
+        .to_string_lossy()
+        .into_owned()
+}
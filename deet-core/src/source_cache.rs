@@ -0,0 +1,91 @@
+use crate::dwarf_data::DwarfData;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+//The source files of the program, by the paths the debug info gives for them. A file is only read
+//the first time one of its lines is needed, from where it was when the program was compiled, or
+//from next to the program if it has been moved since.
+pub struct SourceCache {
+    //the directory the program is in
+    dir: PathBuf,
+    //every source file the debug info refers to
+    paths: Vec<String>,
+    //the paths of the source files by their names without a directory, such as `helper.c`
+    by_name: HashMap<String, Vec<String>>,
+    //the lines of each file read so far, or None if it couldn't be read
+    files: RefCell<HashMap<String, Option<Rc<Vec<String>>>>>,
+}
+
+impl SourceCache {
+    pub fn new(debug_data: &DwarfData, target: &str) -> SourceCache {
+        let paths: Vec<String> = debug_data.get_source_paths().iter().map(|path| path.to_string()).collect();
+        let mut by_name: HashMap<String, Vec<String>> = HashMap::new();
+        for path in &paths {
+            if let Some(name) = Path::new(path).file_name() {
+                by_name.entry(name.to_string_lossy().to_string()).or_default().push(path.clone());
+            }
+        }
+        SourceCache {
+            dir: Path::new(target).parent().unwrap_or_else(|| Path::new(".")).to_path_buf(),
+            paths,
+            by_name,
+            files: RefCell::new(HashMap::new()),
+        }
+    }
+
+    //Find a source file of the program by its path, the end of its path such as `src/helper.c`,
+    //or just its name
+    pub fn find(&self, name: &str) -> Option<&str> {
+        if let Some(path) = self.paths.iter().find(|path| *path == name) {
+            return Some(path);
+        }
+        if let Some(paths) = self.by_name.get(name) {
+            return paths.first().map(|path| path.as_str());
+        }
+        self.paths
+            .iter()
+            .find(|path| path.ends_with(&format!("/{}", name)) || same_file(path, name))
+            .map(|path| path.as_str())
+    }
+
+    //The lines of a source file, reading it if it hasn't been read yet
+    pub fn lines(&self, path: &str) -> Option<Rc<Vec<String>>> {
+        if let Some(lines) = self.files.borrow().get(path) {
+            return lines.clone();
+        }
+        let moved = Path::new(path).file_name().map(|name| self.dir.join(name));
+        let lines = read_lines(Path::new(path)).or_else(|| read_lines(&moved?)).map(Rc::new);
+        self.files.borrow_mut().insert(path.to_string(), lines.clone());
+        lines
+    }
+
+    //The text of a line of a source file, or "" if there is no such line, e.g. line 0, which is
+    //code that belongs to no line such as some made up by the compiler
+    pub fn line(&self, path: &str, number: usize) -> String {
+        match (self.lines(path), number.checked_sub(1)) {
+            (Some(lines), Some(index)) => lines.get(index).cloned().unwrap_or_default(),
+            _ => String::new(),
+        }
+    }
+
+    //How many lines a source file has, or 0 if it can't be read
+    pub fn line_count(&self, path: &str) -> usize {
+        self.lines(path).map_or(0, |lines| lines.len())
+    }
+}
+
+fn read_lines(path: &Path) -> Option<Vec<String>> {
+    let bytes = fs::read(path).ok()?;
+    Some(String::from_utf8_lossy(&bytes).lines().map(|line| line.to_string()).collect())
+}
+
+//Whether two paths are the same file, such as `/src/a.c` and `/build/../src/a.c`
+fn same_file(a: &str, b: &str) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
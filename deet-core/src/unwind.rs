@@ -0,0 +1,110 @@
+use crate::elf;
+use gimli::{BaseAddresses, DebugFrame, EhFrame, LittleEndian, UninitializedUnwindContext, UnwindSection};
+
+//Register number of the return address in the x86-64 DWARF register mapping
+pub const RETURN_ADDRESS: u16 = 16;
+
+//Names of the registers numbered 0 to 16 in the x86-64 DWARF register mapping
+const REGISTER_NAMES: [&str; 17] = [
+    "rax", "rdx", "rcx", "rbx", "rsi", "rdi", "rbp", "rsp", "r8", "r9", "r10", "r11", "r12", "r13",
+    "r14", "r15", "rip",
+];
+
+//The call frame information (CFI) of a program, from .eh_frame or .debug_frame. For every address
+//in a function it says how to find the canonical frame address and the registers of the caller,
+//which works whether or not the function keeps a frame pointer in %rbp.
+pub struct CallFrameInfo {
+    //.eh_frame and the address it is loaded at, which pc-relative pointers in it are relative to
+    eh_frame: Option<(Vec<u8>, usize)>,
+    debug_frame: Option<Vec<u8>>,
+    //the address of .text, which some pointers in .eh_frame are relative to
+    text_addr: usize,
+}
+
+//Where the caller's value of a register is
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SavedRegister {
+    //saved in memory at the canonical frame address plus an offset
+    AtCfa(i64),
+    //the canonical frame address plus an offset
+    CfaPlus(i64),
+    //in another register, by its DWARF number
+    InRegister(u16),
+    //not saved anywhere, such as the return address of the outermost frame
+    Undefined,
+}
+
+//How to unwind a frame at one address: the canonical frame address is a register plus an offset,
+//and the registers that aren't listed are the same in the caller
+pub struct UnwindRow {
+    pub cfa_register: u16,
+    pub cfa_offset: i64,
+    pub registers: Vec<(u16, SavedRegister)>,
+}
+
+impl CallFrameInfo {
+    //Copy the CFI out of an executable file
+    pub fn load(data: &[u8], elf: &elf::ElfFile) -> CallFrameInfo {
+        let section = |name: &str| elf.sections.iter().find(|section| section.name == name);
+        let section_data = |name: &str| {
+            let section = section(name)?;
+            Some((elf::section_data(data, section)?.to_vec(), section.addr))
+        };
+        CallFrameInfo {
+            eh_frame: section_data(".eh_frame"),
+            debug_frame: section_data(".debug_frame").map(|(data, _)| data),
+            text_addr: section(".text").map_or(0, |section| section.addr),
+        }
+    }
+
+    //Find how to unwind a frame stopped at `addr`, or None if there is no CFI for it or it is a
+    //DWARF expression (such as in the PLT), which isn't supported
+    pub fn unwind_row(&self, addr: usize) -> Option<UnwindRow> {
+        let mut ctx = UninitializedUnwindContext::new();
+        if let Some((data, eh_frame_addr)) = &self.eh_frame {
+            let eh_frame = EhFrame::new(data, LittleEndian);
+            let bases = BaseAddresses::default()
+                .set_eh_frame(*eh_frame_addr as u64)
+                .set_text(self.text_addr as u64);
+            if let Ok(row) = eh_frame.unwind_info_for_address(&bases, &mut ctx, addr as u64, EhFrame::cie_from_offset) {
+                return convert_row(&row);
+            }
+        }
+        let debug_frame = DebugFrame::new(self.debug_frame.as_ref()?, LittleEndian);
+        let bases = BaseAddresses::default();
+        let row = debug_frame
+            .unwind_info_for_address(&bases, &mut ctx, addr as u64, DebugFrame::cie_from_offset)
+            .ok()?;
+        convert_row(&row)
+    }
+}
+
+fn convert_row<R: gimli::Reader>(row: &gimli::UnwindTableRow<R>) -> Option<UnwindRow> {
+    let (cfa_register, cfa_offset) = match row.cfa() {
+        gimli::CfaRule::RegisterAndOffset { register, offset } => (register.0, *offset),
+        gimli::CfaRule::Expression(_) => return None,
+    };
+    let mut registers = Vec::new();
+    for (register, rule) in row.registers() {
+        let saved = match rule {
+            gimli::RegisterRule::Offset(offset) => SavedRegister::AtCfa(*offset),
+            gimli::RegisterRule::ValOffset(offset) => SavedRegister::CfaPlus(*offset),
+            gimli::RegisterRule::Register(other) => SavedRegister::InRegister(other.0),
+            gimli::RegisterRule::Undefined => SavedRegister::Undefined,
+            gimli::RegisterRule::SameValue => continue,
+            _ => return None,
+        };
+        registers.push((register.0, saved));
+    }
+    Some(UnwindRow {
+        cfa_register,
+        cfa_offset,
+        registers,
+    })
+}
+
+//The name of a register by its DWARF number, as `register_field` takes it. The return address is
+//named rip, which is where it goes in the caller.
+pub fn register_name(number: u16) -> Option<&'static str> {
+    REGISTER_NAMES.get(number as usize).copied()
+}
@@ -0,0 +1,1024 @@
+use crate::dwarf_data::{DwarfData, Encoding, Enumerator, Member, Type, TypeKind};
+use crate::expression::{Scalar, Value};
+use crate::inferior::Inferior;
+use crate::settings::{Charset, Settings};
+use std::convert::TryInto;
+
+//Renders the raw bytes of a value read from the inferior according to its DWARF type
+pub struct ValueFormatter<'a> {
+    debug_data: &'a DwarfData,
+    //None when there is no process, in which case nothing can be read from memory
+    inferior: Option<&'a Inferior>,
+    settings: &'a Settings,
+    //shows numbers in another base, as `print/x` does, rather than the way their type would
+    format: Option<Format>,
+}
+
+//An output format, given as a letter after `print/` or `x/`. Every number in a value, including
+//those in structs and arrays, is shown in the format.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    //x
+    Hex,
+    //z, hex padded with zeros to the size of the type
+    ZeroHex,
+    //o
+    Octal,
+    //t
+    Binary,
+    //d
+    Decimal,
+    //u
+    Unsigned,
+    //c, the low byte as a character
+    Char,
+    //a, an address followed by the symbol it is in
+    Address,
+    //f
+    Float,
+    //s, a null-terminated string. Values that aren't in memory are shown the usual way.
+    String,
+}
+
+impl Format {
+    pub fn from_letter(letter: char) -> Option<Format> {
+        match letter {
+            'x' => Some(Format::Hex),
+            'z' => Some(Format::ZeroHex),
+            'o' => Some(Format::Octal),
+            't' => Some(Format::Binary),
+            'd' => Some(Format::Decimal),
+            'u' => Some(Format::Unsigned),
+            'c' => Some(Format::Char),
+            'a' => Some(Format::Address),
+            'f' => Some(Format::Float),
+            's' => Some(Format::String),
+            _ => None,
+        }
+    }
+}
+
+//The width of a character type and the prefix of its string literals in C
+#[derive(Debug, Clone, Copy)]
+struct CharType {
+    size: usize,
+    prefix: &'static str,
+}
+
+impl<'a> ValueFormatter<'a> {
+    pub fn new(
+        debug_data: &'a DwarfData,
+        inferior: Option<&'a Inferior>,
+        settings: &'a Settings,
+    ) -> ValueFormatter<'a> {
+        ValueFormatter {
+            debug_data,
+            inferior,
+            settings,
+            format: None,
+        }
+    }
+
+    //Show numbers in the given format rather than the way their type would
+    pub fn with_format(mut self, format: Option<Format>) -> ValueFormatter<'a> {
+        self.format = format.filter(|format| *format != Format::String);
+        self
+    }
+
+    //Format a number of up to 16 bytes, or the bits of a float, in the given format
+    pub fn format_unit(&self, format: Format, bytes: &[u8]) -> String {
+        let size = bytes.len().min(16);
+        if size == 0 {
+            return format_bytes(bytes);
+        }
+        let mut padded = [0u8; 16];
+        padded[..size].copy_from_slice(&bytes[..size]);
+        let bits = u128::from_le_bytes(padded);
+        match format {
+            Format::Hex | Format::String => format!("{:#x}", bits),
+            Format::ZeroHex => format!("0x{:0width$x}", bits, width = size * 2),
+            Format::Octal if bits == 0 => "0".to_string(),
+            Format::Octal => format!("0{:o}", bits),
+            Format::Binary => format!("{:b}", bits),
+            Format::Decimal => {
+                let shift = 128 - size as u32 * 8;
+                ((bits << shift) as i128 >> shift).to_string()
+            }
+            Format::Unsigned => bits.to_string(),
+            Format::Char => format!("{} {}", bytes[0] as i8, format_char(bytes[0] as u64)),
+            Format::Address => match symbol_label(self.debug_data, bits as usize) {
+                Some(label) => format!("{:#x} {}", bits, label),
+                None => format!("{:#x}", bits),
+            },
+            Format::Float => match read_float(&bytes[..size]) {
+                Some(f) => format_float(f, size),
+                None => self.format_unit(Format::Decimal, bytes),
+            },
+        }
+    }
+
+    //Format the null-terminated string at `addr` as `x/s` does. Also returns how many bytes it
+    //takes up, including the null.
+    pub fn format_c_string(&self, addr: usize) -> Result<(String, usize), String> {
+        let bytes = self.read_c_string(addr)?;
+        let units: Vec<u64> = bytes.iter().map(|b| *b as u64).collect();
+        let char_type = CharType { size: 1, prefix: "" };
+        Ok((self.format_string(&units, char_type), bytes.len() + 1))
+    }
+
+    fn read_memory(&self, addr: usize, len: usize) -> Result<Vec<u8>, nix::Error> {
+        match self.inferior {
+            Some(inferior) => inferior.read_mem(addr, len),
+            None => Err(nix::Error::Sys(nix::errno::Errno::ESRCH)),
+        }
+    }
+
+    //Format a value of type `ty` whose bytes are `bytes`
+    pub fn format(&self, ty: &Type, bytes: &[u8]) -> String {
+        self.format_at_depth(ty, bytes, 0)
+    }
+
+    //`depth` is how many structs, unions and arrays the value is nested in
+    fn format_at_depth(&self, ty: &Type, bytes: &[u8], depth: usize) -> String {
+        let wide_char = self.char_type(ty).filter(|c| c.size > 1);
+        let ty = self.debug_data.resolve_type(ty);
+        if bytes.len() < ty.size {
+            return "<unavailable>".to_string();
+        }
+        match &ty.kind {
+            //print/f shows integers as they are, rather than the float their bits would make
+            TypeKind::Base(encoding)
+                if self.format == Some(Format::Float) && *encoding != Encoding::Float =>
+            {
+                self.format_unit(Format::Decimal, &bytes[..ty.size])
+            }
+            TypeKind::Base(_) | TypeKind::Pointer(_) | TypeKind::Enum(_) if self.format.is_some() => {
+                self.format_unit(self.format.unwrap(), &bytes[..ty.size])
+            }
+            //wchar_t and friends are typedefs of integer types in C, but are shown as characters
+            TypeKind::Base(_) if wide_char.is_some() => {
+                let c = read_uint(&bytes[..ty.size]);
+                let mut quoted = String::new();
+                push_escaped_char(&mut quoted, std::char::from_u32(c as u32).ok_or(c), '\'');
+                format!("{} {}'{}'", c, wide_char.unwrap().prefix, quoted)
+            }
+            TypeKind::Base(encoding) => format_base(*encoding, &bytes[..ty.size]),
+            TypeKind::Pointer(target) => {
+                let addr = read_uint(&bytes[..ty.size]) as usize;
+                if addr == 0 {
+                    return self.settings.print.null_pointer.clone();
+                }
+                let char_type = self.debug_data.get_type(*target).and_then(|t| self.char_type(t));
+                match char_type {
+                    Some(char_type) => match self.read_string(addr, char_type) {
+                        Some((units, truncated)) => format!(
+                            "{:#x} {}{}",
+                            addr,
+                            self.format_string(&units, char_type),
+                            if truncated { "..." } else { "" }
+                        ),
+                        None => format!("{:#x} <error: Cannot access memory at address {:#x}>", addr, addr),
+                    },
+                    _ => format!("{:#x}", addr),
+                }
+            }
+            TypeKind::Enum(enumerators) => {
+                format_enum(enumerators, enum_value(enumerators, &bytes[..ty.size]))
+            }
+            TypeKind::Struct(_) | TypeKind::Union(_) | TypeKind::Array(..)
+                if self.settings.print.max_depth.is_some_and(|max| depth >= max) =>
+            {
+                "{...}".to_string()
+            }
+            TypeKind::Struct(members) => self.format_members(members.iter(), bytes, depth),
+            TypeKind::Union(members) => {
+                //A union holds only one of its members at a time. Show the interpretations that
+                //look sensible, falling back to all of them if none does.
+                let plausible: Vec<&Member> = members
+                    .iter()
+                    .filter(|m| self.member_looks_plausible(m, bytes))
+                    .collect();
+                if plausible.is_empty() {
+                    self.format_members(members.iter(), bytes, depth)
+                } else {
+                    self.format_members(plausible.into_iter(), bytes, depth)
+                }
+            }
+            TypeKind::Array(elem, dims) => match self.debug_data.get_type(*elem) {
+                //Character arrays are shown as strings, up to the first null character, unless
+                //a format asks for the numbers
+                Some(elem_type)
+                    if dims.len() == 1 && self.char_type(elem_type).is_some() && self.format.is_none() =>
+                {
+                    let char_type = self.char_type(elem_type).unwrap();
+                    let mut units: Vec<u64> = bytes[..ty.size]
+                        .chunks(char_type.size)
+                        .map(read_uint)
+                        .collect();
+                    if let Some(end) = units.iter().position(|u| *u == 0) {
+                        units.truncate(end);
+                    }
+                    if let Some(limit) = self.settings.print.elements.filter(|limit| units.len() > *limit) {
+                        units.truncate(limit);
+                        return format!("{}...", self.format_string(&units, char_type));
+                    }
+                    self.format_string(&units, char_type)
+                }
+                Some(elem_type) => self.format_array(elem_type, dims, bytes, depth),
+                None => "<unknown element type>".to_string(),
+            },
+            TypeKind::Function => "<function>".to_string(),
+            TypeKind::Alias(_) | TypeKind::Unknown => format_bytes(&bytes[..ty.size]),
+        }
+    }
+
+    fn format_members<'m, I: Iterator<Item = &'m Member>>(
+        &self,
+        members: I,
+        bytes: &[u8],
+        depth: usize,
+    ) -> String {
+        let fields: Vec<String> = members
+            .map(|m| {
+                if m.name.is_empty() {
+                    //anonymous struct or union
+                    self.format_member(m, bytes, depth + 1)
+                } else {
+                    format!("{} = {}", m.name, self.format_member(m, bytes, depth + 1))
+                }
+            })
+            .collect();
+        if self.settings.print.pretty && !fields.is_empty() {
+            //one member per line, indented by nesting level
+            let indent = "  ".repeat(depth + 1);
+            format!(
+                "{{\n{}{}\n{}}}",
+                indent,
+                fields.join(&format!(",\n{}", indent)),
+                "  ".repeat(depth)
+            )
+        } else {
+            format!("{{{}}}", fields.join(", "))
+        }
+    }
+
+    fn format_member(&self, member: &Member, bytes: &[u8], depth: usize) -> String {
+        let member_type = match self.debug_data.get_type(member.type_offset) {
+            Some(ty) => ty,
+            None => return "<unknown type>".to_string(),
+        };
+        let member_bytes = match member_bytes(self.debug_data, member, member_type, bytes) {
+            Some(member_bytes) => member_bytes,
+            None => return "<unavailable>".to_string(),
+        };
+        match (member.bit_size, self.format) {
+            //a signed bitfield shown as bits is only its own bits, e.g. 0xd for a 4-bit -3
+            (Some(bit_size), Some(format))
+                if bit_size < 64
+                    && matches!(format, Format::Hex | Format::ZeroHex | Format::Octal | Format::Binary | Format::Unsigned) =>
+            {
+                let bits = read_uint(&member_bytes) & ((1 << bit_size) - 1);
+                self.format_unit(format, &bits.to_le_bytes()[..member_bytes.len().min(8)])
+            }
+            _ => self.format_at_depth(member_type, &member_bytes, depth),
+        }
+    }
+
+    fn member_looks_plausible(&self, member: &Member, bytes: &[u8]) -> bool {
+        let member_type = match self.debug_data.get_type(member.type_offset) {
+            Some(ty) => ty,
+            None => return false,
+        };
+        match member_bytes(self.debug_data, member, member_type, bytes) {
+            Some(member_bytes) => self.looks_plausible(member_type, &member_bytes),
+            None => false,
+        }
+    }
+
+    //Guess whether `bytes` could be a value of type `ty`
+    fn looks_plausible(&self, ty: &Type, bytes: &[u8]) -> bool {
+        let ty = self.debug_data.resolve_type(ty);
+        match &ty.kind {
+            TypeKind::Base(Encoding::Float) => match read_float(bytes) {
+                Some(f) => f == 0.0 || (f.is_finite() && f.abs() >= 1e-30 && f.abs() <= 1e30),
+                None => false,
+            },
+            TypeKind::Base(Encoding::Boolean) => read_uint(bytes) <= 1,
+            TypeKind::Base(Encoding::SignedChar) | TypeKind::Base(Encoding::UnsignedChar) => {
+                let c = read_uint(bytes);
+                c == 0 || (c < 0x80 && !(c as u8).is_ascii_control()) || c == b'\n' as u64
+            }
+            TypeKind::Enum(enumerators) => {
+                let value = enum_value(enumerators, bytes);
+                format_enum(enumerators, value) != value.to_string()
+            }
+            TypeKind::Pointer(_) => {
+                let addr = read_uint(bytes) as usize;
+                addr == 0 || self.read_memory(addr, 1).is_ok()
+            }
+            _ => true,
+        }
+    }
+
+    //Decide whether `ty` is a character type, and if so how wide its characters are. wchar_t,
+    //char16_t and char32_t are recognized by their typedef names.
+    fn char_type(&self, ty: &Type) -> Option<CharType> {
+        let mut ty = ty;
+        loop {
+            let prefix = match ty.name.as_str() {
+                "wchar_t" => Some("L"),
+                "char16_t" => Some("u"),
+                "char32_t" => Some("U"),
+                _ => None,
+            };
+            if let Some(prefix) = prefix {
+                let size = self.debug_data.resolve_type(ty).size;
+                return Some(CharType { size, prefix });
+            }
+            match ty.kind {
+                TypeKind::Alias(target) => ty = self.debug_data.get_type(target)?,
+                TypeKind::Base(Encoding::SignedChar) | TypeKind::Base(Encoding::UnsignedChar)
+                    if ty.size == 1 =>
+                {
+                    return Some(CharType { size: 1, prefix: "" })
+                }
+                TypeKind::Base(Encoding::Utf) => {
+                    let prefix = match ty.size {
+                        1 => "u8",
+                        2 => "u",
+                        _ => "U",
+                    };
+                    return Some(CharType {
+                        size: ty.size,
+                        prefix,
+                    });
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    //Read a null terminated string of code units starting at `addr`. Also returns whether the
+    //string was cut off before reaching its terminator.
+    fn read_string(&self, addr: usize, char_type: CharType) -> Option<(Vec<u64>, bool)> {
+        let limit = self.settings.print.elements.unwrap_or(usize::MAX);
+        let mut units = Vec::new();
+        //Read in chunks, but fall back to single characters near the end of a mapping
+        let chunk_chars = 32;
+        while units.len() < limit {
+            let chunk_addr = addr + units.len() * char_type.size;
+            let bytes = match self.read_memory(chunk_addr, chunk_chars * char_type.size) {
+                Ok(bytes) => bytes,
+                Err(_) => match self.read_memory(chunk_addr, char_type.size) {
+                    Ok(bytes) => bytes,
+                    Err(_) if units.is_empty() => return None,
+                    Err(_) => return Some((units, true)),
+                },
+            };
+            for unit in bytes.chunks(char_type.size).map(read_uint) {
+                if unit == 0 {
+                    return Some((units, false));
+                }
+                units.push(unit);
+                if units.len() == limit {
+                    break;
+                }
+            }
+        }
+        Some((units, true))
+    }
+
+    //Decode code units into a quoted string literal, escaping anything that isn't printable or
+    //isn't valid in the string's encoding
+    fn format_string(&self, units: &[u64], char_type: CharType) -> String {
+        let mut out = String::new();
+        out.push_str(char_type.prefix);
+        out.push('"');
+        match char_type.size {
+            1 => {
+                let bytes: Vec<u8> = units.iter().map(|u| *u as u8).collect();
+                match self.settings.charset {
+                    Charset::Utf8 => {
+                        let mut rest = &bytes[..];
+                        loop {
+                            match std::str::from_utf8(rest) {
+                                Ok(valid) => {
+                                    valid.chars().for_each(|c| push_escaped_char(&mut out, Ok(c), '"'));
+                                    break;
+                                }
+                                Err(e) => {
+                                    let (valid, invalid) = rest.split_at(e.valid_up_to());
+                                    std::str::from_utf8(valid)
+                                        .unwrap()
+                                        .chars()
+                                        .for_each(|c| push_escaped_char(&mut out, Ok(c), '"'));
+                                    let bad_len = e.error_len().unwrap_or(invalid.len());
+                                    for b in &invalid[..bad_len] {
+                                        push_escaped_char(&mut out, Err(*b as u64), '"');
+                                    }
+                                    rest = &invalid[bad_len..];
+                                }
+                            }
+                        }
+                    }
+                    Charset::Ascii => bytes.iter().for_each(|b| {
+                        let c = if *b < 0x80 { Ok(*b as char) } else { Err(*b as u64) };
+                        push_escaped_char(&mut out, c, '"')
+                    }),
+                    Charset::Latin1 => bytes
+                        .iter()
+                        .for_each(|b| push_escaped_char(&mut out, Ok(*b as char), '"')),
+                }
+            }
+            2 => {
+                for c in std::char::decode_utf16(units.iter().map(|u| *u as u16)) {
+                    push_escaped_char(&mut out, c.map_err(|e| e.unpaired_surrogate() as u64), '"');
+                }
+            }
+            _ => {
+                for unit in units {
+                    push_escaped_char(&mut out, std::char::from_u32(*unit as u32).ok_or(*unit), '"');
+                }
+            }
+        }
+        out.push('"');
+        out
+    }
+
+    //Read a null terminated narrow string for printf's %s, ignoring the print elements limit
+    fn read_c_string(&self, addr: usize) -> Result<Vec<u8>, String> {
+        let mut bytes = Vec::new();
+        loop {
+            let chunk_addr = addr + bytes.len();
+            let chunk = match self.read_memory(chunk_addr, 32) {
+                Ok(chunk) => chunk,
+                Err(_) => self
+                    .read_memory(chunk_addr, 1)
+                    .map_err(|_| format!("Cannot access memory at address {:#x}", chunk_addr))?,
+            };
+            match chunk.iter().position(|b| *b == 0) {
+                Some(end) => {
+                    bytes.extend_from_slice(&chunk[..end]);
+                    return Ok(bytes);
+                }
+                None => bytes.extend_from_slice(&chunk),
+            }
+        }
+    }
+
+    //Format `args` according to a C printf format string
+    pub fn printf(&self, format: &[u8], args: &[Value]) -> Result<String, String> {
+        let mut out = Vec::new();
+        let mut args = args.iter();
+        let mut i = 0;
+        while i < format.len() {
+            if format[i] != b'%' {
+                out.push(format[i]);
+                i += 1;
+                continue;
+            }
+            let (spec, end) = parse_conversion(format, i + 1)?;
+            i = end;
+            if spec.conversion == b'%' {
+                out.push(b'%');
+                continue;
+            }
+            let mut next_arg = || {
+                args.next()
+                    .ok_or("Bad format string, missing arguments".to_string())
+            };
+            let width = match spec.width {
+                Some(Count::Fixed(width)) => Some(width),
+                Some(Count::Arg) => Some(next_arg()?.scalar(self.debug_data)?.as_i64() as usize),
+                None => None,
+            };
+            let precision = match spec.precision {
+                Some(Count::Fixed(precision)) => Some(precision),
+                Some(Count::Arg) => Some(next_arg()?.scalar(self.debug_data)?.as_i64() as usize),
+                None => None,
+            };
+            let arg = next_arg()?;
+            let text = match spec.conversion {
+                b's' => {
+                    let mut bytes = match &arg.ty(self.debug_data).kind {
+                        TypeKind::Array(..) => {
+                            let end = arg.bytes.iter().position(|b| *b == 0).unwrap_or(arg.bytes.len());
+                            arg.bytes[..end].to_vec()
+                        }
+                        TypeKind::Pointer(_) => {
+                            let addr = arg.scalar(self.debug_data)?.as_u64() as usize;
+                            if addr == 0 {
+                                b"(null)".to_vec()
+                            } else {
+                                self.read_c_string(addr)?
+                            }
+                        }
+                        _ => return Err("Value can't be converted to a string.".to_string()),
+                    };
+                    if let Some(precision) = precision {
+                        bytes.truncate(precision);
+                    }
+                    bytes
+                }
+                b'c' => vec![arg.scalar(self.debug_data)?.as_u64() as u8],
+                b'p' => {
+                    let addr = arg.scalar(self.debug_data)?.as_u64();
+                    format!("{:#x}", addr).into_bytes()
+                }
+                b'f' | b'F' | b'e' | b'E' | b'g' | b'G' => {
+                    let value = arg.scalar(self.debug_data)?.as_f64();
+                    format_printf_float(&spec, precision, value).into_bytes()
+                }
+                _ => {
+                    let value = match arg.scalar(self.debug_data)? {
+                        Scalar::Float(_) => {
+                            return Err("Value can't be converted to integer.".to_string())
+                        }
+                        value => value.as_u64(),
+                    };
+                    format_printf_int(&spec, precision, value).into_bytes()
+                }
+            };
+            pad_conversion(&mut out, &spec, width, precision, text);
+        }
+        if args.next().is_some() {
+            return Err("Wrong number of arguments for specified format-string".to_string());
+        }
+        Ok(String::from_utf8_lossy(&out).into_owned())
+    }
+
+    fn format_array(
+        &self,
+        elem_type: &Type,
+        dims: &[Option<usize>],
+        bytes: &[u8],
+        depth: usize,
+    ) -> String {
+        let count = match dims.first() {
+            Some(Some(count)) => *count,
+            _ => return "<unknown length>".to_string(),
+        };
+        let shown = self.settings.print.elements.map_or(count, |limit| count.min(limit));
+        //Every element of the outer dimension is itself an array of the remaining dimensions
+        let stride = dims[1..].iter().map(|c| c.unwrap_or(0)).product::<usize>()
+            * self.debug_data.resolve_type(elem_type).size;
+        let mut elements: Vec<String> = (0..shown)
+            .map(|i| match bytes.get(i * stride..(i + 1) * stride) {
+                Some(elem_bytes) if dims.len() > 1 => {
+                    self.format_array(elem_type, &dims[1..], elem_bytes, depth + 1)
+                }
+                Some(elem_bytes) => self.format_at_depth(elem_type, elem_bytes, depth + 1),
+                None => "<unavailable>".to_string(),
+            })
+            .collect();
+        if shown < count {
+            elements.push("...".to_string());
+        }
+        format!("{{{}}}", elements.join(", "))
+    }
+}
+
+//Name the function or global variable that `addr` is in, as `<name+offset>`
+pub fn symbol_label(debug_data: &DwarfData, addr: usize) -> Option<String> {
+    let (name, offset) = debug_data.get_symbol_at(addr)?;
+    match offset {
+        0 => Some(format!("<{}>", name)),
+        offset => Some(format!("<{}+{}>", name, offset)),
+    }
+}
+
+//Get the bytes of a member out of the bytes of the struct containing it. Bitfields are shifted
+//down and sign extended so that they can be handled like a normal value of their type.
+pub fn member_bytes(
+    debug_data: &DwarfData,
+    member: &Member,
+    member_type: &Type,
+    bytes: &[u8],
+) -> Option<Vec<u8>> {
+    let size = debug_data.resolve_type(member_type).size;
+    let bit_size = match member.bit_size {
+        Some(bit_size) => bit_size,
+        None => return Some(bytes.get(member.offset..member.offset + size)?.to_vec()),
+    };
+    if bit_size == 0 || bit_size > 64 {
+        return None;
+    }
+    let first_byte = member.bit_offset / 8;
+    let last_byte = (member.bit_offset + bit_size).div_ceil(8);
+    let mut raw = 0u128;
+    for (i, b) in bytes.get(first_byte..last_byte)?.iter().enumerate() {
+        raw |= (*b as u128) << (8 * i);
+    }
+    let mut value = ((raw >> (member.bit_offset % 8)) as u64) & (u64::MAX >> (64 - bit_size));
+    if is_signed(debug_data, member_type) && value >> (bit_size - 1) & 1 == 1 {
+        value |= !(u64::MAX >> (64 - bit_size));
+    }
+    Some(value.to_le_bytes()[..size.min(8)].to_vec())
+}
+
+pub fn is_signed(debug_data: &DwarfData, ty: &Type) -> bool {
+    match &debug_data.resolve_type(ty).kind {
+        TypeKind::Base(Encoding::Signed) | TypeKind::Base(Encoding::SignedChar) => true,
+        TypeKind::Enum(enumerators) => enumerators.iter().any(|e| e.value < 0),
+        _ => false,
+    }
+}
+
+//Interpret up to 8 little endian bytes as an unsigned integer
+pub fn read_uint(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .take(8)
+        .enumerate()
+        .fold(0, |acc, (i, b)| acc | (*b as u64) << (8 * i))
+}
+
+//Interpret up to 8 little endian bytes as a two's complement signed integer
+pub fn read_int(bytes: &[u8]) -> i64 {
+    let len = bytes.len().min(8);
+    if len == 0 {
+        return 0;
+    }
+    let shift = 64 - 8 * len;
+    ((read_uint(bytes) << shift) as i64) >> shift
+}
+
+pub fn read_float(bytes: &[u8]) -> Option<f64> {
+    match bytes.len() {
+        4 => Some(f32::from_le_bytes(bytes.try_into().ok()?) as f64),
+        8 => Some(f64::from_le_bytes(bytes.try_into().ok()?)),
+        10 | 16 => Some(x87_extended_to_f64(bytes)),
+        _ => None,
+    }
+}
+
+//Convert an x87 80-bit extended precision float (the layout of `long double`) to an f64
+fn x87_extended_to_f64(bytes: &[u8]) -> f64 {
+    let mantissa = read_uint(&bytes[..8]);
+    let sign_exp = bytes[8] as u16 | (bytes[9] as u16) << 8;
+    let sign = if sign_exp & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exp = (sign_exp & 0x7fff) as i32;
+    if exp == 0 && mantissa == 0 {
+        return sign * 0.0;
+    }
+    if exp == 0x7fff {
+        return if mantissa << 1 == 0 {
+            sign * f64::INFINITY
+        } else {
+            f64::NAN
+        };
+    }
+    sign * (mantissa as f64) * 2f64.powi(exp - 16383 - 63)
+}
+
+fn format_base(encoding: Encoding, bytes: &[u8]) -> String {
+    match encoding {
+        Encoding::Signed => {
+            if bytes.len() == 16 {
+                i128::from_le_bytes(bytes.try_into().unwrap()).to_string()
+            } else {
+                read_int(bytes).to_string()
+            }
+        }
+        Encoding::Unsigned => {
+            if bytes.len() == 16 {
+                u128::from_le_bytes(bytes.try_into().unwrap()).to_string()
+            } else {
+                read_uint(bytes).to_string()
+            }
+        }
+        Encoding::SignedChar => format!("{} {}", read_int(bytes), format_char(read_uint(bytes))),
+        Encoding::UnsignedChar => format!("{} {}", read_uint(bytes), format_char(read_uint(bytes))),
+        Encoding::Utf => format!("{} {}", read_uint(bytes), format_char(read_uint(bytes))),
+        Encoding::Boolean => match read_uint(bytes) {
+            0 => "false".to_string(),
+            1 => "true".to_string(),
+            other => other.to_string(),
+        },
+        Encoding::Float => match read_float(bytes) {
+            Some(f) => format_float(f, bytes.len()),
+            None => format_bytes(bytes),
+        },
+        Encoding::Address => format!("{:#x}", read_uint(bytes)),
+        Encoding::Unknown => format_bytes(bytes),
+    }
+}
+
+//Show a float with as few digits as it takes to read it back, switching to an exponent for very
+//large and very small numbers, as %g does. A float that was 4 bytes gets the digits of an f32.
+fn format_float(f: f64, size: usize) -> String {
+    let abs = f.abs();
+    let exponent = abs != 0.0 && abs.is_finite() && !(1e-4..1e17).contains(&abs);
+    let text = match (size, exponent) {
+        (4, false) => (f as f32).to_string(),
+        (4, true) => format!("{:e}", f as f32),
+        (_, false) => f.to_string(),
+        (_, true) => format!("{:e}", f),
+    };
+    //C writes the sign of a positive exponent
+    match text.find('e') {
+        Some(e) if !text[e + 1..].starts_with('-') => format!("{}e+{}", &text[..e], &text[e + 1..]),
+        _ => text,
+    }
+}
+
+//Append a character to a quoted string or character literal, escaping it if it isn't printable.
+//Code units that couldn't be decoded (given as Err) are always escaped.
+fn push_escaped_char(out: &mut String, c: Result<char, u64>, quote: char) {
+    match c {
+        Ok(c) if c == quote || c == '\\' => {
+            out.push('\\');
+            out.push(c);
+        }
+        Ok('\n') => out.push_str("\\n"),
+        Ok('\t') => out.push_str("\\t"),
+        Ok('\r') => out.push_str("\\r"),
+        Ok(c) if !c.is_control() => out.push(c),
+        Ok(c) if (c as u32) < 0x100 => out.push_str(&format!("\\{:03o}", c as u32)),
+        Ok(c) => out.push_str(&format!("\\x{:x}", c as u32)),
+        Err(unit) if unit < 0x100 => out.push_str(&format!("\\{:03o}", unit)),
+        Err(unit) => out.push_str(&format!("\\x{:x}", unit)),
+    }
+}
+
+//Quote a character the way it would be written in C source
+fn format_char(c: u64) -> String {
+    match c {
+        0x07 => "'\\a'".to_string(),
+        0x08 => "'\\b'".to_string(),
+        0x09 => "'\\t'".to_string(),
+        0x0a => "'\\n'".to_string(),
+        0x0b => "'\\v'".to_string(),
+        0x0c => "'\\f'".to_string(),
+        0x0d => "'\\r'".to_string(),
+        0x27 => "'\\''".to_string(),
+        0x5c => "'\\\\'".to_string(),
+        0x20..=0x7e => format!("'{}'", c as u8 as char),
+        0x00..=0xff => format!("'\\{:03o}'", c),
+        _ => match std::char::from_u32(c as u32) {
+            Some(ch) => format!("'{}'", ch),
+            None => format!("'\\x{:x}'", c),
+        },
+    }
+}
+
+fn enum_value(enumerators: &[Enumerator], bytes: &[u8]) -> i64 {
+    if enumerators.iter().any(|e| e.value < 0) {
+        read_int(bytes)
+    } else {
+        read_uint(bytes) as i64
+    }
+}
+
+//Show an enum value by the name of its enumerator. Values of "flag" enums, whose enumerators
+//don't share any bits, are shown as a combination of enumerators when possible.
+fn format_enum(enumerators: &[Enumerator], value: i64) -> String {
+    if let Some(e) = enumerators.iter().find(|e| e.value == value) {
+        return e.name.clone();
+    }
+    let mut seen_bits = 0;
+    let is_flag_enum = enumerators.iter().all(|e| {
+        let disjoint = e.value >= 0 && seen_bits & e.value == 0;
+        seen_bits |= e.value;
+        disjoint
+    });
+    if is_flag_enum && value > 0 {
+        let mut remaining = value;
+        let mut names = Vec::new();
+        for e in enumerators {
+            if e.value != 0 && remaining & e.value == e.value {
+                names.push(e.name.clone());
+                remaining &= !e.value;
+            }
+        }
+        if !names.is_empty() {
+            if remaining != 0 {
+                names.push(format!("unknown: {:#x}", remaining));
+            }
+            return format!("({})", names.join(" | "));
+        }
+    }
+    value.to_string()
+}
+
+fn format_bytes(bytes: &[u8]) -> String {
+    let hex: Vec<String> = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("<{}>", hex.join(" "))
+}
+
+//A width or precision in a printf conversion, given either inline or as an argument with `*`
+#[derive(Debug, Clone, Copy)]
+enum Count {
+    Fixed(usize),
+    Arg,
+}
+
+//One `%...` conversion of a printf format string
+#[derive(Debug, Default)]
+struct Conversion {
+    left_align: bool,
+    zero_pad: bool,
+    plus_sign: bool,
+    space_sign: bool,
+    alternate: bool,
+    width: Option<Count>,
+    precision: Option<Count>,
+    //size of the integer argument in bytes
+    int_size: usize,
+    conversion: u8,
+}
+
+//Parse the conversion starting just after a `%`. Returns it and the index just past it.
+fn parse_conversion(format: &[u8], mut i: usize) -> Result<(Conversion, usize), String> {
+    let mut spec = Conversion {
+        int_size: 4,
+        ..Default::default()
+    };
+    while let Some(flag) = format.get(i) {
+        match flag {
+            b'-' => spec.left_align = true,
+            b'0' => spec.zero_pad = true,
+            b'+' => spec.plus_sign = true,
+            b' ' => spec.space_sign = true,
+            b'#' => spec.alternate = true,
+            _ => break,
+        }
+        i += 1;
+    }
+    let parse_count = |i: &mut usize| -> Option<Count> {
+        if format.get(*i) == Some(&b'*') {
+            *i += 1;
+            return Some(Count::Arg);
+        }
+        let start = *i;
+        while format.get(*i).is_some_and(|c| c.is_ascii_digit()) {
+            *i += 1;
+        }
+        std::str::from_utf8(&format[start..*i]).unwrap().parse().ok().map(Count::Fixed)
+    };
+    spec.width = parse_count(&mut i);
+    if format.get(i) == Some(&b'.') {
+        i += 1;
+        spec.precision = Some(parse_count(&mut i).unwrap_or(Count::Fixed(0)));
+    }
+    loop {
+        match format.get(i) {
+            Some(b'h') => spec.int_size = if spec.int_size == 2 { 1 } else { 2 },
+            Some(b'l') | Some(b'q') | Some(b'L') | Some(b'z') | Some(b'j') | Some(b't') => {
+                spec.int_size = 8
+            }
+            _ => break,
+        }
+        i += 1;
+    }
+    match format.get(i) {
+        Some(c) if b"diuoxXcspfFeEgG%".contains(c) => {
+            spec.conversion = *c;
+            Ok((spec, i + 1))
+        }
+        Some(c) => Err(format!("Unrecognized format specifier '{}' in printf", *c as char)),
+        None => Err("Incomplete format specifier at end of format string".to_string()),
+    }
+}
+
+//The sign or base prefix that goes before a number, before any zero padding
+fn sign_prefix(spec: &Conversion, negative: bool) -> &'static str {
+    if negative {
+        "-"
+    } else if spec.plus_sign {
+        "+"
+    } else if spec.space_sign {
+        " "
+    } else {
+        ""
+    }
+}
+
+fn format_printf_int(spec: &Conversion, precision: Option<usize>, value: u64) -> String {
+    let bits = spec.int_size * 8;
+    let unsigned = if bits == 64 { value } else { value & ((1 << bits) - 1) };
+    let (prefix, digits) = match spec.conversion {
+        b'd' | b'i' => {
+            let shift = 64 - bits;
+            let signed = ((unsigned << shift) as i64) >> shift;
+            (sign_prefix(spec, signed < 0), signed.unsigned_abs().to_string())
+        }
+        b'u' => ("", unsigned.to_string()),
+        b'o' => ("", format!("{:o}", unsigned)),
+        b'x' => (if spec.alternate && unsigned != 0 { "0x" } else { "" }, format!("{:x}", unsigned)),
+        _ => (if spec.alternate && unsigned != 0 { "0X" } else { "" }, format!("{:X}", unsigned)),
+    };
+    let mut digits = match precision {
+        //a precision of 0 prints nothing for 0
+        Some(0) if unsigned == 0 => String::new(),
+        Some(precision) if precision > digits.len() => {
+            format!("{}{}", "0".repeat(precision - digits.len()), digits)
+        }
+        _ => digits,
+    };
+    if spec.conversion == b'o' && spec.alternate && !digits.starts_with('0') {
+        digits.insert(0, '0');
+    }
+    format!("{}{}", prefix, digits)
+}
+
+fn format_printf_float(spec: &Conversion, precision: Option<usize>, value: f64) -> String {
+    let upper = spec.conversion.is_ascii_uppercase();
+    let prefix = sign_prefix(spec, value.is_sign_negative() && !value.is_nan());
+    let value = value.abs();
+    if !value.is_finite() {
+        let text = if value.is_nan() { "nan" } else { "inf" };
+        let text = if upper { text.to_uppercase() } else { text.to_string() };
+        return format!("{}{}", prefix, text);
+    }
+    let precision = precision.unwrap_or(6);
+    let text = match spec.conversion.to_ascii_lowercase() {
+        b'f' => format!("{:.*}", precision, value),
+        b'e' => format_exponent(value, precision),
+        _ => {
+            //%g picks between %e and %f depending on the exponent, then drops trailing zeros
+            let precision = precision.max(1);
+            let exponent = if value == 0.0 {
+                0
+            } else {
+                format!("{:.*e}", precision - 1, value)
+                    .split('e')
+                    .nth(1)
+                    .unwrap()
+                    .parse::<i32>()
+                    .unwrap()
+            };
+            let text = if exponent < -4 || exponent >= precision as i32 {
+                format_exponent(value, precision - 1)
+            } else {
+                format!("{:.*}", (precision as i32 - 1 - exponent) as usize, value)
+            };
+            if spec.alternate {
+                text
+            } else {
+                strip_trailing_zeros(&text)
+            }
+        }
+    };
+    let text = if upper { text.to_uppercase() } else { text };
+    format!("{}{}", prefix, text)
+}
+
+//Format like C's %e, which always has a signed exponent of at least two digits
+fn format_exponent(value: f64, precision: usize) -> String {
+    let text = format!("{:.*e}", precision, value);
+    let (mantissa, exponent) = text.split_at(text.find('e').unwrap());
+    let exponent: i32 = exponent[1..].parse().unwrap();
+    format!(
+        "{}e{}{:02}",
+        mantissa,
+        if exponent < 0 { '-' } else { '+' },
+        exponent.abs()
+    )
+}
+
+fn strip_trailing_zeros(text: &str) -> String {
+    let (mantissa, exponent) = match text.find('e') {
+        Some(e) => text.split_at(e),
+        None => (text, ""),
+    };
+    let mantissa = if mantissa.contains('.') {
+        mantissa.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        mantissa
+    };
+    format!("{}{}", mantissa, exponent)
+}
+
+//Pad a converted argument to the conversion's field width and append it to `out`
+fn pad_conversion(
+    out: &mut Vec<u8>,
+    spec: &Conversion,
+    width: Option<usize>,
+    precision: Option<usize>,
+    text: Vec<u8>,
+) {
+    let padding = width.unwrap_or(0).saturating_sub(text.len());
+    let is_number = !b"csp".contains(&spec.conversion);
+    //zeros go after any sign or 0x, and an integer precision disables them
+    let zero_pad = spec.zero_pad
+        && !spec.left_align
+        && is_number
+        && (precision.is_none() || b"fFeEgG".contains(&spec.conversion));
+    if spec.left_align {
+        out.extend_from_slice(&text);
+        out.extend(std::iter::repeat_n(b' ', padding));
+    } else if zero_pad {
+        let sign_len = text
+            .iter()
+            .take_while(|c| b"+- ".contains(c))
+            .count()
+            + if text[..].starts_with(b"0x") || text[..].starts_with(b"0X") { 2 } else { 0 };
+        out.extend_from_slice(&text[..sign_len]);
+        out.extend(std::iter::repeat_n(b'0', padding));
+        out.extend_from_slice(&text[sign_len..]);
+    } else {
+        out.extend(std::iter::repeat_n(b' ', padding));
+        out.extend_from_slice(&text);
+    }
+}
@@ -1,24 +1,71 @@
-mod debugger;
-mod debugger_command;
-mod inferior;
-mod dwarf_data;
-mod gimli_wrapper;
-
-use crate::debugger::Debugger;
-use nix::sys::signal::{signal, SigHandler, Signal};
+use deet_core::debugger::Debugger;
 use std::env;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        println!("Usage: {} <target program>", args[0]);
+    let usage = format!(
+        "Usage: {0} <target program> [--core <core file>] [-ex <command>]... [--batch] [-nx]\n       \
+         {0} --server <[host]:port> <target program> [args]...",
+        args[0]
+    );
+    if args.len() < 2 {
+        println!("{}", usage);
         std::process::exit(1);
     }
+    //`deet --server :1234 prog args` runs the program for a frontend that connects to the port,
+    //like gdbserver
+    if args[1] == "--server" {
+        if args.len() < 4 {
+            println!("{}", usage);
+            std::process::exit(1);
+        }
+        if let Err(e) = deet_core::server::serve(&args[2], &args[3], &args[4..]) {
+            println!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
     let target = &args[1];
+    //a core file to look at can follow the program, as in `deet prog --core core.1234`, and
+    //commands to run first, as in `deet prog -ex "break main" -ex run`. With `--batch` the
+    //debugger quits after them rather than reading commands from the terminal.
+    let mut core = None;
+    let mut commands = Vec::new();
+    let mut batch = false;
+    let mut init_files = true;
+    let mut options = args[2..].iter();
+    while let Some(option) = options.next() {
+        match (option.as_str(), options.as_slice().first()) {
+            ("--core", Some(_)) => core = options.next(),
+            ("-ex", Some(_)) | ("--eval-command", Some(_)) => commands.extend(options.next()),
+            //like gdb, batch mode doesn't run the init files, or ask for confirmation
+            ("-batch", _) | ("--batch", _) => {
+                batch = true;
+                init_files = false;
+            }
+            ("-nx", _) | ("--nx", _) => init_files = false,
+            _ => {
+                println!("{}", usage);
+                std::process::exit(1);
+            }
+        }
+    }
 
-    // Disable handling of ctrl+c in this process (so that ctrl+c only gets delivered to child
-    // processes)
-    unsafe { signal(Signal::SIGINT, SigHandler::SigIgn) }.expect("Error disabling SIGINT handling");
-
-    Debugger::new(target).run();
+    let mut debugger = Debugger::new(target, init_files);
+    if let Some(core) = core {
+        debugger.load_core_file(core);
+    }
+    if batch {
+        debugger.run_line("set confirm off");
+    }
+    for command in commands {
+        if !debugger.run_line(command) {
+            return;
+        }
+    }
+    if batch {
+        debugger.run_line("quit");
+    } else {
+        debugger.run();
+    }
 }
@@ -0,0 +1,11 @@
+#include <stdlib.h>
+
+int primes[5] = {2, 3, 5, 7, 11};
+
+int main() {
+    int *squares = malloc(10 * sizeof(int));
+    for (int i = 0; i < 10; i++) {
+        squares[i] = i * i;
+    }
+    return squares[3] + primes[0];
+}
@@ -0,0 +1,19 @@
+#include <stdio.h>
+
+int counter = 0;
+
+int add(int a, int b) {
+    counter++;
+    return a + b;
+}
+
+int twice(int x) {
+    return add(x, x);
+}
+
+int main() {
+    int result = twice(21);
+    result = add(result, 0);
+    printf("%d\n", result);
+    return result == 42 ? 3 : 1;
+}
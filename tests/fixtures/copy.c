@@ -0,0 +1,11 @@
+#include <stdio.h>
+
+int main() {
+    int c, count = 0;
+    while ((c = getchar()) != EOF) {
+        putchar(c);
+        count++;
+    }
+    fprintf(stderr, "%d bytes\n", count);
+    return 0;
+}
@@ -0,0 +1,6 @@
+#include <signal.h>
+
+int main() {
+    raise(SIGABRT);
+    return 0;
+}
@@ -0,0 +1,11 @@
+#include <stdio.h>
+
+int main() {
+    FILE *f = fopen("created.txt", "w");
+    if (f == NULL) {
+        return 1;
+    }
+    fputs("created\n", f);
+    fclose(f);
+    return 0;
+}
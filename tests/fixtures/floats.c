@@ -0,0 +1,12 @@
+#include <stdio.h>
+
+double scale(double x, float y) {
+    double r = x * y;
+    return r;
+}
+
+int main() {
+    double v = scale(1.5, 2.0f);
+    printf("%g\n", v);
+    return 0;
+}
@@ -0,0 +1,20 @@
+#include <sys/wait.h>
+#include <unistd.h>
+
+int work(int x) {
+    return x + 1;
+}
+
+//Forks, or vforks with an argument, and returns what the child and then the parent get from work
+int main(int argc, char **argv) {
+    if (argc > 1) {
+        if (vfork() == 0) {
+            _exit(work(1));
+        }
+    } else if (fork() == 0) {
+        return work(1);
+    }
+    int status;
+    wait(&status);
+    return work(WEXITSTATUS(status));
+}
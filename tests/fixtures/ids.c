@@ -0,0 +1,14 @@
+#include <stdio.h>
+#include <unistd.h>
+
+//Prints the user and group it runs as, and then its supplementary groups
+int main(void) {
+    gid_t groups[64];
+    int count = getgroups(64, groups);
+    printf("%d %d", (int)getuid(), (int)getgid());
+    for (int i = 0; i < count; i++) {
+        printf(" %d", (int)groups[i]);
+    }
+    printf("\n");
+    return 0;
+}
@@ -0,0 +1,13 @@
+#include <unistd.h>
+
+//Stands in for the function of the same name in the program launched, so that a breakpoint can
+//be set on it before that program is loaded
+int add(int a, int b) {
+    return a - b;
+}
+
+//Runs the program it is given in its place, like a launcher script
+int main(int argc, char **argv) {
+    execv(argv[1], argv + 1);
+    return add(100, 0);
+}
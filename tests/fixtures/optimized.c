@@ -0,0 +1,15 @@
+#include <stdio.h>
+
+__attribute__((noinline)) int leaf(int n) {
+    printf("%d\n", n);
+    return n + 1;
+}
+
+__attribute__((noinline)) int middle(int n) {
+    int r = leaf(n * 2);
+    return r * 3;
+}
+
+int main() {
+    return middle(20) == 123 ? 0 : 1;
+}
@@ -0,0 +1,14 @@
+#include <unistd.h>
+
+int work(int x) {
+    return x + 1;
+}
+
+//Runs itself again with an extra argument, and returns what work gets for the number of them
+int main(int argc, char **argv) {
+    if (argc == 1) {
+        execl(argv[0], argv[0], "again", (char *)NULL);
+        return 100;
+    }
+    return work(argc);
+}
@@ -0,0 +1,11 @@
+int *target = 0;
+
+void store(int value) {
+    *target = value;
+}
+
+int main() {
+    int answer = 42;
+    store(answer);
+    return 0;
+}
@@ -0,0 +1,14 @@
+#include <signal.h>
+
+int handled = 0;
+
+void on_usr1(int signal) {
+    handled++;
+}
+
+int main() {
+    signal(SIGUSR1, on_usr1);
+    raise(SIGUSR1);
+    raise(SIGUSR1);
+    return handled;
+}
@@ -0,0 +1,11 @@
+int counter = 0;
+
+void tick() {
+    counter++;
+}
+
+int main() {
+    for (;;) {
+        tick();
+    }
+}
@@ -0,0 +1,25 @@
+#include <pthread.h>
+
+volatile long count;
+
+void *spin(void *arg) {
+    for (;;) {
+        count++;
+    }
+    return arg;
+}
+
+int done(long sum) {
+    return (int)(sum % 7);
+}
+
+//Counts in another thread forever while the main one does some sums of its own
+int main(void) {
+    pthread_t thread;
+    pthread_create(&thread, NULL, spin, NULL);
+    long sum = 0;
+    for (long i = 0; i < 10000000; i++) {
+        sum += i;
+    }
+    return done(sum);
+}
@@ -0,0 +1,13 @@
+#include <stdio.h>
+#include <uchar.h>
+#include <wchar.h>
+
+int main() {
+    const char *greeting = "héllo, wörld";
+    char invalid[8] = {'a', (char)0xff, 'b', '\n', 0};
+    wchar_t *wide = L"wide ✓";
+    char16_t *utf16 = u"utf-16 ✓";
+    wchar_t letter = L'λ';
+    printf("%s %s %ls %d %lc\n", greeting, invalid, wide, utf16[0], letter);
+    return 0;
+}
@@ -0,0 +1,24 @@
+#include <pthread.h>
+
+int work(int x) {
+    return x * 2;
+}
+
+void *worker(void *arg) {
+    return (void *)(long)work((int)(long)arg);
+}
+
+//Runs work in two threads at once, and returns the sum of what they got
+int main(void) {
+    pthread_t threads[2];
+    int total = 0;
+    for (long i = 0; i < 2; i++) {
+        pthread_create(&threads[i], NULL, worker, (void *)(i + 1));
+    }
+    for (int i = 0; i < 2; i++) {
+        void *result;
+        pthread_join(threads[i], &result);
+        total += (int)(long)result;
+    }
+    return total;
+}
@@ -0,0 +1,17 @@
+#include <stdexcept>
+
+int parse(int value) {
+    if (value < 0) {
+        throw std::invalid_argument("negative");
+    }
+    return value;
+}
+
+//Throws from parse and catches it in main
+int main() {
+    try {
+        return parse(-1);
+    } catch (const std::invalid_argument &) {
+        return 7;
+    }
+}
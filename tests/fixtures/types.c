@@ -0,0 +1,37 @@
+#include <stdio.h>
+
+enum color { RED, GREEN, BLUE };
+
+enum permissions { READ = 1, WRITE = 2, EXEC = 4 };
+
+union number {
+    int i;
+    float f;
+};
+
+struct flags {
+    unsigned int ready : 1;
+    unsigned int mode : 3;
+    int delta : 4;
+};
+
+struct point {
+    int x;
+    int y;
+};
+
+enum color favorite = BLUE;
+
+void show(enum color c, union number n, struct flags f) {
+    enum permissions p = READ | EXEC;
+    struct point corners[2] = {{1, 2}, {3, 4}};
+    printf("%d %d %d %d %d %d\n", c, n.i, f.mode, p, corners[0].x, corners[1].y);
+}
+
+int main() {
+    union number n;
+    n.f = 1.5f;
+    struct flags f = {1, 5, -3};
+    show(GREEN, n, f);
+    return 0;
+}
@@ -0,0 +1,30 @@
+#include <stdio.h>
+#include <string.h>
+
+struct account {
+    char owner[16];
+    long balance;
+    unsigned int status;
+};
+
+struct account savings = {"alice", 100, 0};
+int counter = 0;
+
+void deposit(struct account *acct, long amount) {
+    acct->balance += amount;
+    acct->status |= 0x1;
+}
+
+int main() {
+    char buffer[32];
+    memset(buffer, 0, sizeof(buffer));
+    for (int i = 0; i < 3; i++) {
+        counter++;
+        deposit(&savings, 10 * (i + 1));
+    }
+    savings.status |= 0x100;
+    strcpy(buffer, "hello, world");
+    strcpy(savings.owner, "bob");
+    printf("%s %ld %d %s\n", savings.owner, savings.balance, counter, buffer);
+    return 0;
+}
@@ -0,0 +1,1811 @@
+use deet_core::session::{Event, Session};
+use deet_core::{DwarfData, Inferior, Redirections, Settings, Status};
+use nix::sys::signal::Signal;
+use nix::sys::wait::{waitpid, WaitStatus};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT_FIXTURE: AtomicUsize = AtomicUsize::new(0);
+
+//Compile a C program in tests/fixtures the way the README says to, into a directory of its own
+//so that tests running in parallel don't share binaries. The debugger reads the source from next
+//to the binary, so the source is copied there too. A C++ program, in a .cpp file, is compiled as
+//C++.
+fn fixture(name: &str) -> String {
+    fixture_with_flags(name, &["-O0", "-fno-omit-frame-pointer"])
+}
+
+//Compile a C program in tests/fixtures like `fixture`, with other optimization flags. It is a
+//position dependent executable unless the flags ask for `-pie`.
+fn fixture_with_flags(name: &str, flags: &[&str]) -> String {
+    let dir = std::env::temp_dir().join(format!(
+        "deet-tests-{}-{}",
+        std::process::id(),
+        NEXT_FIXTURE.fetch_add(1, Ordering::SeqCst)
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let (source, compiler) = match fixtures.join(format!("{}.cpp", name)) {
+        source if source.exists() => (source, "c++"),
+        _ => (fixtures.join(format!("{}.c", name)), "cc"),
+    };
+    let binary = dir.join(name);
+    std::fs::copy(&source, dir.join(source.file_name().unwrap())).unwrap();
+    let status = Command::new(compiler)
+        .args(flags)
+        .args(["-g", "-gdwarf-4"])
+        .args(if flags.contains(&"-pie") { &[][..] } else { &["-no-pie"][..] })
+        .arg("-o")
+        .arg(&binary)
+        .arg(&source)
+        .status()
+        .expect("cannot run cc");
+    assert!(status.success(), "cannot compile {}", name);
+    binary.to_str().unwrap().to_string()
+}
+
+//Run the debugger itself on a program, typing `commands` at its prompt, and return what it printed
+fn debugger_output(program: &str, commands: &str) -> String {
+    run_debugger(Command::new(env!("CARGO_BIN_EXE_deet")).arg(program), commands)
+}
+
+//Run the debugger like `debugger_output`, with the files it keeps in the home directory, such as
+//its history and aliases, in another directory
+fn debugger_output_with_home(program: &str, commands: &str, home: &str) -> String {
+    run_debugger(Command::new(env!("CARGO_BIN_EXE_deet")).arg(program).env("HOME", home), commands)
+}
+
+fn run_debugger(command: &mut Command, commands: impl AsRef<[u8]>) -> String {
+    use std::io::Write;
+    use std::process::Stdio;
+    let mut debugger = command.stdin(Stdio::piped()).stdout(Stdio::piped()).spawn().unwrap();
+    debugger.stdin.take().unwrap().write_all(commands.as_ref()).unwrap();
+    let output = debugger.wait_with_output().unwrap();
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+//Run a program until it crashes, letting it dump core in its directory, and return the core file.
+//The kernel has to write core files to the working directory, which it doesn't when the
+//pattern in /proc/sys/kernel/core_pattern pipes them to a program or names another directory.
+fn dump_core(program: &str) -> PathBuf {
+    let dir = std::path::Path::new(program).parent().unwrap();
+    Command::new("sh")
+        .arg("-c")
+        .arg(format!("ulimit -c unlimited; exec {}", program))
+        .current_dir(dir)
+        .status()
+        .unwrap();
+    std::fs::read_dir(dir)
+        .unwrap()
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .find(|path| path.file_name().is_some_and(|name| name.to_string_lossy().starts_with("core")))
+        .unwrap_or_else(|| {
+            let pattern = std::fs::read_to_string("/proc/sys/kernel/core_pattern").unwrap_or_default();
+            panic!("no core file was dumped next to {}; core_pattern is {}", program, pattern.trim())
+        })
+}
+
+//Stand in for gdbserver on `listener`, for a program that isn't really running: its memory starts
+//out zeroed, and each `c` gets the next of `stops` as its reply, after moving the program to the
+//address given with it, if any. An address is just after a breakpoint, which has to have been
+//written there, and the stop is in a frame with 21 stored where `add` keeps `a`.
+fn fake_stub(listener: std::net::TcpListener, stops: Vec<(Option<usize>, &str)>) {
+    use std::io::{Read, Write};
+    let (mut stream, _) = listener.accept().unwrap();
+    let mut memory: HashMap<usize, u8> = HashMap::new();
+    //rax to r15, rip, and then eflags to gs, as `g` sends them
+    let mut registers = [0u64; 24];
+    registers[6] = 0x7fe010;
+    registers[7] = 0x7fe000;
+    registers[16] = 0x401000;
+    let mut stops = stops.into_iter();
+    let mut bytes = std::io::BufReader::new(stream.try_clone().unwrap()).bytes().map(|byte| byte.unwrap());
+    loop {
+        //acknowledgements and anything else before the packet are skipped
+        if bytes.by_ref().find(|&byte| byte == b'$').is_none() {
+            return;
+        }
+        let packet: Vec<u8> = bytes.by_ref().take_while(|&byte| byte != b'#').collect();
+        let packet = String::from_utf8(packet).unwrap();
+        bytes.by_ref().take(2).for_each(drop);
+        stream.write_all(b"+").unwrap();
+        let hex = |text: &str| usize::from_str_radix(text, 16).unwrap();
+        let reply = match packet.as_str() {
+            "?" => "S05".to_string(),
+            "qC" => "QCp4d2.4d2".to_string(),
+            "g" => registers
+                .iter()
+                .enumerate()
+                .flat_map(|(i, value)| value.to_le_bytes()[..if i < 17 { 8 } else { 4 }].to_vec())
+                .map(|byte| format!("{:02x}", byte))
+                .collect(),
+            "s" => {
+                registers[16] += 1;
+                "S05".to_string()
+            }
+            "c" => {
+                let (rip, reply) = stops.next().unwrap();
+                if let Some(rip) = rip {
+                    assert_eq!(memory.get(&(rip - 1)), Some(&0xcc));
+                    registers[16] = rip as u64;
+                    //the frame base is the call frame address, 16 bytes above %rbp
+                    for (i, byte) in 21i32.to_le_bytes().iter().enumerate() {
+                        memory.insert(0x7fe010 + 16 - 20 + i, *byte);
+                    }
+                }
+                reply.to_string()
+            }
+            "k" => return,
+            "D" => "OK".to_string(),
+            packet if packet.starts_with('G') => {
+                let block: Vec<u8> = (1..packet.len()).step_by(2).map(|i| hex(&packet[i..i + 2]) as u8).collect();
+                let mut offset = 0;
+                for (i, value) in registers.iter_mut().enumerate() {
+                    let size = if i < 17 { 8 } else { 4 };
+                    let mut word = [0; 8];
+                    word[..size].copy_from_slice(&block[offset..offset + size]);
+                    *value = u64::from_le_bytes(word);
+                    offset += size;
+                }
+                "OK".to_string()
+            }
+            packet if packet.starts_with('m') => {
+                let (addr, len) = packet[1..].split_at(packet.find(',').unwrap() - 1);
+                let (addr, len) = (hex(addr), hex(&len[1..]));
+                (addr..addr + len).map(|a| format!("{:02x}", memory.get(&a).unwrap_or(&0))).collect()
+            }
+            packet if packet.starts_with('M') => {
+                let (place, data) = packet[1..].split_at(packet.find(':').unwrap() - 1);
+                let addr = hex(place.split(',').next().unwrap());
+                for (i, at) in (1..data.len()).step_by(2).enumerate() {
+                    memory.insert(addr + i, hex(&data[at..at + 2]) as u8);
+                }
+                "OK".to_string()
+            }
+            //an empty reply says the packet isn't supported, e.g. qXfer:auxv:read for a program
+            //that isn't position independent
+            _ => String::new(),
+        };
+        let checksum = reply.bytes().fold(0u8, |sum, byte| sum.wrapping_add(byte));
+        stream.write_all(format!("${}#{:02x}", reply, checksum).as_bytes()).unwrap();
+    }
+}
+
+fn stopped_in(events: &[Event]) -> Option<(String, usize)> {
+    match events.last()? {
+        Event::Stopped {
+            function: Some(function),
+            line: Some(line),
+            ..
+        } => Some((function.clone(), *line)),
+        _ => None,
+    }
+}
+
+#[test]
+fn runs_to_exit_without_breakpoints() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    assert_eq!(session.run_command("run").unwrap(), vec![Event::Exited(3)]);
+}
+
+#[test]
+fn sessions_keep_their_own_output() {
+    let program = fixture("calls");
+    let mut first = Session::new(&program).unwrap();
+    let mut second = Session::new(&program).unwrap();
+    first.run_command("print 1").unwrap();
+    second.run_command("print 2").unwrap();
+    assert_eq!(first.take_output(), "$1 = 1\n");
+    //dropping a session doesn't stop the others from keeping their output
+    drop(first);
+    second.run_command("print 3").unwrap();
+    assert_eq!(second.take_output(), "$1 = 2\n$2 = 3\n");
+}
+
+#[test]
+fn drives_the_inferior_directly() {
+    let program = fixture("calls");
+    let debug_data = DwarfData::from_file(&program).unwrap();
+    let addr = debug_data.get_addr_for_function(None, "add").unwrap();
+    assert_eq!(debug_data.get_function_from_addr(addr), Some("add".to_string()));
+    assert_eq!(debug_data.get_line_from_addr(addr).map(|line| line.number), Some(5));
+    let breakpoints = HashMap::new();
+    let mut inferior = Inferior::new(&program, &vec![], &Redirections::default(), &breakpoints, &Settings::new()).unwrap();
+    let status = inferior.cont(&breakpoints).unwrap();
+    assert!(matches!(status, Status::Exited(3)), "{:?}", status);
+}
+
+#[test]
+fn captures_what_the_inferior_reports() {
+    let program = fixture("threads");
+    let breakpoints = HashMap::new();
+    let mut output = String::new();
+    let status = deet_core::capture(&mut output, || {
+        let mut inferior = Inferior::new(&program, &vec![], &Redirections::default(), &breakpoints, &Settings::new()).unwrap();
+        inferior.cont(&breakpoints).unwrap()
+    });
+    assert!(matches!(status, Status::Exited(6)), "{:?}", status);
+    assert_eq!(output.matches("[New thread").count(), 2, "{}", output);
+}
+
+#[test]
+fn runs_again_with_the_same_arguments() {
+    let mut session = Session::new(&fixture("args")).unwrap();
+    assert_eq!(session.run_command("run one two").unwrap(), vec![Event::Exited(3)]);
+    assert_eq!(session.run_command("run").unwrap(), vec![Event::Exited(3)]);
+    session.run_command("break 4").unwrap();
+    session.run_command("run").unwrap();
+    assert_eq!(session.evaluate("argc").unwrap(), "3");
+    //restart starts over with the breakpoints and arguments
+    session.run_command("restart").unwrap();
+    assert_eq!(session.evaluate("argv[2]").unwrap().split(' ').next_back().unwrap(), "\"two\"");
+    session.run_command("delete").unwrap();
+    session.run_command("set args").unwrap();
+    assert_eq!(session.run_command("run").unwrap(), vec![Event::Exited(1)]);
+}
+
+#[test]
+fn redirects_input_and_output() {
+    let binary = fixture("copy");
+    let dir = PathBuf::from(&binary).parent().unwrap().to_path_buf();
+    std::fs::write(dir.join("in.txt"), "hello\n").unwrap();
+    let mut session = Session::new(&binary).unwrap();
+    let run = format!("run < {0}/in.txt >{0}/out.txt 2> {0}/err.txt", dir.display());
+    assert_eq!(session.run_command(&run).unwrap(), vec![Event::Exited(0)]);
+    assert_eq!(std::fs::read_to_string(dir.join("out.txt")).unwrap(), "hello\n");
+    assert_eq!(std::fs::read_to_string(dir.join("err.txt")).unwrap(), "6 bytes\n");
+    //the redirections are remembered, and `>>` appends
+    session.run_command("run").unwrap();
+    assert_eq!(std::fs::read_to_string(dir.join("out.txt")).unwrap(), "hello\n");
+    let run = format!("run <{0}/in.txt >> {0}/out.txt", dir.display());
+    session.run_command(&run).unwrap();
+    assert_eq!(std::fs::read_to_string(dir.join("out.txt")).unwrap(), "hello\nhello\n");
+    assert!(session.run_command("run < missing.txt").unwrap().is_empty());
+}
+
+#[test]
+fn runs_in_the_working_directory_given() {
+    let binary = fixture("cwd");
+    let dir = PathBuf::from(&binary).parent().unwrap().join("work");
+    std::fs::create_dir(&dir).unwrap();
+    let mut session = Session::new(&binary).unwrap();
+    session.run_command(&format!("cd {}", dir.display())).unwrap();
+    //a directory that doesn't exist leaves the setting as it was
+    session.run_command("set cwd /nonexistent").unwrap();
+    assert_eq!(session.run_command("run > out.txt").unwrap(), vec![Event::Exited(0)]);
+    assert_eq!(std::fs::read_to_string(dir.join("created.txt")).unwrap(), "created\n");
+    //redirections are relative to it too
+    assert!(dir.join("out.txt").exists());
+}
+
+#[test]
+fn runs_the_program_as_another_user() {
+    //only root can run the program as someone else
+    if !nix::unistd::geteuid().is_root() {
+        return;
+    }
+    let binary = fixture("ids");
+    let dir = PathBuf::from(&binary).parent().unwrap().to_path_buf();
+    let nobody = nix::unistd::User::from_name("nobody").unwrap().expect("no user nobody");
+    let mut session = Session::new(&binary).unwrap();
+    session.run_command(&format!("set exec-uid {}", nobody.uid.as_raw())).unwrap();
+    session.run_command(&format!("run > {}/ids.txt", dir.display())).unwrap();
+    //the program is in the user's group, and in none of the debugger's
+    let (uid, gid) = (nobody.uid.as_raw(), nobody.gid.as_raw());
+    assert_eq!(std::fs::read_to_string(dir.join("ids.txt")).unwrap(), format!("{} {} {}\n", uid, gid, gid));
+    session.run_command("set exec-gid 0").unwrap();
+    session.run_command("run").unwrap();
+    assert_eq!(std::fs::read_to_string(dir.join("ids.txt")).unwrap(), format!("{} 0 0\n", uid));
+}
+
+#[test]
+fn stops_at_breakpoints() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    session.run_command("break add").unwrap();
+    assert_eq!(session.breakpoints().len(), 1);
+
+    let events = session.run_command("run").unwrap();
+    assert_eq!(stopped_in(&events), Some(("add".to_string(), 5)));
+    let events = session.run_command("continue").unwrap();
+    assert_eq!(stopped_in(&events), Some(("add".to_string(), 5)));
+    assert_eq!(session.run_command("continue").unwrap(), vec![Event::Exited(3)]);
+}
+
+#[test]
+fn detaches_from_the_program() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    session.run_command("break add").unwrap();
+    session.run_command("run").unwrap();
+    let pid = match session.run_command("detach").unwrap()[..] {
+        [Event::Detached(pid)] => pid,
+        ref events => panic!("not detached: {:?}", events),
+    };
+    //the breakpoint is taken out, so the second call to add doesn't trap
+    assert_eq!(waitpid(pid, None), Ok(WaitStatus::Exited(pid, 3)));
+    assert!(session.run_command("continue").unwrap().is_empty());
+    //the breakpoint is still there for the next run
+    let events = session.run_command("run").unwrap();
+    assert_eq!(stopped_in(&events), Some(("add".to_string(), 5)));
+}
+
+#[test]
+fn kills_the_program() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    session.run_command("break 6").unwrap();
+    session.run_command("run").unwrap();
+    session.run_command("up").unwrap();
+    assert_eq!(session.run_command("kill").unwrap(), vec![Event::Signaled(Signal::SIGKILL)]);
+    assert!(session.backtrace().is_err());
+    assert!(session.run_command("kill").unwrap().is_empty());
+    //a new run starts from scratch, with the breakpoints and the innermost frame
+    let events = session.run_command("run").unwrap();
+    assert_eq!(stopped_in(&events), Some(("add".to_string(), 6)));
+    assert_eq!(session.evaluate("a").unwrap(), "21");
+}
+
+#[test]
+fn runs_aliases_and_defined_commands_in_the_background() {
+    let output = debugger_output(
+        &fixture("spin"),
+        "alias bk = break tick\ndefine positive\nprint counter > 0\nend\nrun &\nbk\npositive\nquit\n",
+    );
+    assert!(output.contains("Breakpoint 1 at"), "{}", output);
+    //the defined command is known, though it has to wait for the program to stop
+    assert!(output.contains("Cannot execute this command while the program is running."), "{}", output);
+    assert!(!output.contains("Unrecognized command."), "{}", output);
+}
+
+#[test]
+fn runs_in_the_background() {
+    let mut session = Session::new(&fixture("spin")).unwrap();
+    assert!(session.run_command("run &").unwrap().is_empty());
+    //the program can't be resumed while it runs, but a breakpoint can be set and stops it
+    assert!(session.run_command("continue").unwrap().is_empty());
+    assert!(session.run_command("break tick").unwrap().is_empty());
+    let events = session.wait().unwrap();
+    assert_eq!(stopped_in(&events), Some(("tick".to_string(), 3)));
+    session.run_command("delete").unwrap();
+    session.run_command("continue &").unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    match session.run_command("interrupt").unwrap()[..] {
+        [Event::Stopped { signal, .. }] => assert_eq!(signal, Signal::SIGINT),
+        ref events => panic!("not interrupted: {:?}", events),
+    }
+    assert!(session.evaluate("counter").unwrap().parse::<i64>().unwrap() > 0);
+    assert!(session.wait().is_err());
+}
+
+#[test]
+fn breakpoints_on_file_and_line() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    session.run_command("break calls.c:11").unwrap();
+    session.run_command("break calls.c:add").unwrap();
+    session.run_command("break nonexistent.c:11").unwrap();
+    //the end of the file's path also names it
+    session.run_command("break fixtures/calls.c:16").unwrap();
+    assert_eq!(session.breakpoints().len(), 3);
+    let events = session.run_command("run").unwrap();
+    assert_eq!(stopped_in(&events), Some(("twice".to_string(), 11)));
+    let events = session.run_command("continue").unwrap();
+    assert_eq!(stopped_in(&events), Some(("add".to_string(), 5)));
+}
+
+#[test]
+fn breaks_on_every_function_in_a_file() {
+    let mut session = Session::new(&fixture("types")).unwrap();
+    session.run_command("break types.c:*").unwrap();
+    assert_eq!(session.breakpoints().len(), 2);
+    assert!(session.take_output().contains("Setting breakpoints on 2 functions in types.c:"));
+    let events = session.run_command("run").unwrap();
+    assert_eq!(stopped_in(&events), Some(("main".to_string(), 31)));
+    let events = session.run_command("continue").unwrap();
+    assert_eq!(stopped_in(&events), Some(("show".to_string(), 25)));
+    assert!(session.run_command("break nonexistent.c:*").unwrap().is_empty());
+    assert_eq!(session.breakpoints().len(), 2);
+}
+
+#[test]
+fn hardware_breakpoints_stop_like_breakpoints() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    session.run_command("hbreak 6").unwrap();
+    let events = session.run_command("run").unwrap();
+    assert_eq!(stopped_in(&events), Some(("add".to_string(), 6)));
+    let events = session.run_command("continue").unwrap();
+    assert_eq!(stopped_in(&events), Some(("add".to_string(), 6)));
+    assert_eq!(session.evaluate("a").unwrap(), "42");
+    assert_eq!(session.run_command("continue").unwrap(), vec![Event::Exited(3)]);
+}
+
+#[test]
+fn regex_breakpoints_match_function_names() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    session.run_command("rbreak ^(add|twice)$").unwrap();
+    assert_eq!(session.breakpoints().len(), 2);
+    let events = session.run_command("run").unwrap();
+    assert_eq!(stopped_in(&events), Some(("twice".to_string(), 10)));
+}
+
+#[test]
+fn breakpoints_close_together_are_all_hit() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    //add ends with `pop %rbp; ret` and twice starts straight after it, so these two breakpoints
+    //are two bytes apart
+    session.run_command("break 8").unwrap();
+    let end_of_add = session.breakpoints()[0];
+    session.run_command(&format!("break *{:#x}", end_of_add + 2)).unwrap();
+    let mut lines = vec![];
+    let mut events = session.run_command("run").unwrap();
+    while let Some((_, line)) = stopped_in(&events) {
+        lines.push(line);
+        events = session.run_command("continue").unwrap();
+    }
+    assert_eq!(lines, vec![10, 8, 8]);
+    assert_eq!(events.last(), Some(&Event::Exited(3)));
+}
+
+#[test]
+fn disabled_breakpoints_are_skipped() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    session.run_command("break twice group=calls").unwrap();
+    session.run_command("break add group=calls").unwrap();
+    session.run_command("disable group calls").unwrap();
+    assert_eq!(session.run_command("run").unwrap(), vec![Event::Exited(3)]);
+}
+
+#[test]
+fn breakpoints_are_numbered() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    session.run_command("break twice").unwrap();
+    session.run_command("break add").unwrap();
+    session.run_command("break main").unwrap();
+    session.run_command("disable 1").unwrap();
+    session.run_command("delete 3").unwrap();
+    assert_eq!(session.breakpoints().len(), 2);
+    let events = session.run_command("run").unwrap();
+    assert_eq!(stopped_in(&events), Some(("add".to_string(), 5)));
+    //deleting every breakpoint lets the program run to the end
+    session.run_command("delete").unwrap();
+    assert!(session.breakpoints().is_empty());
+    assert_eq!(session.run_command("continue").unwrap(), vec![Event::Exited(3)]);
+}
+
+#[test]
+fn conditions_are_checked_at_breakpoints() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    //add is called with (21, 21) and then (42, 0)
+    session.run_command("break 6 if b == 0").unwrap();
+    session.run_command("run").unwrap();
+    assert_eq!(session.evaluate("a").unwrap(), "42");
+    session.run_command("condition 1 nonexistent").unwrap();
+    session.run_command("run").unwrap();
+    //a condition that can't be evaluated stops the program
+    assert_eq!(session.evaluate("a").unwrap(), "21");
+    session.run_command("condition 1 a > 100").unwrap();
+    assert_eq!(session.run_command("continue").unwrap(), vec![Event::Exited(3)]);
+}
+
+#[test]
+fn ignored_breakpoints_are_passed() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    session.run_command("break 6").unwrap();
+    session.run_command("ignore 1 1").unwrap();
+    session.run_command("run").unwrap();
+    //the first call, add(21, 21), is ignored
+    assert_eq!(session.evaluate("a").unwrap(), "42");
+    assert_eq!(session.run_command("continue").unwrap(), vec![Event::Exited(3)]);
+}
+
+#[test]
+fn temporary_breakpoints_stop_once() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    session.run_command("tbreak add").unwrap();
+    let events = session.run_command("run").unwrap();
+    assert_eq!(stopped_in(&events), Some(("add".to_string(), 5)));
+    assert!(session.breakpoints().is_empty());
+    assert_eq!(session.run_command("continue").unwrap(), vec![Event::Exited(3)]);
+}
+
+#[test]
+fn breakpoint_commands_run_when_hit() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    session.run_command("break add").unwrap();
+    session.run_command("break 16").unwrap();
+    session.run_block("commands 1", &["continue"]).unwrap();
+    //the commands of add's breakpoint carry the program on to line 16, and then to the end
+    let events = session.run_command("run").unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(stopped_in(&events), Some(("main".to_string(), 16)));
+    let events = session.run_command("continue").unwrap();
+    assert_eq!(events.last(), Some(&Event::Exited(3)));
+}
+
+#[test]
+fn breakpoint_commands_skip_empty_lines() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    session.run_command("break add").unwrap();
+    session.run_command("break 16").unwrap();
+    session.run_block("commands 1", &["", "continue", "  "]).unwrap();
+    session.run_command("info break").unwrap();
+    assert!(!session.take_output().contains("Unrecognized command"));
+    let events = session.run_command("run").unwrap();
+    assert_eq!(stopped_in(&events), Some(("main".to_string(), 16)));
+}
+
+#[test]
+fn dprintf_does_not_stop() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    session.run_command("dprintf add,\"add(%d, %d)\\n\", a, b").unwrap();
+    assert_eq!(session.breakpoints().len(), 1);
+    assert_eq!(session.run_command("run").unwrap(), vec![Event::Exited(3)]);
+}
+
+#[test]
+fn backtrace_lists_callers() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    //past the prologue, so that the frame pointer is set up
+    session.run_command("break 7").unwrap();
+    session.run_command("run").unwrap();
+    let frames = session.backtrace().unwrap();
+    let functions: Vec<&str> = frames.iter().filter_map(|f| f.function.as_deref()).collect();
+    assert_eq!(functions, vec!["add", "twice", "main"]);
+    assert_eq!(frames[1].line, Some(11));
+    assert_eq!(frames[2].line, Some(15));
+}
+
+#[test]
+fn backtrace_has_the_variables_of_each_frame() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    session.run_command("break 7").unwrap();
+    session.run_command("run").unwrap();
+    let pair = |name: &str, value: &str| (name.to_string(), value.to_string());
+    let frames = session.backtrace().unwrap();
+    assert_eq!(frames[0].variables, vec![pair("a", "21"), pair("b", "21")]);
+    assert_eq!(frames[1].variables, vec![pair("x", "21")]);
+    assert_eq!(frames[2].variables, vec![pair("result", "0")]);
+    assert!(session.run_command("bt full").is_ok());
+}
+
+#[test]
+fn backtrace_without_frame_pointers() {
+    let mut session = Session::new(&fixture_with_flags("optimized", &["-O2", "-fomit-frame-pointer"])).unwrap();
+    session.run_command("break 5").unwrap();
+    session.run_command("run").unwrap();
+    let frames = session.backtrace().unwrap();
+    let functions: Vec<&str> = frames.iter().filter_map(|f| f.function.as_deref()).collect();
+    assert_eq!(functions, vec!["leaf", "middle", "main"]);
+    assert_eq!(frames[1].line, Some(9));
+    assert_eq!(frames[2].line, Some(14));
+}
+
+#[test]
+fn step_goes_into_calls_and_over_libc() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    session.run_command("break 15").unwrap();
+    session.run_command("run").unwrap();
+    let expected = [("twice", 11), ("add", 6), ("add", 7), ("add", 8), ("twice", 12), ("main", 16)];
+    for (function, line) in expected {
+        let events = session.run_command("step").unwrap();
+        assert_eq!(stopped_in(&events), Some((function.to_string(), line)));
+    }
+    //printf has no debug info, so it is stepped over
+    let events = session.run_command("step 5").unwrap();
+    assert_eq!(stopped_in(&events), Some(("main".to_string(), 18)));
+}
+
+#[test]
+fn next_steps_over_calls() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    session.run_command("break 15").unwrap();
+    session.run_command("run").unwrap();
+    let events = session.run_command("next").unwrap();
+    assert_eq!(stopped_in(&events), Some(("main".to_string(), 16)));
+    //a breakpoint in the function called stops the program there
+    session.run_command("break 7").unwrap();
+    let events = session.run_command("next").unwrap();
+    assert_eq!(stopped_in(&events), Some(("add".to_string(), 7)));
+    let events = session.run_command("next 2").unwrap();
+    assert_eq!(stopped_in(&events), Some(("main".to_string(), 17)));
+}
+
+#[test]
+fn stepi_enters_calls_and_nexti_does_not() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    //line 15 is `mov $21,%edi` followed by the call to twice
+    session.run_command("break 15").unwrap();
+    session.run_command("run").unwrap();
+    let events = session.run_command("stepi 2").unwrap();
+    assert_eq!(stopped_in(&events), Some(("twice".to_string(), 10)));
+    //back in main, line 16 is three instructions followed by the call to add
+    session.run_command("finish").unwrap();
+    let events = session.run_command("nexti 5").unwrap();
+    assert_eq!(stopped_in(&events), Some(("main".to_string(), 16)));
+}
+
+#[test]
+fn finish_returns_to_the_caller() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    session.run_command("break add").unwrap();
+    session.run_command("run").unwrap();
+    //add's result is already what twice returns, so the call is the last instruction of line 11
+    let events = session.run_command("finish").unwrap();
+    assert_eq!(stopped_in(&events), Some(("twice".to_string(), 12)));
+    let events = session.run_command("finish").unwrap();
+    assert_eq!(stopped_in(&events), Some(("main".to_string(), 15)));
+}
+
+#[test]
+fn evaluates_expressions() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    //constant expressions don't need a running program
+    assert_eq!(session.evaluate("6 * 7").unwrap(), "42");
+    session.run_command("break 7").unwrap();
+    session.run_command("run").unwrap();
+    assert_eq!(session.evaluate("a + b").unwrap(), "42");
+    assert_eq!(session.evaluate("counter").unwrap(), "1");
+    //the second call is add(42, 0)
+    session.run_command("continue").unwrap();
+    assert_eq!(session.evaluate("counter").unwrap(), "2");
+    assert_eq!(session.evaluate("a - b").unwrap(), "42");
+    assert!(session.evaluate("nonexistent").is_err());
+}
+
+#[test]
+fn printed_values_are_kept_in_the_history() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    session.run_command("break 7").unwrap();
+    session.run_command("run").unwrap();
+    session.run_command("print a + b").unwrap();
+    session.run_command("p counter").unwrap();
+    assert_eq!(session.evaluate("$1 * 2").unwrap(), "84");
+    assert_eq!(session.evaluate("$").unwrap(), "1");
+    assert!(session.evaluate("$3").is_err());
+}
+
+#[test]
+fn prints_arrays_and_slices() {
+    let mut session = Session::new(&fixture("arrays")).unwrap();
+    session.run_command("break 10").unwrap();
+    session.run_command("run").unwrap();
+    assert_eq!(session.evaluate("primes").unwrap(), "{2, 3, 5, 7, 11}");
+    assert_eq!(session.evaluate("*squares@5").unwrap(), "{0, 1, 4, 9, 16}");
+    assert_eq!(session.evaluate("squares[7]@3").unwrap(), "{49, 64, 81}");
+    assert_eq!(session.evaluate("(primes[1]@2)[1]").unwrap(), "5");
+    assert!(session.evaluate("1@2").is_err());
+    assert!(session.evaluate("*squares@0").is_err());
+}
+
+#[test]
+fn prints_values_in_other_formats() {
+    let mut session = Session::new(&fixture("arrays")).unwrap();
+    assert_eq!(session.evaluate_formatted("x", "255").unwrap(), "0xff");
+    assert_eq!(session.evaluate_formatted("t", "10").unwrap(), "1010");
+    assert_eq!(session.evaluate_formatted("o", "8").unwrap(), "010");
+    assert_eq!(session.evaluate_formatted("c", "65").unwrap(), "65 'A'");
+    assert_eq!(session.evaluate_formatted("x", "-1").unwrap(), "0xffffffff");
+    assert_eq!(session.evaluate_formatted("d", "(unsigned char)200").unwrap(), "-56");
+    session.run_command("break 10").unwrap();
+    session.run_command("run").unwrap();
+    assert_eq!(session.evaluate_formatted("x", "primes").unwrap(), "{0x2, 0x3, 0x5, 0x7, 0xb}");
+    assert!(session.evaluate_formatted("q", "primes").is_err());
+    assert!(session.evaluate_formatted("w", "primes").is_err());
+}
+
+#[test]
+fn formats_enums_unions_and_bitfields() {
+    let mut session = Session::new(&fixture("types")).unwrap();
+    session.run_command("break 28").unwrap();
+    session.run_command("run").unwrap();
+    assert_eq!(session.evaluate("favorite").unwrap(), "BLUE");
+    assert_eq!(session.evaluate("c").unwrap(), "GREEN");
+    //a flags enum is shown as the flags that make it up
+    assert_eq!(session.evaluate("p").unwrap(), "(READ | EXEC)");
+    assert_eq!(session.evaluate("n").unwrap(), "{i = 1069547520, f = 1.5}");
+    assert_eq!(session.evaluate("f").unwrap(), "{ready = 1, mode = 5, delta = -3}");
+    assert_eq!(session.evaluate_formatted("x", "f").unwrap(), "{ready = 0x1, mode = 0x5, delta = 0xd}");
+    assert_eq!(session.evaluate("corners").unwrap(), "{{x = 1, y = 2}, {x = 3, y = 4}}");
+}
+
+#[test]
+fn casts_values_to_other_types() {
+    let mut session = Session::new(&fixture("types")).unwrap();
+    assert_eq!(session.evaluate("(char)65").unwrap(), "65 'A'");
+    assert_eq!(session.evaluate("(unsigned char)-1").unwrap(), "255 '\\377'");
+    assert_eq!(session.evaluate("(float)7/2").unwrap(), "3.5");
+    assert_eq!(session.evaluate("(enum color)2").unwrap(), "BLUE");
+    assert_eq!(session.evaluate("(enum permissions)3").unwrap(), "(READ | WRITE)");
+    session.run_command("break 28").unwrap();
+    session.run_command("run").unwrap();
+    assert_eq!(session.evaluate("(int)c").unwrap(), "1");
+    assert_eq!(session.evaluate("*(struct point *)&corners[1]").unwrap(), "{x = 3, y = 4}");
+    assert_eq!(session.evaluate("((struct point *)corners)[1].y").unwrap(), "4");
+    assert!(session.evaluate("(struct nonexistent *)0").is_err());
+}
+
+#[test]
+fn follows_the_print_settings() {
+    let mut session = Session::new(&fixture("types")).unwrap();
+    session.run_command("break 28").unwrap();
+    session.run_command("run").unwrap();
+    session.run_command("set print elements 1").unwrap();
+    assert_eq!(session.evaluate("corners").unwrap(), "{{x = 1, y = 2}, ...}");
+    session.run_command("set print elements 3").unwrap();
+    assert_eq!(session.evaluate("\"abcdef\"").unwrap(), "\"abc\"...");
+    session.run_command("set print elements unlimited").unwrap();
+    session.run_command("set print max-depth 1").unwrap();
+    assert_eq!(session.evaluate("corners").unwrap(), "{{...}, {...}}");
+    session.run_command("set print max-depth unlimited").unwrap();
+    assert_eq!(session.evaluate("corners").unwrap(), "{{x = 1, y = 2}, {x = 3, y = 4}}");
+    //0 means no limit too
+    session.run_command("set print elements 0").unwrap();
+    session.run_command("set print max-depth 0").unwrap();
+    assert_eq!(session.evaluate("corners").unwrap(), "{{x = 1, y = 2}, {x = 3, y = 4}}");
+    session.run_command("set print pretty on").unwrap();
+    assert_eq!(session.evaluate("f").unwrap(), "{\n  ready = 1,\n  mode = 5,\n  delta = -3\n}");
+    session.run_command("set print pretty off").unwrap();
+    session.run_command("set print null-pointer NULL").unwrap();
+    assert_eq!(session.evaluate("(int *)0").unwrap(), "NULL");
+    session.take_output();
+    session.run_command("show print").unwrap();
+    assert_eq!(
+        session.take_output(),
+        "print pretty:  Pretty formatting of structures is off.\n\
+         print elements:  Limit on string chars or array elements to print is unlimited.\n\
+         print max-depth:  Maximum print depth is unlimited.\n\
+         print null-pointer:  Null pointers are printed as \"NULL\".\n"
+    );
+}
+
+#[test]
+fn prints_strings_in_the_charset() {
+    let mut session = Session::new(&fixture("strings")).unwrap();
+    session.run_command("break 11").unwrap();
+    session.run_command("run").unwrap();
+    assert!(session.evaluate("greeting").unwrap().ends_with(" \"héllo, wörld\""));
+    //bytes that aren't valid UTF-8 are escaped
+    assert_eq!(session.evaluate("invalid").unwrap(), "\"a\\377b\\n\"");
+    assert!(session.evaluate("wide").unwrap().ends_with(" L\"wide ✓\""));
+    assert!(session.evaluate("utf16").unwrap().ends_with(" u\"utf-16 ✓\""));
+    assert_eq!(session.evaluate("letter").unwrap(), "955 L'λ'");
+    session.run_command("set charset ascii").unwrap();
+    assert!(session.evaluate("greeting").unwrap().ends_with(" \"h\\303\\251llo, w\\303\\266rld\""));
+    session.run_command("set charset ISO-8859-1").unwrap();
+    assert!(session.evaluate("greeting").unwrap().ends_with(" \"hÃ©llo, wÃ¶rld\""));
+    session.take_output();
+    session.run_command("show charset").unwrap();
+    assert_eq!(session.take_output(), "The charset is \"ISO-8859-1\".\n");
+}
+
+#[test]
+fn examines_memory() {
+    let mut session = Session::new(&fixture("arrays")).unwrap();
+    session.run_command("break 10").unwrap();
+    session.run_command("run").unwrap();
+    let lines = session.examine("5dw", "primes").unwrap();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].ends_with(" <primes>:\t2\t3\t5\t7"), "{:?}", lines);
+    assert!(lines[1].ends_with(" <primes+16>:\t11"), "{:?}", lines);
+    let lines = session.examine("2xb", "&primes[1]").unwrap();
+    assert!(lines[0].ends_with(" <primes+4>:\t0x03\t0x00"), "{:?}", lines);
+    //without an address, x carries on where it left off, in the same format
+    let lines = session.examine("", "").unwrap();
+    assert!(lines[0].ends_with(" <primes+6>:\t0x00"), "{:?}", lines);
+    let lines = session.examine("3uw", "squares + 2").unwrap();
+    assert!(lines[0].ends_with(":\t4\t9\t16"), "{:?}", lines);
+    assert!(session.examine("xw", "0").is_err());
+}
+
+#[test]
+fn examines_large_blocks_of_memory() {
+    let mut session = Session::new(&fixture("arrays")).unwrap();
+    session.run_command("break 10").unwrap();
+    session.run_command("run").unwrap();
+    //spans several pages of the heap, which is read in one go
+    let lines = session.examine("4096uw", "squares").unwrap();
+    assert_eq!(lines.len(), 1024);
+    assert!(lines[0].ends_with(":\t0\t1\t4\t9"), "{:?}", lines[0]);
+    assert!(lines[2].contains(":\t64\t81\t"), "{:?}", lines[2]);
+    assert!(session.examine("4096xb", "0").is_err());
+}
+
+#[test]
+fn writes_typed_values_to_memory() {
+    let mut session = Session::new(&fixture("arrays")).unwrap();
+    session.run_command("break 10").unwrap();
+    session.run_command("run").unwrap();
+    session.run_command("set {int}&primes[1] = 42").unwrap();
+    session.run_command("set {short}&primes[2] = -1").unwrap();
+    assert_eq!(session.evaluate("primes").unwrap(), "{2, 42, 65535, 7, 11}");
+    assert_eq!(session.evaluate("{int}squares = 5").unwrap(), "5");
+    assert_eq!(session.evaluate("squares[0]").unwrap(), "5");
+    assert!(session.evaluate("1 = 2").is_err());
+    //main returns squares[3] + primes[0]
+    session.run_command("set {char}primes = 1").unwrap();
+    assert_eq!(session.run_command("continue").unwrap(), vec![Event::Exited(10)]);
+}
+
+#[test]
+fn set_var_changes_variables() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    session.run_command("break 7").unwrap();
+    session.run_command("run").unwrap();
+    session.run_command("set var a = 100").unwrap();
+    session.run_command("set counter = 2.5").unwrap();
+    assert_eq!(session.evaluate("a + b").unwrap(), "121");
+    assert_eq!(session.evaluate("counter").unwrap(), "2");
+    assert_eq!(session.evaluate("$rdx = 3").unwrap(), "3");
+    session.run_command("delete").unwrap();
+    //twice(21) now returns 121 rather than 42, so main returns 1
+    assert_eq!(session.run_command("continue").unwrap(), vec![Event::Exited(1)]);
+}
+
+#[test]
+fn selects_frames_to_evaluate_in() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    session.run_command("break 7").unwrap();
+    session.run_command("run").unwrap();
+    assert_eq!(session.evaluate("a").unwrap(), "21");
+    session.run_command("up").unwrap();
+    assert_eq!(session.evaluate("x").unwrap(), "21");
+    assert!(session.evaluate("a").is_err());
+    session.run_command("frame 2").unwrap();
+    assert_eq!(session.evaluate("result").unwrap(), "0");
+    session.run_command("down 2").unwrap();
+    assert_eq!(session.evaluate("a + b").unwrap(), "42");
+    //stopping again selects the innermost frame
+    session.run_command("up").unwrap();
+    session.run_command("continue").unwrap();
+    assert_eq!(session.evaluate("a").unwrap(), "42");
+}
+
+#[test]
+fn lists_locals_of_the_selected_frame() {
+    let mut session = Session::new(&fixture("arrays")).unwrap();
+    session.run_command("break 8").unwrap();
+    session.run_command("run").unwrap();
+    session.run_command("continue").unwrap();
+    let locals = session.locals().unwrap();
+    assert_eq!(locals.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>(), ["squares", "i"]);
+    assert_eq!(locals[1].1, "1");
+
+    //parameters are not locals
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    session.run_command("break 7").unwrap();
+    session.run_command("run").unwrap();
+    assert_eq!(session.locals().unwrap(), vec![]);
+    session.run_command("up 2").unwrap();
+    assert_eq!(session.locals().unwrap(), vec![("result".to_string(), "0".to_string())]);
+}
+
+#[test]
+fn lists_source_lines() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    let numbers = |lines: Vec<String>| -> Vec<usize> {
+        lines.iter().map(|line| line.split('\t').next().unwrap().parse().unwrap()).collect()
+    };
+    //around main, then carrying on to the end of the file
+    assert_eq!(numbers(session.list("").unwrap()), (9..=18).collect::<Vec<_>>());
+    assert_eq!(numbers(session.list("").unwrap()), vec![19]);
+    assert!(session.list("").is_err());
+    assert_eq!(session.list("add").unwrap()[0], "1\t#include <stdio.h>");
+    session.run_command("set listsize 3").unwrap();
+    assert_eq!(session.list("11").unwrap(), vec!["10\tint twice(int x) {", "11\t    return add(x, x);", "12\t}"]);
+    assert_eq!(numbers(session.list("-").unwrap()), vec![7, 8, 9]);
+    //where the program stopped
+    session.run_command("break 7").unwrap();
+    session.run_command("run").unwrap();
+    assert_eq!(numbers(session.list("").unwrap()), vec![6, 7, 8]);
+    assert!(session.list("nothing").is_err());
+    //in a file named by the debug info
+    assert_eq!(numbers(session.list("calls.c:11").unwrap()), vec![10, 11, 12]);
+    assert_eq!(numbers(session.list("calls.c:twice").unwrap()), vec![9, 10, 11]);
+    assert!(session.list("other.c:1").is_err());
+}
+
+#[test]
+fn searches_the_source() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    session.run_command("set listsize 3").unwrap();
+    session.list("1").unwrap();
+    assert_eq!(session.search("return", true).unwrap(), "7\t    return a + b;");
+    assert_eq!(session.search("return", true).unwrap(), "11\t    return add(x, x);");
+    assert_eq!(session.search("^int [a-z]+\\(", false).unwrap(), "10\tint twice(int x) {");
+    //the listing carries on from what was found
+    assert_eq!(session.list("").unwrap()[0], "9\t");
+    assert!(session.search("nowhere", true).is_err());
+    assert!(session.search("(", true).is_err());
+}
+
+#[test]
+fn disassembles_functions() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    //before the program runs the code comes from the file
+    let before = session.disassemble("/r add").unwrap();
+    assert_eq!(before[0], "Dump of assembler code for function add:");
+    assert!(before[1].ends_with(":\t55\tpush   %rbp"));
+    assert_eq!(before.last().unwrap(), "End of assembler dump.");
+    session.run_command("break add").unwrap();
+    session.run_command("run").unwrap();
+    //the breakpoint shows the instruction it replaced, not int3
+    let after = session.disassemble("/r").unwrap();
+    let stopped: Vec<&String> = after.iter().filter(|line| line.starts_with("b> ")).collect();
+    assert_eq!(stopped.len(), 1);
+    assert!(!stopped[0].contains("\tcc"));
+    let unmarked: Vec<String> = after.iter().map(|line| line.replacen("b> ", "   ", 1)).collect();
+    assert_eq!(unmarked, before);
+    session.run_command("up").unwrap();
+    let twice = session.disassemble("").unwrap();
+    assert_eq!(twice[0], "Dump of assembler code for function twice:");
+    assert!(twice.iter().any(|line| line.starts_with("=> ") && line.contains("leave")));
+    assert!(twice.iter().any(|line| line.ends_with("<add>")));
+    assert!(session.disassemble("/q add").is_err());
+}
+
+#[test]
+fn reads_float_and_vector_registers() {
+    let mut session = Session::new(&fixture("floats")).unwrap();
+    session.run_command("break 5").unwrap();
+    session.run_command("run").unwrap();
+    //r = x * y was just worked out in xmm0, from x in xmm1
+    assert_eq!(session.evaluate("r").unwrap(), "3");
+    assert_eq!(session.evaluate("$xmm0.v2_double[0]").unwrap(), "3");
+    assert_eq!(session.evaluate("$xmm1.v2_double[0] * 2").unwrap(), "3");
+    assert_eq!(session.evaluate("$mxcsr & 0x1f80").unwrap(), "8064");
+    assert!(session.evaluate("$st7").is_ok());
+    assert!(session.evaluate("$xmm16").is_err());
+}
+
+#[test]
+fn watchpoint_stops_after_write() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    session.run_command("break main").unwrap();
+    session.run_command("run").unwrap();
+    session.run_command("watch -l counter").unwrap();
+    let events = session.run_command("continue").unwrap();
+    assert_eq!(stopped_in(&events).map(|(function, _)| function), Some("add".to_string()));
+    assert_eq!(session.evaluate("counter").unwrap(), "1");
+}
+
+#[test]
+fn watchpoint_on_local_is_deleted_on_return() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    session.run_command("break 6").unwrap();
+    session.run_command("run").unwrap();
+    session.run_command("watch a").unwrap();
+    //add doesn't change `a`, so the program stops when add returns to twice, whose line 11 ends
+    //with the call
+    let events = session.run_command("continue").unwrap();
+    assert_eq!(stopped_in(&events), Some(("twice".to_string(), 12)));
+    let events = session.run_command("continue").unwrap();
+    assert_eq!(stopped_in(&events), Some(("add".to_string(), 6)));
+    assert_eq!(session.run_command("continue").unwrap(), vec![Event::Exited(3)]);
+}
+
+#[test]
+fn watches_ranges_of_memory() {
+    let mut session = Session::new(&fixture("watch")).unwrap();
+    session.run_command("break main").unwrap();
+    session.run_command("run").unwrap();
+    session.run_command("watch &buffer len 32").unwrap();
+    //the loop before the strcpy doesn't write to the buffer
+    let events = session.run_command("continue").unwrap();
+    assert_eq!(stopped_in(&events).map(|(function, _)| function), Some("main".to_string()));
+    assert_eq!(session.evaluate("counter").unwrap(), "3");
+    assert!(session.take_output().contains("Hardware watchpoint 2: &buffer len 32\n\nOld value = \"\"\n"));
+    assert!(session.run_command("watch &buffer").unwrap().is_empty());
+    assert_eq!(session.breakpoints().len(), 1);
+}
+
+#[test]
+fn masked_watchpoints_ignore_other_bits() {
+    let mut session = Session::new(&fixture("watch")).unwrap();
+    session.run_command("break main").unwrap();
+    session.run_command("run").unwrap();
+    session.run_command("watch -l savings.status mask 0x100").unwrap();
+    //deposit sets the lowest bit three times, and then main sets the masked one
+    let events = session.run_command("continue").unwrap();
+    assert_eq!(stopped_in(&events), Some(("main".to_string(), 26)));
+    assert!(session.take_output().contains("Old value = 1\nNew value = 257\n"));
+    assert_eq!(session.run_command("continue").unwrap(), vec![Event::Exited(0)]);
+}
+
+#[test]
+fn catches_exceptions_where_they_are_thrown_and_caught() {
+    let output = debugger_output(&fixture("throws"), "catch throw\ncatch catch\nrun\ncontinue\ncontinue\n");
+    assert!(output.contains("Catchpoint 1 (throw)\nCatchpoint 2 (catch)\n"), "{}", output);
+    let thrown = output.find("Catchpoint 1 (exception thrown), type std::invalid_argument\n").expect(&output);
+    let caught = output.find("Catchpoint 2 (exception caught), type std::invalid_argument\n").expect(&output);
+    assert!(thrown < caught);
+    assert!(output[thrown..caught].contains(" in _Z5parsei at "), "{}", output);
+    assert!(output[caught..].contains(" in main at "), "{}", output);
+    assert!(output.contains("Child existed (status 7)"), "{}", output);
+}
+
+#[test]
+fn reports_signals() {
+    let mut session = Session::new(&fixture("crash")).unwrap();
+    match session.run_command("run").unwrap().as_slice() {
+        [Event::Stopped { signal, .. }] => assert_eq!(*signal, Signal::SIGABRT),
+        events => panic!("unexpected events {:?}", events),
+    }
+}
+
+#[test]
+fn handles_signals_as_told() {
+    let mut session = Session::new(&fixture("signals")).unwrap();
+    //by default the program stops for the signal, and gets it when it carries on
+    match session.run_command("run").unwrap()[..] {
+        [Event::Stopped { signal, .. }] => assert_eq!(signal, Signal::SIGUSR1),
+        ref events => panic!("not stopped by the signal: {:?}", events),
+    }
+    session.run_command("handle SIGUSR1 nostop noprint").unwrap();
+    assert_eq!(session.run_command("continue").unwrap(), vec![Event::Exited(2)]);
+    //a signal that isn't passed never reaches the program
+    session.run_command("handle usr1 nopass").unwrap();
+    assert_eq!(session.run_command("run").unwrap(), vec![Event::Exited(0)]);
+    assert!(session.run_command("handle SIGFOO stop").unwrap().is_empty());
+}
+
+#[test]
+fn shows_what_is_done_with_each_signal() {
+    let mut session = Session::new(&fixture("signals")).unwrap();
+    let header = "Signal        Stop\tPrint\tPass to program\tDescription\n";
+    //`handle` shows the signals it changed
+    session.run_command("handle SIGUSR1 nostop noprint").unwrap();
+    let usr1 = format!("{}SIGUSR1       No\tNo\tYes\t\tUser defined signal 1\n", header);
+    assert_eq!(session.take_output(), usr1);
+    session.run_command("info signals usr1").unwrap();
+    assert_eq!(session.take_output(), usr1);
+    session.run_command("info signals").unwrap();
+    let table = session.take_output();
+    assert!(table.starts_with(header), "{}", table);
+    assert!(table.contains("\nSIGINT        Yes\tYes\tNo\t\tInterrupt\n"), "{}", table);
+    assert!(table.contains("\nSIGCHLD       No\tNo\tYes\t\tChild exited\n"), "{}", table);
+    assert!(table.contains(&usr1[header.len()..]), "{}", table);
+    assert!(table.ends_with("\nUse the \"handle\" command to change these tables.\n"), "{}", table);
+    //`all` leaves out the signals the debugger uses itself
+    session.run_command("handle all nopass").unwrap();
+    let changed = session.take_output();
+    assert!(changed.contains("\nSIGHUP        Yes\tYes\tNo\t\tHangup\n"), "{}", changed);
+    assert!(!changed.contains("SIGINT") && !changed.contains("SIGTRAP"), "{}", changed);
+}
+
+#[test]
+fn passes_the_signal_on_when_continued() {
+    let mut session = Session::new(&fixture("crash")).unwrap();
+    session.run_command("run").unwrap();
+    assert_eq!(session.run_command("continue").unwrap(), vec![Event::Signaled(Signal::SIGABRT)]);
+    //the table is looked at when the program is resumed, so a change after the stop counts
+    let mut session = Session::new(&fixture("signals")).unwrap();
+    session.run_command("run").unwrap();
+    session.run_command("handle SIGUSR1 nopass").unwrap();
+    match session.run_command("continue").unwrap()[..] {
+        [Event::Stopped { signal, .. }] => assert_eq!(signal, Signal::SIGUSR1),
+        ref events => panic!("not stopped by the signal: {:?}", events),
+    }
+    session.run_command("handle SIGUSR1 pass").unwrap();
+    assert_eq!(session.run_command("continue").unwrap(), vec![Event::Exited(1)]);
+}
+
+#[test]
+fn continues_with_a_signal() {
+    let mut session = Session::new(&fixture("signals")).unwrap();
+    session.run_command("break signals.c:11").unwrap();
+    session.run_command("run").unwrap();
+    session.run_command("handle SIGUSR1 nostop noprint").unwrap();
+    //the handler counts the signal given along with the two the program raises
+    assert_eq!(session.run_command("signal SIGUSR1").unwrap(), vec![Event::Exited(3)]);
+    //`signal 0` throws away the signal the program stopped with
+    session.run_command("delete").unwrap();
+    session.run_command("handle SIGUSR1 stop").unwrap();
+    session.run_command("run").unwrap();
+    session.run_command("handle SIGUSR1 nostop noprint").unwrap();
+    assert_eq!(session.run_command("signal 0").unwrap(), vec![Event::Exited(1)]);
+}
+
+#[test]
+fn takes_breakpoints_out_of_forked_children() {
+    //the child returns from work without trapping, and the parent stops in it
+    for args in &["run", "run vfork"] {
+        let mut session = Session::new(&fixture("fork")).unwrap();
+        session.run_command("break 5").unwrap();
+        let events = session.run_command(args).unwrap();
+        assert_eq!(stopped_in(&events), Some(("work".to_string(), 5)));
+        assert_eq!(session.evaluate("x").unwrap(), "2");
+        assert_eq!(session.run_command("continue").unwrap(), vec![Event::Exited(3)]);
+    }
+}
+
+#[test]
+fn follows_the_child_when_told() {
+    //the child stops in work, and the parent is let go to finish on its own
+    for args in &["run", "run vfork"] {
+        let mut session = Session::new(&fixture("fork")).unwrap();
+        session.run_command("set follow-fork-mode child").unwrap();
+        session.run_command("break 5").unwrap();
+        let events = session.run_command(args).unwrap();
+        assert_eq!(stopped_in(&events), Some(("work".to_string(), 5)));
+        assert_eq!(session.evaluate("x").unwrap(), "1");
+        assert_eq!(session.run_command("continue").unwrap(), vec![Event::Exited(2)]);
+    }
+}
+
+#[test]
+fn keeps_both_sides_of_a_fork_as_inferiors() {
+    //the parent waits, stopped at the fork, until it is switched to once the child has exited
+    let mut session = Session::new(&fixture("fork")).unwrap();
+    session.run_command("set follow-fork-mode child").unwrap();
+    session.run_command("set detach-on-fork off").unwrap();
+    session.run_command("break 5").unwrap();
+    let events = session.run_command("run").unwrap();
+    assert_eq!(stopped_in(&events), Some(("work".to_string(), 5)));
+    assert_eq!(session.evaluate("x").unwrap(), "1");
+    assert_eq!(session.run_command("continue").unwrap(), vec![Event::Exited(2)]);
+    session.run_command("inferior 2").unwrap();
+    let events = session.run_command("continue").unwrap();
+    assert_eq!(stopped_in(&events), Some(("work".to_string(), 5)));
+    assert_eq!(session.evaluate("x").unwrap(), "2");
+    assert_eq!(session.run_command("continue").unwrap(), vec![Event::Exited(3)]);
+}
+
+#[test]
+fn stops_in_threads() {
+    //both threads stop at the breakpoint, in whichever order they reach it
+    let mut session = Session::new(&fixture_with_flags("threads", &["-O0", "-pthread"])).unwrap();
+    session.run_command("break 4").unwrap();
+    let mut seen = Vec::new();
+    for command in &["run", "continue"] {
+        let events = session.run_command(command).unwrap();
+        assert_eq!(stopped_in(&events), Some(("work".to_string(), 4)));
+        seen.push(session.evaluate("x").unwrap());
+    }
+    seen.sort();
+    assert_eq!(seen, vec!["1", "2"]);
+    assert_eq!(session.run_command("continue").unwrap(), vec![Event::Exited(6)]);
+}
+
+#[test]
+fn switches_between_threads() {
+    //the thread that hit the breakpoint steps over it when resumed after another was selected,
+    //rather than reporting the same hit again
+    let mut session = Session::new(&fixture_with_flags("threads", &["-O0", "-pthread"])).unwrap();
+    session.run_command("break 4").unwrap();
+    let events = session.run_command("run").unwrap();
+    assert_eq!(stopped_in(&events), Some(("work".to_string(), 4)));
+    let first = session.evaluate("x").unwrap();
+    session.run_command("thread 1").unwrap();
+    assert!(session.evaluate("x").is_err());
+    let events = session.run_command("continue").unwrap();
+    assert_eq!(stopped_in(&events), Some(("work".to_string(), 4)));
+    assert_ne!(session.evaluate("x").unwrap(), first);
+    assert_eq!(session.run_command("continue").unwrap(), vec![Event::Exited(6)]);
+}
+
+#[test]
+fn runs_only_the_current_thread_with_scheduler_locking() {
+    //the thread counting doesn't run while main does its sums
+    let mut session = Session::new(&fixture_with_flags("spinner", &["-O0", "-pthread"])).unwrap();
+    session.run_command("break 20").unwrap();
+    session.run_command("break done").unwrap();
+    let events = session.run_command("run").unwrap();
+    assert_eq!(stopped_in(&events), Some(("main".to_string(), 20)));
+    let count = session.evaluate("count").unwrap();
+    session.run_command("set scheduler-locking on").unwrap();
+    let events = session.run_command("continue").unwrap();
+    assert_eq!(stopped_in(&events), Some(("done".to_string(), 12)));
+    assert_eq!(session.evaluate("count").unwrap(), count);
+    assert_eq!(session.run_command("continue").unwrap(), vec![Event::Exited(3)]);
+}
+
+#[test]
+fn keeps_breakpoints_when_the_program_execs_itself() {
+    let mut session = Session::new(&fixture("reexec")).unwrap();
+    session.run_command("break 4").unwrap();
+    let events = session.run_command("run").unwrap();
+    assert_eq!(stopped_in(&events), Some(("work".to_string(), 4)));
+    assert_eq!(session.evaluate("x").unwrap(), "2");
+    assert_eq!(session.run_command("continue").unwrap(), vec![Event::Exited(3)]);
+}
+
+#[test]
+fn follows_the_program_into_another_it_execs() {
+    //the breakpoint on add is set again in calls once it has been loaded
+    let mut session = Session::new(&fixture("launcher")).unwrap();
+    session.run_command("break add").unwrap();
+    let events = session.run_command(&format!("run {}", fixture("calls"))).unwrap();
+    assert_eq!(stopped_in(&events), Some(("add".to_string(), 5)));
+    assert_eq!(session.evaluate("counter").unwrap(), "0");
+    session.run_command("continue").unwrap();
+    assert_eq!(session.evaluate("counter").unwrap(), "1");
+    assert_eq!(session.run_command("continue").unwrap(), vec![Event::Exited(3)]);
+}
+
+#[test]
+fn kills_the_program_if_the_debugger_is_killed() {
+    use std::io::Write;
+    use std::process::Stdio;
+    let mut debugger = Command::new(env!("CARGO_BIN_EXE_deet"))
+        .arg(fixture("spin"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+    debugger.stdin.as_mut().unwrap().write_all(b"run\n").unwrap();
+    //the program is the debugger's only child
+    let children = format!("/proc/{}/task/{}/children", debugger.id(), debugger.id());
+    let mut program = None;
+    for _ in 0..100 {
+        program = std::fs::read_to_string(&children).ok().and_then(|pids| pids.trim().parse::<i32>().ok());
+        if program.is_some() {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    let program = program.expect("the program was not started");
+    debugger.kill().unwrap();
+    debugger.wait().unwrap();
+    //once killed, the program is gone or a zombie waiting to be reaped
+    let killed = (0..100).any(|_| {
+        let state = std::fs::read_to_string(format!("/proc/{}/stat", program))
+            .map(|stat| stat.rsplit(") ").next().unwrap_or("").starts_with('Z'))
+            .unwrap_or(true);
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        state
+    });
+    if !killed {
+        nix::sys::signal::kill(nix::unistd::Pid::from_raw(program), Signal::SIGKILL).ok();
+    }
+    assert!(killed);
+}
+
+#[test]
+fn debugs_position_independent_executables() {
+    //the breakpoint and the symbols move to wherever the program is loaded, each time it is run
+    let mut session = Session::new(&fixture_with_flags("calls", &["-O0", "-fPIE", "-pie"])).unwrap();
+    session.run_command("break add").unwrap();
+    for _ in 0..2 {
+        let events = session.run_command("run").unwrap();
+        assert_eq!(stopped_in(&events), Some(("add".to_string(), 5)));
+        let frames = session.backtrace().unwrap();
+        let functions: Vec<&str> = frames.iter().filter_map(|f| f.function.as_deref()).collect();
+        assert_eq!(functions, vec!["add", "twice", "main"]);
+        assert_eq!(session.evaluate("counter").unwrap(), "0");
+        session.run_command("continue").unwrap();
+        assert_eq!(session.evaluate("counter").unwrap(), "1");
+        assert_eq!(session.run_command("continue").unwrap(), vec![Event::Exited(3)]);
+    }
+}
+
+#[test]
+fn sets_breakpoints_in_libraries_once_they_are_loaded() {
+    //printf is in the C library, which isn't loaded until the program runs, and may be loaded
+    //somewhere else the next time
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    session.run_command("break printf").unwrap();
+    for _ in 0..2 {
+        let events = session.run_command("run").unwrap();
+        assert!(matches!(
+            events.last(),
+            Some(Event::Stopped { signal: Signal::SIGTRAP, function: None, .. })
+        ));
+        assert_eq!(session.evaluate("counter").unwrap(), "2");
+        assert_eq!(session.run_command("continue").unwrap(), vec![Event::Exited(3)]);
+    }
+}
+
+#[test]
+fn traces_where_library_functions_are_bound() {
+    let output = debugger_output(&fixture("types"), "set trace-plt on\nrun\nrun\n");
+    let bound: Vec<&str> = output.lines().filter(|line| line.starts_with("Bound printf to ")).collect();
+    //once for each time the program is run
+    assert_eq!(bound.len(), 2, "{}", output);
+    assert!(bound[0].contains("/libc.so.6 at 0x"), "{}", output);
+}
+
+#[test]
+fn lists_shared_libraries() {
+    let output = debugger_output(&fixture("calls"), "info sharedlibrary\nbreak add\nrun\ninfo sharedlibrary\n");
+    assert!(output.contains("No shared libraries loaded at this time."));
+    let libc = output.lines().find(|line| line.ends_with("/libc.so.6")).expect("libc is not listed");
+    let fields: Vec<&str> = libc.split_whitespace().collect();
+    let from = usize::from_str_radix(fields[0].trim_start_matches("0x"), 16).unwrap();
+    let to = usize::from_str_radix(fields[1].trim_start_matches("0x"), 16).unwrap();
+    assert!(from < to);
+    assert_eq!(fields[2], "Yes");
+}
+
+#[test]
+fn lists_the_memory_mappings_of_the_program() {
+    let program = fixture("calls");
+    let output = debugger_output(&program, "break add\nrun\ninfo proc mappings\n");
+    //the code of the program is mapped readable and executable, from its file
+    let code = output
+        .lines()
+        .find(|line| line.ends_with(&program) && line.contains(" r-xp "))
+        .expect("the program's code is not listed");
+    let fields: Vec<&str> = code.split_whitespace().collect();
+    let number = |field: &str| usize::from_str_radix(field.trim_start_matches("0x"), 16).unwrap();
+    assert_eq!(number(fields[1]) - number(fields[0]), number(fields[2]));
+    assert!(output.lines().any(|line| line.ends_with("[stack]")));
+}
+
+#[test]
+fn lists_the_sections_and_segments_of_the_program() {
+    let output = debugger_output(&fixture("types"), "info files\n");
+    assert!(output.contains("Local exec file:\n"), "{}", output);
+    assert!(output.contains("\tLoad bias: 0x0\n"), "{}", output);
+    assert!(output.lines().any(|line| line.contains(" r-x LOAD offset ")), "{}", output);
+    assert!(output.lines().any(|line| line.ends_with(" is .text (AX)")), "{}", output);
+    //a position independent executable is moved when it is run
+    let output = debugger_output(&fixture_with_flags("types", &["-O0", "-fPIE", "-pie"]), "break main\nrun\ninfo files\n");
+    let bias = output.lines().find_map(|line| line.strip_prefix("\tLoad bias: 0x")).expect(&output);
+    let entry = output.lines().find_map(|line| line.strip_prefix("\tEntry point: 0x")).expect(&output);
+    let bias = usize::from_str_radix(bias, 16).unwrap();
+    assert_ne!(bias, 0);
+    assert!(usize::from_str_radix(entry, 16).unwrap() > bias);
+}
+
+#[test]
+fn examines_core_files() {
+    let program = fixture("segv");
+    let core = dump_core(&program);
+    let commands = "bt\nprint value\nup\nprint answer\ninfo registers rip\ncontinue\ncore-file\nbt\n";
+    let output = debugger_output(&program, &format!("core-file {}\n{}", core.display(), commands));
+    assert!(output.contains("Program terminated with signal SIGSEGV, Segmentation fault."), "{}", output);
+    assert!(output.contains("in store at ") && output.contains("\n#1  0x"), "{}", output);
+    assert!(output.contains("$1 = 42\n") && output.contains("$2 = 42\n"), "{}", output);
+    assert!(output.lines().any(|line| line.starts_with("rip ") && line.ends_with("<main+25>")), "{}", output);
+    assert!(output.contains("The program is not being run.\nNo core file now.\nNo process is currently being run"));
+    //the core file can also be given when the debugger starts
+    let mut debugger = Command::new(env!("CARGO_BIN_EXE_deet"))
+        .arg(&program)
+        .arg("--core")
+        .arg(&core)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut output = String::new();
+    std::io::Read::read_to_string(debugger.stdout.as_mut().unwrap(), &mut output).unwrap();
+    debugger.wait().unwrap();
+    assert!(output.contains("#0  0x") && output.contains("in store at "), "{}", output);
+}
+
+#[test]
+fn rejects_core_files_with_offsets_past_the_end() {
+    let program = fixture("segv");
+    //an ELF header for a core file, with its program headers at `table`
+    let header = |table: u64, count: u16| {
+        let mut data = vec![0; 64];
+        data[..6].copy_from_slice(b"\x7fELF\x02\x01");
+        data[0x10..0x12].copy_from_slice(&4u16.to_le_bytes());
+        data[0x20..0x28].copy_from_slice(&table.to_le_bytes());
+        data[0x36..0x38].copy_from_slice(&56u16.to_le_bytes());
+        data[0x38..0x3a].copy_from_slice(&count.to_le_bytes());
+        data
+    };
+    let core = format!("{}.core", program);
+    let mut session = Session::new(&program).unwrap();
+    std::fs::write(&core, header(u64::MAX - 8, 2)).unwrap();
+    session.run_command(&format!("core-file {}", core)).unwrap();
+    assert!(session.take_output().contains("is not a core dump"));
+    //a note segment that would end past the end of the address space
+    let mut data = header(64, 1);
+    let mut note = vec![0; 56];
+    note[..4].copy_from_slice(&4u32.to_le_bytes());
+    note[8..0x10].copy_from_slice(&(u64::MAX - 8).to_le_bytes());
+    note[0x20..0x28].copy_from_slice(&16u64.to_le_bytes());
+    data.extend(note);
+    std::fs::write(&core, data).unwrap();
+    session.run_command(&format!("core-file {}", core)).unwrap();
+    assert!(session.take_output().contains("core file has no threads."));
+}
+
+#[test]
+fn writes_core_files_of_the_running_program() {
+    let program = fixture("calls");
+    let core = format!("{}.core", program);
+    let commands = format!("break 6\nrun\ngcore {0}\nkill\ncore-file {0}\nbt\nprint a\nx/1xb $pc\n", core);
+    let output = debugger_output(&program, &commands);
+    assert!(output.contains(&format!("Saved corefile {}", core)), "{}", output);
+    assert!(output.contains("Program terminated with signal SIGTRAP"), "{}", output);
+    assert!(output.contains("#2  0x") && output.contains(" in main at "), "{}", output);
+    assert!(output.contains("$1 = 21\n"), "{}", output);
+    //the breakpoint isn't in the core file's code
+    assert!(!output.contains(":\t0xcc"), "{}", output);
+}
+
+#[test]
+fn restarts_from_checkpoints() {
+    let commands = "break 6\nrun\ncheckpoint\ncontinue\ncontinue\nrestart 1\nprint counter\nprint a\n\
+                    continue\ncontinue\ninfo checkpoints\n";
+    let output = debugger_output(&fixture("calls"), commands);
+    assert!(output.contains("checkpoint 1: fork returned pid "), "{}", output);
+    assert!(output.contains("Switching to process "), "{}", output);
+    //the program is back where it was, before either call changed the counter
+    assert!(output.contains("$1 = 0\n$2 = 21\n"), "{}", output);
+    //and the breakpoints are still hit in the new process, which runs to the end again
+    assert_eq!(output.matches("(status 3)").count(), 2, "{}", output);
+    assert!(output.contains("  1    process "), "{}", output);
+}
+
+#[test]
+fn goes_back_through_recorded_execution() {
+    let commands = "break 6\nrun\nrecord\ncontinue\nprint a\nreverse-continue\nprint a\nprint counter\n\
+                    reverse-step\ncontinue\nprint a\ncontinue\n";
+    let output = debugger_output(&fixture("calls"), commands);
+    //back at the first call from the second, before it changed the counter
+    assert!(output.contains("$1 = 42\n"), "{}", output);
+    assert!(output.contains("$2 = 21\n$3 = 0\n"), "{}", output);
+    //there is nothing recorded before the first call
+    assert!(output.contains("No more reverse-execution history."), "{}", output);
+    //and the program runs forward again from there
+    assert!(output.contains("$4 = 42\n"), "{}", output);
+    assert!(output.contains("(status 3)"), "{}", output);
+}
+
+#[test]
+fn displays_expressions_at_every_stop() {
+    let commands = "break 6\nrun\ndisplay counter\ndisplay/x a\ncontinue\nundisplay 1\ninfo display\n";
+    let output = debugger_output(&fixture("calls"), commands);
+    //shown as soon as they are set, and again when the program stops
+    assert!(output.contains("1: counter = 0\n2: /x a = 0x15\n"), "{}", output);
+    assert!(output.contains("1: counter: old = 0, new = 1\n2: /x a: old = 0x15, new = 0x2a\n"), "{}", output);
+    assert!(output.contains("Num Enb Expression\n2:   y  /x a\n"), "{}", output);
+}
+
+#[test]
+fn displays_show_how_values_changed() {
+    let commands = "break 7\nrun\ndisplay b\ndisplay counter > 5\ncontinue\nrun\n";
+    let output = debugger_output(&fixture("calls"), commands);
+    //a value that is the same as at the last stop is shown as it is
+    assert!(output.contains("1: b: old = 21, new = 0\n2: counter > 5 = 0\n"), "{}", output);
+    //and the values from a process aren't compared with the next one's
+    assert_eq!(output.matches("1: b = 21\n2: counter > 5 = 0\n").count(), 2, "{}", output);
+}
+
+#[test]
+fn runs_until_past_loops() {
+    let commands = "break 8\nrun\ndelete\nuntil\nprint squares[9]\nrun\nuntil 10\n";
+    let output = debugger_output(&fixture("arrays"), commands);
+    //the jump back to the top of the loop doesn't stop the program, only getting past it does
+    assert!(output.contains("Stopped at ") && output.contains("arrays.c:10\n"), "{}", output);
+    assert!(output.contains("$1 = 81\n"), "{}", output);
+    let commands = "break add\nrun\nuntil 17\nbt\n";
+    let output = debugger_output(&fixture("calls"), commands);
+    //the location isn't reached before add returns, which stops the program in its caller
+    assert!(output.contains("#0  twice at "), "{}", output);
+}
+
+#[test]
+fn advances_to_locations_in_any_frame() {
+    let commands = "break main\nrun\nadvance 6\nprint a\ncontinue\n";
+    let output = debugger_output(&fixture("calls"), commands);
+    //stopped in add, called from twice, rather than only in main's frame as `until` would
+    assert!(output.contains("$1 = 21\n"), "{}", output);
+    //and no breakpoint is left behind on add for the second call
+    assert!(output.contains("(status 3)"), "{}", output);
+}
+
+#[test]
+fn jumps_over_lines() {
+    let commands = "break 16\nbreak 18\nrun\njump 18\nprint counter\ncontinue\n";
+    let output = debugger_output(&fixture("calls"), commands);
+    //the commands don't come from a terminal, so there is nothing to confirm
+    assert!(output.contains("Continuing at 0x"), "{}", output);
+    //the second call to add is skipped, and the breakpoint at the line jumped to stops the program
+    assert!(output.contains("$1 = 1\n"), "{}", output);
+    assert!(output.contains("(status 3)"), "{}", output);
+}
+
+#[test]
+fn returns_early_from_functions() {
+    let commands = "break add\nrun\nreturn 5\ndelete\ncontinue\n";
+    let output = debugger_output(&fixture("calls"), commands);
+    //add's frame is popped, leaving twice about to return the value given
+    assert!(output.contains("#0  twice at "), "{}", output);
+    //main gets 5 from twice, and adds 0 to it, which it doesn't return as 42
+    assert!(output.contains("\n5\n"), "{}", output);
+    assert!(output.contains("(status 1)"), "{}", output);
+}
+
+#[test]
+fn suggests_functions_for_misspelt_breakpoints() {
+    let commands = "break twcie\nbreak calls.c:mian\ninfo break\n";
+    let output = debugger_output(&fixture("calls"), commands);
+    assert!(output.contains("Function \"twcie\" not defined. Did you mean \"twice\"?"), "{}", output);
+    assert!(output.contains("Function \"mian\" not defined. Did you mean \"main\"?"), "{}", output);
+    //no pending breakpoint is left behind for the misspelt names
+    assert!(output.contains("No breakpoints or watchpoints."), "{}", output);
+}
+
+#[test]
+fn finds_commands_by_keyword() {
+    let output = debugger_output(&fixture("calls"), "apropos WATCH\n");
+    assert!(output.contains("watch -- Set a watchpoint"), "{}", output);
+    //descriptions are searched as well as names
+    assert!(output.contains("delete -- Delete breakpoints, watchpoints"), "{}", output);
+    assert!(!output.contains("print -- "), "{}", output);
+}
+
+#[test]
+fn keeps_aliases_between_sessions() {
+    let program = fixture("calls");
+    let home = std::path::Path::new(&program).parent().unwrap().to_str().unwrap().to_string();
+    let output = debugger_output_with_home(&program, "alias ba = break 6\nalias print = x\n", &home);
+    assert!(output.contains("Alias already exists: print"), "{}", output);
+    //the alias is saved, and the rest of the line is added to the command it stands for
+    let output = debugger_output_with_home(&program, "ba if a == 42\nrun\nprint b\n", &home);
+    assert!(output.contains("Breakpoint 1 at "), "{}", output);
+    assert!(output.contains("$1 = 0"), "{}", output);
+}
+
+#[test]
+fn keeps_the_history_of_each_program_under_a_stable_name() {
+    let program = fixture("calls");
+    let home = std::path::Path::new(&program).parent().unwrap().to_str().unwrap().to_string();
+    debugger_output_with_home(&program, "break add\ninfo break\n", &home);
+    //64-bit FNV-1a of the program's full path
+    let path = std::fs::canonicalize(&program).unwrap();
+    let mut hash = 0xcbf29ce484222325u64;
+    for byte in path.to_str().unwrap().bytes() {
+        hash = (hash ^ byte as u64).wrapping_mul(0x100000001b3);
+    }
+    let history = std::fs::read_to_string(format!("{}/.deet_history.d/{:016x}", home, hash)).unwrap();
+    assert_eq!(history.lines().filter(|line| !line.starts_with('#')).collect::<Vec<_>>(), ["break add", "info break"]);
+}
+
+#[test]
+fn user_defined_commands_skip_empty_lines() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    session.run_block("define show-args", &["print a", "", "print b", "  "]).unwrap();
+    session.run_command("break 6").unwrap();
+    session.run_command("run").unwrap();
+    session.take_output();
+    session.run_command("show-args").unwrap();
+    assert_eq!(session.take_output(), "$1 = 21\n$2 = 21\n");
+}
+
+#[test]
+fn reports_lines_that_cannot_be_read() {
+    //a line that isn't UTF-8 ends a block, and then the debugger, rather than crashing it
+    let commands = b"define one\nprint 1\n\xff\none\n\xff\nprint 2\n";
+    let output = run_debugger(Command::new(env!("CARGO_BIN_EXE_deet")).arg(fixture("calls")), &commands[..]);
+    assert!(output.contains("Cannot read the rest of the block: stream did not contain valid UTF-8\n$1 = 1\n"), "{}", output);
+    assert!(output.ends_with("Cannot read a command: stream did not contain valid UTF-8\n"), "{}", output);
+}
+
+#[test]
+fn runs_user_defined_commands() {
+    let commands = "define start\nbreak $arg0\nrun\nprint $argc\nend\nstart twice\nshow user\n";
+    let output = debugger_output(&fixture("calls"), commands);
+    assert!(output.contains("Inside function twice"), "{}", output);
+    assert!(output.contains("$1 = 1\n"), "{}", output);
+    assert!(output.contains("User command \"start\":\n  break $arg0\n  run\n"), "{}", output);
+}
+
+#[test]
+fn runs_the_stop_hook_at_every_stop() {
+    let commands = "define hook-stop\nprint counter\nend\nbreak 6\ncommands\necho in commands\\n\nend\nrun\nnext\n";
+    let output = debugger_output(&fixture("calls"), commands);
+    //the hook runs before the breakpoint's commands, and again after stepping
+    assert!(output.contains("$1 = 0\nin commands\n"), "{}", output);
+    assert!(output.contains("$2 = 1\n"), "{}", output);
+}
+
+#[test]
+fn runs_init_files_at_startup() {
+    let program = fixture("calls");
+    let home = std::path::Path::new(&program).parent().unwrap().to_path_buf();
+    let project = home.join("project");
+    std::fs::create_dir_all(&project).unwrap();
+    std::fs::write(home.join(".deetrc"), "# settings for every session\nset listsize 3\n").unwrap();
+    std::fs::write(project.join(".deetrc"), "define show-b\nprint b\nend\n\nbreak 6\n").unwrap();
+    let mut command = Command::new(env!("CARGO_BIN_EXE_deet"));
+    command.arg(&program).env("HOME", &home).current_dir(&project);
+    let output = run_debugger(&mut command, "show listsize\nrun\nshow-b\n");
+    assert!(output.contains("Number of source lines listed by default is 3."), "{}", output);
+    assert!(output.contains("Breakpoint 1 at "), "{}", output);
+    assert!(output.contains("$1 = 21"), "{}", output);
+}
+
+#[test]
+fn runs_command_line_commands_in_batch_mode() {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_deet"));
+    command.arg(fixture("calls")).args(["-ex", "break add", "-ex", "", "-ex", "run", "-ex", "bt", "--batch"]);
+    //nothing is read from standard input in batch mode
+    let output = run_debugger(&mut command, "print 1\n");
+    assert!(output.contains("#1  0x"), "{}", output);
+    assert!(output.contains(" in twice at "), "{}", output);
+    assert!(!output.contains("$1 = 1"), "{}", output);
+    assert!(output.contains("killed"), "{}", output);
+}
+
+#[test]
+fn runs_commands_from_files() {
+    let program = fixture("calls");
+    let dir = std::path::Path::new(&program).parent().unwrap().to_path_buf();
+    let script = dir.join("checks.deet");
+    std::fs::write(&script, "# stop in add\nbreak 6\nrun\n\nprint b\nfrobnicate\nprint a\n").unwrap();
+    let output = debugger_output(&program, &format!("source {}\n", script.display()));
+    assert!(output.contains("Breakpoint 1 at "), "{}", output);
+    assert!(output.contains("$1 = 21"), "{}", output);
+    assert!(output.contains(&format!("{}:6: Undefined command: \"frobnicate\".", script.display())), "{}", output);
+    assert!(!output.contains("$2 = "), "{}", output);
+}
+
+//Write a Rhai script next to a program and run it in a session with `script`
+#[cfg(feature = "scripting")]
+fn load_script(session: &mut Session, program: &str, script: &str) {
+    let path = std::path::Path::new(program).parent().unwrap().join("test.rhai");
+    std::fs::write(&path, script).unwrap();
+    session.run_command(&format!("script {}", path.display())).unwrap();
+}
+
+#[cfg(feature = "scripting")]
+#[test]
+fn scripts_decide_whether_breakpoints_stop() {
+    let program = fixture("calls");
+    let mut session = Session::new(&program).unwrap();
+    session.run_command("break 6").unwrap();
+    //add is called with (21, 21) and then (42, 0)
+    let script = r#"
+        fn on_stop(event) {
+            event.breakpoint == 1 && event.function == "add" && event.line == 6 && eval("b") == 0
+        }
+    "#;
+    load_script(&mut session, &program, script);
+    let events = session.run_command("run").unwrap();
+    assert_eq!(stopped_in(&events), Some(("add".to_string(), 6)));
+    assert_eq!(session.evaluate("a").unwrap(), "42");
+    assert_eq!(session.run_command("continue").unwrap(), vec![Event::Exited(3)]);
+}
+
+#[cfg(feature = "scripting")]
+#[test]
+fn scripts_evaluate_expressions_and_read_memory() {
+    let program = fixture("calls");
+    let mut session = Session::new(&program).unwrap();
+    session.run_command("break 7").unwrap();
+    session.run_command("run").unwrap();
+    session.take_output();
+    let script = r#"
+        execute("print " + (eval("a + b") * 2));
+        let bytes = read_memory(eval("(long)&counter"), 4);
+        execute("print " + bytes[0]);
+        execute("print " + (register("rip") - eval("(long)$pc")));
+    "#;
+    load_script(&mut session, &program, script);
+    assert_eq!(session.take_output(), "$1 = 84\n$2 = 1\n$3 = 0\n");
+}
+
+#[cfg(feature = "scripting")]
+#[test]
+fn scripts_print_values_with_printers() {
+    let program = fixture("calls");
+    let mut session = Session::new(&program).unwrap();
+    session.run_command("break 6").unwrap();
+    session.run_command("run").unwrap();
+    load_script(&mut session, &program, r#"add_printer("int", |expr| `int ${eval(expr) + 1}`);"#);
+    session.take_output();
+    session.run_command("print a").unwrap();
+    //a format asked for doesn't go through the printer
+    session.run_command("print/x a").unwrap();
+    assert_eq!(session.take_output(), "$1 = int 22\n$2 = 0x15\n");
+}
+
+#[test]
+fn copies_output_to_a_log_file() {
+    let program = fixture("calls");
+    let log = std::path::Path::new(&program).parent().unwrap().join("session.log");
+    let commands = format!(
+        "set logging commands on\nset logging on {}\nbreak 6\nrun\nprint b\nset logging off\nprint a\n",
+        log.display()
+    );
+    let output = debugger_output(&program, &commands);
+    assert!(output.contains(&format!("Copying output to {}.", log.display())), "{}", output);
+    let logged = std::fs::read_to_string(&log).unwrap();
+    assert!(logged.contains("(deet) break 6\nBreakpoint 1 at "), "{}", logged);
+    assert!(logged.contains("(deet) print b\n$1 = 21\n"), "{}", logged);
+    assert!(output.contains("$2 = 21"), "{}", output);
+    assert!(!logged.contains("$2 = "), "{}", logged);
+}
+
+#[test]
+fn debugs_programs_under_a_remote_stub() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    let line = session.address_of("6").unwrap();
+    let stub = std::thread::spawn(move || fake_stub(listener, vec![(Some(line + 1), "T05thread:p4d2.4d2;"), (None, "W03")]));
+    session.set_breakpoint("6").unwrap();
+    session.run_command(&format!("target remote 127.0.0.1:{}", port)).unwrap();
+    assert!(session.take_output().starts_with(&format!("Remote debugging using 127.0.0.1:{}\n", port)));
+    let events = session.run_command("continue").unwrap();
+    assert_eq!(stopped_in(&events), Some(("add".to_string(), 6)));
+    assert_eq!(session.evaluate("a").unwrap(), "21");
+    assert_eq!(session.run_command("continue").unwrap(), vec![Event::Exited(3)]);
+    drop(session);
+    stub.join().unwrap();
+}
+
+#[test]
+fn reports_stops_at_system_calls() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let stops = vec![(None, "T05syscall_entry:e7;thread:p4d2.4d2;"), (None, "W03")];
+    let stub = std::thread::spawn(move || fake_stub(listener, stops));
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    session.run_command(&format!("target remote 127.0.0.1:{}", port)).unwrap();
+    session.take_output();
+    let events = session.run_command("continue").unwrap();
+    assert_eq!(events, vec![Event::Syscall { number: 231, addr: 0x401000 }]);
+    assert!(session.take_output().starts_with("Child stopped (system call 231)\n"));
+    //the program is left stopped there, and carries on from it
+    assert_eq!(session.run_command("continue").unwrap(), vec![Event::Exited(3)]);
+    drop(session);
+    stub.join().unwrap();
+}
+
+#[test]
+fn debugs_programs_through_the_remote_protocol() {
+    use std::io::BufRead;
+    use std::process::Stdio;
+    let program = fixture_with_flags("calls", &["-O0", "-fno-omit-frame-pointer", "-pie", "-fPIE"]);
+    let mut server = Command::new(env!("CARGO_BIN_EXE_deet"))
+        .args(["--server", "127.0.0.1:0", &program])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    //the server says which port it was given once it is listening
+    let mut lines = std::io::BufReader::new(server.stdout.take().unwrap()).lines();
+    let port = lines
+        .by_ref()
+        .map(|line| line.unwrap())
+        .find_map(|line| line.strip_prefix("Listening on port ").map(|port| port.to_string()))
+        .unwrap();
+    let commands = format!("break 6\ntarget remote 127.0.0.1:{}\ncontinue\nbt\nprint b\ncontinue\ncontinue\n", port);
+    let output = debugger_output(&program, &commands);
+    assert!(output.contains(&format!("Remote debugging using 127.0.0.1:{}", port)), "{}", output);
+    assert!(output.contains(" in twice at "), "{}", output);
+    assert!(output.contains("$1 = 21"), "{}", output);
+    assert!(output.contains("Child existed (status 3)"), "{}", output);
+    assert!(server.wait().unwrap().success());
+    let rest: Vec<String> = lines.map(|line| line.unwrap()).collect();
+    assert!(rest.iter().any(|line| line == "Child exited with status 3"), "{:?}", rest);
+}
+
+#[test]
+fn embeds_the_debugger_without_printing() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    assert!(session.functions().contains(&"twice".to_string()));
+    assert_eq!(session.set_breakpoint("add").unwrap(), 1);
+    assert_eq!(session.set_breakpoint("add").unwrap(), 1);
+    assert!(session.set_breakpoint("twcie").unwrap_err().contains("twice"));
+    let addr = session.address_of("add").unwrap();
+    assert_eq!(session.breakpoints(), vec![addr]);
+    let location = session.location_of(addr);
+    assert_eq!(location.function.as_deref(), Some("add"));
+    assert!(location.file.unwrap().ends_with("calls.c"));
+    assert_eq!(location.line, Some(5));
+
+    let events = session.run_command("run").unwrap();
+    assert_eq!(stopped_in(&events), Some(("add".to_string(), 5)));
+    assert_eq!(session.evaluate("counter + 42").unwrap(), "42");
+    //what the commands printed is kept for the program embedding the debugger
+    session.run_command("print counter").unwrap();
+    let output = session.take_output();
+    assert!(output.contains("Inside function add"), "{}", output);
+    assert!(output.ends_with("$1 = 0\n"), "{}", output);
+    assert_eq!(session.take_output(), "");
+}
+
+#[test]
+fn rejects_unknown_commands() {
+    let mut session = Session::new(&fixture("calls")).unwrap();
+    assert!(session.run_command("frobnicate").is_err());
+    assert!(session.run_command("").is_err());
+    assert!(session.run_command("&").is_err());
+    assert!(session.run_command("  & ").is_err());
+}